@@ -0,0 +1,12 @@
+fn main() {
+    // Generate the gRPC service from proto/realbook.proto when the `grpc`
+    // feature is on, using a vendored protoc so no system install is needed
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc not available");
+        // SAFETY: build scripts are single-threaded; nothing else reads the environment concurrently
+        unsafe { std::env::set_var("PROTOC", protoc) };
+
+        tonic_build::compile_protos("proto/realbook.proto").expect("Failed to compile realbook.proto");
+    }
+}
@@ -0,0 +1,64 @@
+//! Access control for maintenance routes (uploading attachments, moderating
+//! corrections, reviewing reported pages)
+//!
+//! Admin routes are off unless an `admin_token` is configured (e.g.
+//! `ROCKET_ADMIN_TOKEN=...`); requests then authenticate with
+//! `Authorization: Bearer <token>`. While off, the routes answer 404 as if they
+//! didn't exist.
+
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use crate::errors::ApiError;
+
+/// The configured admin token, if any
+pub struct AdminConfig {
+    token: Option<String>,
+}
+
+impl AdminConfig {
+    pub fn new(token: Option<String>) -> Self {
+        // A blank token would let anyone in with an empty bearer
+        AdminConfig { token: token.filter(|token| !token.trim().is_empty()) }
+    }
+}
+
+/// Compare without returning early on the first differing byte, so response
+/// timing doesn't reveal how much of a guess was right
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Request guard for admin routes; take it as `Result<Admin, ApiError>` and
+/// `?` it to answer with the guard's error
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ApiError> {
+        let token = request
+            .rocket()
+            .state::<AdminConfig>()
+            .and_then(|config| config.token.as_deref());
+        let Some(token) = token else {
+            let path = request.uri().path().to_string();
+            return Outcome::Error((
+                Status::NotFound,
+                ApiError::new(Status::NotFound, "not_found", format!("No resource at {}", path)),
+            ));
+        };
+
+        let presented = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match presented {
+            Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => Outcome::Success(Admin),
+            _ => Outcome::Error((
+                Status::Unauthorized,
+                ApiError::new(Status::Unauthorized, "unauthorized", "A valid admin token is required"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! User data archives: one JSON file holding everything a user has built up
+//!
+//! There are no accounts yet, so most of a user's data (favorites, setlists,
+//! annotations, notes, practice log, tags, repertoire) lives in their browser.
+//! `/me/export` answers with the part the server holds (the anonymous
+//! session's recently viewed songs) in the archive format, and the UI adds its
+//! local sections under their own keys before offering the file for download.
+//! Sections the server doesn't know are carried along untouched.
+//!
+//! `/me/import` is the way back in: it checks an archive's format, version, and
+//! section shapes, merges its recently viewed songs into the session, and
+//! reports what it found; the UI then merges the local sections the same way.
+
+use rocket::http::{Header, Status};
+use rocket::serde::json::Json;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::errors::ApiError;
+
+/// `format` tag identifying an archive file
+pub const ARCHIVE_FORMAT: &str = "realbook-archive";
+
+/// Current archive version; bumped when a section changes shape
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// A user's data, as exported
+#[derive(Debug, Serialize)]
+pub struct UserArchive {
+    pub format: &'static str,
+    pub version: u32,
+    /// Day the archive was made (YYYY-MM-DD, UTC)
+    pub exported_at: String,
+    /// Songs viewed in the anonymous session, newest first (entry ids)
+    pub recently_viewed: Vec<u32>,
+    /// Sections kept elsewhere (the browser's), by name
+    #[serde(flatten)]
+    pub sections: Map<String, Value>,
+}
+
+/// An archive served as a file download
+#[derive(Responder)]
+#[response(content_type = "json")]
+pub struct ArchiveDownload {
+    archive: Json<UserArchive>,
+    disposition: Header<'static>,
+}
+
+impl ArchiveDownload {
+    pub fn new(archive: UserArchive) -> Self {
+        let filename = format!("realbook-archive-{}.json", archive.exported_at);
+        ArchiveDownload {
+            archive: Json(archive),
+            disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", filename)),
+        }
+    }
+}
+
+/// Largest archive `/me/import` reads
+pub const MAX_ARCHIVE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Sections kept in the browser and the JSON shape each must have: a list, or
+/// an object keyed by entry id
+const LOCAL_SECTIONS: &[(&str, SectionShape)] = &[
+    ("favorites", SectionShape::List),
+    ("setlists", SectionShape::List),
+    ("annotations", SectionShape::ById),
+    ("notes", SectionShape::ById),
+    ("practice", SectionShape::List),
+    ("tags", SectionShape::ById),
+    ("repertoire", SectionShape::ById),
+];
+
+#[derive(Clone, Copy)]
+enum SectionShape {
+    List,
+    ById,
+}
+
+/// Whether `name` is one of the sections kept in the browser
+pub fn is_local_section(name: &str) -> bool {
+    LOCAL_SECTIONS.iter().any(|(section, _)| *section == name)
+}
+
+/// How an imported archive combines with the data already there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStrategy {
+    /// Keep everything from both; where both have the same item, keep the one
+    /// already there
+    #[default]
+    Merge,
+    /// Like merge, but the archive's version of an item wins
+    Overwrite,
+    /// Drop the existing data and take the archive's
+    Replace,
+}
+
+impl ImportStrategy {
+    /// Parse a `strategy` parameter ("merge", "overwrite", or "replace")
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "merge" => Some(ImportStrategy::Merge),
+            "overwrite" => Some(ImportStrategy::Overwrite),
+            "replace" => Some(ImportStrategy::Replace),
+            _ => None,
+        }
+    }
+}
+
+/// What `/me/import` found and applied
+#[derive(Debug, Serialize)]
+pub struct ImportSummary {
+    pub strategy: ImportStrategy,
+    pub version: u32,
+    /// The browser sections present in the archive, for the UI to merge
+    pub sections: Vec<String>,
+    /// Recently viewed songs in the session after the import
+    pub recently_viewed: usize,
+    /// Ids in `recently_viewed` not in the catalog (skipped)
+    pub unknown_entries: Vec<u32>,
+}
+
+fn invalid(message: impl Into<String>) -> ApiError {
+    ApiError::new(Status::UnprocessableEntity, "invalid_archive", message)
+}
+
+/// Check an uploaded archive; answers with its version, its recently viewed
+/// ids, and the browser sections it has
+pub fn validate(archive: &Value) -> Result<(u32, Vec<u32>, Vec<String>), ApiError> {
+    let archive = archive.as_object().ok_or_else(|| invalid("An archive is a JSON object"))?;
+    if archive.get("format").and_then(Value::as_str) != Some(ARCHIVE_FORMAT) {
+        return Err(invalid(format!("Not a user data archive (format must be \"{}\")", ARCHIVE_FORMAT)));
+    }
+    let version = archive
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .ok_or_else(|| invalid("version must be a whole number"))?;
+    if version > ARCHIVE_VERSION {
+        return Err(invalid("This archive was exported by a newer version of the app"));
+    }
+
+    let recently_viewed = match archive.get("recently_viewed") {
+        None => Vec::new(),
+        Some(ids) => serde_json::from_value(ids.clone())
+            .map_err(|_| invalid("recently_viewed must be a list of entry ids"))?,
+    };
+
+    let mut sections = Vec::new();
+    for (name, shape) in LOCAL_SECTIONS {
+        let Some(section) = archive.get(*name) else {
+            continue;
+        };
+        let valid = match shape {
+            SectionShape::List => section.is_array(),
+            SectionShape::ById => section
+                .as_object()
+                .is_some_and(|items| items.keys().all(|id| id.parse::<u32>().is_ok())),
+        };
+        if !valid {
+            return Err(invalid(match shape {
+                SectionShape::List => format!("{} must be a list", name),
+                SectionShape::ById => format!("{} must be an object keyed by entry id", name),
+            }));
+        }
+        sections.push(name.to_string());
+    }
+    Ok((version, recently_viewed, sections))
+}
+
+/// Combine two newest-first id lists by `strategy`, keeping at most `max`
+pub fn combine_recent(current: &[u32], incoming: &[u32], strategy: ImportStrategy, max: usize) -> Vec<u32> {
+    let (first, second): (&[u32], &[u32]) = match strategy {
+        ImportStrategy::Merge => (current, incoming),
+        ImportStrategy::Overwrite => (incoming, current),
+        ImportStrategy::Replace => (incoming, &[]),
+    };
+    let mut ids: Vec<u32> = Vec::new();
+    for id in first.iter().chain(second) {
+        if !ids.contains(id) {
+            ids.push(*id);
+        }
+    }
+    ids.truncate(max);
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rejection(archive: Value) -> String {
+        let error = validate(&archive).expect_err("archive should be refused");
+        assert_eq!(error.status, Status::UnprocessableEntity);
+        assert_eq!(error.body.code, "invalid_archive");
+        error.body.message
+    }
+
+    #[test]
+    fn a_valid_archive_lists_its_sections() {
+        let archive = json!({
+            "format": "realbook-archive",
+            "version": 1,
+            "recently_viewed": [3, 1],
+            "favorites": [],
+            "notes": { "42": { "text": "swing" } },
+            "somewhere_else": "ignored",
+        });
+        let (version, recent, sections) = validate(&archive).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(recent, vec![3, 1]);
+        assert_eq!(sections, vec!["favorites", "notes"]);
+
+        let bare = validate(&json!({ "format": "realbook-archive", "version": 0 })).unwrap();
+        assert_eq!(bare, (0, Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn malformed_archives_are_refused() {
+        assert!(rejection(json!([])).contains("JSON object"));
+        assert!(rejection(json!({ "format": "zip", "version": 1 })).contains("format"));
+        assert!(rejection(json!({ "format": "realbook-archive" })).contains("version"));
+        assert!(rejection(json!({ "format": "realbook-archive", "version": -1 })).contains("version"));
+        assert!(rejection(json!({ "format": "realbook-archive", "version": ARCHIVE_VERSION + 1 })).contains("newer"));
+        assert!(
+            rejection(json!({ "format": "realbook-archive", "version": 1, "recently_viewed": ["x"] }))
+                .contains("recently_viewed")
+        );
+    }
+
+    #[test]
+    fn sections_must_have_their_shape() {
+        let with = |name: &str, section: Value| {
+            let mut archive = json!({ "format": "realbook-archive", "version": 1 });
+            archive[name] = section;
+            archive
+        };
+        assert_eq!(rejection(with("setlists", json!({}))), "setlists must be a list");
+        assert_eq!(rejection(with("practice", json!("x"))), "practice must be a list");
+        assert_eq!(rejection(with("tags", json!([]))), "tags must be an object keyed by entry id");
+        assert_eq!(rejection(with("annotations", json!({ "one": {} }))), "annotations must be an object keyed by entry id");
+        assert!(validate(&with("repertoire", json!({ "7": "learning" }))).is_ok());
+    }
+
+    #[test]
+    fn recent_views_combine_by_strategy() {
+        let current = [1, 2, 3];
+        let incoming = [3, 4];
+        assert_eq!(combine_recent(&current, &incoming, ImportStrategy::Merge, 10), vec![1, 2, 3, 4]);
+        assert_eq!(combine_recent(&current, &incoming, ImportStrategy::Overwrite, 10), vec![3, 4, 1, 2]);
+        assert_eq!(combine_recent(&current, &incoming, ImportStrategy::Replace, 10), vec![3, 4]);
+    }
+
+    #[test]
+    fn recent_views_are_deduplicated_and_capped() {
+        assert_eq!(combine_recent(&[5, 5, 6], &[6, 7], ImportStrategy::Merge, 10), vec![5, 6, 7]);
+        assert_eq!(combine_recent(&[1, 2, 3], &[4, 5], ImportStrategy::Overwrite, 3), vec![4, 5, 1]);
+        assert_eq!(combine_recent(&[], &[9, 9], ImportStrategy::Replace, 10), vec![9]);
+    }
+}
@@ -0,0 +1,249 @@
+//! Optional extras attached to catalog entries (reference recordings,
+//! iReal Pro charts, MusicXML lead sheets)
+//!
+//! Few songs have any, so they live apart from realbook.json, in
+//! `api/resources/attachments.json`, keyed by entry id:
+//!
+//! ```json
+//! { "6": { "youtube_id": "dQw4w9WgXcQ" }, "7": { "audio_url": "https://..." } }
+//! ```
+//!
+//! Clients fetch them per song (`/entries/<id>/attachments`), which keeps the
+//! catalog and search responses the same shape for every entry. The file in
+//! the repository is still empty; extras are added as they're checked against
+//! the charts.
+//!
+//! MusicXML can also be uploaded through the admin route; uploads are kept in
+//! `api/resources/musicxml/` as `<entry id>.musicxml` (or `.mxl` when
+//! compressed) and take precedence over a `musicxml_url` from the file. The
+//! files are read and written with async I/O; uploads and deletions take turns
+//! so two of them never interleave their steps.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use rocket::http::{ContentType, Header};
+use rocket::tokio::sync::Mutex as AsyncMutex;
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use serde::{Deserialize, Serialize};
+
+/// Where the attachments are read from at startup (a missing file means none)
+const ATTACHMENTS_PATH: &str = "api/resources/attachments.json";
+
+/// Where uploaded MusicXML files are kept
+const MUSICXML_DIR: &str = "api/resources/musicxml";
+
+/// Largest MusicXML upload accepted, in bytes (lead sheets are tens of KB)
+pub const MAX_MUSICXML_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Everything attached to one entry (all optional)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Attachments {
+    /// YouTube video id of a reference recording to play along with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub youtube_id: Option<String>,
+    /// Direct https link to an audio file of a reference recording (the UI's
+    /// Content-Security-Policy only plays https media)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_url: Option<String>,
+    /// iReal Pro link (`irealb://` or `irealbook://`) to the song's chart, for
+    /// play-along backing tracks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub irealpro: Option<String>,
+    /// Where to download an editable MusicXML version of the song: an https
+    /// URL from the file, or this API's copy of an uploaded one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musicxml_url: Option<String>,
+}
+
+impl Attachments {
+    /// Reject values that couldn't be embedded safely (plain http links would
+    /// be blocked as mixed content on an https page)
+    fn validate(&self) -> Result<(), String> {
+        if let Some(id) = &self.youtube_id
+            && !is_youtube_id(id) {
+            return Err(format!("youtube_id {:?} is not an 11-character video id", id));
+        }
+        if let Some(url) = &self.audio_url
+            && !url.starts_with("https://") {
+            return Err(format!("audio_url {:?} is not an https URL", url));
+        }
+        if let Some(uri) = &self.irealpro
+            && !(uri.starts_with("irealb://") || uri.starts_with("irealbook://")) {
+            return Err(format!("irealpro {:?} is not an irealb:// or irealbook:// link", uri));
+        }
+        if let Some(url) = &self.musicxml_url
+            && !url.starts_with("https://") {
+            return Err(format!("musicxml_url {:?} is not an https URL", url));
+        }
+        Ok(())
+    }
+}
+
+/// YouTube video ids are 11 characters of URL-safe base64
+fn is_youtube_id(id: &str) -> bool {
+    id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// MusicXML comes plain or zipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusicXmlFormat {
+    /// `.musicxml`: the score as XML
+    Plain,
+    /// `.mxl`: a zip archive holding the XML
+    Compressed,
+}
+
+impl MusicXmlFormat {
+    const ALL: [MusicXmlFormat; 2] = [MusicXmlFormat::Plain, MusicXmlFormat::Compressed];
+
+    /// Recognize an upload: a zip archive, or XML with a MusicXML score root
+    /// near the start (after the declaration and doctype)
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PK\x03\x04") {
+            return Some(MusicXmlFormat::Compressed);
+        }
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(4096)]);
+        (head.trim_start().starts_with('<')
+            && (head.contains("<score-partwise") || head.contains("<score-timewise")))
+            .then_some(MusicXmlFormat::Plain)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            MusicXmlFormat::Plain => "musicxml",
+            MusicXmlFormat::Compressed => "mxl",
+        }
+    }
+
+    fn content_type(self) -> ContentType {
+        match self {
+            MusicXmlFormat::Plain => ContentType::new("application", "vnd.recordare.musicxml+xml"),
+            MusicXmlFormat::Compressed => ContentType::new("application", "vnd.recordare.musicxml"),
+        }
+    }
+}
+
+/// Path of an entry's uploaded MusicXML in the given format
+fn musicxml_path(id: u32, format: MusicXmlFormat) -> PathBuf {
+    PathBuf::from(MUSICXML_DIR).join(format!("{}.{}", id, format.extension()))
+}
+
+/// Attachments of every entry that has some
+#[derive(Debug, Default)]
+pub struct AttachmentStore {
+    by_entry: HashMap<u32, Attachments>,
+    /// Entries with an uploaded MusicXML file, and its format (only locked
+    /// to look up or change an entry, never across file I/O)
+    uploads: Mutex<HashMap<u32, MusicXmlFormat>>,
+    /// Held while an upload or deletion changes the files
+    writing: AsyncMutex<()>,
+}
+
+impl AttachmentStore {
+    /// Read and validate the attachments file, panicking on bad data like the
+    /// catalog load does (a missing file is fine), and find the uploads
+    pub fn load() -> Self {
+        let by_entry: HashMap<u32, Attachments> = match std::fs::read_to_string(ATTACHMENTS_PATH) {
+            Ok(data) => serde_json::from_str(&data).expect("Failed to parse attachments.json"),
+            Err(_) => HashMap::new(),
+        };
+        for (id, attachments) in &by_entry {
+            if let Err(problem) = attachments.validate() {
+                panic!("Invalid attachments for entry {} in attachments.json: {}", id, problem);
+            }
+        }
+        AttachmentStore { by_entry, uploads: Mutex::new(scan_uploads()), writing: AsyncMutex::new(()) }
+    }
+
+    /// Format of the entry's uploaded MusicXML, if it has one
+    fn upload(&self, id: u32) -> Option<MusicXmlFormat> {
+        self.uploads.lock().unwrap().get(&id).copied()
+    }
+
+    /// Attachments of an entry (empty if it has none)
+    pub fn get(&self, id: u32) -> Attachments {
+        let mut attachments = self.by_entry.get(&id).cloned().unwrap_or_default();
+        if self.upload(id).is_some() {
+            attachments.musicxml_url = Some(format!("{}/entries/{}/musicxml", crate::API_PREFIX, id));
+        }
+        attachments
+    }
+
+    /// Format and contents of an entry's uploaded MusicXML, if it has one
+    pub async fn musicxml(&self, id: u32) -> std::io::Result<Option<(MusicXmlFormat, Vec<u8>)>> {
+        let Some(format) = self.upload(id) else {
+            return Ok(None);
+        };
+        Ok(Some((format, rocket::tokio::fs::read(musicxml_path(id, format)).await?)))
+    }
+
+    /// Store an upload as the entry's MusicXML, replacing any earlier one
+    pub async fn save_musicxml(&self, id: u32, format: MusicXmlFormat, bytes: &[u8]) -> std::io::Result<()> {
+        let _writing = self.writing.lock().await;
+        rocket::tokio::fs::create_dir_all(MUSICXML_DIR).await?;
+        // Write then rename, so a failed upload never leaves a truncated file
+        let path = musicxml_path(id, format);
+        let partial = path.with_extension("part");
+        rocket::tokio::fs::write(&partial, bytes).await?;
+        rocket::tokio::fs::rename(&partial, &path).await?;
+        let previous = self.uploads.lock().unwrap().insert(id, format);
+        if let Some(previous) = previous
+            && previous != format {
+            rocket::tokio::fs::remove_file(musicxml_path(id, previous)).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete the entry's uploaded MusicXML; false if it had none. The file
+    /// goes first, so a failure leaves the upload listed and still served.
+    pub async fn remove_musicxml(&self, id: u32) -> std::io::Result<bool> {
+        let _writing = self.writing.lock().await;
+        let Some(format) = self.upload(id) else {
+            return Ok(false);
+        };
+        rocket::tokio::fs::remove_file(musicxml_path(id, format)).await?;
+        self.uploads.lock().unwrap().remove(&id);
+        Ok(true)
+    }
+}
+
+/// Uploaded MusicXML files already on disk (none if the folder is missing)
+fn scan_uploads() -> HashMap<u32, MusicXmlFormat> {
+    let Ok(files) = std::fs::read_dir(MUSICXML_DIR) else {
+        return HashMap::new();
+    };
+    files
+        .filter_map(|file| {
+            let path = file.ok()?.path();
+            let id = path.file_stem()?.to_str()?.parse().ok()?;
+            let extension = path.extension()?.to_str()?;
+            let format = MusicXmlFormat::ALL.into_iter().find(|format| format.extension() == extension)?;
+            Some((id, format))
+        })
+        .collect()
+}
+
+/// A MusicXML file served as a download with the given file name (without
+/// extension, which follows the format)
+pub struct MusicXmlDownload {
+    pub filename: String,
+    pub format: MusicXmlFormat,
+    pub bytes: Vec<u8>,
+}
+
+impl<'r> Responder<'r, 'static> for MusicXmlDownload {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(self.format.content_type())
+            .header(Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}.{}\"", self.filename, self.format.extension()),
+            ))
+            .sized_body(self.bytes.len(), Cursor::new(self.bytes))
+            .ok()
+    }
+}
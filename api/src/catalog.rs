@@ -0,0 +1,234 @@
+//! Catalog search shared by the REST routes and the gRPC service
+
+use realbook_core::{Matches, SearchFilter, search_entries};
+use crate::models::{API_VERSION, Param, RealBookEntry, SearchQuery, SearchResponse, VolumeInfo};
+
+/// Run a (validated) search over the catalog
+pub fn search_catalog(data: &[RealBookEntry], params: &SearchQuery) -> SearchResponse {
+    let SearchQuery {
+        query,
+        within,
+        initial,
+        volume: Param(volume),
+        page: Param(page),
+        page_from: Param(page_from),
+        page_to: Param(page_to),
+        page_near: Param(page_near),
+        tolerance: Param(tolerance),
+        offset: Param(offset),
+        limit: Param(limit),
+    } = params;
+    let filter = SearchFilter {
+        query: query.as_deref(),
+        within,
+        initial: initial.as_ref().and_then(|letter| letter.chars().next()),
+        volume: *volume,
+        page: *page,
+        page_from: *page_from,
+        page_to: *page_to,
+        page_near: *page_near,
+        tolerance: *tolerance,
+        offset: *offset,
+        limit: *limit,
+    };
+    let Matches { results, total, volume_counts, suggestions } = search_entries(data, &filter);
+    let volume_counts = volume_counts.into_iter().map(|(volume, count)| VolumeInfo { volume, count }).collect();
+
+    SearchResponse { api_version: API_VERSION, results, total, volume_counts, suggestions }
+}
+
+/// Count entries per volume, sorted by volume number
+pub fn count_by_volume(entries: &[RealBookEntry]) -> Vec<VolumeInfo> {
+    realbook_core::count_by_volume(entries)
+        .into_iter()
+        .map(|(volume, count)| VolumeInfo { volume, count })
+        .collect()
+}
+
+/// Content hash of the catalog, so cached copies can tell when they're out of date
+///
+/// SHA-256 over each entry as JSON, one per line, so every field counts and
+/// the value is the same across builds and Rust versions; shortened to 16 hex
+/// digits.
+pub fn catalog_version(data: &[RealBookEntry]) -> String {
+    use ring::digest::{Context, SHA256};
+    let mut context = Context::new(&SHA256);
+    for entry in data {
+        context.update(&serde_json::to_vec(entry).expect("catalog entries serialize to JSON"));
+        context.update(b"\n");
+    }
+    context.finish().as_ref()[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Look up an entry by its id
+pub fn find_entry(data: &[RealBookEntry], id: u32) -> Option<&RealBookEntry> {
+    data.iter().find(|entry| entry.id == id)
+}
+
+/// Pick a uniformly random entry among the search's matches, ignoring its
+/// pagination (None if nothing matches). With a seed the pick is the same
+/// every time for the same seed and filters. Entries in `exclude` (songs
+/// recently served) are passed over while any other song matches.
+pub fn random_match(
+    data: &[RealBookEntry],
+    params: &SearchQuery,
+    seed: Option<&str>,
+    exclude: &[u32],
+) -> Option<RealBookEntry> {
+    let params = SearchQuery { offset: Param(None), limit: Param(None), ..params.clone() };
+    let mut matches = search_catalog(data, &params).results;
+    // Skip the excluded songs, unless that would leave nothing to pick
+    if matches.iter().any(|entry| !exclude.contains(&entry.id)) {
+        matches.retain(|entry| !exclude.contains(&entry.id));
+    }
+    match seed {
+        Some(seed) => seeded_pick(&matches, hash_seed(seed)),
+        None => random_entry(&matches),
+    }
+    .cloned()
+}
+
+/// Up to `count` distinct random entries among the search's matches, in
+/// random order, ignoring its pagination
+pub fn shuffled_matches(data: &[RealBookEntry], params: &SearchQuery, count: usize) -> Vec<RealBookEntry> {
+    use rand::seq::SliceRandom;
+    let params = SearchQuery { offset: Param(None), limit: Param(None), ..params.clone() };
+    let mut rng = rand::thread_rng();
+    search_catalog(data, &params).results.choose_multiple(&mut rng, count).cloned().collect()
+}
+
+/// The song picked for a day (days since 1970-01-01): the same all day for
+/// everyone, and spread over the catalog from one day to the next
+pub fn daily_pick(data: &[RealBookEntry], day: i64) -> Option<&RealBookEntry> {
+    seeded_pick(data, day as u64)
+}
+
+/// Pick an entry determined by `seed` (None only if `data` is empty). Uses
+/// splitmix64 rather than `rand`'s generators, whose output may change between
+/// versions, so a seed keeps landing on the same tune across upgrades; nearby
+/// seeds land far apart.
+fn seeded_pick(data: &[RealBookEntry], seed: u64) -> Option<&RealBookEntry> {
+    if data.is_empty() {
+        return None;
+    }
+    let mut seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    seed = (seed ^ (seed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    seed = (seed ^ (seed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    seed ^= seed >> 31;
+    data.get((seed % data.len() as u64) as usize)
+}
+
+/// A seed as typed ("42", "tuesday-gig") turned into a number (64-bit FNV-1a)
+fn hash_seed(seed: &str) -> u64 {
+    seed.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Pick a uniformly random entry (None only if the catalog is empty)
+pub fn random_entry(data: &[RealBookEntry]) -> Option<&RealBookEntry> {
+    use rand::seq::SliceRandom;
+    let mut rng = rand::thread_rng();
+    data.choose(&mut rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+
+    fn entry(id: u32, volume: u32) -> RealBookEntry {
+        let entry = serde_json::json!({
+            "id": id,
+            "title": format!("Tune {}", id),
+            "volume": volume,
+            "page_s": id,
+            "page_e": id,
+        });
+        serde_json::from_value(entry).unwrap()
+    }
+
+    fn catalog() -> Vec<RealBookEntry> {
+        (1..=30).map(|id| entry(id, 1 + id % 3)).collect()
+    }
+
+    #[test]
+    fn daily_pick_is_stable_and_moves_day_to_day() {
+        let data = catalog();
+        assert_eq!(daily_pick(&data, 19_782), daily_pick(&data, 19_782));
+        let week: HashSet<u32> = (19_782..19_789).filter_map(|day| daily_pick(&data, day)).map(|entry| entry.id).collect();
+        assert!(week.len() > 1, "a week of picks should not all be the same tune");
+        assert!(daily_pick(&[], 19_782).is_none());
+    }
+
+    #[test]
+    fn daily_pick_is_pinned_across_versions() {
+        // splitmix64 of the day, so everyone keeps getting the same song
+        // for a date after an upgrade
+        let data = catalog();
+        let picks: Vec<u32> = (0..5).map(|day| daily_pick(&data, day).unwrap().id).collect();
+        assert_eq!(picks, [26, 6, 11, 4, 29]);
+    }
+
+    /// A search as its query string would give it
+    fn query(fields: &str) -> SearchQuery {
+        rocket::form::Form::parse(fields).unwrap()
+    }
+
+    #[test]
+    fn seeded_random_match_repeats_for_a_seed() {
+        let data = catalog();
+        let first = random_match(&data, &query(""), Some("tuesday-gig"), &[]).unwrap();
+        for _ in 0..5 {
+            assert_eq!(random_match(&data, &query(""), Some("tuesday-gig"), &[]).unwrap().id, first.id);
+        }
+        let seeds: HashSet<u32> = (0..20)
+            .filter_map(|seed| random_match(&data, &query(""), Some(&seed.to_string()), &[]))
+            .map(|entry| entry.id)
+            .collect();
+        assert!(seeds.len() > 1, "different seeds should land on different tunes");
+    }
+
+    #[test]
+    fn seeded_random_match_is_pinned_across_versions() {
+        let data = catalog();
+        let picks: Vec<u32> = ["42", "tuesday-gig", "Blue Monk"]
+            .iter()
+            .map(|seed| random_match(&data, &query(""), Some(seed), &[]).unwrap().id)
+            .collect();
+        assert_eq!(picks, [23, 10, 9]);
+    }
+
+    #[test]
+    fn seeded_random_match_keeps_to_the_filters() {
+        let data = catalog();
+        for seed in 0..20 {
+            let pick = random_match(&data, &query("volume=2&limit=1"), Some(&seed.to_string()), &[]).unwrap();
+            assert_eq!(pick.volume, 2);
+        }
+    }
+
+    #[test]
+    fn random_match_skips_excluded_songs_while_others_match() {
+        let data = catalog();
+        let excluded: Vec<u32> = data.iter().filter(|entry| entry.volume == 2 && entry.id != 4).map(|entry| entry.id).collect();
+        assert_eq!(random_match(&data, &query("volume=2"), None, &excluded).unwrap().id, 4);
+        // With every match excluded, one is still picked
+        let all: Vec<u32> = data.iter().map(|entry| entry.id).collect();
+        assert!(random_match(&data, &query("volume=2"), None, &all).is_some());
+    }
+
+    #[test]
+    fn catalog_version_follows_the_contents() {
+        let version = catalog_version(&catalog());
+        assert_eq!(version.len(), 16);
+        assert!(version.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(catalog_version(&catalog()), version);
+
+        let mut retitled = catalog();
+        retitled[3].title.push('!');
+        assert_ne!(catalog_version(&retitled), version);
+
+        let mut moved = catalog();
+        moved[3].page_e += 1;
+        assert_ne!(catalog_version(&moved), version);
+    }
+}
@@ -0,0 +1,67 @@
+//! Dataset changelog: human-readable notes on each catalog release
+//!
+//! Whenever realbook.json is edited (an accepted correction, a fixed page
+//! range), a release is added at the top of `api/resources/changelog.json`
+//! with the next version number, the date, and a line per change:
+//!
+//! ```json
+//! [{ "version": 2, "date": "2026-11-02", "changes": ["Fixed page range for Nardis"] }]
+//! ```
+//!
+//! `/changelog` serves them newest first, with the catalog's content hash, so
+//! the UI can tell readers what changed since their last visit. The file is
+//! checked at startup: versions must go down and dates must be real.
+
+use serde::{Deserialize, Serialize};
+use crate::calendar::Date;
+
+/// Where the changelog is read from at startup (a missing file means none)
+const CHANGELOG_PATH: &str = "api/resources/changelog.json";
+
+/// One catalog release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Release {
+    /// Increases by one each release
+    pub version: u32,
+    /// YYYY-MM-DD
+    pub date: String,
+    /// What changed, one line each
+    pub changes: Vec<String>,
+}
+
+/// The releases, newest first (managed Rocket state)
+pub struct Changelog {
+    releases: Vec<Release>,
+}
+
+impl Changelog {
+    pub fn load() -> Self {
+        let releases: Vec<Release> = match std::fs::read_to_string(CHANGELOG_PATH) {
+            Ok(data) => serde_json::from_str(&data).expect("Failed to parse changelog.json"),
+            Err(_) => Vec::new(),
+        };
+        for release in &releases {
+            assert!(Date::parse(&release.date).is_some(), "changelog.json: release {} has a bad date", release.version);
+        }
+        assert!(
+            releases.windows(2).all(|pair| pair[0].version > pair[1].version),
+            "changelog.json: releases must be listed newest (highest version) first"
+        );
+        Changelog { releases }
+    }
+
+    /// The newest release's version (None before the first)
+    pub fn latest(&self) -> Option<u32> {
+        self.releases.first().map(|release| release.version)
+    }
+
+    /// Releases after version `since` (all of them if None), newest first
+    pub fn since(&self, since: Option<u32>) -> Vec<Release> {
+        self.releases
+            .iter()
+            .filter(|release| since.is_none_or(|since| release.version > since))
+            .cloned()
+            .collect()
+    }
+}
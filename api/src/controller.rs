@@ -1,8 +1,28 @@
+use std::net::IpAddr;
 use std::path::Path;
 use std::sync::Arc;
-use rocket::{fs::NamedFile, State, serde::json::Json};
-use crate::models::{RealBookEntry, SearchResponse, VolumeInfo};
-use std::collections::HashMap;
+use rocket::{fs::NamedFile, State, form::Errors, http::{CookieJar, Status}, serde::json::Json};
+use rocket::data::{Data, ToByteUnit};
+use realbook_core::suggestions::completions;
+use webauthn_rs::prelude::{CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse};
+use crate::admin::Admin;
+use crate::csrf::Csrf;
+use crate::archive::{ARCHIVE_FORMAT, ARCHIVE_VERSION, ArchiveDownload, ImportStrategy, ImportSummary, MAX_ARCHIVE_BYTES, UserArchive, combine_recent, validate};
+use crate::corrections::{Correction, CorrectionRequest, CorrectionStatus, CorrectionStore, MAX_CORRECTION_BYTES, Review};
+use crate::attachments::{AttachmentStore, Attachments, MAX_MUSICXML_BYTES, MusicXmlDownload, MusicXmlFormat};
+use crate::errors::{ApiError, ErrorDetail};
+use crate::formats::{Negotiated, ResponseFormat};
+use crate::images::{CircuitState, DegradedReason, FetchError, ImageMirrors, SheetImage, UPSTREAM_STATUS_HEADER};
+use crate::page_reports::{MAX_PAGE_REPORT_BYTES, PageReportRequest, PageReportStore};
+use crate::pdf::{PdfDownload, book_pdf, file_stem, jpegs_to_pdf, text_page_pdf};
+use crate::models::{API_VERSION, CatalogSnapshot, ChangelogResponse, Param, CatalogStats, MAX_PAGE, LinkCode, RealBookEntry, SearchQuery, SearchResponse, SessionSummary, ReadyResponse, SongOfTheDay, StatusResponse, SuggestQuery, SuggestResponse, VOLUMES, ValidationReport, VolumeInfo, validate_random_options};
+use crate::calendar::Date;
+use crate::changelog::Changelog;
+use crate::catalog::{catalog_version, count_by_volume, daily_pick, find_entry, random_match, search_catalog, shuffled_matches};
+use crate::passkeys::{MAX_PASSKEY_BYTES, PasskeyStore, PasskeySummary};
+use crate::sessions::{AnonSession, LINK_TTL, MAX_VIEWED, SessionStore};
+use crate::sync::{MAX_SYNC_BYTES, SyncRequest, SyncResponse};
+use std::collections::HashSet;
 
 #[get("/")]
 pub fn index() -> &'static str {
@@ -18,62 +38,878 @@ pub async fn rickroll() -> Option<NamedFile> {
 /// Search endpoint with optional filters
 /// Query parameters:
 /// - query: text search in title (case-insensitive, partial match)
+/// - within: earlier queries to refine (repeatable); every term must match
 /// - volume: filter by volume number (1, 2, or 3)
 /// - page: filter by page number (returns entries containing this page)
-#[get("/search?<query>&<volume>&<page>")]
+/// - page_from / page_to: filter by page span (returns entries overlapping
+///   the span, in page order); either bound may be omitted
+/// - page_near / tolerance: approximate page (returns entries overlapping
+///   page_near ± tolerance, default 5, closest first)
+/// - offset / limit: return a window of the matches (`total` still counts all)
+///
+/// Invalid parameters (unknown volume, out-of-range page, over-long query)
+/// are rejected with a 400 describing each problem.
+///
+/// Responds with JSON, CSV (results only), or MessagePack depending on the
+/// `format` parameter or `Accept` header.
+#[get("/search?<params..>")]
 pub fn search(
     data: &State<Arc<Vec<RealBookEntry>>>,
-    query: Option<String>,
-    volume: Option<u32>,
-    page: Option<u32>,
-) -> Json<SearchResponse> {
-    let mut results: Vec<RealBookEntry> = data.iter().cloned().collect();
+    params: Result<SearchQuery, Errors<'_>>,
+    format: Result<ResponseFormat, ApiError>,
+) -> Result<Negotiated<SearchResponse>, ApiError> {
+    Ok(Negotiated::new(format?, search_catalog(data, &params?)))
+}
 
-    // Filter by text query
-    if let Some(q) = query
-        && !q.is_empty() {
-        results.retain(|entry| entry.matches(&q));
-    }
+/// Autocomplete: up to eight titles completing `query` (empty when it's blank),
+/// optionally within one `volume`, e.g. `/suggest?query=sta&volume=1`
+#[get("/suggest?<params..>")]
+pub fn suggest(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    params: Result<SuggestQuery, Errors<'_>>,
+) -> Result<Json<SuggestResponse>, ApiError> {
+    let SuggestQuery { query, volume: Param(volume) } = params?;
+    let suggestions = completions(
+        data.iter().filter(|entry| volume.is_none_or(|vol| entry.volume == vol)),
+        query.as_deref().unwrap_or_default(),
+    );
+    Ok(Json(SuggestResponse { api_version: API_VERSION, suggestions }))
+}
+
+/// Get a single entry by id
+#[get("/entries/<id>")]
+pub fn entry(data: &State<Arc<Vec<RealBookEntry>>>, id: u32) -> Result<Json<RealBookEntry>, ApiError> {
+    find_entry(data, id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))
+}
+
+/// Extras attached to an entry, such as a reference recording (`{}` if none)
+#[get("/entries/<id>/attachments")]
+pub fn entry_attachments(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    attachments: &State<AttachmentStore>,
+    id: u32,
+) -> Result<Json<Attachments>, ApiError> {
+    find_entry(data, id)
+        .map(|_| Json(attachments.get(id)))
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))
+}
 
-    // Filter by volume
-    if let Some(vol) = volume {
-        results.retain(|entry| entry.volume == vol);
+/// An entry's uploaded MusicXML as a download, named after the song
+#[get("/entries/<id>/musicxml")]
+pub async fn entry_musicxml(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    attachments: &State<AttachmentStore>,
+    id: u32,
+) -> Result<MusicXmlDownload, ApiError> {
+    let entry = find_entry(data, id)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))?;
+    let (format, bytes) = attachments
+        .musicxml(id)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to read MusicXML: {}", e)))?
+        .ok_or_else(|| ApiError::new(Status::NotFound, "musicxml_not_found", format!("No MusicXML uploaded for entry {}", id)))?;
+    Ok(MusicXmlDownload { filename: file_stem(&entry.title), format, bytes })
+}
+
+/// Upload (or replace) an entry's MusicXML, plain (`.musicxml`) or compressed
+/// (`.mxl`), as the raw request body; answers with the entry's attachments
+#[put("/admin/entries/<id>/musicxml", data = "<body>")]
+pub async fn upload_musicxml(
+    admin: Result<Admin, ApiError>,
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    attachments: &State<AttachmentStore>,
+    id: u32,
+    body: Data<'_>,
+) -> Result<Json<Attachments>, ApiError> {
+    admin?;
+    find_entry(data, id)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))?;
+
+    let bytes = body
+        .open(MAX_MUSICXML_BYTES.bytes())
+        .into_bytes()
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to read upload: {}", e)))?;
+    if !bytes.is_complete() {
+        return Err(ApiError::new(
+            Status::PayloadTooLarge,
+            "payload_too_large",
+            format!("MusicXML uploads are limited to {} MiB", MAX_MUSICXML_BYTES / (1024 * 1024)),
+        ));
     }
+    let format = MusicXmlFormat::detect(&bytes).ok_or_else(|| {
+        ApiError::new(
+            Status::UnsupportedMediaType,
+            "unsupported_media_type",
+            "The upload is not a MusicXML file (.musicxml or .mxl)",
+        )
+    })?;
 
-    // Filter by page (entry must contain this page)
-    if let Some(p) = page {
-        results.retain(|entry| entry.page_s <= p && p <= entry.page_e);
+    attachments
+        .save_musicxml(id, format, &bytes)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to store MusicXML: {}", e)))?;
+    Ok(Json(attachments.get(id)))
+}
+
+/// Delete an entry's uploaded MusicXML; answers with the entry's attachments
+#[delete("/admin/entries/<id>/musicxml")]
+pub async fn delete_musicxml(
+    admin: Result<Admin, ApiError>,
+    attachments: &State<AttachmentStore>,
+    id: u32,
+) -> Result<Json<Attachments>, ApiError> {
+    admin?;
+    let removed = attachments
+        .remove_musicxml(id)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to delete MusicXML: {}", e)))?;
+    if !removed {
+        return Err(ApiError::new(Status::NotFound, "musicxml_not_found", format!("No MusicXML uploaded for entry {}", id)));
     }
+    Ok(Json(attachments.get(id)))
+}
 
-    let total = results.len();
+/// Report a mistake in an entry (a misspelled title, a wrong page range) with
+/// the fix proposed (the JSON body); it waits in the moderation queue
+#[post("/entries/<id>/corrections", data = "<body>")]
+pub async fn submit_correction(
+    csrf: Result<Csrf, ApiError>,
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    corrections: &State<CorrectionStore>,
+    session: Result<AnonSession, ApiError>,
+    client: Option<IpAddr>,
+    id: u32,
+    body: Data<'_>,
+) -> Result<(Status, Json<Correction>), ApiError> {
+    csrf?;
+    let session = session?;
+    let entry = find_entry(data, id)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))?;
+    let request: CorrectionRequest = read_json(body, MAX_CORRECTION_BYTES, "correction").await?;
+    let correction = corrections.submit(session.id(), client, entry, request).await?;
+    Ok((Status::Created, Json(correction)))
+}
 
-    Json(SearchResponse { results, total })
+/// The moderation queue: reported corrections with `status` (default
+/// pending; `all` for every one), oldest first
+#[get("/admin/corrections?<status>")]
+pub async fn correction_queue(
+    admin: Result<Admin, ApiError>,
+    corrections: &State<CorrectionStore>,
+    status: Option<&str>,
+) -> Result<Json<Vec<Correction>>, ApiError> {
+    admin?;
+    let status = match status.unwrap_or("pending") {
+        "all" => None,
+        value => Some(CorrectionStatus::from_value(value).ok_or_else(|| {
+            ApiError::new(
+                Status::BadRequest,
+                "invalid_parameters",
+                format!("Unknown status \"{}\" (pending, accepted, rejected, or all)", value),
+            )
+        })?),
+    };
+    Ok(Json(corrections.list(status).await))
 }
 
-/// List all volumes with entry counts
-#[get("/volumes")]
-pub fn volumes(data: &State<Arc<Vec<RealBookEntry>>>) -> Json<Vec<VolumeInfo>> {
-    let mut volume_counts: HashMap<u32, usize> = HashMap::new();
+/// Accept or reject a reported correction (the JSON body is `{ "status": ... }`);
+/// accepted fixes are then made in realbook.json
+#[put("/admin/corrections/<id>", data = "<body>")]
+pub async fn review_correction(
+    admin: Result<Admin, ApiError>,
+    corrections: &State<CorrectionStore>,
+    id: u64,
+    body: Data<'_>,
+) -> Result<Json<Correction>, ApiError> {
+    admin?;
+    let review: Review = read_json(body, MAX_CORRECTION_BYTES, "review").await?;
+    corrections
+        .review(id, review)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "correction_not_found", format!("No correction with id {}", id)))
+}
+
+/// Proxy a sheet music page image, failing over across the configured mirrors
+#[get("/images/<volume>/<page>")]
+pub async fn image(mirrors: &State<ImageMirrors>, volume: u32, page: u32) -> Result<SheetImage, ApiError> {
+    check_page_exists(volume, page)?;
+    mirrors.fetch(volume, page).await.map(SheetImage).map_err(upstream_unavailable)
+}
+
+/// A tiny version of a page image, shown blurred while the full page loads
+#[get("/images/<volume>/<page>/thumb")]
+pub async fn image_thumbnail(mirrors: &State<ImageMirrors>, volume: u32, page: u32) -> Result<SheetImage, ApiError> {
+    check_page_exists(volume, page)?;
+    mirrors.fetch_thumbnail(volume, page).await.map(SheetImage).map_err(upstream_unavailable)
+}
+
+/// Report a sheet image that doesn't load or shows a bad scan (the JSON body
+/// is `{ "problem": "missing" | "bad_scan" }`); reporting again is a no-op
+#[post("/images/<volume>/<page>/reports", data = "<body>")]
+pub async fn report_page(
+    csrf: Result<Csrf, ApiError>,
+    reports: &State<PageReportStore>,
+    session: Result<AnonSession, ApiError>,
+    client: Option<IpAddr>,
+    volume: u32,
+    page: u32,
+    body: Data<'_>,
+) -> Result<Status, ApiError> {
+    csrf?;
+    let session = session?;
+    check_page_exists(volume, page)?;
+    let request: PageReportRequest = read_json(body, MAX_PAGE_REPORT_BYTES, "page report").await?;
+    reports.report(session.id(), client, volume, page, request.problem).await?;
+    Ok(Status::NoContent)
+}
 
-    for entry in data.iter() {
-        *volume_counts.entry(entry.volume).or_insert(0) += 1;
+/// Catalog problems readers have reported: broken pages (with the songs on
+/// them), most reported first, and how many corrections await moderation
+#[get("/admin/validation")]
+pub async fn validation(
+    admin: Result<Admin, ApiError>,
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    reports: &State<PageReportStore>,
+    corrections: &State<CorrectionStore>,
+) -> Result<Json<ValidationReport>, ApiError> {
+    admin?;
+    let mut broken_pages = reports.broken_pages().await;
+    for broken in &mut broken_pages {
+        broken.songs = data
+            .iter()
+            .filter(|entry| entry.volume == broken.volume && (entry.page_s..=entry.page_e).contains(&broken.page))
+            .map(|entry| entry.title.clone())
+            .collect();
+    }
+    Ok(Json(ValidationReport { api_version: API_VERSION, broken_pages, pending_corrections: corrections.pending().await }))
+}
+
+/// Clear a page's broken-image reports once its scan is fixed
+#[delete("/admin/page-reports/<volume>/<page>")]
+pub async fn clear_page_reports(
+    admin: Result<Admin, ApiError>,
+    reports: &State<PageReportStore>,
+    volume: u32,
+    page: u32,
+) -> Result<Status, ApiError> {
+    admin?;
+    if !reports.clear(volume, page).await? {
+        return Err(ApiError::new(
+            Status::NotFound,
+            "page_reports_not_found",
+            format!("No reports for page {} in volume {}", page, volume),
+        ));
     }
+    Ok(Status::NoContent)
+}
+
+/// 404 for a page outside the volumes' page range
+fn check_page_exists(volume: u32, page: u32) -> Result<(), ApiError> {
+    if !VOLUMES.contains(&volume) || !(1..=MAX_PAGE).contains(&page) {
+        return Err(ApiError::new(
+            Status::NotFound,
+            "image_not_found",
+            format!("No page {} in volume {}", page, volume),
+        ));
+    }
+    Ok(())
+}
 
-    let mut volumes: Vec<VolumeInfo> = volume_counts
+/// 502 for a page no image mirror could serve, with one detail per mirror
+/// tried; 503 for one never cached while the circuit breaker is open, marked
+/// degraded like the stale pages served meanwhile
+fn upstream_unavailable(error: FetchError) -> ApiError {
+    let errors = match error {
+        FetchError::Unavailable(errors) => errors,
+        FetchError::CircuitOpen { retry_after } => {
+            return ApiError::new(
+                Status::ServiceUnavailable,
+                "upstream_degraded",
+                format!("The image mirrors are failing and this page isn't cached; try again in {} seconds", retry_after),
+            )
+            .with_header(UPSTREAM_STATUS_HEADER, format!("degraded; reason={}", DegradedReason::CircuitOpen.as_str()))
+            .with_header("Retry-After", retry_after.to_string());
+        }
+    };
+    let mut error = ApiError::new(
+        Status::BadGateway,
+        "upstream_unavailable",
+        "No image mirror could serve this page",
+    );
+    error.body.details = errors
         .into_iter()
-        .map(|(volume, count)| VolumeInfo { volume, count })
+        .map(|message| ErrorDetail { field: None, message })
         .collect();
+    error
+}
+
+/// All pages of an entry as one PDF download, named after the song
+#[get("/entries/<id>/pdf")]
+pub async fn entry_pdf(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    mirrors: &State<ImageMirrors>,
+    id: u32,
+) -> Result<PdfDownload, ApiError> {
+    let entry = find_entry(data, id)
+        .cloned()
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))?;
+
+    let mut pages = Vec::new();
+    for page in entry.page_s..=entry.page_e {
+        pages.push(mirrors.fetch(entry.volume, page).await.map_err(upstream_unavailable)?.bytes);
+    }
+
+    let bytes = jpegs_to_pdf(&pages).map_err(ApiError::internal)?;
+    Ok(PdfDownload { filename: format!("{}.pdf", file_stem(&entry.title)), bytes })
+}
+
+/// Longest setlist the printable index and gig book accept
+const MAX_SETLIST_SONGS: usize = 100;
+
+/// Catalog entries for a setlist's comma-separated `ids`, in order
+fn setlist_entries(data: &[RealBookEntry], ids: &str) -> Result<Vec<RealBookEntry>, ApiError> {
+    let ids: Vec<u32> = ids
+        .split(',')
+        .map(|id| id.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| ApiError::new(Status::BadRequest, "invalid_parameters", "ids must be a comma-separated list of entry ids"))?;
+    if ids.len() > MAX_SETLIST_SONGS {
+        return Err(ApiError::new(
+            Status::BadRequest,
+            "invalid_parameters",
+            format!("A setlist index holds at most {} songs", MAX_SETLIST_SONGS),
+        ));
+    }
+
+    ids.iter()
+        .map(|id| {
+            find_entry(data, *id)
+                .cloned()
+                .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))
+        })
+        .collect()
+}
+
+/// A setlist's index line, e.g. "3. STELLA BY STARLIGHT  -  Vol. 1, p. 432"
+fn index_line(position: usize, entry: &RealBookEntry) -> String {
+    let pages = if entry.page_count() == 1 {
+        format!("p. {}", entry.page_s)
+    } else {
+        format!("pp. {}-{}", entry.page_s, entry.page_e)
+    };
+    format!("{}. {}  -  Vol. {}, {}", position + 1, entry.title, entry.volume, pages)
+}
+
+/// The setlist's name, or "Setlist" when it has none
+fn setlist_heading(name: Option<&str>) -> &str {
+    name.map(str::trim).filter(|name| !name.is_empty()).unwrap_or("Setlist")
+}
+
+/// Printable one-page index of a setlist (title, volume, and pages of each song),
+/// e.g. `/setlists/index.pdf?name=Friday&ids=12,40,7`
+#[get("/setlists/index.pdf?<name>&<ids>")]
+pub fn setlist_index_pdf(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    name: Option<&str>,
+    ids: &str,
+) -> Result<PdfDownload, ApiError> {
+    let entries = setlist_entries(data, ids)?;
+    let lines: Vec<String> = entries.iter().enumerate().map(|(position, entry)| index_line(position, entry)).collect();
+
+    let name = setlist_heading(name);
+    Ok(PdfDownload { filename: format!("{}.pdf", file_stem(name)), bytes: text_page_pdf(name, &lines) })
+}
+
+/// Most scanned pages a gig book may hold (a long set of two-page tunes is
+/// well under this; each page is fetched through the mirrors)
+const MAX_BOOK_PAGES: u32 = 300;
+
+/// A whole setlist as one PDF for printing: the index page, then every page of
+/// every song in order, e.g. `/setlists/book.pdf?name=Friday&ids=12,40,7`.
+/// Index lines also give the page of the book each song starts on.
+#[get("/setlists/book.pdf?<name>&<ids>")]
+pub async fn setlist_book_pdf(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    mirrors: &State<ImageMirrors>,
+    name: Option<&str>,
+    ids: &str,
+) -> Result<PdfDownload, ApiError> {
+    let entries = setlist_entries(data, ids)?;
+    let page_total: u32 = entries.iter().map(RealBookEntry::page_count).sum();
+    if page_total > MAX_BOOK_PAGES {
+        return Err(ApiError::new(
+            Status::BadRequest,
+            "invalid_parameters",
+            format!("A gig book holds at most {} pages ({} requested)", MAX_BOOK_PAGES, page_total),
+        ));
+    }
+
+    // The index is page 1 of the book
+    let mut lines = Vec::new();
+    let mut book_page = 2;
+    for (position, entry) in entries.iter().enumerate() {
+        lines.push(format!("{}  (page {})", index_line(position, entry), book_page));
+        book_page += entry.page_count();
+    }
+
+    let mut pages = Vec::new();
+    for entry in &entries {
+        for page in entry.page_s..=entry.page_e {
+            pages.push(mirrors.fetch(entry.volume, page).await.map_err(upstream_unavailable)?.bytes);
+        }
+    }
+
+    let name = setlist_heading(name);
+    let bytes = book_pdf(name, &lines, &pages).map_err(ApiError::internal)?;
+    Ok(PdfDownload { filename: format!("{}_book.pdf", file_stem(name)), bytes })
+}
+
+/// Service health: catalog size and image mirror status
+#[get("/status")]
+pub fn status(data: &State<Arc<Vec<RealBookEntry>>>, mirrors: &State<ImageMirrors>) -> Json<StatusResponse> {
+    let image_mirrors = mirrors.status();
+    let all_healthy = image_mirrors.iter().all(|mirror| mirror.healthy);
+
+    Json(StatusResponse {
+        api_version: API_VERSION,
+        status: if all_healthy { "ok" } else { "degraded" },
+        entries: data.len(),
+        image_mirrors,
+    })
+}
+
+/// Readiness: the catalog is loaded and the image proxy can serve pages,
+/// though from its cache only while the circuit breaker is open ("degraded";
+/// still 200, since the mirrors are shared by every instance)
+#[get("/ready")]
+pub fn ready(mirrors: &State<ImageMirrors>) -> Json<ReadyResponse> {
+    let image_proxy = mirrors.proxy_status();
+    Json(ReadyResponse {
+        api_version: API_VERSION,
+        status: if image_proxy.circuit == CircuitState::Closed { "ready" } else { "degraded" },
+        image_proxy,
+    })
+}
 
-    volumes.sort_by_key(|v| v.volume);
+/// List all volumes with entry counts (JSON, CSV, or MessagePack)
+#[get("/volumes")]
+pub fn volumes(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    format: Result<ResponseFormat, ApiError>,
+) -> Result<Negotiated<Vec<VolumeInfo>>, ApiError> {
+    Ok(Negotiated::new(format?, count_by_volume(data)))
+}
+
+/// The full catalog, for clients that cache it and search offline
+/// (JSON, CSV, or MessagePack)
+#[get("/catalog")]
+pub fn full_catalog(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    format: Result<ResponseFormat, ApiError>,
+) -> Result<Negotiated<CatalogSnapshot>, ApiError> {
+    Ok(Negotiated::new(format?, CatalogSnapshot {
+        api_version: API_VERSION,
+        version: catalog_version(data),
+        entries: data.to_vec(),
+    }))
+}
+
+/// What changed in the catalog: releases after version `since` (all if not
+/// given), newest first, with the latest version and the catalog's content hash
+#[get("/changelog?<since>")]
+pub fn catalog_changelog(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    changelog: &State<Changelog>,
+    since: Option<u32>,
+) -> Json<ChangelogResponse> {
+    Json(ChangelogResponse {
+        api_version: API_VERSION,
+        catalog_version: catalog_version(data),
+        latest: changelog.latest(),
+        releases: changelog.since(since),
+    })
+}
+
+/// Catalog statistics: song counts, page totals, and chart length extremes
+#[get("/stats")]
+pub fn stats(data: &State<Arc<Vec<RealBookEntry>>>) -> Json<CatalogStats> {
+    let total_songs = data.len();
+
+    // Distinct (volume, page) pairs - several short charts can share a page
+    let total_pages = data
+        .iter()
+        .flat_map(|entry| (entry.page_s..=entry.page_e).map(move |page| (entry.volume, page)))
+        .collect::<HashSet<_>>()
+        .len();
+
+    let chart_pages: u32 = data.iter().map(|entry| entry.page_count()).sum();
+    let average_pages_per_song = if total_songs > 0 {
+        chart_pages as f64 / total_songs as f64
+    } else {
+        0.0
+    };
+
+    let longest_chart = data.iter().max_by_key(|entry| entry.page_count()).cloned();
+    let shortest_chart = data.iter().min_by_key(|entry| entry.page_count()).cloned();
+
+    Json(CatalogStats {
+        api_version: API_VERSION,
+        total_songs,
+        songs_per_volume: count_by_volume(data),
+        total_pages,
+        average_pages_per_song,
+        longest_chart,
+        shortest_chart,
+    })
+}
+
+/// Get a random Real Book entry, optionally one matching the search filters,
+/// e.g. `/random?volume=2` for a tune from Volume 2. With `seed` (any text, like
+/// `seed=tuesday-gig`) the pick is deterministic: everyone asking with the same
+/// seed and filters lands on the same tune. `exclude` is a comma-separated
+/// list of entry ids to pass over, e.g. the last few picks so repeated presses
+/// don't keep landing on the same songs; they're only picked again once every
+/// other match is excluded too. Unseeded picks also pass over the session's
+/// recent picks, and are remembered in it (if the caller has a session; none
+/// is started here).
+#[get("/random?<seed>&<exclude>&<params..>")]
+pub fn random(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+    seed: Option<&str>,
+    exclude: Option<&str>,
+    params: Result<SearchQuery, Errors<'_>>,
+) -> Result<Json<RealBookEntry>, ApiError> {
+    let mut exclude: Vec<u32> = exclude
+        .filter(|ids| !ids.trim().is_empty())
+        .map(|ids| ids.split(',').map(|id| id.trim().parse()).collect::<Result<_, _>>())
+        .transpose()
+        .map_err(|_| ApiError::new(Status::BadRequest, "invalid_parameters", "exclude must be a comma-separated list of entry ids"))?
+        .unwrap_or_default();
+    validate_random_options(seed, &exclude)?;
+    // A seeded pick has to come out the same for everyone sharing the seed
+    let session = sessions.current(cookies).filter(|_| seed.is_none());
+    if let Some(session) = &session {
+        exclude.extend(sessions.randoms(session));
+    }
+    let entry = random_match(data, &params?, seed, &exclude)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "no_match", "No songs match these filters"))?;
+    if let Some(session) = &session {
+        sessions.record_random(session, entry.id);
+    }
+    Ok(Json(entry))
+}
+
+/// Entries for session-remembered ids, skipping any no longer in the catalog
+fn session_entries(data: &[RealBookEntry], ids: &[u32]) -> Vec<RealBookEntry> {
+    ids.iter().filter_map(|id| find_entry(data, *id).cloned()).collect()
+}
+
+/// What `session` remembers (nothing without one)
+fn session_summary(data: &[RealBookEntry], sessions: &SessionStore, session: Option<&AnonSession>) -> SessionSummary {
+    let Some(session) = session else {
+        return SessionSummary { recently_viewed: Vec::new(), recent_randoms: Vec::new() };
+    };
+    SessionSummary {
+        recently_viewed: session_entries(data, &sessions.viewed(session)),
+        recent_randoms: session_entries(data, &sessions.randoms(session)),
+    }
+}
+
+/// What the caller's anonymous session remembers (see `sessions.rs`)
+#[get("/session")]
+pub fn session(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+) -> Json<SessionSummary> {
+    Json(session_summary(data, sessions, sessions.current(cookies).as_ref()))
+}
+
+/// Note that the caller opened an entry; answers with the updated session
+#[post("/session/viewed/<id>")]
+pub fn record_view(
+    csrf: Result<Csrf, ApiError>,
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    sessions: &State<SessionStore>,
+    session: Result<AnonSession, ApiError>,
+    id: u32,
+) -> Result<Json<SessionSummary>, ApiError> {
+    csrf?;
+    let session = session?;
+    find_entry(data, id)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "entry_not_found", format!("No entry with id {}", id)))?;
+    sessions.record_view(&session, id);
+    Ok(Json(session_summary(data, sessions, Some(&session))))
+}
+
+/// Download the caller's data held on the server as a user data archive
+/// (see `archive.rs`; the UI adds the sections kept in the browser)
+#[get("/me/export")]
+pub fn export_archive(sessions: &State<SessionStore>, cookies: &CookieJar<'_>) -> ArchiveDownload {
+    ArchiveDownload::new(UserArchive {
+        format: ARCHIVE_FORMAT,
+        version: ARCHIVE_VERSION,
+        exported_at: Date::today().to_string(),
+        recently_viewed: sessions.current(cookies).map(|session| sessions.viewed(&session)).unwrap_or_default(),
+        sections: Default::default(),
+    })
+}
+
+/// Read a JSON request body of at most `limit` bytes (`what` names it in errors)
+async fn read_json<T: serde::de::DeserializeOwned>(body: Data<'_>, limit: usize, what: &str) -> Result<T, ApiError> {
+    let bytes = body
+        .open(limit.bytes())
+        .into_bytes()
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to read upload: {}", e)))?;
+    if !bytes.is_complete() {
+        return Err(ApiError::new(
+            Status::PayloadTooLarge,
+            "payload_too_large",
+            format!("The {} is limited to {} MiB", what, limit / (1024 * 1024)),
+        ));
+    }
+    serde_json::from_slice(&bytes)
+        .map_err(|e| ApiError::new(Status::BadRequest, "invalid_json", format!("The {} isn't valid: {}", what, e)))
+}
+
+/// Check an exported archive (the JSON request body) and merge its
+/// server-held part into the caller's session, by `strategy` (merge,
+/// overwrite, or replace; default merge). Answers with what it found, so the
+/// UI can merge the browser sections the same way.
+#[post("/me/import?<strategy>", data = "<body>")]
+pub async fn import_archive(
+    csrf: Result<Csrf, ApiError>,
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    sessions: &State<SessionStore>,
+    session: Result<AnonSession, ApiError>,
+    strategy: Option<&str>,
+    body: Data<'_>,
+) -> Result<Json<ImportSummary>, ApiError> {
+    csrf?;
+    let session = session?;
+    let strategy = match strategy {
+        Some(text) => ImportStrategy::parse(text).ok_or_else(|| {
+            ApiError::new(Status::BadRequest, "invalid_parameters", "strategy must be merge, overwrite, or replace")
+        })?,
+        None => ImportStrategy::default(),
+    };
+    let archive: serde_json::Value = read_json(body, MAX_ARCHIVE_BYTES, "archive").await?;
+    let (version, incoming, sections) = validate(&archive)?;
+
+    let (known, unknown_entries): (Vec<u32>, Vec<u32>) =
+        incoming.into_iter().partition(|id| find_entry(data, *id).is_some());
+    let viewed = combine_recent(&sessions.viewed(&session), &known, strategy, MAX_VIEWED);
+    let recently_viewed = viewed.len();
+    sessions.set_viewed(&session, viewed);
+
+    Ok(Json(ImportSummary { strategy, version, sections, recently_viewed, unknown_entries }))
+}
+
+/// Exchange changed records of the browser's local stores with the other
+/// devices on the session (see `sync.rs`); the body is
+/// `{ cursor, epoch, device, changes }`
+#[post("/sync", data = "<body>")]
+pub async fn sync_records(
+    csrf: Result<Csrf, ApiError>,
+    sessions: &State<SessionStore>,
+    session: Result<AnonSession, ApiError>,
+    body: Data<'_>,
+) -> Result<Json<SyncResponse>, ApiError> {
+    csrf?;
+    let session = session?;
+    let request: SyncRequest = read_json(body, MAX_SYNC_BYTES, "sync request").await?;
+    request.validate()?;
+    sessions.with_sync(&session, |log, elsewhere| log.exchange(request, elsewhere)).map(Json)
+}
+
+/// A code another device can use to join the caller's session, valid for ten minutes
+#[post("/session/link")]
+pub fn link_session(
+    csrf: Result<Csrf, ApiError>,
+    sessions: &State<SessionStore>,
+    session: Result<AnonSession, ApiError>,
+) -> Result<Json<LinkCode>, ApiError> {
+    csrf?;
+    let session = session?;
+    Ok(Json(LinkCode { code: sessions.link_code(&session), expires_in: LINK_TTL.as_secs() }))
+}
 
-    Json(volumes)
+/// Join the session a link code was made for (this browser's own session is
+/// left behind)
+#[post("/session/join/<code>")]
+pub fn join_session(
+    csrf: Result<Csrf, ApiError>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+    code: &str,
+) -> Result<Status, ApiError> {
+    csrf?;
+    if sessions.join(code, cookies) {
+        Ok(Status::NoContent)
+    } else {
+        Err(ApiError::new(Status::NotFound, "link_not_found", "That code is unknown or has expired"))
+    }
+}
+
+/// Start adding a passkey named `name` (e.g. "Dana's phone") to the caller's
+/// session; answers with the options for `navigator.credentials.create`
+#[post("/passkeys/challenge?<name>")]
+pub async fn begin_passkey_registration(
+    csrf: Result<Csrf, ApiError>,
+    passkeys: &State<PasskeyStore>,
+    session: Result<AnonSession, ApiError>,
+    name: Option<&str>,
+) -> Result<Json<CreationChallengeResponse>, ApiError> {
+    csrf?;
+    let session = session?;
+    passkeys.begin_registration(session.id(), name.unwrap_or_default()).await.map(Json)
+}
+
+/// Finish adding a passkey: check the authenticator's response (the JSON
+/// body) and keep the passkey for the caller's session
+#[post("/passkeys", data = "<body>")]
+pub async fn register_passkey(
+    csrf: Result<Csrf, ApiError>,
+    passkeys: &State<PasskeyStore>,
+    session: Result<AnonSession, ApiError>,
+    body: Data<'_>,
+) -> Result<(Status, Json<PasskeySummary>), ApiError> {
+    csrf?;
+    let session = session?;
+    let credential: RegisterPublicKeyCredential = read_json(body, MAX_PASSKEY_BYTES, "passkey registration").await?;
+    let passkey = passkeys.finish_registration(session.id(), credential).await?;
+    Ok((Status::Created, Json(passkey)))
+}
+
+/// The passkeys that sign in to the caller's session
+#[get("/passkeys")]
+pub async fn list_passkeys(
+    passkeys: &State<PasskeyStore>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+) -> Json<Vec<PasskeySummary>> {
+    match sessions.current(cookies) {
+        Some(session) => Json(passkeys.list(session.id()).await),
+        None => Json(Vec::new()),
+    }
 }
 
-/// Get a random Real Book entry
-#[get("/random")]
-pub fn random(data: &State<Arc<Vec<RealBookEntry>>>) -> Json<RealBookEntry> {
-    use rand::seq::SliceRandom;
-    let mut rng = rand::thread_rng();
-    let entry = data.choose(&mut rng).unwrap().clone();
-    Json(entry)
+/// Remove one of the caller's passkeys (it no longer signs in)
+#[delete("/passkeys/<id>")]
+pub async fn delete_passkey(
+    csrf: Result<Csrf, ApiError>,
+    passkeys: &State<PasskeyStore>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+    id: &str,
+) -> Result<Status, ApiError> {
+    csrf?;
+    let removed = match sessions.current(cookies) {
+        Some(session) => passkeys.remove(session.id(), id).await?,
+        None => false,
+    };
+    if removed {
+        Ok(Status::NoContent)
+    } else {
+        Err(ApiError::new(Status::NotFound, "passkey_not_found", "This session has no such passkey"))
+    }
+}
+
+/// Start signing in with a passkey; answers with the options for
+/// `navigator.credentials.get`
+#[post("/session/passkey/challenge")]
+pub fn begin_passkey_sign_in(
+    csrf: Result<Csrf, ApiError>,
+    passkeys: &State<PasskeyStore>,
+) -> Result<Json<RequestChallengeResponse>, ApiError> {
+    csrf?;
+    passkeys.begin_sign_in().map(Json)
+}
+
+/// Finish signing in: check the signed challenge (the JSON body) and point
+/// the caller's cookie at the passkey's session; answers with the passkey used
+#[post("/session/passkey", data = "<body>")]
+pub async fn passkey_sign_in(
+    csrf: Result<Csrf, ApiError>,
+    passkeys: &State<PasskeyStore>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+    body: Data<'_>,
+) -> Result<Json<PasskeySummary>, ApiError> {
+    csrf?;
+    let credential: PublicKeyCredential = read_json(body, MAX_PASSKEY_BYTES, "passkey sign-in").await?;
+    let (account, passkey) = passkeys.finish_sign_in(credential).await?;
+    sessions.resume(&account, cookies);
+    Ok(Json(passkey))
+}
+
+/// Sign out: drop the caller's cookie but keep the session for the other
+/// devices on it (and its passkeys)
+#[post("/session/sign-out")]
+pub fn sign_out(csrf: Result<Csrf, ApiError>, sessions: &State<SessionStore>, cookies: &CookieJar<'_>) -> Result<Status, ApiError> {
+    csrf?;
+    sessions.leave(cookies);
+    Ok(Status::NoContent)
+}
+
+/// Forget the caller's anonymous session and clear its cookie
+#[delete("/session")]
+pub fn forget_session(
+    csrf: Result<Csrf, ApiError>,
+    sessions: &State<SessionStore>,
+    cookies: &CookieJar<'_>,
+) -> Result<Status, ApiError> {
+    csrf?;
+    sessions.forget(cookies);
+    Ok(Status::NoContent)
+}
+
+/// The song of the day: the same pick all day for everyone, changing at
+/// midnight UTC. `date=YYYY-MM-DD` asks for another day's (clients pass their
+/// local date, so the song changes at their own midnight).
+#[get("/song-of-the-day?<date>")]
+pub fn song_of_the_day(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    date: Option<&str>,
+) -> Result<Json<SongOfTheDay>, ApiError> {
+    let date = match date {
+        Some(text) => Date::parse(text).ok_or_else(|| {
+            ApiError::new(Status::BadRequest, "invalid_parameters", "date must be a calendar date as YYYY-MM-DD")
+        })?,
+        None => Date::today(),
+    };
+    let entry = daily_pick(data, date.days())
+        .ok_or_else(|| ApiError::new(Status::NotFound, "no_match", "The catalog is empty"))?;
+    Ok(Json(SongOfTheDay { date: date.to_string(), entry: entry.clone() }))
+}
+
+/// Most songs `/shuffle` deals at once
+const MAX_SHUFFLE: usize = 20;
+
+/// Deal `count` different random songs (default 5), optionally among those
+/// matching the search filters, e.g. `/shuffle?count=5&volume=1`; fewer come
+/// back when fewer match
+#[get("/shuffle?<count>&<params..>")]
+pub fn shuffle(
+    data: &State<Arc<Vec<RealBookEntry>>>,
+    count: Result<Param<usize>, Errors<'_>>,
+    params: Result<SearchQuery, Errors<'_>>,
+) -> Result<Json<Vec<RealBookEntry>>, ApiError> {
+    let count = count?.0.unwrap_or(5);
+    if !(1..=MAX_SHUFFLE).contains(&count) {
+        return Err(ApiError::new(
+            Status::BadRequest,
+            "invalid_parameters",
+            format!("count must be between 1 and {}", MAX_SHUFFLE),
+        ));
+    }
+    Ok(Json(shuffled_matches(data, &params?, count)))
 }
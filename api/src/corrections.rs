@@ -0,0 +1,389 @@
+//! Crowdsourced corrections to the catalog
+//!
+//! Anyone reading a chart can report that its title is misspelled or its page
+//! range is off, with the fix they propose (`POST /entries/<id>/corrections`).
+//! Reports wait in a moderation queue that admins list and then accept or
+//! reject (`/admin/corrections`). Accepting only records the decision: the
+//! catalog is loaded once from realbook.json, so an accepted fix is applied by
+//! editing that file, with the queue as the to-do list.
+//!
+//! Each report keeps the entry as it was when reported next to the proposal,
+//! so a moderator can see what it would change even after the file is edited.
+//! Reports are kept on disk (`api/resources/corrections.json`) so the queue
+//! survives restarts. A report identical to one already pending is refused, a
+//! session can only have a few pending at once, and each client address can
+//! only send so many an hour (a new session is just a cleared cookie away, a
+//! new address isn't). None of that stops someone with many addresses; the cap
+//! on the whole queue bounds what they can do, at the cost of other readers'
+//! reports being refused until moderators catch up.
+
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use rocket::http::Status;
+use rocket::tokio::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::calendar::Date;
+use crate::errors::ApiError;
+use crate::models::{MAX_PAGE, RealBookEntry};
+use crate::rate_limit::RateLimiter;
+
+/// Where reports are kept (a missing file means none)
+const CORRECTIONS_PATH: &str = "api/resources/corrections.json";
+
+/// Reports one session can have pending at once
+const MAX_PENDING_PER_SESSION: usize = 20;
+
+/// Reports pending at once across everyone; past this, new ones are refused
+const MAX_PENDING: usize = 5_000;
+
+/// Reports one client address can send an hour (a venue's wifi is one address)
+const MAX_PER_CLIENT_HOUR: usize = 30;
+
+/// Longest proposed title, in characters
+const MAX_TITLE_CHARS: usize = 200;
+
+/// Longest comment, in characters
+const MAX_COMMENT_CHARS: usize = 1_000;
+
+/// Largest report body read
+pub const MAX_CORRECTION_BYTES: usize = 16 * 1024;
+
+/// What a report proposes; fields left out stay as they are
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Proposal {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_s: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_e: Option<u32>,
+}
+
+/// A report as submitted (the request body)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorrectionRequest {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub page_s: Option<u32>,
+    #[serde(default)]
+    pub page_e: Option<u32>,
+    /// Why, or anything else the moderator should know
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Where a report stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrectionStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl CorrectionStatus {
+    pub fn from_value(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(CorrectionStatus::Pending),
+            "accepted" => Some(CorrectionStatus::Accepted),
+            "rejected" => Some(CorrectionStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// The entry's fields a report can change, as they were when it was made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntrySnapshot {
+    pub title: String,
+    pub volume: u32,
+    pub page_s: u32,
+    pub page_e: u32,
+}
+
+/// A report in the moderation queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Correction {
+    pub id: u64,
+    pub entry_id: u32,
+    pub current: EntrySnapshot,
+    pub proposed: Proposal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub status: CorrectionStatus,
+    /// YYYY-MM-DD (UTC)
+    pub submitted_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<String>,
+    /// Session that sent it (never shown to clients)
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    session: String,
+}
+
+/// A moderator's decision (the admin request body)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Review {
+    pub status: CorrectionStatus,
+}
+
+fn invalid(message: impl Into<String>) -> ApiError {
+    ApiError::new(Status::UnprocessableEntity, "invalid_correction", message)
+}
+
+impl CorrectionRequest {
+    /// Tidy the report and check it proposes a real change to `entry`
+    fn normalize(self, entry: &RealBookEntry) -> Result<(Proposal, Option<String>), ApiError> {
+        let title = self.title.map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "));
+        if let Some(title) = &title {
+            if title.is_empty() {
+                return Err(invalid("The proposed title is blank"));
+            }
+            if title.chars().count() > MAX_TITLE_CHARS {
+                return Err(invalid(format!("The proposed title is longer than {} characters", MAX_TITLE_CHARS)));
+            }
+        }
+        let page_s = self.page_s.unwrap_or(entry.page_s);
+        let page_e = self.page_e.unwrap_or(entry.page_e);
+        for page in [page_s, page_e] {
+            if !(1..=MAX_PAGE).contains(&page) {
+                return Err(invalid(format!("Pages must be between 1 and {}", MAX_PAGE)));
+            }
+        }
+        if page_s > page_e {
+            return Err(invalid(format!("The song can't start on page {} and end on page {}", page_s, page_e)));
+        }
+        let comment = self.comment.map(|comment| comment.trim().to_string()).filter(|comment| !comment.is_empty());
+        if comment.as_ref().is_some_and(|comment| comment.chars().count() > MAX_COMMENT_CHARS) {
+            return Err(invalid(format!("The comment is longer than {} characters", MAX_COMMENT_CHARS)));
+        }
+
+        // Keep only what would change
+        let proposal = Proposal {
+            title: title.filter(|title| *title != entry.title),
+            page_s: (page_s != entry.page_s).then_some(page_s),
+            page_e: (page_e != entry.page_e).then_some(page_e),
+        };
+        if proposal == Proposal::default() {
+            return Err(invalid("The report doesn't change anything about the entry"));
+        }
+        Ok((proposal, comment))
+    }
+}
+
+/// The moderation queue (managed Rocket state)
+///
+/// The queue's lock is async and held while it's saved, so saves happen one
+/// at a time, in order, without blocking a worker thread.
+pub struct CorrectionStore {
+    corrections: Mutex<Vec<Correction>>,
+    limiter: RateLimiter,
+    path: PathBuf,
+}
+
+impl CorrectionStore {
+    pub fn load() -> Self {
+        Self::open(CORRECTIONS_PATH)
+    }
+
+    /// The queue kept in the file at `path`
+    fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let corrections = match std::fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).expect("Failed to parse corrections.json"),
+            Err(_) => Vec::new(),
+        };
+        CorrectionStore {
+            corrections: Mutex::new(corrections),
+            limiter: RateLimiter::new(MAX_PER_CLIENT_HOUR, Duration::from_secs(60 * 60)),
+            path,
+        }
+    }
+
+    async fn save(&self, corrections: &[Correction]) -> Result<(), ApiError> {
+        let json = serde_json::to_string_pretty(corrections).map_err(|e| ApiError::internal(e.to_string()))?;
+        // Write then rename, so a failed write never leaves a truncated file
+        let partial = self.path.with_extension("json.part");
+        let result = match rocket::tokio::fs::write(&partial, json).await {
+            Ok(()) => rocket::tokio::fs::rename(&partial, &self.path).await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| ApiError::internal(format!("Failed to save corrections: {}", e)))
+    }
+
+    /// Queue a report on `entry` from `session`, sent from `client`
+    pub async fn submit(
+        &self,
+        session: &str,
+        client: Option<IpAddr>,
+        entry: &RealBookEntry,
+        request: CorrectionRequest,
+    ) -> Result<Correction, ApiError> {
+        let (proposed, comment) = request.normalize(entry)?;
+        let mut corrections = self.corrections.lock().await;
+        let pending: Vec<&Correction> =
+            corrections.iter().filter(|correction| correction.status == CorrectionStatus::Pending).collect();
+        if pending.iter().any(|correction| correction.entry_id == entry.id && correction.proposed == proposed) {
+            return Err(ApiError::new(
+                Status::Conflict,
+                "duplicate_correction",
+                "The same correction is already waiting for review",
+            ));
+        }
+        if pending.len() >= MAX_PENDING
+            || pending.iter().filter(|correction| correction.session == session).count() >= MAX_PENDING_PER_SESSION
+        {
+            return Err(ApiError::new(
+                Status::TooManyRequests,
+                "too_many_corrections",
+                "Too many corrections are waiting for review; try again once they've been looked at",
+            ));
+        }
+        if !self.limiter.allow(client) {
+            return Err(ApiError::new(
+                Status::TooManyRequests,
+                "too_many_corrections",
+                "Too many corrections have been sent from your network lately; try again in an hour",
+            ));
+        }
+
+        let correction = Correction {
+            id: corrections.iter().map(|correction| correction.id).max().unwrap_or(0) + 1,
+            entry_id: entry.id,
+            current: EntrySnapshot {
+                title: entry.title.clone(),
+                volume: entry.volume,
+                page_s: entry.page_s,
+                page_e: entry.page_e,
+            },
+            proposed,
+            comment,
+            status: CorrectionStatus::Pending,
+            submitted_at: Date::today().to_string(),
+            reviewed_at: None,
+            session: session.to_string(),
+        };
+        corrections.push(correction.clone());
+        if let Err(error) = self.save(&corrections).await {
+            corrections.pop();
+            return Err(error);
+        }
+        Ok(Correction { session: String::new(), ..correction })
+    }
+
+    /// Reports with `status` (all of them if None), oldest first
+    pub async fn list(&self, status: Option<CorrectionStatus>) -> Vec<Correction> {
+        self.corrections
+            .lock()
+            .await
+            .iter()
+            .filter(|correction| status.is_none_or(|status| correction.status == status))
+            .map(|correction| Correction { session: String::new(), ..correction.clone() })
+            .collect()
+    }
+
+    /// How many reports wait for a decision
+    pub async fn pending(&self) -> usize {
+        self.corrections.lock().await.iter().filter(|correction| correction.status == CorrectionStatus::Pending).count()
+    }
+
+    /// Record a moderator's decision on report `id` (None if there's no such report)
+    pub async fn review(&self, id: u64, review: Review) -> Result<Option<Correction>, ApiError> {
+        let mut corrections = self.corrections.lock().await;
+        let Some(index) = corrections.iter().position(|correction| correction.id == id) else {
+            return Ok(None);
+        };
+        let previous = corrections[index].clone();
+        let correction = &mut corrections[index];
+        correction.status = review.status;
+        correction.reviewed_at = (review.status != CorrectionStatus::Pending).then(|| Date::today().to_string());
+        let correction = Correction { session: String::new(), ..correction.clone() };
+        if let Err(error) = self.save(&corrections).await {
+            corrections[index] = previous;
+            return Err(error);
+        }
+        Ok(Some(correction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use super::*;
+
+    fn entry() -> RealBookEntry {
+        RealBookEntry { id: 42, title: "Solar".to_string(), volume: 1, page_s: 10, page_e: 11 }
+    }
+
+    fn retitle(title: &str) -> CorrectionRequest {
+        CorrectionRequest { title: Some(title.to_string()), page_s: None, page_e: None, comment: None }
+    }
+
+    fn client(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, last)))
+    }
+
+    fn temp_store() -> (CorrectionStore, PathBuf) {
+        let path = std::env::temp_dir().join(format!("realbook-corrections-{:016x}.json", rand::random::<u64>()));
+        (CorrectionStore::open(&path), path)
+    }
+
+    #[rocket::async_test]
+    async fn the_queue_survives_a_restart() {
+        let (store, path) = temp_store();
+        let correction = store.submit("a", client(1), &entry(), retitle("Solar Flare")).await.unwrap();
+        store.review(correction.id, Review { status: CorrectionStatus::Accepted }).await.unwrap();
+        store.submit("a", client(1), &entry(), retitle("Solaris")).await.unwrap();
+
+        let reopened = CorrectionStore::open(&path);
+        let listed = reopened.list(None).await;
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].status, CorrectionStatus::Accepted);
+        assert!(listed[0].reviewed_at.is_some());
+        assert!(listed.iter().all(|correction| correction.session.is_empty()), "sessions aren't handed out");
+        // The pending report is still known, and still counts as a duplicate
+        let duplicate = reopened.submit("b", client(2), &entry(), retitle("Solaris")).await.unwrap_err();
+        assert_eq!(duplicate.status, Status::Conflict);
+        assert_eq!(reopened.submit("b", client(2), &entry(), retitle("Solar 2")).await.unwrap().id, 3);
+
+        assert!(!path.with_extension("json.part").exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn each_address_is_limited_per_hour() {
+        let (store, path) = temp_store();
+        for n in 0..MAX_PER_CLIENT_HOUR {
+            // A new session every time, as with a cleared cookie
+            let session = format!("s{}", n);
+            store.submit(&session, client(1), &entry(), retitle(&format!("Solar {}", n))).await.unwrap();
+        }
+        let refused = store.submit("fresh", client(1), &entry(), retitle("Solar again")).await.unwrap_err();
+        assert_eq!(refused.status, Status::TooManyRequests);
+        assert!(refused.body.message.contains("network"));
+        assert_eq!(store.pending().await, MAX_PER_CLIENT_HOUR, "refused reports aren't queued");
+
+        assert!(store.submit("fresh", client(2), &entry(), retitle("Solar again")).await.is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn each_session_is_limited_in_what_it_has_pending() {
+        let (store, path) = temp_store();
+        for n in 0..MAX_PENDING_PER_SESSION {
+            let n = u8::try_from(n).unwrap();
+            store.submit("a", client(n), &entry(), retitle(&format!("Solar {}", n))).await.unwrap();
+        }
+        let refused = store.submit("a", client(200), &entry(), retitle("Solar again")).await.unwrap_err();
+        assert_eq!(refused.status, Status::TooManyRequests);
+        assert_eq!(refused.body.code, "too_many_corrections");
+
+        // Once one is reviewed there's room again
+        store.review(1, Review { status: CorrectionStatus::Rejected }).await.unwrap();
+        assert!(store.submit("a", client(200), &entry(), retitle("Solar again")).await.is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
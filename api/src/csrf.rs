@@ -0,0 +1,141 @@
+//! Cross-site request forgery protection for the cookie-authenticated routes
+//!
+//! The session cookie (`sessions.rs`) rides along with any request the
+//! browser makes to the API, including one a hostile page triggers with a
+//! form post. SameSite=Lax already keeps it off cross-site POSTs in current
+//! browsers; on top of that every state-changing route takes the `Csrf` guard,
+//! a double-submit check: the API hands out a random token in the
+//! `realbook_csrf` cookie (readable by scripts, SameSite=Strict), and a
+//! mutating request must repeat it in the `X-CSRF-Token` header. Another site
+//! can make the browser send the cookie but can't read it, so it can't forge
+//! the header. This includes the routes that sign in (joining with a link
+//! code, passkeys), so a hostile page can't sign a browser in to an
+//! attacker's session and collect what it syncs.
+//!
+//! Any response to a request without the cookie sets one (see `issue_tokens`),
+//! so a client's first GET gets it; a rejected mutating request does too, so
+//! the client can just repeat it. Admin routes authenticate with a bearer
+//! token, which browsers never attach on their own, so they don't need this.
+
+use rocket::fairing::AdHoc;
+use rocket::http::{Cookie, SameSite, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use crate::admin::constant_time_eq;
+use crate::errors::ApiError;
+
+/// Cookie carrying the token
+pub const CSRF_COOKIE: &str = "realbook_csrf";
+
+/// Header a mutating request repeats the token in
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Request guard for state-changing routes; take it as `Result<Csrf,
+/// ApiError>` and `?` it, like `Admin`
+pub struct Csrf;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Csrf {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ApiError> {
+        let expected = request.cookies().get(CSRF_COOKIE).map(|cookie| cookie.value().to_string());
+        let presented = request.headers().get_one(CSRF_HEADER);
+        match (expected, presented) {
+            (Some(expected), Some(presented)) if constant_time_eq(expected.as_bytes(), presented.as_bytes()) => {
+                Outcome::Success(Csrf)
+            }
+            _ => Outcome::Error((
+                Status::Forbidden,
+                ApiError::new(
+                    Status::Forbidden,
+                    "csrf_failed",
+                    format!("Repeat the {} cookie's value in the {} header", CSRF_COOKIE, CSRF_HEADER),
+                ),
+            )),
+        }
+    }
+}
+
+/// Give every client without a token one (Secure when `secure`, i.e. served
+/// over https)
+pub fn issue_tokens(secure: bool) -> AdHoc {
+    AdHoc::on_response("CSRF Tokens", move |request, response| Box::pin(async move {
+        if request.cookies().get(CSRF_COOKIE).is_some() {
+            return;
+        }
+        let token = format!("{:032x}", rand::random::<u128>());
+        response.adjoin_header(
+            Cookie::build((CSRF_COOKIE, token))
+                .path("/")
+                .same_site(SameSite::Strict)
+                .secure(secure)
+                .build(),
+        );
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::{Cookie, Header, Status};
+    use rocket::local::blocking::{Client, LocalRequest};
+    use super::*;
+
+    #[rocket::post("/")]
+    fn guarded(csrf: Result<Csrf, ApiError>) -> Result<&'static str, ApiError> {
+        csrf?;
+        Ok("done")
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build().mount("/", rocket::routes![guarded]).attach(issue_tokens(false));
+        Client::untracked(rocket).unwrap()
+    }
+
+    fn with_cookie<'c>(request: LocalRequest<'c>, token: &str) -> LocalRequest<'c> {
+        request.cookie(Cookie::new(CSRF_COOKIE, token.to_string()))
+    }
+
+    fn with_header<'c>(request: LocalRequest<'c>, token: &str) -> LocalRequest<'c> {
+        request.header(Header::new(CSRF_HEADER, token.to_string()))
+    }
+
+    /// Status and error code (if any) a request gets
+    fn outcome(request: LocalRequest<'_>) -> (Status, Option<String>) {
+        let response = request.dispatch();
+        let status = response.status();
+        let body: serde_json::Value = response.into_json().unwrap_or_default();
+        (status, body["code"].as_str().map(str::to_string))
+    }
+
+    #[test]
+    fn a_matching_cookie_and_header_pass() {
+        let client = client();
+        let request = with_header(with_cookie(client.post("/"), "0123abcd"), "0123abcd");
+        assert_eq!(outcome(request), (Status::Ok, None));
+    }
+
+    #[test]
+    fn anything_else_is_refused() {
+        let client = client();
+        let refused = (Status::Forbidden, Some("csrf_failed".to_string()));
+        // No cookie: a page that never read one
+        assert_eq!(outcome(with_header(client.post("/"), "0123abcd")), refused);
+        // No header: a plain form post from another site
+        assert_eq!(outcome(with_cookie(client.post("/"), "0123abcd")), refused);
+        // A header that isn't the cookie's value
+        assert_eq!(outcome(with_header(with_cookie(client.post("/"), "0123abcd"), "0123abce")), refused);
+        assert_eq!(outcome(with_header(with_cookie(client.post("/"), "0123abcd"), "")), refused);
+    }
+
+    #[test]
+    fn a_token_is_issued_only_to_clients_without_one() {
+        let client = client();
+        let response = client.post("/").dispatch();
+        let cookie = response.cookies().get(CSRF_COOKIE).expect("a refused request still gets a token");
+        assert_eq!(cookie.value().len(), 32);
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+
+        let response = with_cookie(client.post("/"), "0123abcd").dispatch();
+        assert!(response.cookies().get(CSRF_COOKIE).is_none());
+    }
+}
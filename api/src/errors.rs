@@ -0,0 +1,132 @@
+use rocket::http::{Header, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::form;
+use serde::Serialize;
+use crate::models::API_VERSION;
+
+/// Extra information about one part of a failed request
+#[derive(Debug, Serialize)]
+pub struct ErrorDetail {
+    /// Name of the offending parameter, if the problem is tied to one
+    pub field: Option<String>,
+    /// Human-readable reason
+    pub message: String,
+}
+
+/// Machine-readable error body returned by every route and catcher
+#[derive(Debug, Serialize)]
+pub struct ApiErrorResponse {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Stable identifier clients can branch on (e.g. "invalid_parameters")
+    pub code: String,
+    /// Human-readable summary suitable for display
+    pub message: String,
+    /// Per-field or per-cause details (empty when there is nothing to add)
+    pub details: Vec<ErrorDetail>,
+}
+
+/// Error returned from route handlers, rendered as an `ApiErrorResponse`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub body: ApiErrorResponse,
+    /// Extra response headers (e.g. `Retry-After`)
+    pub headers: Vec<Header<'static>>,
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: &str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            body: ApiErrorResponse {
+                api_version: API_VERSION,
+                code: code.to_string(),
+                message: message.into(),
+                details: Vec::new(),
+            },
+            headers: Vec::new(),
+        }
+    }
+
+    /// Send `name: value` along with the error
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push(Header::new(name, value.into()));
+        self
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::new(Status::InternalServerError, "internal_error", message)
+    }
+}
+
+/// Rejected query parameters become a 400 listing every problem
+impl From<form::Errors<'_>> for ApiError {
+    fn from(errors: form::Errors<'_>) -> Self {
+        let mut error = ApiError::new(
+            Status::BadRequest,
+            "invalid_parameters",
+            "One or more query parameters are invalid",
+        );
+        error.body.details = errors
+            .iter()
+            .map(|e| ErrorDetail {
+                field: e.name.as_ref().map(|name| name.to_string()),
+                message: e.kind.to_string(),
+            })
+            .collect();
+        error
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = (self.status, Json(self.body)).respond_to(request)?;
+        for header in self.headers {
+            response.set_header(header);
+        }
+        Ok(response)
+    }
+}
+
+/// Build the error body for a status produced outside a handler (catchers)
+fn catcher_body(status: Status, code: &str, message: String) -> Json<ApiErrorResponse> {
+    Json(ApiError::new(status, code, message).body)
+}
+
+#[catch(404)]
+pub fn not_found(request: &Request) -> Json<ApiErrorResponse> {
+    catcher_body(
+        Status::NotFound,
+        "not_found",
+        format!("No resource at {}", request.uri().path()),
+    )
+}
+
+#[catch(422)]
+pub fn unprocessable_entity() -> Json<ApiErrorResponse> {
+    catcher_body(
+        Status::UnprocessableEntity,
+        "unprocessable_entity",
+        "The request was well-formed but could not be processed".to_string(),
+    )
+}
+
+#[catch(500)]
+pub fn internal_error() -> Json<ApiErrorResponse> {
+    catcher_body(
+        Status::InternalServerError,
+        "internal_error",
+        "The server encountered an internal error".to_string(),
+    )
+}
+
+/// Fallback for every other status (e.g. 400 from malformed requests)
+#[catch(default)]
+pub fn default_catcher(status: Status, _request: &Request) -> Json<ApiErrorResponse> {
+    let reason = status.reason().unwrap_or("Unknown error");
+    let code = reason.to_lowercase().replace([' ', '-'], "_");
+    catcher_body(status, &code, reason.to_string())
+}
@@ -0,0 +1,251 @@
+use rocket::http::{ContentType, Header, MediaType, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use serde::Serialize;
+use std::io::Cursor;
+use crate::errors::{ApiError, ErrorDetail};
+use crate::models::{CatalogSnapshot, SearchResponse, VolumeInfo};
+
+/// Serialization format for a negotiated response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Csv,
+    MsgPack,
+}
+
+impl ResponseFormat {
+    /// Parse the value of the `format` query parameter
+    fn from_param(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Some(ResponseFormat::Json),
+            "csv" => Some(ResponseFormat::Csv),
+            "msgpack" | "messagepack" => Some(ResponseFormat::MsgPack),
+            _ => None,
+        }
+    }
+
+    /// Map an `Accept` media type to a format (`application/x-msgpack` is
+    /// still common in the wild, so it's accepted alongside the IANA name)
+    fn from_media_type(media_type: &MediaType) -> Option<Self> {
+        if media_type.is_json() {
+            Some(ResponseFormat::Json)
+        } else if media_type.is_csv() {
+            Some(ResponseFormat::Csv)
+        } else if media_type.is_msgpack()
+            || (media_type.top() == "application" && media_type.sub() == "x-msgpack")
+        {
+            Some(ResponseFormat::MsgPack)
+        } else {
+            None
+        }
+    }
+}
+
+/// Values the `format` query parameter accepts, as listed in error messages
+const FORMAT_NAMES: &str = "json, csv, msgpack";
+
+/// 400 for a `format=` value that isn't one of the supported formats
+fn unknown_format(value: &str) -> ApiError {
+    let mut error = ApiError::new(
+        Status::BadRequest,
+        "invalid_parameters",
+        "One or more query parameters are invalid",
+    );
+    error.body.details.push(ErrorDetail {
+        field: Some("format".to_string()),
+        message: format!("unknown format '{}'; expected one of: {}", value, FORMAT_NAMES),
+    });
+    error
+}
+
+/// Picks the response format: an explicit `format=` query parameter wins,
+/// then the highest-weighted supported `Accept` type, then JSON
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ResponseFormat {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, ApiError> {
+        if let Some(Ok(value)) = request.query_value::<&str>("format") {
+            return match ResponseFormat::from_param(value) {
+                Some(format) => request::Outcome::Success(format),
+                None => request::Outcome::Error((Status::BadRequest, unknown_format(value))),
+            };
+        }
+
+        let mut accepted: Vec<_> = request
+            .accept()
+            .map(|accept| accept.iter().collect())
+            .unwrap_or_default();
+        // Stable sort keeps header order among equal weights
+        accepted.sort_by(|a, b| b.weight_or(1.0).total_cmp(&a.weight_or(1.0)));
+
+        let format = accepted
+            .iter()
+            .find_map(|qmedia| ResponseFormat::from_media_type(qmedia.media_type()))
+            .unwrap_or(ResponseFormat::Json);
+
+        request::Outcome::Success(format)
+    }
+}
+
+/// Responses that can be flattened into CSV rows
+pub trait CsvRows {
+    type Row: Serialize;
+
+    fn csv_rows(&self) -> &[Self::Row];
+}
+
+impl CsvRows for SearchResponse {
+    type Row = crate::models::RealBookEntry;
+
+    fn csv_rows(&self) -> &[Self::Row] {
+        &self.results
+    }
+}
+
+impl CsvRows for CatalogSnapshot {
+    type Row = crate::models::RealBookEntry;
+
+    fn csv_rows(&self) -> &[Self::Row] {
+        &self.entries
+    }
+}
+
+impl CsvRows for Vec<VolumeInfo> {
+    type Row = VolumeInfo;
+
+    fn csv_rows(&self) -> &[Self::Row] {
+        self
+    }
+}
+
+/// A response body serialized in the format the client asked for
+pub struct Negotiated<T> {
+    pub format: ResponseFormat,
+    pub value: T,
+}
+
+impl<T> Negotiated<T> {
+    pub fn new(format: ResponseFormat, value: T) -> Self {
+        Negotiated { format, value }
+    }
+}
+
+fn to_csv<T: CsvRows>(value: &T) -> Result<Vec<u8>, ApiError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in value.csv_rows() {
+        writer
+            .serialize(row)
+            .map_err(|e| ApiError::internal(format!("CSV encoding failed: {}", e)))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| ApiError::internal(format!("CSV encoding failed: {}", e)))
+}
+
+impl<'r, T: Serialize + CsvRows> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let (content_type, body) = match self.format {
+            ResponseFormat::Json => {
+                let mut response = Json(self.value).respond_to(request)?;
+                response.set_header(Header::new("Vary", "Accept"));
+                return Ok(response);
+            }
+            ResponseFormat::Csv => match to_csv(&self.value) {
+                Ok(body) => (ContentType::CSV, body),
+                Err(error) => return error.respond_to(request),
+            },
+            ResponseFormat::MsgPack => match rmp_serde::to_vec_named(&self.value) {
+                Ok(body) => (ContentType::MsgPack, body),
+                Err(e) => {
+                    return ApiError::internal(format!("MessagePack encoding failed: {}", e))
+                        .respond_to(request);
+                }
+            },
+        };
+
+        Response::build()
+            .header(content_type)
+            .raw_header("Vary", "Accept")
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::Accept;
+    use rocket::local::blocking::{Client, LocalResponse};
+
+    #[rocket::get("/volumes")]
+    fn volumes(format: ResponseFormat) -> Negotiated<Vec<VolumeInfo>> {
+        Negotiated::new(format, vec![VolumeInfo { volume: 1, count: 2 }, VolumeInfo { volume: 3, count: 4 }])
+    }
+
+    fn client() -> Client {
+        Client::untracked(rocket::build().mount("/", rocket::routes![volumes])).unwrap()
+    }
+
+    fn accepting<'c>(client: &'c Client, accept: &str) -> LocalResponse<'c> {
+        client.get("/volumes").header(accept.parse::<Accept>().unwrap()).dispatch()
+    }
+
+    fn format_of(response: &LocalResponse) -> Option<ResponseFormat> {
+        ResponseFormat::from_media_type(response.content_type()?.media_type())
+    }
+
+    #[test]
+    fn the_format_parameter_wins() {
+        let client = client();
+        let response = client.get("/volumes?format=CSV").header(Accept::JSON).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(format_of(&response), Some(ResponseFormat::Csv));
+        assert_eq!(response.into_string().unwrap(), "volume,count\n1,2\n3,4\n");
+
+        let response = client.get("/volumes?format=messagepack").dispatch();
+        assert_eq!(format_of(&response), Some(ResponseFormat::MsgPack));
+        assert_eq!(client.get("/volumes?format=xml").dispatch().status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn accept_picks_the_heaviest_supported_type() {
+        let client = client();
+        assert_eq!(format_of(&accepting(&client, "text/csv")), Some(ResponseFormat::Csv));
+        assert_eq!(
+            format_of(&accepting(&client, "application/json; q=0.5, application/msgpack; q=0.9")),
+            Some(ResponseFormat::MsgPack)
+        );
+        assert_eq!(format_of(&accepting(&client, "application/x-msgpack")), Some(ResponseFormat::MsgPack));
+        // Equal weights keep header order
+        assert_eq!(format_of(&accepting(&client, "text/csv, application/json")), Some(ResponseFormat::Csv));
+        // Unsupported types are skipped, even when they weigh more
+        assert_eq!(format_of(&accepting(&client, "text/html, text/csv; q=0.1")), Some(ResponseFormat::Csv));
+    }
+
+    #[test]
+    fn anything_else_gets_json() {
+        let client = client();
+        for accept in [None, Some("text/html"), Some("*/*"), Some("image/png; q=1, text/plain; q=0.5")] {
+            let request = client.get("/volumes");
+            let request = match accept {
+                Some(accept) => request.header(accept.parse::<Accept>().unwrap()),
+                None => request,
+            };
+            let response = request.dispatch();
+            assert_eq!(format_of(&response), Some(ResponseFormat::Json), "Accept: {:?}", accept);
+            assert_eq!(response.headers().get_one("Vary"), Some("Accept"));
+        }
+    }
+
+    #[test]
+    fn msgpack_keeps_field_names() {
+        let client = client();
+        let response = client.get("/volumes?format=msgpack").dispatch();
+        assert_eq!(response.headers().get_one("Vary"), Some("Accept"));
+        let body: serde_json::Value = rmp_serde::from_slice(&response.into_bytes().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!([{ "volume": 1, "count": 2 }, { "volume": 3, "count": 4 }]));
+    }
+}
@@ -0,0 +1,107 @@
+//! gRPC service exposing the catalog (enabled with the `grpc` feature)
+//!
+//! Shares search, lookup, and random selection with the REST routes via
+//! `crate::catalog`, so both APIs return the same results.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use crate::catalog::{find_entry, random_match, search_catalog};
+use crate::models::{Param, RealBookEntry, SearchQuery, validate_random_options};
+
+pub mod proto {
+    tonic::include_proto!("realbook.v1");
+}
+
+use proto::real_book_server::{RealBook, RealBookServer};
+
+impl From<&RealBookEntry> for proto::Entry {
+    fn from(entry: &RealBookEntry) -> Self {
+        proto::Entry {
+            id: entry.id,
+            title: entry.title.clone(),
+            volume: entry.volume,
+            page_s: entry.page_s,
+            page_e: entry.page_e,
+        }
+    }
+}
+
+impl From<proto::SearchRequest> for SearchQuery {
+    fn from(request: proto::SearchRequest) -> Self {
+        SearchQuery {
+            query: request.query,
+            within: request.within,
+            initial: request.initial,
+            volume: Param(request.volume),
+            page: Param(request.page),
+            page_from: Param(request.page_from),
+            page_to: Param(request.page_to),
+            page_near: Param(request.page_near),
+            tolerance: Param(request.tolerance),
+            offset: Param(request.offset.map(|o| o as usize)),
+            limit: Param(request.limit.map(|l| l as usize)),
+        }
+    }
+}
+
+/// Catalog service backed by the same in-memory data as the REST API
+pub struct RealBookService {
+    data: Arc<Vec<RealBookEntry>>,
+}
+
+#[tonic::async_trait]
+impl RealBook for RealBookService {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchReply>, Status> {
+        let params = SearchQuery::from(request.into_inner());
+        params.validate().map_err(|errors| Status::invalid_argument(errors.to_string()))?;
+
+        let response = search_catalog(&self.data, &params);
+        Ok(Response::new(proto::SearchReply {
+            results: response.results.iter().map(proto::Entry::from).collect(),
+            total: response.total as u64,
+            suggestions: response.suggestions,
+            volume_counts: response
+                .volume_counts
+                .iter()
+                .map(|info| proto::VolumeCount { volume: info.volume, count: info.count as u64 })
+                .collect(),
+        }))
+    }
+
+    async fn get_entry(
+        &self,
+        request: Request<proto::GetEntryRequest>,
+    ) -> Result<Response<proto::Entry>, Status> {
+        let id = request.into_inner().id;
+        find_entry(&self.data, id)
+            .map(|entry| Response::new(entry.into()))
+            .ok_or_else(|| Status::not_found(format!("No entry with id {}", id)))
+    }
+
+    async fn random(
+        &self,
+        request: Request<proto::RandomRequest>,
+    ) -> Result<Response<proto::Entry>, Status> {
+        let request = request.into_inner();
+        let params = SearchQuery::from(request.filter.unwrap_or_default());
+        params.validate().map_err(|errors| Status::invalid_argument(errors.to_string()))?;
+        validate_random_options(request.seed.as_deref(), &request.exclude)
+            .map_err(|errors| Status::invalid_argument(errors.to_string()))?;
+
+        random_match(&self.data, &params, request.seed.as_deref(), &request.exclude)
+            .map(|entry| Response::new((&entry).into()))
+            .ok_or_else(|| Status::not_found("No songs match these filters"))
+    }
+}
+
+/// Serve the gRPC API until the process exits
+pub async fn serve(data: Arc<Vec<RealBookEntry>>, address: SocketAddr) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(RealBookServer::new(RealBookService { data }))
+        .serve(address)
+        .await
+}
@@ -0,0 +1,645 @@
+//! Sheet image proxy with failover across a prioritized list of mirrors
+//!
+//! Each mirror serves the same `{volume * 1000 + page}.jpeg` layout. Mirrors
+//! are tried in priority order; one that fails repeatedly is skipped for a
+//! cooldown period (but still tried as a last resort) so a dead primary
+//! doesn't add a timeout to every page load.
+//!
+//! Pages fetched are also kept on disk (`image_cache_dir`), and a page cached
+//! in the last day is served from there without asking the mirrors. When every
+//! mirror has failed several requests in a row, a circuit breaker stops asking
+//! them for a while: pages come from the cache however old, marked with an
+//! `X-Upstream-Status: degraded` header, and pages never cached fail at once
+//! rather than after a timeout per mirror. Once the wait is over, one request
+//! is let through to try the mirrors again; if it succeeds the breaker closes,
+//! if not it waits again. `/ready` shows where the breaker stands.
+//!
+//! Tiny thumbnails of each page are cut from the same scans and kept in
+//! memory, so the viewer can show a blurred preview while the full page loads.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use rocket::http::{ContentType, Header};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use image::codecs::jpeg::JpegEncoder;
+use serde::Serialize;
+
+/// Mirrors used when none are configured (`image_mirrors` in Rocket config)
+pub const DEFAULT_IMAGE_MIRRORS: &[&str] = &["https://wypn9z41ir5bzmgjjalyna.on.drv.tw/realbook/rendered"];
+
+/// Consecutive failures after which a mirror is considered unhealthy
+const FAILURE_THRESHOLD: u32 = 3;
+
+/// How long an unhealthy mirror is moved to the back of the queue
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Where fetched pages are kept when none is configured (`image_cache_dir`;
+/// an empty value turns the cache off)
+pub const DEFAULT_IMAGE_CACHE_DIR: &str = "api/resources/image_cache";
+
+/// Age up to which a cached page is served without asking the mirrors
+const CACHE_FRESH_FOR: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Requests in a row that every mirror failed, after which the breaker opens
+const BREAKER_THRESHOLD: u32 = 5;
+
+/// How long an open breaker keeps requests away from the mirrors
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// A trial request that hasn't finished in this long is assumed lost (its
+/// client went away), so another may try
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Per-request timeout when fetching from a mirror
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long browsers may cache a proxied page (scans never change)
+const CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// A stale page served in place of the mirrors mustn't outlive the outage in
+/// the browser's cache
+const DEGRADED_CACHE_CONTROL: &str = "no-store";
+
+/// Width of a page thumbnail in pixels (it's shown blurred, so detail is wasted)
+const THUMBNAIL_WIDTH: u32 = 48;
+
+/// JPEG quality of a page thumbnail
+const THUMBNAIL_QUALITY: u8 = 50;
+
+#[derive(Debug, Default)]
+struct MirrorState {
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    last_failure: Option<Instant>,
+    last_success: Option<Instant>,
+}
+
+impl MirrorState {
+    fn is_cooling_down(&self) -> bool {
+        self.consecutive_failures >= FAILURE_THRESHOLD
+            && self.last_failure.is_some_and(|at| at.elapsed() < COOLDOWN)
+    }
+}
+
+/// Where the circuit breaker stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests go to the mirrors
+    Closed,
+    /// Requests are served from the cache only
+    Open,
+    /// The wait is over; one request is trying the mirrors
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    /// Requests in a row that every mirror failed
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open breaker let a trial request through
+    probe_started: Option<Instant>,
+}
+
+impl Breaker {
+    fn state(&self) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(at) if at.elapsed() < BREAKER_COOLDOWN => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether a request may go to the mirrors (half-open lets one through)
+    fn allow(&mut self) -> bool {
+        match self.state() {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if self.probe_started.is_some_and(|at| at.elapsed() < PROBE_TIMEOUT) {
+                    return false;
+                }
+                self.probe_started = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    fn record_success(&mut self) {
+        *self = Breaker::default();
+    }
+
+    /// A failed trial reopens the breaker straight away
+    fn record_failure(&mut self) {
+        self.consecutive_failures += 1;
+        self.probe_started = None;
+        if self.opened_at.is_some() || self.consecutive_failures >= BREAKER_THRESHOLD {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Seconds until an open breaker lets a trial request through
+    fn seconds_until_retry(&self) -> Option<u64> {
+        match self.state() {
+            CircuitState::Open => self.opened_at.map(|at| BREAKER_COOLDOWN.saturating_sub(at.elapsed()).as_secs() + 1),
+            _ => None,
+        }
+    }
+}
+
+/// Why a page came from the cache though it may be out of date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DegradedReason {
+    /// The breaker is open, so the mirrors weren't asked
+    CircuitOpen,
+    /// Every mirror failed this request
+    UpstreamFailed,
+}
+
+/// A page served stale from the cache, described in the `X-Upstream-Status`
+/// header as a structured field, e.g. `degraded; reason=circuit_open; age=3600`
+#[derive(Debug, Clone, Copy)]
+pub struct Degraded {
+    pub reason: DegradedReason,
+    /// Seconds since the cached copy was fetched
+    pub age: u64,
+}
+
+/// Header describing how the mirrors are doing, on stale pages and on pages
+/// refused while the breaker is open
+pub const UPSTREAM_STATUS_HEADER: &str = "X-Upstream-Status";
+
+impl DegradedReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DegradedReason::CircuitOpen => "circuit_open",
+            DegradedReason::UpstreamFailed => "upstream_failed",
+        }
+    }
+}
+
+impl Degraded {
+    fn header_value(&self) -> String {
+        format!("degraded; reason={}; age={}", self.reason.as_str(), self.age)
+    }
+}
+
+/// A page image, and whether it's a stale copy served in place of the mirrors
+pub struct Page {
+    pub bytes: Vec<u8>,
+    pub degraded: Option<Degraded>,
+}
+
+/// Why a page couldn't be served
+#[derive(Debug)]
+pub enum FetchError {
+    /// Every mirror failed; one message per mirror tried
+    Unavailable(Vec<String>),
+    /// The breaker is open and the page was never cached
+    CircuitOpen { retry_after: u64 },
+}
+
+/// State of the image proxy, as reported by `/ready`
+#[derive(Debug, Serialize)]
+pub struct ProxyStatus {
+    pub circuit: CircuitState,
+    /// Requests in a row that every mirror failed
+    pub consecutive_failures: u32,
+    /// Seconds until an open breaker tries the mirrors again
+    pub seconds_until_retry: Option<u64>,
+    pub cache_enabled: bool,
+    /// Pages in the cache
+    pub cached_pages: usize,
+}
+
+/// Fetched pages on disk, as `{volume * 1000 + page}.jpeg`
+#[derive(Debug)]
+struct PageCache {
+    /// None when the cache is off
+    dir: Option<PathBuf>,
+    /// Pages in the cache, counted at startup and kept up to date by `write`
+    /// so `/ready` doesn't list the directory
+    pages: AtomicUsize,
+}
+
+impl PageCache {
+    fn new(dir: &str) -> Self {
+        let dir = Some(dir.trim()).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        if let Some(path) = &dir
+            && let Err(e) = std::fs::create_dir_all(path) {
+            eprintln!("Image cache disabled, can't create {}: {}", path.display(), e);
+            return PageCache { dir: None, pages: AtomicUsize::new(0) };
+        }
+        let pages = AtomicUsize::new(count_pages(dir.as_deref()));
+        PageCache { dir, pages }
+    }
+
+    fn path(&self, key: u32) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.jpeg", key)))
+    }
+
+    /// A cached page and how long ago it was fetched
+    async fn read(&self, key: u32) -> Option<(Vec<u8>, Duration)> {
+        let path = self.path(key)?;
+        let modified = rocket::tokio::fs::metadata(&path).await.ok()?.modified().ok()?;
+        let bytes = rocket::tokio::fs::read(&path).await.ok()?;
+        Some((bytes, modified.elapsed().unwrap_or_default()))
+    }
+
+    /// Keep a page (a failure only means it isn't cached)
+    async fn write(&self, key: u32, bytes: &[u8]) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+        // Write to a file of its own, then move it into place, so a reader
+        // never sees half a page and concurrent writes of a page don't mix
+        let partial = path.with_extension(format!("jpeg.{:016x}.part", rand::random::<u64>()));
+        if let Err(e) = rocket::tokio::fs::write(&partial, bytes).await {
+            eprintln!("Failed to cache {}: {}", path.display(), e);
+            let _ = rocket::tokio::fs::remove_file(&partial).await;
+            return;
+        }
+        // Linking only succeeds if the page wasn't cached yet, so whether to
+        // count it comes from the same step that adds it
+        let result = match rocket::tokio::fs::hard_link(&partial, &path).await {
+            Ok(()) => {
+                self.pages.fetch_add(1, Ordering::Relaxed);
+                rocket::tokio::fs::remove_file(&partial).await
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => rocket::tokio::fs::rename(&partial, &path).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to cache {}: {}", path.display(), e);
+            let _ = rocket::tokio::fs::remove_file(&partial).await;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.pages.load(Ordering::Relaxed)
+    }
+}
+
+/// Pages already in a cache directory (pages left half-written by a crash
+/// are removed)
+fn count_pages(dir: Option<&std::path::Path>) -> usize {
+    dir.and_then(|dir| std::fs::read_dir(dir).ok())
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| match path.extension() {
+                    Some(extension) if extension == "part" => {
+                        let _ = std::fs::remove_file(path);
+                        false
+                    }
+                    extension => extension.is_some_and(|extension| extension == "jpeg"),
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// A mirror's failure to serve a page
+struct UpstreamError {
+    message: String,
+    /// The mirror answered that it has no such page (so it's up)
+    not_found: bool,
+}
+
+#[derive(Debug)]
+struct Mirror {
+    base_url: String,
+    state: Mutex<MirrorState>,
+}
+
+/// Health snapshot of one mirror, as reported by `/status`
+#[derive(Debug, Serialize)]
+pub struct MirrorStatus {
+    pub base_url: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub seconds_since_last_failure: Option<u64>,
+    pub seconds_since_last_success: Option<u64>,
+}
+
+/// Prioritized image mirrors plus the HTTP client used to reach them
+pub struct ImageMirrors {
+    client: reqwest::Client,
+    mirrors: Vec<Mirror>,
+    breaker: Mutex<Breaker>,
+    cache: PageCache,
+    /// Thumbnails made so far, by `volume * 1000 + page` (a few KB each, and
+    /// there are only as many as there are pages)
+    thumbnails: Mutex<HashMap<u32, Vec<u8>>>,
+}
+
+impl ImageMirrors {
+    /// `cache_dir` empty turns the page cache off
+    pub fn new(base_urls: Vec<String>, cache_dir: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .expect("Failed to build image proxy HTTP client");
+
+        let mirrors = base_urls
+            .into_iter()
+            .map(|base_url| Mirror {
+                base_url: base_url.trim_end_matches('/').to_string(),
+                state: Mutex::new(MirrorState::default()),
+            })
+            .collect();
+
+        ImageMirrors {
+            client,
+            mirrors,
+            breaker: Mutex::new(Breaker::default()),
+            cache: PageCache::new(cache_dir),
+            thumbnails: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Mirrors in the order to try: healthy ones by priority, then the rest
+    fn candidates(&self) -> Vec<&Mirror> {
+        let mut candidates: Vec<&Mirror> = self.mirrors.iter().collect();
+        // Stable sort keeps configured priority within each group
+        candidates.sort_by_key(|mirror| mirror.state.lock().unwrap().is_cooling_down());
+        candidates
+    }
+
+    /// Fetch a page image: from the cache if fetched lately, else from the
+    /// mirrors (failing over through them), else from the cache however old
+    pub async fn fetch(&self, volume: u32, page: u32) -> Result<Page, FetchError> {
+        let key = volume * 1000 + page;
+        let cached = self.cache.read(key).await;
+        let stale = |reason: DegradedReason, (bytes, age): (Vec<u8>, Duration)| Page {
+            bytes,
+            degraded: Some(Degraded { reason, age: age.as_secs() }),
+        };
+        if let Some((bytes, age)) = &cached
+            && *age < CACHE_FRESH_FOR {
+            return Ok(Page { bytes: bytes.clone(), degraded: None });
+        }
+
+        if !self.breaker.lock().unwrap().allow() {
+            return match cached {
+                Some(cached) => Ok(stale(DegradedReason::CircuitOpen, cached)),
+                None => Err(FetchError::CircuitOpen {
+                    retry_after: self.breaker.lock().unwrap().seconds_until_retry().unwrap_or(1),
+                }),
+            };
+        }
+
+        match self.fetch_from_mirrors(key).await {
+            Ok(bytes) => {
+                self.breaker.lock().unwrap().record_success();
+                self.cache.write(key, &bytes).await;
+                Ok(Page { bytes, degraded: None })
+            }
+            Err((errors, not_found)) => {
+                // A mirror that says it has no such page is up: that's a bad
+                // page number (or a missing scan), not an outage
+                let mut breaker = self.breaker.lock().unwrap();
+                if not_found {
+                    breaker.probe_started = None;
+                } else {
+                    breaker.record_failure();
+                }
+                match cached {
+                    Some(cached) => Ok(stale(DegradedReason::UpstreamFailed, cached)),
+                    None => Err(FetchError::Unavailable(errors)),
+                }
+            }
+        }
+    }
+
+    /// Fetch a page from the first mirror that has it.
+    /// On total failure, returns one error message per mirror tried, and
+    /// whether any of them answered that it has no such page.
+    async fn fetch_from_mirrors(&self, key: u32) -> Result<Vec<u8>, (Vec<String>, bool)> {
+        let mut errors = Vec::new();
+        let mut not_found = false;
+
+        for mirror in self.candidates() {
+            let url = format!("{}/{}.jpeg", mirror.base_url, key);
+
+            match self.fetch_from(&url).await {
+                Ok(bytes) => {
+                    let mut state = mirror.state.lock().unwrap();
+                    state.consecutive_failures = 0;
+                    state.last_success = Some(Instant::now());
+                    return Ok(bytes);
+                }
+                Err(error) => {
+                    let mut state = mirror.state.lock().unwrap();
+                    state.consecutive_failures += 1;
+                    state.last_failure = Some(Instant::now());
+                    state.last_error = Some(error.message.clone());
+                    not_found |= error.not_found;
+                    errors.push(format!("{}: {}", mirror.base_url, error.message));
+                }
+            }
+        }
+
+        Err((errors, not_found))
+    }
+
+    /// Fetch a page's thumbnail, made from the full scan on first request.
+    /// Fails like `fetch`, plus one error if the scan can't be decoded.
+    pub async fn fetch_thumbnail(&self, volume: u32, page: u32) -> Result<Page, FetchError> {
+        let key = volume * 1000 + page;
+        if let Some(thumbnail) = self.thumbnails.lock().unwrap().get(&key) {
+            return Ok(Page { bytes: thumbnail.clone(), degraded: None });
+        }
+
+        let Page { bytes: scan, degraded } = self.fetch(volume, page).await?;
+        let thumbnail = rocket::tokio::task::spawn_blocking(move || make_thumbnail(&scan))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result)
+            .map_err(|error| FetchError::Unavailable(vec![format!("thumbnail: {}", error)]))?;
+
+        self.thumbnails.lock().unwrap().insert(key, thumbnail.clone());
+        Ok(Page { bytes: thumbnail, degraded })
+    }
+
+    async fn fetch_from(&self, url: &str) -> Result<Vec<u8>, UpstreamError> {
+        let failed = |message: String| UpstreamError { message, not_found: false };
+        let response = self.client.get(url).send().await.map_err(|e| failed(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(UpstreamError {
+                message: format!("upstream returned {}", response.status()),
+                not_found: response.status() == reqwest::StatusCode::NOT_FOUND,
+            });
+        }
+
+        // A captive portal or a misconfigured mirror can answer 200 with an
+        // HTML page; that mustn't be served, or cached, as a scan
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        if !content_type.trim_start().to_ascii_lowercase().starts_with("image/") {
+            return Err(failed(format!("upstream returned content type '{}', not an image", content_type)));
+        }
+
+        response.bytes().await.map(|b| b.to_vec()).map_err(|e| failed(e.to_string()))
+    }
+
+    /// The breaker and cache, for `/ready`
+    pub fn proxy_status(&self) -> ProxyStatus {
+        let (circuit, consecutive_failures, seconds_until_retry) = {
+            let breaker = self.breaker.lock().unwrap();
+            (breaker.state(), breaker.consecutive_failures, breaker.seconds_until_retry())
+        };
+        ProxyStatus {
+            circuit,
+            consecutive_failures,
+            seconds_until_retry,
+            cache_enabled: self.cache.dir.is_some(),
+            cached_pages: self.cache.len(),
+        }
+    }
+
+    /// Health of every mirror, in priority order
+    pub fn status(&self) -> Vec<MirrorStatus> {
+        self.mirrors
+            .iter()
+            .map(|mirror| {
+                let state = mirror.state.lock().unwrap();
+                MirrorStatus {
+                    base_url: mirror.base_url.clone(),
+                    healthy: state.consecutive_failures < FAILURE_THRESHOLD,
+                    consecutive_failures: state.consecutive_failures,
+                    last_error: state.last_error.clone(),
+                    seconds_since_last_failure: state.last_failure.map(|at| at.elapsed().as_secs()),
+                    seconds_since_last_success: state.last_success.map(|at| at.elapsed().as_secs()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Scale a JPEG scan down to `THUMBNAIL_WIDTH`, keeping its proportions
+fn make_thumbnail(scan: &[u8]) -> Result<Vec<u8>, String> {
+    let page = image::load_from_memory_with_format(scan, image::ImageFormat::Jpeg).map_err(|e| e.to_string())?;
+    let thumbnail = page.thumbnail(THUMBNAIL_WIDTH, u32::MAX).to_rgb8();
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, THUMBNAIL_QUALITY)
+        .encode_image(&thumbnail)
+        .map_err(|e| e.to_string())?;
+    Ok(jpeg)
+}
+
+/// A proxied JPEG page (or page thumbnail), cacheable by the browser unless
+/// it's a stale copy
+pub struct SheetImage(pub Page);
+
+impl<'r> Responder<'r, 'static> for SheetImage {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let Page { bytes, degraded } = self.0;
+        let mut response = Response::build();
+        response.header(ContentType::JPEG);
+        match degraded {
+            Some(degraded) => {
+                response
+                    .header(Header::new("Cache-Control", DEGRADED_CACHE_CONTROL))
+                    .header(Header::new(UPSTREAM_STATUS_HEADER, degraded.header_value()));
+            }
+            None => {
+                response.header(Header::new("Cache-Control", CACHE_CONTROL));
+            }
+        }
+        response.sized_body(bytes.len(), Cursor::new(bytes)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A breaker that opened `ago`
+    fn opened(ago: Duration) -> Breaker {
+        Breaker { consecutive_failures: BREAKER_THRESHOLD, opened_at: Instant::now().checked_sub(ago), probe_started: None }
+    }
+
+    #[test]
+    fn opens_after_enough_failures_in_a_row() {
+        let mut breaker = Breaker::default();
+        for _ in 1..BREAKER_THRESHOLD {
+            breaker.record_failure();
+            assert_eq!(breaker.state(), CircuitState::Closed);
+            assert!(breaker.allow());
+        }
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+        assert!(breaker.seconds_until_retry().is_some_and(|seconds| seconds <= BREAKER_COOLDOWN.as_secs() + 1));
+    }
+
+    #[test]
+    fn a_success_resets_the_count() {
+        let mut breaker = Breaker::default();
+        for _ in 1..BREAKER_THRESHOLD {
+            breaker.record_failure();
+        }
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn half_open_lets_one_trial_through() {
+        let mut breaker = opened(BREAKER_COOLDOWN);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.seconds_until_retry(), None);
+        assert!(breaker.allow());
+        assert!(!breaker.allow(), "a second request waits for the trial");
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn a_failed_trial_reopens_straight_away() {
+        let mut breaker = opened(BREAKER_COOLDOWN);
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn a_stuck_trial_is_given_up_on() {
+        let mut breaker = opened(BREAKER_COOLDOWN);
+        breaker.probe_started = Instant::now().checked_sub(PROBE_TIMEOUT);
+        assert!(breaker.allow());
+    }
+
+    #[rocket::async_test]
+    async fn the_cache_counts_each_page_once() {
+        let dir = std::env::temp_dir().join(format!("realbook-cache-{:016x}", rand::random::<u64>()));
+        let cache = PageCache::new(dir.to_str().unwrap());
+        // The same page fetched by two requests at once
+        rocket::tokio::join!(cache.write(1001, b"first"), cache.write(1001, b"second"), cache.write(1002, b"other"));
+        cache.write(1001, b"refetched").await;
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.read(1001).await.unwrap().0, b"refetched");
+
+        std::fs::write(dir.join("1003.jpeg.0123456789abcdef.part"), b"half").unwrap();
+        assert_eq!(PageCache::new(dir.to_str().unwrap()).len(), 2);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2, "no partial files are left behind");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,23 +1,180 @@
+mod admin;
+mod archive;
+mod attachments;
+mod calendar;
+mod changelog;
 mod controller;
+mod corrections;
+mod csrf;
+mod errors;
+mod formats;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod images;
 mod models;
+mod page_reports;
+mod passkeys;
+mod pdf;
+mod rate_limit;
+mod catalog;
+mod security;
+mod sessions;
+mod sync;
 
 use crate::controller::*;
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use rocket::Route;
 use std::sync::Arc;
 
 #[macro_use] extern crate rocket;
 
-/// Load Real Book data from JSON file
+/// Current API version, mounted at `/api/v1`
+const API_PREFIX: &str = "/api/v1";
+
+/// Unversioned prefix kept as a deprecated alias for older clients
+const LEGACY_API_PREFIX: &str = "/api";
+
+/// Default port for the gRPC service (override with `grpc_port` in Rocket config)
+#[cfg(feature = "grpc")]
+const DEFAULT_GRPC_PORT: u16 = 50051;
+
+/// All API routes (mounted under both the versioned and the legacy prefix)
+fn api_routes() -> Vec<Route> {
+    routes![index, rickroll, search, suggest, entry, entry_attachments, entry_musicxml, upload_musicxml, delete_musicxml, submit_correction, correction_queue, review_correction, entry_pdf, setlist_index_pdf, setlist_book_pdf, volumes, full_catalog, catalog_changelog, stats, random, shuffle, song_of_the_day, session, record_view, forget_session, link_session, join_session, sync_records, begin_passkey_registration, register_passkey, list_passkeys, delete_passkey, begin_passkey_sign_in, passkey_sign_in, sign_out, export_archive, import_archive, image, image_thumbnail, report_page, validation, clear_page_reports, status, ready]
+}
+
+/// Tag every API response with its version, and mark responses served
+/// through the unversioned alias as deprecated, pointing at the successor
+fn api_version_headers() -> AdHoc {
+    AdHoc::on_response("API Version Headers", |request, response| Box::pin(async move {
+        let path = request.uri().path();
+        if !path.starts_with(LEGACY_API_PREFIX) {
+            return;
+        }
+
+        response.set_header(Header::new("X-API-Version", models::API_VERSION));
+
+        if !path.starts_with(API_PREFIX) {
+            let successor = format!("{}{}", API_PREFIX, &path.as_str()[LEGACY_API_PREFIX.len()..]);
+            response.set_header(Header::new("Deprecation", "true"));
+            response.set_header(Header::new("Link", format!("<{}>; rel=\"successor-version\"", successor)));
+        }
+    }))
+}
+
+/// Load Real Book data from JSON file, assigning each entry its id
 fn load_realbook_data() -> Vec<models::RealBookEntry> {
     let data = std::fs::read_to_string("api/resources/realbook.json")
         .expect("Failed to read realbook.json");
-    serde_json::from_str(&data).expect("Failed to parse realbook.json")
+    let mut entries: Vec<models::RealBookEntry> =
+        serde_json::from_str(&data).expect("Failed to parse realbook.json");
+
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.id = index as u32 + 1;
+    }
+
+    entries
+}
+
+/// Start the gRPC service next to the HTTP server once Rocket is up,
+/// on Rocket's bind address and the configured `grpc_port`
+#[cfg(feature = "grpc")]
+fn grpc_server(data: Arc<Vec<models::RealBookEntry>>) -> AdHoc {
+    AdHoc::on_liftoff("gRPC Server", |rocket| Box::pin(async move {
+        let port = rocket
+            .figment()
+            .extract_inner::<u16>("grpc_port")
+            .unwrap_or(DEFAULT_GRPC_PORT);
+        let address = std::net::SocketAddr::new(rocket.config().address, port);
+
+        rocket::tokio::spawn(async move {
+            println!("gRPC service listening on {}", address);
+            if let Err(e) = grpc::serve(data, address).await {
+                eprintln!("gRPC server stopped: {}", e);
+            }
+        });
+    }))
 }
 
 #[launch]
 fn rocket() -> _ {
     let realbook_data = Arc::new(load_realbook_data());
 
-    rocket::build()
+    let rocket = rocket::build();
+
+    // Image mirrors in priority order, e.g. ROCKET_IMAGE_MIRRORS='["https://a", "https://b"]'
+    let image_mirrors: Vec<String> = rocket
+        .figment()
+        .extract_inner("image_mirrors")
+        .unwrap_or_else(|_| images::DEFAULT_IMAGE_MIRRORS.iter().map(|url| url.to_string()).collect());
+
+    // Where fetched pages are kept for when the mirrors fail, e.g.
+    // ROCKET_IMAGE_CACHE_DIR=/var/cache/realbook (an empty value turns it off)
+    let image_cache_dir: String = rocket
+        .figment()
+        .extract_inner("image_cache_dir")
+        .unwrap_or_else(|_| images::DEFAULT_IMAGE_CACHE_DIR.to_string());
+
+    // Enables the admin (upload) routes, e.g. ROCKET_ADMIN_TOKEN=...
+    let admin_token: Option<String> = rocket.figment().extract_inner("admin_token").ok();
+
+    // Mark cookies Secure when served over https, e.g. ROCKET_SECURE_COOKIES=true
+    let secure_cookies: bool = rocket.figment().extract_inner("secure_cookies").unwrap_or(false);
+
+    // Where passkeys are used: the site's domain and the UI's origin, e.g.
+    // ROCKET_PASSKEY_RP_ID=realbook.example ROCKET_PASSKEY_ORIGIN=https://realbook.example
+    let passkey_rp_id: String = rocket
+        .figment()
+        .extract_inner("passkey_rp_id")
+        .unwrap_or_else(|_| passkeys::DEFAULT_RP_ID.to_string());
+    let passkey_origin: String = rocket
+        .figment()
+        .extract_inner("passkey_origin")
+        .unwrap_or_else(|_| passkeys::DEFAULT_ORIGIN.to_string());
+
+    // Security headers, e.g. ROCKET_CONTENT_SECURITY_POLICY="default-src 'self'"
+    // (an empty value leaves the header out)
+    let content_security_policy: String = rocket
+        .figment()
+        .extract_inner("content_security_policy")
+        .unwrap_or_else(|_| security::default_policy(&image_mirrors));
+    let frame_options: String = rocket
+        .figment()
+        .extract_inner("frame_options")
+        .unwrap_or_else(|_| security::DEFAULT_FRAME_OPTIONS.to_string());
+    let referrer_policy: String = rocket
+        .figment()
+        .extract_inner("referrer_policy")
+        .unwrap_or_else(|_| security::DEFAULT_REFERRER_POLICY.to_string());
+
+    #[cfg(feature = "grpc")]
+    let rocket = rocket.attach(grpc_server(realbook_data.clone()));
+
+    rocket
         .manage(realbook_data)
-        .mount("/api", routes![index, rickroll, search, volumes, random])
+        .manage(changelog::Changelog::load())
+        .manage(attachments::AttachmentStore::load())
+        .manage(admin::AdminConfig::new(admin_token))
+        .manage(corrections::CorrectionStore::load())
+        .manage(page_reports::PageReportStore::load())
+        .manage(images::ImageMirrors::new(image_mirrors, &image_cache_dir))
+        .manage(sessions::SessionStore::new(secure_cookies))
+        .manage(passkeys::PasskeyStore::load(passkey_rp_id, passkey_origin))
+        .mount(API_PREFIX, api_routes())
+        .mount(LEGACY_API_PREFIX, api_routes())
+        .attach(api_version_headers())
+        .attach(csrf::issue_tokens(secure_cookies))
+        .attach(security::shield())
+        .attach(security::security_headers(security::SecurityHeaders::new(
+            content_security_policy,
+            frame_options,
+            referrer_policy,
+        )))
+        .register("/", catchers![
+            errors::not_found,
+            errors::unprocessable_entity,
+            errors::internal_error,
+            errors::default_catcher,
+        ])
 }
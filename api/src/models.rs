@@ -1,5 +1,27 @@
+use rocket::form::{self, DataField, FromForm, ValueField};
 use serde::{Deserialize, Deserializer, Serialize};
 
+/// API version reported in response bodies and the `X-API-Version` header
+pub const API_VERSION: &str = "v1";
+
+/// Volumes present in the Real Book catalog
+pub const VOLUMES: std::ops::RangeInclusive<u32> = 1..=3;
+
+/// Highest page number accepted in search filters (the largest volume has ~500 pages)
+pub const MAX_PAGE: u32 = 1000;
+
+/// Widest tolerance accepted for `page_near`
+pub use realbook_core::search::MAX_PAGE_TOLERANCE;
+
+/// Maximum length of a search query, in characters
+pub const MAX_QUERY_LEN: usize = 100;
+
+/// Longest `seed` accepted by random picks
+pub const MAX_SEED_LEN: usize = 100;
+
+/// Most entry ids a random pick accepts in `exclude`
+pub const MAX_EXCLUDED: usize = 200;
+
 /// Custom deserializer for title field that accepts both strings and numbers
 fn deserialize_title<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
@@ -45,6 +67,9 @@ where
 /// Represents a single entry in the Real Book
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RealBookEntry {
+    /// Stable identifier: 1-based position in realbook.json, assigned at load
+    #[serde(default)]
+    pub id: u32,
     /// Song title
     #[serde(deserialize_with = "deserialize_title")]
     pub title: String,
@@ -57,45 +82,300 @@ pub struct RealBookEntry {
 }
 
 impl RealBookEntry {
-    /// Generate the image URL for a specific page in this entry
-    pub fn image_url(&self, page: u32) -> String {
-        format!(
-            "https://wypn9z41ir5bzmgjjalyna.on.drv.tw/realbook/rendered/{}.jpeg",
-            self.volume * 1000 + page
-        )
+    /// Number of pages this entry spans
+    pub fn page_count(&self) -> u32 {
+        self.page_e - self.page_s + 1
+    }
+
+}
+
+impl realbook_core::Entry for RealBookEntry {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn volume(&self) -> u32 {
+        self.volume
+    }
+
+    fn pages(&self) -> (u32, u32) {
+        (self.page_s, self.page_e)
+    }
+}
+
+/// An optional query parameter that, unlike `Option<T>`, rejects values
+/// that fail to parse (`volume=abc`) instead of silently treating them as absent
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Param<T>(pub Option<T>);
+
+#[rocket::async_trait]
+impl<'v, T: FromForm<'v>> FromForm<'v> for Param<T> {
+    type Context = T::Context;
+
+    fn init(_opts: form::Options) -> Self::Context {
+        T::init(form::Options::Strict)
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: ValueField<'v>) {
+        T::push_value(ctxt, field)
     }
 
-    /// Get all image URLs for this entry (from page_s to page_e)
-    pub fn all_image_urls(&self) -> Vec<String> {
-        (self.page_s..=self.page_e)
-            .map(|page| self.image_url(page))
-            .collect()
+    async fn push_data(ctxt: &mut Self::Context, field: DataField<'v, '_>) {
+        T::push_data(ctxt, field).await
     }
 
-    /// Check if this entry matches a search query (case-insensitive)
-    pub fn matches(&self, query: &str) -> bool {
-        self.title.to_lowercase().contains(&query.to_lowercase())
+    fn finalize(ctxt: Self::Context) -> form::Result<'v, Self> {
+        match T::finalize(ctxt) {
+            Ok(value) => Ok(Param(Some(value))),
+            Err(errors) if errors.iter().all(|e| matches!(e.kind, form::error::ErrorKind::Missing)) => {
+                Ok(Param(None))
+            }
+            Err(errors) => Err(errors),
+        }
     }
 }
 
 /// Search query parameters
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Parsed and validated by Rocket's `FromForm`; any invalid field rejects
+/// the whole request with a 400 listing every problem.
+#[derive(Debug, Clone, FromForm)]
 pub struct SearchQuery {
     /// Search text (searches in title)
+    #[field(validate = valid_query())]
     pub query: Option<String>,
+    /// Earlier queries to refine; every term must also match
+    #[field(validate = valid_terms())]
+    pub within: Vec<String>,
+    /// Only titles filed under this index letter ("A"-"Z", or "#" for digits)
+    #[field(validate = valid_initial())]
+    pub initial: Option<String>,
     /// Filter by volume
-    pub volume: Option<u32>,
+    #[field(validate = valid_volume())]
+    pub volume: Param<u32>,
     /// Filter by page number (checks if page is within page_s..=page_e)
-    pub page: Option<u32>,
+    #[field(validate = valid_page())]
+    pub page: Param<u32>,
+    /// Start of a page span filter (inclusive)
+    #[field(validate = valid_page())]
+    pub page_from: Param<u32>,
+    /// End of a page span filter (inclusive)
+    #[field(validate = valid_page())]
+    #[field(validate = valid_page_span(self.page_from))]
+    pub page_to: Param<u32>,
+    /// Approximate page: entries overlapping `page_near ± tolerance`, closest first
+    #[field(validate = valid_page())]
+    pub page_near: Param<u32>,
+    /// Pages either side of `page_near` (default 5)
+    #[field(validate = valid_tolerance(self.page_near))]
+    pub tolerance: Param<u32>,
+    /// Number of matches to skip
+    pub offset: Param<usize>,
+    /// Maximum number of matches to return
+    #[field(validate = valid_limit())]
+    pub limit: Param<usize>,
+}
+
+impl SearchQuery {
+    /// Run the same checks as the `FromForm` field validators, for callers
+    /// that build a query by hand rather than parsing one (e.g. gRPC)
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub fn validate(&self) -> Result<(), form::Errors<'static>> {
+        let checks = [
+            ("query", valid_query(&self.query)),
+            ("within", valid_terms(&self.within)),
+            ("initial", valid_initial(&self.initial)),
+            ("volume", valid_volume(&self.volume)),
+            ("page", valid_page(&self.page)),
+            ("page_from", valid_page(&self.page_from)),
+            ("page_to", valid_page(&self.page_to)),
+            ("page_to", valid_page_span(&self.page_to, self.page_from)),
+            ("page_near", valid_page(&self.page_near)),
+            ("tolerance", valid_tolerance(&self.tolerance, self.page_near)),
+            ("limit", valid_limit(&self.limit)),
+        ];
+
+        let mut errors = form::Errors::new();
+        for (name, result) in checks {
+            if let Err(e) = result {
+                errors.extend(e.with_name(name));
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+fn valid_query<'v>(query: &Option<String>) -> form::Result<'v, ()> {
+    match query {
+        Some(q) if q.chars().count() > MAX_QUERY_LEN => Err(form::Error::validation(
+            format!("must be at most {} characters", MAX_QUERY_LEN),
+        ))?,
+        _ => Ok(()),
+    }
+}
+
+fn valid_terms<'v>(terms: &[String]) -> form::Result<'v, ()> {
+    for term in terms {
+        valid_query(&Some(term.clone()))?;
+    }
+    Ok(())
+}
+
+fn valid_initial<'v>(initial: &Option<String>) -> form::Result<'v, ()> {
+    match initial.as_deref() {
+        Some(letter) if !(letter == "#" || letter.len() == 1 && letter.chars().all(|c| c.is_ascii_alphabetic())) => {
+            Err(form::Error::validation("must be a letter A-Z or #"))?
+        }
+        _ => Ok(()),
+    }
+}
+
+fn valid_volume<'v>(volume: &Param<u32>) -> form::Result<'v, ()> {
+    match &volume.0 {
+        Some(v) if !VOLUMES.contains(v) => Err(form::Error::validation(format!(
+            "must be between {} and {}",
+            VOLUMES.start(),
+            VOLUMES.end()
+        )))?,
+        _ => Ok(()),
+    }
+}
+
+fn valid_page<'v>(page: &Param<u32>) -> form::Result<'v, ()> {
+    match &page.0 {
+        Some(p) if !(1..=MAX_PAGE).contains(p) => Err(form::Error::validation(
+            format!("must be between 1 and {}", MAX_PAGE),
+        ))?,
+        _ => Ok(()),
+    }
+}
+
+fn valid_page_span<'v>(page_to: &Param<u32>, page_from: Param<u32>) -> form::Result<'v, ()> {
+    match (page_from.0, page_to.0) {
+        (Some(from), Some(to)) if from > to => Err(form::Error::validation(
+            "must not be less than page_from",
+        ))?,
+        _ => Ok(()),
+    }
+}
+
+fn valid_tolerance<'v>(tolerance: &Param<u32>, page_near: Param<u32>) -> form::Result<'v, ()> {
+    match (page_near.0, tolerance.0) {
+        (None, Some(_)) => Err(form::Error::validation("needs page_near"))?,
+        (_, Some(t)) if t > MAX_PAGE_TOLERANCE => Err(form::Error::validation(
+            format!("must be at most {}", MAX_PAGE_TOLERANCE),
+        ))?,
+        _ => Ok(()),
+    }
+}
+
+fn valid_limit<'v>(limit: &Param<usize>) -> form::Result<'v, ()> {
+    match &limit.0 {
+        Some(0) => Err(form::Error::validation("must be at least 1"))?,
+        _ => Ok(()),
+    }
+}
+
+/// Check a random pick's `seed` and `exclude` against their limits, so the
+/// REST route and gRPC reject the same requests
+pub fn validate_random_options(seed: Option<&str>, exclude: &[u32]) -> Result<(), form::Errors<'static>> {
+    let mut errors = form::Errors::new();
+    if seed.is_some_and(|seed| seed.chars().count() > MAX_SEED_LEN) {
+        errors.push(form::Error::validation(format!("must be at most {} characters", MAX_SEED_LEN)).with_name("seed"));
+    }
+    if exclude.len() > MAX_EXCLUDED {
+        errors.push(form::Error::validation(format!("must hold at most {} entry ids", MAX_EXCLUDED)).with_name("exclude"));
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Autocomplete query parameters
+#[derive(Debug, Clone, FromForm)]
+pub struct SuggestQuery {
+    /// What has been typed so far
+    #[field(validate = valid_query())]
+    pub query: Option<String>,
+    /// Only complete titles in this volume
+    #[field(validate = valid_volume())]
+    pub volume: Param<u32>,
+}
+
+/// Autocomplete response
+#[derive(Debug, Serialize)]
+pub struct SuggestResponse {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Titles completing the query, best first
+    pub suggestions: Vec<String>,
 }
 
 /// Search results response
 #[derive(Debug, Serialize)]
 pub struct SearchResponse {
-    /// Matching entries
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Matching entries (the requested window when paginated)
     pub results: Vec<RealBookEntry>,
-    /// Total number of results
+    /// Total number of results before pagination
     pub total: usize,
+    /// Matches in each volume, ignoring the volume filter (volumes without
+    /// matches are left out)
+    pub volume_counts: Vec<VolumeInfo>,
+    /// Closest titles when the query matched nothing ("did you mean")
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+/// The whole catalog in one response, for clients that search locally
+#[derive(Debug, Serialize)]
+pub struct CatalogSnapshot {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Content hash of the catalog; changes whenever any entry does
+    pub version: String,
+    /// Every entry, in catalog order
+    pub entries: Vec<RealBookEntry>,
+}
+
+/// Catalog releases from `/changelog`
+#[derive(Debug, Serialize)]
+pub struct ChangelogResponse {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Content hash of the catalog (as in `/catalog`)
+    pub catalog_version: String,
+    /// The newest release's version (None before the first)
+    pub latest: Option<u32>,
+    /// Releases after `since` (all if not given), newest first
+    pub releases: Vec<crate::changelog::Release>,
+}
+
+/// The day's pick from `/song-of-the-day`
+#[derive(Debug, Serialize)]
+pub struct SongOfTheDay {
+    /// Day the song is picked for (YYYY-MM-DD)
+    pub date: String,
+    pub entry: RealBookEntry,
+}
+
+/// What the caller's anonymous session remembers, from `/session`
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    /// Songs viewed, newest first
+    pub recently_viewed: Vec<RealBookEntry>,
+    /// Songs picked by `/random`, newest first
+    pub recent_randoms: Vec<RealBookEntry>,
+}
+
+/// A code for joining the caller's session from another device
+#[derive(Debug, Serialize)]
+pub struct LinkCode {
+    /// e.g. `K7QM-2XPD`
+    pub code: String,
+    /// Seconds until the code stops working
+    pub expires_in: u64,
 }
 
 /// Volume information
@@ -103,4 +383,119 @@ pub struct SearchResponse {
 pub struct VolumeInfo {
     pub volume: u32,
     pub count: usize,
-}
\ No newline at end of file
+}
+
+/// Service health reported by `/status`
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// "ok" when every image mirror is healthy, "degraded" otherwise
+    pub status: &'static str,
+    /// Number of entries loaded into the catalog
+    pub entries: usize,
+    /// Health of each image mirror, in priority order
+    pub image_mirrors: Vec<crate::images::MirrorStatus>,
+}
+
+/// Readiness response, from `/ready`
+#[derive(Debug, Serialize)]
+pub struct ReadyResponse {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// "ready" while the image circuit breaker is closed, "degraded" otherwise
+    pub status: &'static str,
+    /// Circuit breaker and page cache
+    pub image_proxy: crate::images::ProxyStatus,
+}
+
+/// Catalog problems readers have reported, from `/admin/validation`
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Pages reported as broken, most reported first
+    pub broken_pages: Vec<crate::page_reports::BrokenPage>,
+    /// Corrections waiting in the moderation queue (`/admin/corrections`)
+    pub pending_corrections: usize,
+}
+
+/// Catalog-wide statistics
+#[derive(Debug, Serialize)]
+pub struct CatalogStats {
+    /// API version that produced this response
+    pub api_version: &'static str,
+    /// Total number of songs in the catalog
+    pub total_songs: usize,
+    /// Song counts per volume, sorted by volume number
+    pub songs_per_volume: Vec<VolumeInfo>,
+    /// Number of distinct pages covered by at least one song
+    pub total_pages: usize,
+    /// Average number of pages per song
+    pub average_pages_per_song: f64,
+    /// Song spanning the most pages
+    pub longest_chart: Option<RealBookEntry>,
+    /// Song spanning the fewest pages
+    pub shortest_chart: Option<RealBookEntry>,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names of the fields a query string is refused for (empty if it's accepted)
+    fn rejected(fields: &str) -> Vec<String> {
+        match rocket::form::Form::<SearchQuery>::parse(fields) {
+            Ok(_) => Vec::new(),
+            Err(errors) => names(errors),
+        }
+    }
+
+    fn names(errors: form::Errors<'_>) -> Vec<String> {
+        errors.iter().filter_map(|error| error.name.as_ref().map(|name| name.to_string())).collect()
+    }
+
+    #[test]
+    fn pages_are_limited_to_max_page() {
+        assert!(rejected(&format!("page={}", MAX_PAGE)).is_empty());
+        assert_eq!(rejected(&format!("page={}", MAX_PAGE + 1)), ["page"]);
+        assert_eq!(rejected("page=0"), ["page"]);
+        assert_eq!(rejected(&format!("page_from=1&page_to={}", MAX_PAGE + 1)), ["page_to"]);
+        assert_eq!(rejected(&format!("page_near={}", MAX_PAGE + 1)), ["page_near"]);
+        assert_eq!(rejected("page_from=120&page_to=100"), ["page_to"]);
+    }
+
+    #[test]
+    fn tolerance_is_limited_and_needs_page_near() {
+        assert!(rejected(&format!("page_near=300&tolerance={}", MAX_PAGE_TOLERANCE)).is_empty());
+        assert_eq!(rejected(&format!("page_near=300&tolerance={}", MAX_PAGE_TOLERANCE + 1)), ["tolerance"]);
+        assert_eq!(rejected("tolerance=3"), ["tolerance"]);
+    }
+
+    #[test]
+    fn every_bad_field_is_reported() {
+        let mut names = rejected("volume=4&initial=ab&limit=0&page=abc");
+        names.sort();
+        assert_eq!(names, ["initial", "limit", "page", "volume"]);
+        assert_eq!(rejected(&format!("query={}", "a".repeat(MAX_QUERY_LEN + 1))), ["query"]);
+    }
+
+    #[test]
+    fn hand_built_queries_get_the_same_checks() {
+        // gRPC builds the query itself, so `validate` has to catch what the
+        // form validators would
+        let mut query: SearchQuery = rocket::form::Form::parse("page_near=300").unwrap();
+        assert!(query.validate().is_ok());
+        query.page = Param(Some(MAX_PAGE + 1));
+        query.tolerance = Param(Some(MAX_PAGE_TOLERANCE + 1));
+        assert_eq!(names(query.validate().unwrap_err()), ["page", "tolerance"]);
+    }
+
+    #[test]
+    fn random_options_are_limited() {
+        assert!(validate_random_options(Some(&"s".repeat(MAX_SEED_LEN)), &[1; MAX_EXCLUDED]).is_ok());
+        let errors = validate_random_options(Some(&"s".repeat(MAX_SEED_LEN + 1)), &[]).unwrap_err();
+        assert_eq!(names(errors), ["seed"]);
+        let errors = validate_random_options(None, &[1; MAX_EXCLUDED + 1]).unwrap_err();
+        assert_eq!(names(errors), ["exclude"]);
+    }
+}
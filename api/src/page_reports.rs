@@ -0,0 +1,284 @@
+//! Reader reports of broken sheet images
+//!
+//! A page that won't load, or loads as the wrong or an unreadable scan, can be
+//! reported from the viewer (`POST /images/<volume>/<page>/reports`). Reports
+//! are kept per session, so one reader pressing the button again doesn't count
+//! twice, and are added up per page for the admin validation view
+//! (`GET /admin/validation`), most reported first. Once a scan is fixed on the
+//! mirrors, its reports are cleared (`DELETE /admin/page-reports/<volume>/<page>`).
+//!
+//! Reports are kept on disk (`api/resources/page_reports.json`) so they
+//! survive restarts; the session ids in it never leave the server. A session
+//! can only have so many open, and since a new session is just a cleared
+//! cookie away, so can a client address per hour; past that (or the cap on
+//! all reports) new ones are refused.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use rocket::http::Status;
+use rocket::tokio::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::calendar::Date;
+use crate::errors::ApiError;
+use crate::rate_limit::RateLimiter;
+
+/// Where reports are kept (a missing file means none)
+const PAGE_REPORTS_PATH: &str = "api/resources/page_reports.json";
+
+/// Open reports one session can have at once
+const MAX_REPORTS_PER_SESSION: usize = 100;
+
+/// Open reports kept at once across everyone; past this, new ones are refused
+const MAX_REPORTS: usize = 50_000;
+
+/// Reports one client address can send an hour
+const MAX_PER_CLIENT_HOUR: usize = 60;
+
+/// Largest report body read
+pub const MAX_PAGE_REPORT_BYTES: usize = 1024;
+
+/// What's wrong with the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageProblem {
+    /// The image doesn't load at all
+    Missing,
+    /// It loads, but it's the wrong page or can't be read
+    BadScan,
+}
+
+/// A report as submitted (the request body)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageReportRequest {
+    pub problem: PageProblem,
+}
+
+/// One session's report of one page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredReport {
+    volume: u32,
+    page: u32,
+    problem: PageProblem,
+    session: String,
+    /// YYYY-MM-DD (UTC)
+    reported_at: String,
+}
+
+/// A page's reports, added up
+#[derive(Debug, Serialize)]
+pub struct BrokenPage {
+    pub volume: u32,
+    pub page: u32,
+    /// Titles of the songs on the page (filled in by the caller)
+    pub songs: Vec<String>,
+    /// Sessions that reported it
+    pub reports: usize,
+    /// Reports by problem
+    pub problems: BTreeMap<PageProblem, usize>,
+    /// YYYY-MM-DD (UTC)
+    pub first_reported: String,
+    pub last_reported: String,
+}
+
+/// The reports (managed Rocket state)
+///
+/// The lock is async and held while the reports are saved, so saves happen
+/// one at a time, in order, without blocking a worker thread.
+pub struct PageReportStore {
+    reports: Mutex<Vec<StoredReport>>,
+    limiter: RateLimiter,
+    path: PathBuf,
+}
+
+impl PageReportStore {
+    pub fn load() -> Self {
+        Self::open(PAGE_REPORTS_PATH)
+    }
+
+    /// The reports kept in the file at `path`
+    fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let reports = match std::fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).expect("Failed to parse page_reports.json"),
+            Err(_) => Vec::new(),
+        };
+        PageReportStore {
+            reports: Mutex::new(reports),
+            limiter: RateLimiter::new(MAX_PER_CLIENT_HOUR, Duration::from_secs(60 * 60)),
+            path,
+        }
+    }
+
+    async fn save(&self, reports: &[StoredReport]) -> Result<(), ApiError> {
+        let json = serde_json::to_string_pretty(reports).map_err(|e| ApiError::internal(e.to_string()))?;
+        // Write then rename, so a failed write never leaves a truncated file
+        let partial = self.path.with_extension("json.part");
+        let result = match rocket::tokio::fs::write(&partial, json).await {
+            Ok(()) => rocket::tokio::fs::rename(&partial, &self.path).await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| ApiError::internal(format!("Failed to save page reports: {}", e)))
+    }
+
+    /// Record `session`'s report of a page, sent from `client` (again is a no-op)
+    pub async fn report(
+        &self,
+        session: &str,
+        client: Option<IpAddr>,
+        volume: u32,
+        page: u32,
+        problem: PageProblem,
+    ) -> Result<(), ApiError> {
+        let mut reports = self.reports.lock().await;
+        let repeat = reports.iter().any(|report| {
+            report.volume == volume && report.page == page && report.problem == problem && report.session == session
+        });
+        if repeat {
+            return Ok(());
+        }
+        if reports.len() >= MAX_REPORTS
+            || reports.iter().filter(|report| report.session == session).count() >= MAX_REPORTS_PER_SESSION
+        {
+            return Err(ApiError::new(
+                Status::TooManyRequests,
+                "too_many_reports",
+                "Too many page reports are waiting; try again once they've been looked at",
+            ));
+        }
+        if !self.limiter.allow(client) {
+            return Err(ApiError::new(
+                Status::TooManyRequests,
+                "too_many_reports",
+                "Too many page reports have been sent from your network lately; try again in an hour",
+            ));
+        }
+        reports.push(StoredReport {
+            volume,
+            page,
+            problem,
+            session: session.to_string(),
+            reported_at: Date::today().to_string(),
+        });
+        if let Err(error) = self.save(&reports).await {
+            reports.pop();
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Every reported page, most reported first (then by volume and page)
+    pub async fn broken_pages(&self) -> Vec<BrokenPage> {
+        let mut pages: BTreeMap<(u32, u32), BrokenPage> = BTreeMap::new();
+        let mut sessions: BTreeMap<(u32, u32), Vec<&str>> = BTreeMap::new();
+        let reports = self.reports.lock().await;
+        for report in reports.iter() {
+            let key = (report.volume, report.page);
+            let page = pages.entry(key).or_insert_with(|| BrokenPage {
+                volume: report.volume,
+                page: report.page,
+                songs: Vec::new(),
+                reports: 0,
+                problems: BTreeMap::new(),
+                first_reported: report.reported_at.clone(),
+                last_reported: report.reported_at.clone(),
+            });
+            *page.problems.entry(report.problem).or_default() += 1;
+            page.first_reported = page.first_reported.clone().min(report.reported_at.clone());
+            page.last_reported = page.last_reported.clone().max(report.reported_at.clone());
+            let reporters = sessions.entry(key).or_default();
+            if !reporters.contains(&report.session.as_str()) {
+                reporters.push(&report.session);
+                page.reports += 1;
+            }
+        }
+        let mut pages: Vec<BrokenPage> = pages.into_values().collect();
+        // Stable, so equally reported pages stay in volume and page order
+        pages.sort_by_key(|page| std::cmp::Reverse(page.reports));
+        pages
+    }
+
+    /// Clear a page's reports once its scan is fixed; false if it had none
+    pub async fn clear(&self, volume: u32, page: u32) -> Result<bool, ApiError> {
+        let mut reports = self.reports.lock().await;
+        let before = reports.len();
+        let kept: Vec<StoredReport> =
+            reports.iter().filter(|report| (report.volume, report.page) != (volume, page)).cloned().collect();
+        if kept.len() == before {
+            return Ok(false);
+        }
+        self.save(&kept).await?;
+        *reports = kept;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use super::*;
+
+    fn client(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, last)))
+    }
+
+    fn temp_store() -> (PageReportStore, PathBuf) {
+        let path = std::env::temp_dir().join(format!("realbook-page-reports-{:016x}.json", rand::random::<u64>()));
+        (PageReportStore::open(&path), path)
+    }
+
+    #[rocket::async_test]
+    async fn reports_add_up_per_page_and_survive_a_restart() {
+        let (store, path) = temp_store();
+        store.report("a", client(1), 1, 20, PageProblem::Missing).await.unwrap();
+        store.report("a", client(1), 1, 20, PageProblem::Missing).await.unwrap();
+        store.report("a", client(1), 1, 20, PageProblem::BadScan).await.unwrap();
+        store.report("b", client(2), 1, 20, PageProblem::Missing).await.unwrap();
+        store.report("b", client(2), 2, 5, PageProblem::BadScan).await.unwrap();
+
+        let pages = PageReportStore::open(&path).broken_pages().await;
+        let summary: Vec<_> = pages.iter().map(|page| (page.volume, page.page, page.reports)).collect();
+        assert_eq!(summary, [(1, 20, 2), (2, 5, 1)]);
+        assert_eq!(pages[0].problems, BTreeMap::from([(PageProblem::Missing, 2), (PageProblem::BadScan, 1)]));
+
+        assert!(store.clear(1, 20).await.unwrap());
+        assert!(!store.clear(1, 20).await.unwrap());
+        let pages = PageReportStore::open(&path).broken_pages().await;
+        assert_eq!(pages.iter().map(|page| page.page).collect::<Vec<_>>(), [5]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn each_address_is_limited_per_hour() {
+        let (store, path) = temp_store();
+        for page in 1..=MAX_PER_CLIENT_HOUR as u32 {
+            let session = format!("s{}", page);
+            store.report(&session, client(1), 1, page, PageProblem::Missing).await.unwrap();
+        }
+        let refused = store.report("fresh", client(1), 2, 1, PageProblem::Missing).await.unwrap_err();
+        assert_eq!(refused.status, Status::TooManyRequests);
+        assert!(refused.body.message.contains("network"));
+        // A repeat isn't a new report, so it isn't refused
+        assert!(store.report("s1", client(1), 1, 1, PageProblem::Missing).await.is_ok());
+
+        assert!(store.report("fresh", client(2), 2, 1, PageProblem::Missing).await.is_ok());
+        assert_eq!(store.broken_pages().await.len(), MAX_PER_CLIENT_HOUR + 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn each_session_is_limited_in_what_it_has_open() {
+        let (store, path) = temp_store();
+        for page in 1..=MAX_REPORTS_PER_SESSION as u32 {
+            // Spread over addresses so the hourly limit doesn't get there first
+            let address = u8::try_from(page % 100).unwrap();
+            store.report("a", client(address), 1, page, PageProblem::BadScan).await.unwrap();
+        }
+        let refused = store.report("a", client(250), 2, 1, PageProblem::BadScan).await.unwrap_err();
+        assert_eq!(refused.body.code, "too_many_reports");
+        assert!(store.report("b", client(250), 2, 1, PageProblem::BadScan).await.is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+//! Passkeys (WebAuthn): signing in to a session without typing anything
+//!
+//! The app is often used on shared band tablets, where typing a password is
+//! awkward. A passkey is registered while in a session and from then on signs
+//! any browser back in to it (`sessions.rs`), bringing along its synced data.
+//! The session is the account: there are no passwords to fall back on.
+//!
+//! Both ceremonies start with a one-use challenge from the server, and the
+//! browser hands back what the authenticator made of it. Checking that is
+//! webauthn-rs's job: the challenge, ceremony type, origin, and relying party,
+//! the signature, and that the authenticator verified the user (fingerprint,
+//! face, or PIN; being present isn't enough, since tablets are shared). A new
+//! passkey's public key is the COSE key in its attested authenticator data,
+//! never one the browser reports separately. Registration uses attestation
+//! "none" (we don't care which make of authenticator it is), and sign-in is
+//! discoverable: the passkey says which session it's for, so nobody types a
+//! user name.
+//!
+//! Unlike sessions, passkeys are kept on disk (`api/resources/passkeys.json`),
+//! so they still sign in after a restart; the session comes back empty and
+//! sync refills it from the devices. The file maps passkeys to session ids, so
+//! it must be kept as private as the server's memory.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use ring::digest;
+use rocket::http::Status;
+use rocket::tokio::sync::Mutex as AsyncMutex;
+use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, DiscoverableAuthentication, DiscoverableKey, Passkey, PasskeyRegistration,
+    PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse, Url, Uuid, Webauthn, WebauthnBuilder,
+    WebauthnError,
+};
+use crate::calendar::Date;
+use crate::errors::ApiError;
+
+/// Where passkeys are kept (a missing file means none)
+const PASSKEYS_PATH: &str = "api/resources/passkeys.json";
+
+/// Relying party id and origin used when none are configured (the dev server)
+pub const DEFAULT_RP_ID: &str = "localhost";
+pub const DEFAULT_ORIGIN: &str = "http://localhost:8080";
+
+/// Name authenticators show for the site
+const RP_NAME: &str = "Real Book Search";
+
+/// How long a ceremony may take, from its challenge to its answer
+const CHALLENGE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Challenges outstanding at once; past this the oldest are dropped
+const MAX_CHALLENGES: usize = 10_000;
+
+/// Passkeys one session can have
+const MAX_PASSKEYS: usize = 20;
+
+/// Longest passkey name, in characters
+const MAX_NAME_CHARS: usize = 64;
+
+/// Largest registration or sign-in body read
+pub const MAX_PASSKEY_BYTES: usize = 64 * 1024;
+
+/// A registered passkey
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredPasskey {
+    /// Credential id (base64url)
+    id: String,
+    /// Session it signs in to
+    account: String,
+    name: String,
+    /// The credential: its COSE public key, signature counter, and flags
+    passkey: Passkey,
+    /// YYYY-MM-DD (UTC)
+    created_at: String,
+    last_used: Option<String>,
+}
+
+/// A passkey, as listed to its session
+#[derive(Debug, Serialize)]
+pub struct PasskeySummary {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub last_used: Option<String>,
+}
+
+impl From<&StoredPasskey> for PasskeySummary {
+    fn from(passkey: &StoredPasskey) -> Self {
+        PasskeySummary {
+            id: passkey.id.clone(),
+            name: passkey.name.clone(),
+            created_at: passkey.created_at.clone(),
+            last_used: passkey.last_used.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Ceremony {
+    /// Adding a passkey named `name` to session `account`
+    Register { account: String, name: String, state: PasskeyRegistration },
+    SignIn(DiscoverableAuthentication),
+}
+
+fn rejected(message: impl Into<String>) -> ApiError {
+    ApiError::new(Status::Unauthorized, "passkey_rejected", message)
+}
+
+/// Why webauthn-rs turned down a response, for the caller
+fn refused(error: WebauthnError) -> ApiError {
+    match error {
+        WebauthnError::UserNotVerified => {
+            rejected("The authenticator didn't verify who's using it (with a fingerprint, face, or PIN)")
+        }
+        WebauthnError::InvalidRPOrigin | WebauthnError::InvalidRPIDHash => rejected("The passkey is for another site"),
+        WebauthnError::CredentialPossibleCompromise => rejected("The passkey's signature counter went backwards"),
+        error => rejected(format!("The passkey didn't check out: {}", error)),
+    }
+}
+
+/// The user handle authenticators keep for session `account`: a hash of the
+/// session id, not the id itself
+fn user_id(account: &str) -> Uuid {
+    let hash = digest::digest(&digest::SHA256, account.as_bytes());
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&hash.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
+
+/// The challenge a response answers, from its `clientDataJSON`
+fn answered_challenge(client_data_json: &[u8]) -> Result<String, ApiError> {
+    #[derive(Deserialize)]
+    struct ClientData {
+        challenge: String,
+    }
+    serde_json::from_slice::<ClientData>(client_data_json)
+        .map(|client_data| client_data.challenge)
+        .map_err(|_| ApiError::new(Status::BadRequest, "invalid_passkey", "clientDataJSON isn't WebAuthn client data"))
+}
+
+/// Every registered passkey, and the ceremonies under way
+#[derive(Debug)]
+pub struct PasskeyStore {
+    webauthn: Webauthn,
+    /// Held across the file write, so saves land in order
+    passkeys: AsyncMutex<Vec<StoredPasskey>>,
+    /// By challenge (base64url)
+    challenges: Mutex<HashMap<String, (Ceremony, Instant)>>,
+}
+
+impl PasskeyStore {
+    /// Read the passkeys file, panicking on bad data or a relying party id
+    /// that doesn't suit the origin, like the catalog load does (a missing
+    /// file is fine)
+    pub fn load(rp_id: String, origin: String) -> Self {
+        let origin = Url::parse(&origin).expect("passkey_origin must be a URL");
+        let webauthn = WebauthnBuilder::new(&rp_id, &origin)
+            .and_then(|builder| builder.rp_name(RP_NAME).timeout(CHALLENGE_TTL).build())
+            .expect("passkey_rp_id must be passkey_origin's host or a parent domain of it");
+        let passkeys = match std::fs::read_to_string(PASSKEYS_PATH) {
+            Ok(data) => serde_json::from_str(&data).expect("Failed to parse passkeys.json"),
+            Err(_) => Vec::new(),
+        };
+        PasskeyStore { webauthn, passkeys: AsyncMutex::new(passkeys), challenges: Mutex::new(HashMap::new()) }
+    }
+
+    async fn save(passkeys: &[StoredPasskey]) -> Result<(), ApiError> {
+        let json = serde_json::to_string_pretty(passkeys).map_err(|e| ApiError::internal(e.to_string()))?;
+        // Write then rename, so a failed write never leaves a truncated file
+        let partial = format!("{}.part", PASSKEYS_PATH);
+        let result = match rocket::tokio::fs::write(&partial, json).await {
+            Ok(()) => rocket::tokio::fs::rename(&partial, PASSKEYS_PATH).await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| ApiError::internal(format!("Failed to save passkeys: {}", e)))
+    }
+
+    fn issue(&self, challenge: &[u8], ceremony: Ceremony) {
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, (_, made)| made.elapsed() < CHALLENGE_TTL);
+        while challenges.len() >= MAX_CHALLENGES {
+            let oldest = challenges.iter().min_by_key(|(_, (_, made))| *made).map(|(challenge, _)| challenge.clone());
+            match oldest {
+                Some(oldest) => drop(challenges.remove(&oldest)),
+                None => break,
+            }
+        }
+        challenges.insert(URL_SAFE_NO_PAD.encode(challenge), (ceremony, Instant::now()));
+    }
+
+    /// Use up the challenge a response answers, answering with the ceremony
+    /// it was issued for
+    fn take_challenge(&self, client_data_json: &[u8]) -> Result<Ceremony, ApiError> {
+        let challenge = answered_challenge(client_data_json)?;
+        let ceremony = self.challenges.lock().unwrap().remove(&challenge);
+        match ceremony.filter(|(_, made)| made.elapsed() < CHALLENGE_TTL) {
+            Some((ceremony, _)) => Ok(ceremony),
+            None => Err(rejected("The challenge is unknown or has expired; try again")),
+        }
+    }
+
+    /// Options for adding a passkey named `name` to session `account`
+    pub async fn begin_registration(&self, account: &str, name: &str) -> Result<CreationChallengeResponse, ApiError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+            return Err(ApiError::new(
+                Status::BadRequest,
+                "invalid_parameters",
+                format!("name must be 1 to {} characters", MAX_NAME_CHARS),
+            ));
+        }
+        // The session's passkeys, so an authenticator isn't registered twice
+        let exclude_credentials: Vec<_> = self
+            .passkeys
+            .lock()
+            .await
+            .iter()
+            .filter(|passkey| passkey.account == account)
+            .map(|passkey| passkey.passkey.cred_id().clone())
+            .collect();
+        if exclude_credentials.len() >= MAX_PASSKEYS {
+            return Err(ApiError::new(
+                Status::UnprocessableEntity,
+                "too_many_passkeys",
+                format!("A session can have at most {} passkeys", MAX_PASSKEYS),
+            ));
+        }
+        let (options, state) = self
+            .webauthn
+            .start_passkey_registration(user_id(account), name, name, Some(exclude_credentials))
+            .map_err(|e| ApiError::internal(format!("Failed to start a passkey: {}", e)))?;
+        self.issue(&options.public_key.challenge, Ceremony::Register {
+            account: account.to_string(),
+            name: name.to_string(),
+            state,
+        });
+        Ok(options)
+    }
+
+    /// Check a new passkey and keep it for the session it was begun in
+    /// (`account`, which must be the caller's)
+    pub async fn finish_registration(
+        &self,
+        account: &str,
+        credential: RegisterPublicKeyCredential,
+    ) -> Result<PasskeySummary, ApiError> {
+        let Ceremony::Register { account: begun_in, name, state } = self.take_challenge(&credential.response.client_data_json)?
+        else {
+            return Err(rejected("That challenge was for signing in"));
+        };
+        if begun_in != account {
+            return Err(rejected("The passkey was begun in another session"));
+        }
+        let passkey = self.webauthn.finish_passkey_registration(&credential, &state).map_err(refused)?;
+
+        let passkey = StoredPasskey {
+            id: URL_SAFE_NO_PAD.encode(passkey.cred_id()),
+            account: account.to_string(),
+            name,
+            passkey,
+            created_at: Date::today().to_string(),
+            last_used: None,
+        };
+        let mut passkeys = self.passkeys.lock().await;
+        if passkeys.iter().any(|existing| existing.id == passkey.id) {
+            return Err(ApiError::new(Status::Conflict, "passkey_exists", "That passkey is already registered"));
+        }
+        passkeys.push(passkey);
+        if let Err(error) = Self::save(&passkeys).await {
+            passkeys.pop();
+            return Err(error);
+        }
+        Ok(PasskeySummary::from(passkeys.last().unwrap()))
+    }
+
+    /// Options for signing in with any passkey for this site
+    pub fn begin_sign_in(&self) -> Result<RequestChallengeResponse, ApiError> {
+        let (mut options, state) = self
+            .webauthn
+            .start_discoverable_authentication()
+            .map_err(|e| ApiError::internal(format!("Failed to start a passkey sign-in: {}", e)))?;
+        // Asked for with a button, so the browser's usual prompt rather than
+        // the quiet autofill ("conditional") one
+        options.mediation = None;
+        self.issue(&options.public_key.challenge, Ceremony::SignIn(state));
+        Ok(options)
+    }
+
+    /// Check a signed challenge, answering with the session the passkey
+    /// signs in to and the passkey
+    pub async fn finish_sign_in(&self, credential: PublicKeyCredential) -> Result<(String, PasskeySummary), ApiError> {
+        let Ceremony::SignIn(state) = self.take_challenge(&credential.response.client_data_json)? else {
+            return Err(rejected("That challenge was for adding a passkey"));
+        };
+        let (user, credential_id) = self.webauthn.identify_discoverable_authentication(&credential).map_err(refused)?;
+        let credential_id = URL_SAFE_NO_PAD.encode(credential_id);
+
+        let mut passkeys = self.passkeys.lock().await;
+        let Some(index) = passkeys.iter().position(|passkey| passkey.id == credential_id) else {
+            return Err(rejected("That passkey isn't registered here (it may have been removed)"));
+        };
+        if user_id(&passkeys[index].account) != user {
+            return Err(rejected("The passkey is for another session"));
+        }
+        let result = self
+            .webauthn
+            .finish_discoverable_authentication(&credential, state, &[DiscoverableKey::from(&passkeys[index].passkey)])
+            .map_err(refused)?;
+
+        let previous = passkeys[index].clone();
+        let passkey = &mut passkeys[index];
+        passkey.passkey.update_credential(&result);
+        passkey.last_used = Some(Date::today().to_string());
+        let signed_in = (passkey.account.clone(), PasskeySummary::from(&*passkey));
+        if let Err(error) = Self::save(&passkeys).await {
+            passkeys[index] = previous;
+            return Err(error);
+        }
+        Ok(signed_in)
+    }
+
+    /// Passkeys that sign in to session `account`, oldest first
+    pub async fn list(&self, account: &str) -> Vec<PasskeySummary> {
+        self.passkeys
+            .lock()
+            .await
+            .iter()
+            .filter(|passkey| passkey.account == account)
+            .map(PasskeySummary::from)
+            .collect()
+    }
+
+    /// Remove one of session `account`'s passkeys; false if it has no such passkey
+    pub async fn remove(&self, account: &str, id: &str) -> Result<bool, ApiError> {
+        let mut passkeys = self.passkeys.lock().await;
+        let Some(index) = passkeys.iter().position(|passkey| passkey.account == account && passkey.id == id) else {
+            return Ok(false);
+        };
+        let removed = passkeys.remove(index);
+        if let Err(error) = Self::save(&passkeys).await {
+            passkeys.insert(index, removed);
+            return Err(error);
+        }
+        Ok(true)
+    }
+}
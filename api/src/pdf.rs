@@ -0,0 +1,307 @@
+//! Minimal PDF writer for a song's scanned pages and setlist indexes
+//!
+//! Each JPEG is embedded as-is (DCTDecode) on its own page, scaled to US Letter
+//! width, so no image decoding or PDF library is needed. Setlist indexes are a
+//! single page of text in the built-in Helvetica font; a setlist's gig book is
+//! that index followed by every song's scans.
+
+use std::io::Cursor;
+use rocket::http::{ContentType, Header};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+
+/// Page width in points (US Letter); height follows the scan's aspect ratio
+const PAGE_WIDTH: f64 = 612.0;
+
+/// Pixel size and color components of a baseline or progressive JPEG,
+/// read from its start-of-frame marker
+fn jpeg_info(data: &[u8]) -> Option<(u32, u32, u8)> {
+    if data.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        let length = usize::from(u16::from_be_bytes([data[pos + 2], data[pos + 3]]));
+
+        // SOF0-SOF15, excluding DHT (C4), JPG (C8), and DAC (CC)
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let frame = data.get(pos + 4..pos + 4 + 6)?;
+            let height = u32::from(u16::from_be_bytes([frame[1], frame[2]]));
+            let width = u32::from(u16::from_be_bytes([frame[3], frame[4]]));
+            return Some((width, height, frame[5]));
+        }
+
+        pos += 2 + length;
+    }
+
+    None
+}
+
+/// Collects numbered objects (written in id order, starting at 1) and finishes
+/// the document with its cross-reference table
+struct PdfWriter {
+    pdf: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        PdfWriter { pdf: b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec(), offsets: Vec::new() }
+    }
+
+    fn begin_object(&mut self, id: usize) {
+        self.offsets.push(self.pdf.len());
+        self.pdf.extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+    }
+
+    /// The catalog (object 1) and a page tree (object 2) with the given pages
+    fn write_catalog(&mut self, page_ids: &[usize]) {
+        self.begin_object(1);
+        self.pdf.extend_from_slice(b"<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+
+        self.begin_object(2);
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        self.pdf.extend_from_slice(
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>\nendobj\n", kids.join(" "), page_ids.len()).as_bytes(),
+        );
+    }
+
+    fn write_stream(&mut self, id: usize, contents: &str) {
+        self.begin_object(id);
+        self.pdf.extend_from_slice(
+            format!("<< /Length {} >>\nstream\n{}\nendstream\nendobj\n", contents.len(), contents).as_bytes(),
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let object_count = self.offsets.len();
+        let xref_offset = self.pdf.len();
+        self.pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", object_count + 1).as_bytes());
+        for offset in &self.offsets {
+            self.pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        self.pdf.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", object_count + 1, xref_offset)
+                .as_bytes(),
+        );
+        self.pdf
+    }
+}
+
+/// Write a scanned page as objects `page_id` (page), `page_id + 1` (image), and
+/// `page_id + 2` (contents); `number` names the page in errors
+fn write_image_page(writer: &mut PdfWriter, page_id: usize, jpeg: &[u8], number: usize) -> Result<(), String> {
+    let (width, height, components) =
+        jpeg_info(jpeg).ok_or_else(|| format!("Page {} is not a readable JPEG", number))?;
+    let color_space = match components {
+        1 => "/DeviceGray",
+        4 => "/DeviceCMYK",
+        _ => "/DeviceRGB",
+    };
+    let (image_id, contents_id) = (page_id + 1, page_id + 2);
+    let page_height = PAGE_WIDTH * f64::from(height) / f64::from(width.max(1));
+
+    writer.begin_object(page_id);
+    writer.pdf.extend_from_slice(
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+             /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>\nendobj\n",
+            PAGE_WIDTH, page_height, image_id, contents_id,
+        )
+        .as_bytes(),
+    );
+
+    writer.begin_object(image_id);
+    writer.pdf.extend_from_slice(
+        format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} \
+             /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            width, height, color_space, jpeg.len(),
+        )
+        .as_bytes(),
+    );
+    writer.pdf.extend_from_slice(jpeg);
+    writer.pdf.extend_from_slice(b"\nendstream\nendobj\n");
+
+    let contents = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q", PAGE_WIDTH, page_height);
+    writer.write_stream(contents_id, &contents);
+    Ok(())
+}
+
+/// Assemble JPEG pages into a PDF document
+pub fn jpegs_to_pdf(pages: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    let mut writer = PdfWriter::new();
+
+    // Objects: 1 catalog, 2 page tree, then per page: page, image, contents
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 3 + i * 3).collect();
+    writer.write_catalog(&page_ids);
+
+    for (index, (jpeg, page_id)) in pages.iter().zip(&page_ids).enumerate() {
+        write_image_page(&mut writer, *page_id, jpeg, index + 1)?;
+    }
+
+    Ok(writer.finish())
+}
+
+/// Page height in points (US Letter) and margin for text pages
+const LETTER_HEIGHT: f64 = 792.0;
+const MARGIN: f64 = 54.0;
+
+/// Text as a PDF string literal (Helvetica here only covers ASCII reliably)
+fn pdf_text(text: &str) -> String {
+    let escaped: String = text
+        .chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect();
+    format!("({})", escaped)
+}
+
+/// Write an index page as objects 3 (page), 4 (font), and 5 (contents): a
+/// heading, then one line per row, shrinking the type as needed so even a long
+/// setlist fits on the page
+fn write_text_page(writer: &mut PdfWriter, heading: &str, lines: &[String]) {
+    writer.begin_object(3);
+    writer.pdf.extend_from_slice(
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.0} {:.0}] \
+             /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>\nendobj\n",
+            PAGE_WIDTH, LETTER_HEIGHT,
+        )
+        .as_bytes(),
+    );
+
+    writer.begin_object(4);
+    writer.pdf.extend_from_slice(
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>\nendobj\n",
+    );
+
+    // Heading takes about three lines' worth of space
+    let leading = ((LETTER_HEIGHT - 2.0 * MARGIN) / (lines.len() as f64 + 3.0)).min(16.0);
+    let size = leading * 0.8;
+    let mut contents = format!(
+        "BT /F1 20 Tf {:.2} {:.2} Td {} Tj ET\nBT /F1 {:.2} Tf {:.2} TL {:.2} {:.2} Td",
+        MARGIN,
+        LETTER_HEIGHT - MARGIN - 20.0,
+        pdf_text(heading),
+        size,
+        leading,
+        MARGIN,
+        LETTER_HEIGHT - MARGIN - 20.0 - 2.0 * leading,
+    );
+    for line in lines {
+        contents.push_str(&format!(" {} Tj T*", pdf_text(line)));
+    }
+    contents.push_str(" ET");
+    writer.write_stream(5, &contents);
+}
+
+/// A one-page index: a heading, then one line per row
+pub fn text_page_pdf(heading: &str, lines: &[String]) -> Vec<u8> {
+    let mut writer = PdfWriter::new();
+
+    // Objects: 1 catalog, 2 page tree, 3 page, 4 font, 5 contents
+    writer.write_catalog(&[3]);
+    write_text_page(&mut writer, heading, lines);
+
+    writer.finish()
+}
+
+/// An index page followed by JPEG pages, e.g. a setlist's index and then
+/// every page of every song, for printing the whole set at once
+pub fn book_pdf(heading: &str, lines: &[String], pages: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    let mut writer = PdfWriter::new();
+
+    // Objects: 1 catalog, 2 page tree, 3-5 index page, then per scan: page,
+    // image, contents
+    let scan_ids: Vec<usize> = (0..pages.len()).map(|i| 6 + i * 3).collect();
+    let page_ids: Vec<usize> = std::iter::once(3).chain(scan_ids.iter().copied()).collect();
+    writer.write_catalog(&page_ids);
+    write_text_page(&mut writer, heading, lines);
+
+    for (index, (jpeg, page_id)) in pages.iter().zip(&scan_ids).enumerate() {
+        // The index is page 1
+        write_image_page(&mut writer, *page_id, jpeg, index + 2)?;
+    }
+
+    Ok(writer.finish())
+}
+
+/// File-name-safe version of a song title (ASCII letters, digits, and
+/// underscores; e.g. "STELLA BY STARLIGHT" -> "STELLA_BY_STARLIGHT")
+pub fn file_stem(title: &str) -> String {
+    let stem: Vec<&str> = title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if stem.is_empty() { "chart".to_string() } else { stem.join("_") }
+}
+
+/// A PDF served as a download with the given file name
+pub struct PdfDownload {
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+impl<'r> Responder<'r, 'static> for PdfDownload {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::PDF)
+            .header(Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .sized_body(self.bytes.len(), Cursor::new(self.bytes))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::jpeg_info;
+
+    /// A JPEG's start, with an APP0 segment before the frame header
+    fn jpeg(frame_marker: u8, width: u16, height: u16, components: u8) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        data.extend(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        data.extend([0xFF, frame_marker, 0x00, 0x11, 0x08]);
+        data.extend(height.to_be_bytes());
+        data.extend(width.to_be_bytes());
+        data.push(components);
+        data
+    }
+
+    #[test]
+    fn reads_the_frame_header() {
+        assert_eq!(jpeg_info(&jpeg(0xC0, 1700, 2200, 1)), Some((1700, 2200, 1)));
+        assert_eq!(jpeg_info(&jpeg(0xC2, 640, 480, 3)), Some((640, 480, 3)));
+    }
+
+    #[test]
+    fn skips_markers_that_arent_frames() {
+        // A Huffman table (C4) comes before the frame in some encoders
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xC4, 0x00, 0x04, 0x00, 0x00];
+        data.extend(&jpeg(0xC0, 800, 600, 3)[2..]);
+        assert_eq!(jpeg_info(&data), Some((800, 600, 3)));
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_jpeg() {
+        assert_eq!(jpeg_info(b""), None);
+        assert_eq!(jpeg_info(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(jpeg_info(&[0xFF, 0xD8, 0x00, 0xC0]), None);
+        // Cut off inside the frame header
+        assert_eq!(jpeg_info(&jpeg(0xC0, 800, 600, 3)[..27]), None);
+        // No frame header at all
+        assert_eq!(jpeg_info(&[0xFF, 0xD8, 0xFF, 0xD9, 0x00, 0x02]), None);
+    }
+}
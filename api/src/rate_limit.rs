@@ -0,0 +1,117 @@
+//! Per-client-address limits on anonymous writes
+//!
+//! Limits per session only hold back a visitor who keeps their cookie; clearing
+//! it starts a new session with a clean slate. A `RateLimiter` counts requests
+//! by the client's address instead, over a sliding window. IPv6 clients are
+//! counted by their /64, since one host is usually handed a whole /64.
+//!
+//! The address is Rocket's `client_ip`: the `X-Real-IP` header when present,
+//! else the peer address. Behind a reverse proxy that sets that header this is
+//! the real client; a server exposed directly should turn the header off
+//! (`ROCKET_IP_HEADER=false`), or clients can pick their own address.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Addresses tracked at once; past this, expired ones are dropped, and if
+/// none have expired, requests from new addresses are refused
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// At most `limit` requests per client address in any `window`
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    /// When each address's requests in the window were made, oldest first
+    hits: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        RateLimiter { limit, window, hits: Mutex::new(HashMap::new()) }
+    }
+
+    /// Count a request from `client` (None when Rocket doesn't know it, e.g. in
+    /// local tests); false if it's over the limit, in which case it isn't counted
+    pub fn allow(&self, client: Option<IpAddr>) -> bool {
+        let client = client.map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), bucket);
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap();
+        if hits.len() >= MAX_TRACKED_CLIENTS && !hits.contains_key(&client) {
+            hits.retain(|_, times| times.back().is_some_and(|last| now.duration_since(*last) < self.window));
+            if hits.len() >= MAX_TRACKED_CLIENTS {
+                return false;
+            }
+        }
+
+        let times = hits.entry(client).or_default();
+        while times.front().is_some_and(|first| now.duration_since(*first) >= self.window) {
+            times.pop_front();
+        }
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// The address requests are counted under: IPv4 as is, IPv6 by its /64
+fn bucket(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V4(_) => address,
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => IpAddr::V6((u128::from(v6) & !((1u128 << 64) - 1)).into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+    use super::*;
+
+    fn v6(address: &str) -> Option<IpAddr> {
+        Some(IpAddr::V6(address.parse::<Ipv6Addr>().unwrap()))
+    }
+
+    #[test]
+    fn ipv6_is_counted_by_its_64() {
+        let address: IpAddr = "2001:db8:1:2:aaaa:bbbb:cccc:dddd".parse().unwrap();
+        assert_eq!(bucket(address), "2001:db8:1:2::".parse::<IpAddr>().unwrap());
+        let mapped: IpAddr = "::ffff:192.0.2.7".parse().unwrap();
+        assert_eq!(bucket(mapped), IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7)));
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7));
+        assert_eq!(bucket(v4), v4);
+    }
+
+    #[test]
+    fn one_64_shares_a_limit() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        assert!(limiter.allow(v6("2001:db8::1")));
+        assert!(limiter.allow(v6("2001:db8::ffff:2")));
+        assert!(!limiter.allow(v6("2001:db8::3")));
+        // The next /64 over is someone else
+        assert!(limiter.allow(v6("2001:db8:0:1::1")));
+    }
+
+    #[test]
+    fn unknown_clients_share_a_limit() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        assert!(limiter.allow(None));
+        assert!(!limiter.allow(None));
+        assert!(limiter.allow(Some(IpAddr::V4(Ipv4Addr::LOCALHOST))));
+    }
+
+    #[test]
+    fn the_window_slides() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(50));
+        let client = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert!(limiter.allow(client));
+        assert!(!limiter.allow(client));
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.allow(client));
+    }
+}
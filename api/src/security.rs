@@ -0,0 +1,198 @@
+//! Security headers for every response, so public instances pass basic scans
+//!
+//! A fairing adds a Content-Security-Policy, X-Frame-Options, and
+//! Referrer-Policy to each response. The default policy is the one the UI
+//! needs, so a deployment that serves the UI through Rocket (or copies these
+//! headers onto its static host) can use it as is:
+//!
+//! - scripts from the UI's own origin only, plus `'wasm-unsafe-eval'` to
+//!   compile the WASM bundle (the UI's build moves Trunk's loader out of
+//!   `index.html`, and registers the service worker from a file, so nothing
+//!   runs inline)
+//! - inline styles (the `<style>` block and Yew's `style` attributes) and Pico
+//!   from jsDelivr
+//! - images from the UI's origin and the image mirrors (the CDN), plus `data:`
+//!   and `blob:` URLs
+//! - audio from any https origin (attachments link anywhere, but only over
+//!   https) and the embedded YouTube player
+//! - no framing by other sites
+//!
+//! Each header can be replaced per deployment (`content_security_policy`,
+//! `frame_options`, `referrer_policy` in Rocket config); an empty value drops
+//! the header. Rocket's Shield keeps adding X-Content-Type-Options.
+
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use rocket::shield::{Frame, Shield};
+
+/// Default X-Frame-Options: never framed (matches `frame-ancestors 'none'`)
+pub const DEFAULT_FRAME_OPTIONS: &str = "DENY";
+
+/// Default Referrer-Policy: full URLs only to this origin, just the origin
+/// to others over https
+pub const DEFAULT_REFERRER_POLICY: &str = "strict-origin-when-cross-origin";
+
+/// Stylesheet host (Pico CSS in `ui/index.html`)
+const STYLE_CDN: &str = "https://cdn.jsdelivr.net";
+
+/// Where reference recordings are embedded from (`recording_player.rs`)
+const VIDEO_EMBED: &str = "https://www.youtube-nocookie.com";
+
+/// The headers added to each response (None leaves one out)
+pub struct SecurityHeaders {
+    content_security_policy: Option<String>,
+    frame_options: Option<String>,
+    referrer_policy: Option<String>,
+}
+
+impl SecurityHeaders {
+    /// Empty values are dropped
+    pub fn new(content_security_policy: String, frame_options: String, referrer_policy: String) -> Self {
+        let non_empty = |value: String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        SecurityHeaders {
+            content_security_policy: non_empty(content_security_policy),
+            frame_options: non_empty(frame_options),
+            referrer_policy: non_empty(referrer_policy),
+        }
+    }
+}
+
+/// The scheme and host of a URL (`https://a.example/x` -> `https://a.example`)
+fn origin(url: &str) -> &str {
+    let host_start = url.find("://").map_or(0, |index| index + 3);
+    url[host_start..].find(['/', '?', '#']).map_or(url, |index| &url[..host_start + index])
+}
+
+/// The policy the UI needs, with images allowed from `image_mirrors`
+pub fn default_policy(image_mirrors: &[String]) -> String {
+    let mut image_sources = vec!["'self'", "data:", "blob:"];
+    for mirror in image_mirrors {
+        let mirror = origin(mirror);
+        if !image_sources.contains(&mirror) {
+            image_sources.push(mirror);
+        }
+    }
+    [
+        "default-src 'self'".to_string(),
+        "script-src 'self' 'wasm-unsafe-eval'".to_string(),
+        format!("style-src 'self' 'unsafe-inline' {}", STYLE_CDN),
+        format!("img-src {}", image_sources.join(" ")),
+        "media-src 'self' https:".to_string(),
+        format!("frame-src {}", VIDEO_EMBED),
+        "connect-src 'self'".to_string(),
+        "worker-src 'self'".to_string(),
+        "manifest-src 'self'".to_string(),
+        "object-src 'none'".to_string(),
+        "base-uri 'self'".to_string(),
+        "form-action 'self'".to_string(),
+        "frame-ancestors 'none'".to_string(),
+    ]
+    .join("; ")
+}
+
+/// Rocket's Shield without its own X-Frame-Options (it keeps
+/// X-Content-Type-Options and Permissions-Policy), so an empty
+/// `frame_options` really leaves the header out
+pub fn shield() -> Shield {
+    Shield::default().disable::<Frame>()
+}
+
+/// Add the headers to every response
+pub fn security_headers(headers: SecurityHeaders) -> AdHoc {
+    let headers = std::sync::Arc::new(headers);
+    AdHoc::on_response("Security Headers", move |_, response| {
+        let headers = headers.clone();
+        Box::pin(async move {
+            let all = [
+                ("Content-Security-Policy", &headers.content_security_policy),
+                ("X-Frame-Options", &headers.frame_options),
+                ("Referrer-Policy", &headers.referrer_policy),
+            ];
+            for (name, value) in all {
+                if let Some(value) = value {
+                    response.set_header(Header::new(name, value.clone()));
+                }
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+    use super::*;
+
+    #[test]
+    fn origin_keeps_the_scheme_host_and_port() {
+        assert_eq!(origin("https://a.example/x/y"), "https://a.example");
+        assert_eq!(origin("https://a.example"), "https://a.example");
+        assert_eq!(origin("http://localhost:9000/pages/"), "http://localhost:9000");
+        assert_eq!(origin("https://a.example?size=full"), "https://a.example");
+        assert_eq!(origin("a.example/pages"), "a.example");
+    }
+
+    #[test]
+    fn the_default_policy_allows_each_mirror_host_once() {
+        let mirrors = [
+            "https://cdn.example/realbook/".to_string(),
+            "https://cdn.example/backup/".to_string(),
+            "http://localhost:9000/pages".to_string(),
+        ];
+        assert_eq!(
+            default_policy(&mirrors),
+            "default-src 'self'; \
+             script-src 'self' 'wasm-unsafe-eval'; \
+             style-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; \
+             img-src 'self' data: blob: https://cdn.example http://localhost:9000; \
+             media-src 'self' https:; \
+             frame-src https://www.youtube-nocookie.com; \
+             connect-src 'self'; \
+             worker-src 'self'; \
+             manifest-src 'self'; \
+             object-src 'none'; \
+             base-uri 'self'; \
+             form-action 'self'; \
+             frame-ancestors 'none'"
+        );
+    }
+
+    #[rocket::get("/")]
+    fn index() -> &'static str {
+        "ok"
+    }
+
+    fn client(headers: SecurityHeaders) -> Client {
+        let rocket = rocket::build()
+            .mount("/", rocket::routes![index])
+            .attach(shield())
+            .attach(security_headers(headers));
+        Client::untracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn every_response_gets_the_headers() {
+        let client = client(SecurityHeaders::new(
+            default_policy(&[]),
+            DEFAULT_FRAME_OPTIONS.to_string(),
+            DEFAULT_REFERRER_POLICY.to_string(),
+        ));
+        for path in ["/", "/missing"] {
+            let response = client.get(path).dispatch();
+            let headers = response.headers();
+            assert_eq!(headers.get_one("Content-Security-Policy"), Some(default_policy(&[]).as_str()));
+            assert_eq!(headers.get("X-Frame-Options").collect::<Vec<_>>(), ["DENY"]);
+            assert_eq!(headers.get_one("Referrer-Policy"), Some(DEFAULT_REFERRER_POLICY));
+            assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+        }
+    }
+
+    #[test]
+    fn an_empty_value_leaves_its_header_out() {
+        let client = client(SecurityHeaders::new(" ".to_string(), String::new(), "no-referrer".to_string()));
+        let response = client.get("/").dispatch();
+        let headers = response.headers();
+        assert!(!headers.contains("Content-Security-Policy"));
+        assert!(!headers.contains("X-Frame-Options"));
+        assert_eq!(headers.get_one("Referrer-Policy"), Some("no-referrer"));
+    }
+}
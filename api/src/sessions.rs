@@ -0,0 +1,379 @@
+//! Anonymous server-side sessions: a step between local storage and accounts
+//!
+//! A browser gets a `realbook_session` cookie the first time it hits a route
+//! that takes the `AnonSession` guard (routes that store something in the
+//! session, or link it to another device), renewed on each request that uses
+//! the session. Routes that only read it (`/session`, `/random`, `/me/export`)
+//! look it up with `SessionStore::current` and make do without one, so casual
+//! visitors and crawlers don't fill the store. Starting sessions is limited
+//! per client address (`rate_limit.rs`), as clearing the cookie starts another. The
+//! cookie holds only a random 128-bit id (HttpOnly, SameSite=Lax, and Secure
+//! when `secure_cookies` is set); what the session remembers (recently viewed
+//! songs and recent Random picks) stays in memory on the server, so there's
+//! nothing in the cookie to read or tamper with and Rocket's private
+//! (encrypted) cookies aren't needed. Sessions idle for `SESSION_TTL` are
+//! dropped, and everything is forgotten on restart. Routes that change a
+//! session also take the `Csrf` guard (`csrf.rs`).
+//!
+//! A session also holds the records synced between devices (`sync.rs`). To
+//! bring another device in, one device asks for a short-lived link code and
+//! the other enters it, which points its cookie at the same session. A passkey
+//! (`passkeys.rs`) does the same without a second device at hand.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use rocket::http::{Cookie, CookieJar, SameSite, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use crate::errors::ApiError;
+use crate::rate_limit::RateLimiter;
+use crate::sync::SyncLog;
+
+/// Name of the cookie carrying the session id
+pub const SESSION_COOKIE: &str = "realbook_session";
+
+/// How long a session survives without requests
+const SESSION_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Sessions kept at once; past this the least recently seen is dropped
+const MAX_SESSIONS: usize = 10_000;
+
+/// Sessions started per client address per hour
+const MAX_STARTS_PER_CLIENT_HOUR: usize = 30;
+
+/// Recently viewed songs remembered per session
+pub const MAX_VIEWED: usize = 50;
+
+/// Recent Random picks remembered per session (and passed over by `/random`)
+const MAX_RANDOMS: usize = 20;
+
+/// How long a link code can be used
+pub const LINK_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Characters in link codes (no 0/O or 1/I/L to misread on a tablet)
+const LINK_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+#[derive(Debug)]
+struct SessionState {
+    /// Entry ids, newest first
+    viewed: Vec<u32>,
+    /// Entry ids, newest first
+    randoms: Vec<u32>,
+    sync: SyncLog,
+    last_seen: Instant,
+}
+
+impl SessionState {
+    fn new() -> Self {
+        SessionState { viewed: Vec::new(), randoms: Vec::new(), sync: SyncLog::default(), last_seen: Instant::now() }
+    }
+}
+
+/// Move `id` to the front of `ids`, keeping at most `max`
+fn push_recent(ids: &mut Vec<u32>, id: u32, max: usize) {
+    ids.retain(|existing| *existing != id);
+    ids.insert(0, id);
+    ids.truncate(max);
+}
+
+/// Every live session, by id
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, SessionState>>,
+    /// Unused link codes: the session they join and when they were made
+    links: Mutex<HashMap<String, (String, Instant)>>,
+    /// Whether cookies are marked Secure (served over https)
+    secure_cookies: bool,
+    starts: RateLimiter,
+}
+
+impl SessionStore {
+    pub fn new(secure_cookies: bool) -> Self {
+        SessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            links: Mutex::new(HashMap::new()),
+            secure_cookies,
+            starts: RateLimiter::new(MAX_STARTS_PER_CLIENT_HOUR, Duration::from_secs(60 * 60)),
+        }
+    }
+
+    /// Set the session cookie, expiring with the session
+    fn set_cookie(&self, cookies: &CookieJar<'_>, id: String) {
+        cookies.add(
+            Cookie::build((SESSION_COOKIE, id))
+                .path("/")
+                .http_only(true)
+                .secure(self.secure_cookies)
+                .same_site(SameSite::Lax)
+                .max_age(rocket::time::Duration::seconds(SESSION_TTL.as_secs() as i64)),
+        );
+    }
+
+    /// Refresh the live session `id` as of `now`; false if it's unknown or
+    /// expired
+    fn touch(&self, id: &str, now: Instant) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get_mut(id) {
+            Some(state) if now.duration_since(state.last_seen) < SESSION_TTL => {
+                state.last_seen = now;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The caller's live session, refreshed along with its cookie, or None
+    /// if the request carries none (nothing is started)
+    pub fn current(&self, cookies: &CookieJar<'_>) -> Option<AnonSession> {
+        let id = cookies.get(SESSION_COOKIE).map(|cookie| cookie.value().to_string())?;
+        if !self.touch(&id, Instant::now()) {
+            return None;
+        }
+        self.set_cookie(cookies, id.clone());
+        Some(AnonSession { id })
+    }
+
+    /// The id of a new session for `client`, started at `now`; None if that
+    /// address has started too many lately
+    fn start(&self, client: Option<IpAddr>, now: Instant) -> Option<String> {
+        if !self.starts.allow(client) {
+            return None;
+        }
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= MAX_SESSIONS {
+            sessions.retain(|_, state| now.duration_since(state.last_seen) < SESSION_TTL);
+        }
+        if sessions.len() >= MAX_SESSIONS {
+            let stalest = sessions.iter().min_by_key(|(_, state)| state.last_seen).map(|(id, _)| id.clone());
+            if let Some(stalest) = stalest {
+                sessions.remove(&stalest);
+            }
+        }
+        let id = format!("{:032x}", rand::random::<u128>());
+        sessions.insert(id.clone(), SessionState { last_seen: now, ..SessionState::new() });
+        Some(id)
+    }
+
+    fn with<T>(&self, session: &AnonSession, f: impl FnOnce(&mut SessionState) -> T) -> T {
+        let mut sessions = self.sessions.lock().unwrap();
+        // Forgotten since the guard ran: carry on with a blank slate
+        f(sessions.entry(session.id.clone()).or_insert_with(SessionState::new))
+    }
+
+    /// Songs viewed in this session, newest first
+    pub fn viewed(&self, session: &AnonSession) -> Vec<u32> {
+        self.with(session, |state| state.viewed.clone())
+    }
+
+    pub fn record_view(&self, session: &AnonSession, entry_id: u32) {
+        self.with(session, |state| push_recent(&mut state.viewed, entry_id, MAX_VIEWED));
+    }
+
+    /// Replace the songs viewed in this session (newest first)
+    pub fn set_viewed(&self, session: &AnonSession, mut entry_ids: Vec<u32>) {
+        entry_ids.truncate(MAX_VIEWED);
+        self.with(session, |state| state.viewed = entry_ids);
+    }
+
+    /// Recent Random picks in this session, newest first
+    pub fn randoms(&self, session: &AnonSession) -> Vec<u32> {
+        self.with(session, |state| state.randoms.clone())
+    }
+
+    pub fn record_random(&self, session: &AnonSession, entry_id: u32) {
+        self.with(session, |state| push_recent(&mut state.randoms, entry_id, MAX_RANDOMS));
+    }
+
+    /// The session's synced records, and the size of every other session's
+    pub fn with_sync<T>(&self, session: &AnonSession, f: impl FnOnce(&mut SyncLog, usize) -> T) -> T {
+        let mut sessions = self.sessions.lock().unwrap();
+        let elsewhere = sessions
+            .iter()
+            .filter(|(id, _)| **id != session.id)
+            .map(|(_, state)| state.sync.bytes())
+            .sum();
+        let state = sessions.entry(session.id.clone()).or_insert_with(SessionState::new);
+        f(&mut state.sync, elsewhere)
+    }
+
+    /// A new code that lets another browser join this session within `LINK_TTL`
+    pub fn link_code(&self, session: &AnonSession) -> String {
+        let mut links = self.links.lock().unwrap();
+        links.retain(|_, (_, made)| made.elapsed() < LINK_TTL);
+        let code: String = (0..8)
+            .map(|_| LINK_ALPHABET[rand::random::<usize>() % LINK_ALPHABET.len()] as char)
+            .collect();
+        links.insert(code.clone(), (session.id.clone(), Instant::now()));
+        format!("{}-{}", &code[..4], &code[4..])
+    }
+
+    /// Use up a link code at `now`: the session it was made for, or None if
+    /// the code is unknown, used, or expired, or the session is gone. Case,
+    /// spaces, and dashes don't matter.
+    fn redeem(&self, code: &str, now: Instant) -> Option<String> {
+        let code: String = code.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_uppercase()).collect();
+        let (id, made) = self.links.lock().unwrap().remove(&code)?;
+        if now.duration_since(made) >= LINK_TTL || !self.sessions.lock().unwrap().contains_key(&id) {
+            return None;
+        }
+        Some(id)
+    }
+
+    /// Point this browser's cookie at the session a link code was made for;
+    /// false if the code can't be used (see `redeem`)
+    pub fn join(&self, code: &str, cookies: &CookieJar<'_>) -> bool {
+        let Some(id) = self.redeem(code, Instant::now()) else {
+            return false;
+        };
+        self.set_cookie(cookies, id);
+        true
+    }
+
+    /// Point this browser's cookie at session `id` (signing in with a
+    /// passkey), starting it over if it's gone (after a restart)
+    pub fn resume(&self, id: &str, cookies: &CookieJar<'_>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        let state = sessions.entry(id.to_string()).or_insert_with(SessionState::new);
+        state.last_seen = Instant::now();
+        self.set_cookie(cookies, id.to_string());
+    }
+
+    /// Drop this browser's cookie, leaving the session to the other devices
+    /// on it (signing out); the next request starts a new session
+    pub fn leave(&self, cookies: &CookieJar<'_>) {
+        cookies.remove(Cookie::from(SESSION_COOKIE));
+    }
+
+    /// Drop the caller's session, if any, and its cookie
+    pub fn forget(&self, cookies: &CookieJar<'_>) {
+        if let Some(cookie) = cookies.get(SESSION_COOKIE) {
+            self.sessions.lock().unwrap().remove(cookie.value());
+        }
+        cookies.remove(Cookie::from(SESSION_COOKIE));
+    }
+}
+
+/// Request guard for the caller's anonymous session, started if the request
+/// didn't carry a live one; the cookie is (re)set so it expires with the
+/// session. Take it as `Result<AnonSession, ApiError>` and `?` it, like `Csrf`:
+/// starting a session fails once the client's address has started too many.
+pub struct AnonSession {
+    id: String,
+}
+
+impl AnonSession {
+    /// The session's id (what passkeys sign in to)
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AnonSession {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ApiError> {
+        let store = request.rocket().state::<SessionStore>().expect("SessionStore is managed");
+        let cookies = request.cookies();
+        if let Some(session) = store.current(cookies) {
+            return Outcome::Success(session);
+        }
+        match store.start(request.client_ip(), Instant::now()) {
+            Some(id) => {
+                store.set_cookie(cookies, id.clone());
+                Outcome::Success(AnonSession { id })
+            }
+            None => Outcome::Error((
+                Status::TooManyRequests,
+                ApiError::new(
+                    Status::TooManyRequests,
+                    "too_many_sessions",
+                    "Too many sessions have been started from your network lately; try again in an hour",
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use super::*;
+
+    fn client(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, last)))
+    }
+
+    #[test]
+    fn sessions_expire_when_idle() {
+        let store = SessionStore::new(false);
+        let now = Instant::now();
+        let id = store.start(client(1), now).unwrap();
+        assert!(store.touch(&id, now + SESSION_TTL / 2));
+        // Touching renewed it, so it's measured from then
+        assert!(store.touch(&id, now + SESSION_TTL));
+        assert!(!store.touch(&id, now + SESSION_TTL * 2 + Duration::from_secs(1)));
+        assert!(!store.touch("not-a-session", now));
+    }
+
+    #[test]
+    fn starting_sessions_is_limited_per_address() {
+        let store = SessionStore::new(false);
+        let now = Instant::now();
+        for _ in 0..MAX_STARTS_PER_CLIENT_HOUR {
+            assert!(store.start(client(1), now).is_some());
+        }
+        assert!(store.start(client(1), now).is_none());
+        assert!(store.start(client(2), now).is_some());
+    }
+
+    #[test]
+    fn a_full_store_drops_expired_sessions_then_the_stalest() {
+        let store = SessionStore::new(false);
+        let now = Instant::now();
+        {
+            let mut sessions = store.sessions.lock().unwrap();
+            for number in 0..MAX_SESSIONS {
+                let last_seen = now + Duration::from_secs(number as u64);
+                sessions.insert(number.to_string(), SessionState { last_seen, ..SessionState::new() });
+            }
+        }
+        let id = store.start(client(1), now + Duration::from_secs(MAX_SESSIONS as u64)).unwrap();
+        {
+            let sessions = store.sessions.lock().unwrap();
+            assert_eq!(sessions.len(), MAX_SESSIONS);
+            assert!(!sessions.contains_key("0") && sessions.contains_key("1") && sessions.contains_key(&id));
+        }
+
+        // Once they've all gone idle, they all go at once
+        let later = now + SESSION_TTL + Duration::from_secs(MAX_SESSIONS as u64 + 1);
+        let last = store.start(client(2), later).unwrap();
+        let sessions = store.sessions.lock().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions.contains_key(&last));
+    }
+
+    #[test]
+    fn link_codes_are_used_once_within_their_time() {
+        let store = SessionStore::new(false);
+        let now = Instant::now();
+        let session = AnonSession { id: store.start(client(1), now).unwrap() };
+
+        let code = store.link_code(&session);
+        assert_eq!(code.len(), 9);
+        assert_eq!(&code[4..5], "-");
+        assert!(code.bytes().filter(|c| *c != b'-').all(|c| LINK_ALPHABET.contains(&c)));
+        // Typed on a phone: lower case, no dash
+        let typed = code.replace('-', " ").to_lowercase();
+        assert_eq!(store.redeem(&typed, now).as_deref(), Some(session.id()));
+        assert_eq!(store.redeem(&code, now), None);
+
+        let code = store.link_code(&session);
+        assert_eq!(store.redeem(&code, Instant::now() + LINK_TTL), None);
+
+        let code = store.link_code(&session);
+        store.sessions.lock().unwrap().remove(session.id());
+        assert_eq!(store.redeem(&code, Instant::now()), None);
+        assert_eq!(store.redeem("ABCD-EFGH", now), None);
+    }
+}
@@ -0,0 +1,425 @@
+//! Sync of the browser's local-first data through the anonymous session
+//!
+//! The UI keeps favorites, setlists, notes, and the rest in localStorage and
+//! works fully offline. To carry them across devices it splits each store into
+//! records (one per song, setlist, or practice session) and exchanges the ones
+//! that changed with `/sync`. Each record carries the time it was last changed
+//! on the device that changed it; the later one wins (last write wins), and a
+//! deletion is a record without a value so it travels like any other change.
+//!
+//! The server numbers every record it accepts. A client sends the highest
+//! number it has seen (its cursor) and gets back everything accepted since,
+//! so a device that was offline for a week catches up in one exchange. Records
+//! live in the session (see `sessions.rs`), which a second device joins with a
+//! link code; they're lost on restart. Each log has a random epoch that the
+//! client keeps beside its cursor, so a cursor from a log that's gone (even
+//! one whose numbers have since caught up) comes back `reset` and the client
+//! sends everything it has again.
+//!
+//! A deletion is kept until every device on the session has synced past it,
+//! then dropped, so deleted records don't count against the limits forever.
+//! Devices that haven't synced for `DEVICE_TTL` stop holding deletions back;
+//! one that comes back after that starts over with a reset.
+//!
+//! Records are held in memory, so they're bounded three ways: each record's
+//! size, each session's total, and the total across every session (sessions
+//! are many and cheap to make, so the per-session budget alone doesn't bound
+//! memory). A request that would go over any of them is refused whole.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::archive::is_local_section;
+use crate::errors::ApiError;
+
+/// Largest record, counting its store, key, and value as JSON (a song's
+/// annotations are the big ones)
+const MAX_RECORD_BYTES: usize = 256 * 1024;
+
+/// Bytes of records kept per session, counted as for `MAX_RECORD_BYTES`
+const MAX_SESSION_BYTES: usize = 4 * 1024 * 1024;
+
+/// Bytes of records kept across every session
+const MAX_TOTAL_BYTES: usize = 256 * 1024 * 1024;
+
+/// Largest `/sync` request read (no request can usefully carry more than a
+/// session keeps)
+pub const MAX_SYNC_BYTES: usize = MAX_SESSION_BYTES;
+
+/// Records kept per session (deletions included)
+const MAX_RECORDS: usize = 20_000;
+
+/// How long a device that stops syncing still holds back dropping deletions
+const DEVICE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Devices tracked per session; past this the one synced longest ago is
+/// forgotten
+const MAX_DEVICES: usize = 64;
+
+/// Longest device id
+const MAX_DEVICE_ID: usize = 64;
+
+/// One item of a local store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRecord {
+    /// Store it belongs to (an archive section name, e.g. `notes`)
+    pub store: String,
+    /// The item's key within the store (an entry id, setlist id, ...)
+    pub key: String,
+    /// The item, or None once it's been deleted
+    pub value: Option<Value>,
+    /// When it was last changed (ms since the Unix epoch, device clock)
+    pub updated_at: f64,
+}
+
+/// A client's half of an exchange
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    /// Highest record number the client has seen (0 the first time)
+    #[serde(default)]
+    pub cursor: u64,
+    /// Epoch of the log the cursor is from (None the first time)
+    #[serde(default)]
+    pub epoch: Option<String>,
+    /// Random id the device made for itself, to know when every device has
+    /// seen a deletion
+    pub device: String,
+    /// Records changed on the client since its last exchange
+    #[serde(default)]
+    pub changes: Vec<SyncRecord>,
+}
+
+/// The server's half of an exchange
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    /// Highest record number now, to send next time
+    pub cursor: u64,
+    /// Epoch of this log, to send with the cursor
+    pub epoch: String,
+    /// The client's cursor was unknown here (from a log the server lost, or
+    /// behind deletions already dropped), so it should send all of its
+    /// records again
+    pub reset: bool,
+    /// How many of the client's changes were newer and taken
+    pub accepted: usize,
+    /// Records the client doesn't have: changed elsewhere since its cursor, or
+    /// newer than the change it sent
+    pub changes: Vec<SyncRecord>,
+}
+
+impl SyncRecord {
+    /// What the record counts for against the size limits
+    fn size(&self) -> usize {
+        self.store.len() + self.key.len() + self.value.as_ref().map_or(0, |value| value.to_string().len())
+    }
+}
+
+impl SyncRequest {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.device.is_empty() || self.device.len() > MAX_DEVICE_ID {
+            return Err(ApiError::new(
+                Status::UnprocessableEntity,
+                "invalid_device",
+                format!("device must be 1-{} characters", MAX_DEVICE_ID),
+            ));
+        }
+        for record in &self.changes {
+            if !is_local_section(&record.store) {
+                return Err(ApiError::new(
+                    Status::UnprocessableEntity,
+                    "invalid_record",
+                    format!("Unknown store \"{}\"", record.store),
+                ));
+            }
+            if !record.updated_at.is_finite() || record.updated_at < 0.0 {
+                return Err(ApiError::new(
+                    Status::UnprocessableEntity,
+                    "invalid_record",
+                    "updated_at must be a time in ms since the Unix epoch",
+                ));
+            }
+            if record.size() > MAX_RECORD_BYTES {
+                return Err(ApiError::new(
+                    Status::PayloadTooLarge,
+                    "record_too_large",
+                    format!(
+                        "Record {}/{} is {} KiB; a synced record is at most {} KiB",
+                        record.store,
+                        record.key,
+                        record.size().div_ceil(1024),
+                        MAX_RECORD_BYTES / 1024
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A session's synced records, each with the number it was accepted under
+#[derive(Debug)]
+pub struct SyncLog {
+    records: HashMap<(String, String), (SyncRecord, u64)>,
+    last: u64,
+    /// Total size of the records
+    bytes: usize,
+    /// Random id of this log, so cursors from another one are recognized
+    epoch: String,
+    /// Each device's cursor as of its last exchange, and when that was
+    devices: HashMap<String, (u64, Instant)>,
+    /// Highest number of a deletion dropped; a cursor behind it missed one
+    pruned: u64,
+}
+
+impl Default for SyncLog {
+    fn default() -> Self {
+        SyncLog {
+            records: HashMap::new(),
+            last: 0,
+            bytes: 0,
+            epoch: format!("{:016x}", rand::random::<u64>()),
+            devices: HashMap::new(),
+            pruned: 0,
+        }
+    }
+}
+
+impl SyncLog {
+    /// Total size of the records, as counted against the limits
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Note where `device` is up to, then drop the deletions every device has
+    /// synced past
+    fn prune(&mut self, device: &str, cursor: u64) {
+        self.devices.retain(|_, (_, seen)| seen.elapsed() < DEVICE_TTL);
+        if !self.devices.contains_key(device) && self.devices.len() >= MAX_DEVICES {
+            let stalest = self.devices.iter().min_by_key(|(_, (_, seen))| *seen).map(|(id, _)| id.clone());
+            if let Some(stalest) = stalest {
+                self.devices.remove(&stalest);
+            }
+        }
+        self.devices.insert(device.to_string(), (cursor, Instant::now()));
+
+        let seen_by_all = self.devices.values().map(|(cursor, _)| *cursor).min().unwrap_or_default();
+        let (mut bytes, mut pruned) = (0, self.pruned);
+        self.records.retain(|_, (record, number)| {
+            let keep = record.value.is_some() || *number > seen_by_all;
+            if !keep {
+                bytes += record.size();
+                pruned = pruned.max(*number);
+            }
+            keep
+        });
+        self.bytes -= bytes;
+        self.pruned = pruned;
+    }
+
+    /// Take the request's newer changes and answer with what the client
+    /// lacks; `elsewhere` is the size of every other session's records
+    pub fn exchange(&mut self, request: SyncRequest, elsewhere: usize) -> Result<SyncResponse, ApiError> {
+        let known = request.epoch.as_deref() == Some(self.epoch.as_str())
+            && request.cursor <= self.last
+            && request.cursor >= self.pruned;
+        let reset = request.cursor > 0 && !known;
+        let cursor = if reset { 0 } else { request.cursor };
+        self.prune(&request.device, cursor);
+
+        // What the request's changes would leave here, before taking any
+        let mut taken: HashMap<(&str, &str), (f64, usize)> = HashMap::new();
+        for record in &request.changes {
+            let key = (record.store.as_str(), record.key.as_str());
+            let current = taken.get(&key).map(|(updated_at, _)| *updated_at).or_else(|| {
+                self.records.get(&(key.0.to_string(), key.1.to_string())).map(|(existing, _)| existing.updated_at)
+            });
+            if current.is_none_or(|updated_at| updated_at < record.updated_at) {
+                taken.insert(key, (record.updated_at, record.size()));
+            }
+        }
+        let mut added = 0;
+        let mut bytes = self.bytes;
+        for ((store, key), (_, size)) in &taken {
+            match self.records.get(&(store.to_string(), key.to_string())) {
+                Some((existing, _)) => bytes = bytes - existing.size() + size,
+                None => {
+                    added += 1;
+                    bytes += size;
+                }
+            }
+        }
+        if self.records.len() + added > MAX_RECORDS {
+            return Err(ApiError::new(
+                Status::PayloadTooLarge,
+                "too_many_records",
+                format!("A session syncs at most {} records", MAX_RECORDS),
+            ));
+        }
+        if bytes > self.bytes && bytes > MAX_SESSION_BYTES {
+            return Err(ApiError::new(
+                Status::PayloadTooLarge,
+                "sync_quota_exceeded",
+                format!("A session syncs at most {} MiB of records", MAX_SESSION_BYTES / (1024 * 1024)),
+            ));
+        }
+        if bytes > self.bytes && elsewhere + bytes > MAX_TOTAL_BYTES {
+            return Err(ApiError::new(
+                Status::InsufficientStorage,
+                "sync_storage_full",
+                "The server is holding all the synced data it can; try again later",
+            ));
+        }
+
+        let mut settled = HashSet::new();
+        let mut newer_here = Vec::new();
+        let mut accepted = 0;
+        for record in request.changes {
+            let key = (record.store.clone(), record.key.clone());
+            match self.records.get(&key) {
+                // Ties go to the copy already here, so every device settles on it
+                Some((existing, _)) if existing.updated_at >= record.updated_at => newer_here.push(existing.clone()),
+                _ => {
+                    self.last += 1;
+                    self.bytes += record.size();
+                    if let Some((replaced, _)) = self.records.insert(key.clone(), (record, self.last)) {
+                        self.bytes -= replaced.size();
+                    }
+                    accepted += 1;
+                }
+            }
+            settled.insert(key);
+        }
+
+        let mut missed: Vec<&(SyncRecord, u64)> = self
+            .records
+            .iter()
+            .filter(|(key, (_, number))| *number > cursor && !settled.contains(*key))
+            .map(|(_, entry)| entry)
+            .collect();
+        missed.sort_by_key(|(_, number)| *number);
+        let changes = missed.into_iter().map(|(record, _)| record.clone()).chain(newer_here).collect();
+        Ok(SyncResponse { cursor: self.last, epoch: self.epoch.clone(), reset, accepted, changes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn record(key: &str, value: Option<Value>, updated_at: f64) -> SyncRecord {
+        SyncRecord { store: "notes".to_string(), key: key.to_string(), value, updated_at }
+    }
+
+    fn request(device: &str, cursor: u64, epoch: Option<&str>, changes: Vec<SyncRecord>) -> SyncRequest {
+        SyncRequest { cursor, epoch: epoch.map(str::to_string), device: device.to_string(), changes }
+    }
+
+    fn send(log: &mut SyncLog, cursor: u64, changes: Vec<SyncRecord>) -> SyncResponse {
+        let epoch = log.epoch.clone();
+        log.exchange(request("tablet", cursor, Some(&epoch), changes), 0).unwrap()
+    }
+
+    fn values(response: &SyncResponse) -> Vec<(String, Option<Value>)> {
+        response.changes.iter().map(|record| (record.key.clone(), record.value.clone())).collect()
+    }
+
+    #[test]
+    fn later_write_wins() {
+        let mut log = SyncLog::default();
+        let first = send(&mut log, 0, vec![record("1", Some(json!("swing it")), 100.0)]);
+        assert_eq!((first.accepted, first.cursor), (1, 1));
+
+        // An older edit from another device loses, and it's sent the newer one
+        let stale = send(&mut log, 0, vec![record("1", Some(json!("straight")), 50.0)]);
+        assert_eq!(stale.accepted, 0);
+        assert_eq!(values(&stale), [("1".to_string(), Some(json!("swing it")))]);
+
+        let newer = send(&mut log, 1, vec![record("1", Some(json!("half time")), 200.0)]);
+        assert_eq!((newer.accepted, newer.cursor), (1, 2));
+        assert!(newer.changes.is_empty());
+    }
+
+    #[test]
+    fn ties_keep_the_copy_already_here() {
+        let mut log = SyncLog::default();
+        send(&mut log, 0, vec![record("1", Some(json!("first")), 100.0)]);
+        let tie = send(&mut log, 1, vec![record("1", Some(json!("second")), 100.0)]);
+        assert_eq!(tie.accepted, 0);
+        assert_eq!(values(&tie), [("1".to_string(), Some(json!("first")))]);
+    }
+
+    #[test]
+    fn deletions_travel_and_catch_up_is_in_order() {
+        let mut log = SyncLog::default();
+        send(&mut log, 0, vec![record("1", Some(json!("a")), 100.0), record("2", Some(json!("b")), 100.0)]);
+        send(&mut log, 2, vec![record("1", None, 300.0)]);
+
+        let behind = send(&mut log, 0, Vec::new());
+        assert_eq!(values(&behind), [("2".to_string(), Some(json!("b"))), ("1".to_string(), None)]);
+        assert_eq!(log.bytes(), record("2", Some(json!("b")), 0.0).size() + record("1", None, 0.0).size());
+    }
+
+    #[test]
+    fn an_unknown_cursor_resets() {
+        let mut log = SyncLog::default();
+        send(&mut log, 0, vec![record("1", Some(json!("a")), 100.0)]);
+        let lost = send(&mut log, 40, Vec::new());
+        assert!(lost.reset);
+        assert_eq!(lost.changes.len(), 1);
+    }
+
+    #[test]
+    fn a_cursor_from_another_log_resets_even_when_in_range() {
+        // After a restart another device has already pushed the new log past
+        // this one's old cursor
+        let mut log = SyncLog::default();
+        send(&mut log, 0, (0..5).map(|key| record(&key.to_string(), Some(json!("a")), 100.0)).collect());
+        let stale = log.exchange(request("phone", 3, Some("0123456789abcdef"), Vec::new()), 0).unwrap();
+        assert!(stale.reset);
+        assert_eq!(stale.changes.len(), 5);
+        assert_eq!(stale.epoch, log.epoch);
+    }
+
+    #[test]
+    fn deletions_are_dropped_once_every_device_has_them() {
+        let mut log = SyncLog::default();
+        let epoch = Some(log.epoch.clone());
+        let epoch = epoch.as_deref();
+        log.exchange(request("tablet", 0, None, vec![record("1", Some(json!("a")), 100.0)]), 0).unwrap();
+        log.exchange(request("phone", 0, None, Vec::new()), 0).unwrap();
+        let deleted = log.exchange(request("tablet", 1, epoch, vec![record("1", None, 200.0)]), 0).unwrap();
+        assert_eq!(deleted.cursor, 2);
+
+        // The phone hasn't seen the deletion yet, so it's kept
+        log.exchange(request("tablet", 2, epoch, Vec::new()), 0).unwrap();
+        assert_eq!(log.records.len(), 1);
+        let caught_up = log.exchange(request("phone", 1, epoch, Vec::new()), 0).unwrap();
+        assert_eq!(values(&caught_up), [("1".to_string(), None)]);
+        assert_eq!(log.records.len(), 1);
+
+        // Both have synced past it now
+        log.exchange(request("phone", 2, epoch, Vec::new()), 0).unwrap();
+        assert!(log.records.is_empty());
+        assert_eq!(log.bytes(), 0);
+
+        // A device still behind the dropped deletion has to start over
+        let behind = log.exchange(request("laptop", 1, epoch, Vec::new()), 0).unwrap();
+        assert!(behind.reset);
+    }
+
+    #[test]
+    fn refuses_a_request_over_the_session_budget_whole() {
+        let mut log = SyncLog::default();
+        let big = |key: &str| record(key, Some(json!("x".repeat(MAX_RECORD_BYTES - 100))), 100.0);
+        let changes: Vec<SyncRecord> = (0..MAX_SESSION_BYTES / MAX_RECORD_BYTES + 1).map(|key| big(&key.to_string())).collect();
+        let error = log.exchange(request("tablet", 0, None, changes), 0).unwrap_err();
+        assert_eq!(error.body.code, "sync_quota_exceeded");
+        assert_eq!(log.bytes(), 0);
+
+        let error = log.exchange(request("tablet", 0, None, vec![big("1")]), MAX_TOTAL_BYTES).unwrap_err();
+        assert_eq!(error.body.code, "sync_storage_full");
+    }
+}
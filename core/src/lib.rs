@@ -0,0 +1,45 @@
+//! Catalog matching shared by the API and the UI
+//!
+//! The API searches realbook.json for `/search` and `/suggest`; the UI runs
+//! the same searches over its cached copy of the catalog, offline and on every
+//! keystroke. Both call this crate, so a query finds the same songs, in the
+//! same order and with the same "did you mean" titles, wherever it runs. Each
+//! side keeps its own entry type (the API's parses realbook.json, the UI's
+//! builds image URLs) and implements [`Entry`] for it.
+
+pub mod search;
+pub mod suggestions;
+
+pub use search::{Matches, SearchFilter, count_by_volume, search_entries};
+
+/// A catalog entry as far as matching is concerned
+pub trait Entry {
+    /// Song title
+    fn title(&self) -> &str;
+
+    /// Volume number (1, 2, or 3)
+    fn volume(&self) -> u32;
+
+    /// First and last page
+    fn pages(&self) -> (u32, u32);
+
+    /// Pages between this entry and `page` (0 if the entry contains it)
+    fn page_distance(&self, page: u32) -> u32 {
+        let (first, last) = self.pages();
+        first.saturating_sub(page).max(page.saturating_sub(last))
+    }
+
+    /// Index letter the title files under: its first letter or digit,
+    /// uppercased, with digits (and titles without either) under '#'
+    fn initial(&self) -> char {
+        match self.title().chars().find(|c| c.is_alphanumeric()) {
+            Some(c) if c.is_alphabetic() => c.to_ascii_uppercase(),
+            _ => '#',
+        }
+    }
+
+    /// Whether the title contains `query` (case-insensitive)
+    fn matches(&self, query: &str) -> bool {
+        self.title().to_lowercase().contains(&query.to_lowercase())
+    }
+}
@@ -0,0 +1,131 @@
+//! The catalog search behind `/api/v1/search` and the UI's local search
+
+use std::collections::BTreeMap;
+use crate::Entry;
+use crate::suggestions::closest_titles;
+
+/// Page window either side of `page_near` when no tolerance is given
+pub const DEFAULT_PAGE_TOLERANCE: u32 = 5;
+
+/// Widest tolerance for `page_near`
+pub const MAX_PAGE_TOLERANCE: u32 = 50;
+
+/// What to search for; `None` (or empty) fields don't filter
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchFilter<'a> {
+    /// Text the title must contain
+    pub query: Option<&'a str>,
+    /// Earlier queries to refine; every term must also match
+    pub within: &'a [String],
+    /// Index letter the title files under ('#' for digits)
+    pub initial: Option<char>,
+    pub volume: Option<u32>,
+    /// A page the entry must contain
+    pub page: Option<u32>,
+    /// Start of a page span the entry must overlap (inclusive)
+    pub page_from: Option<u32>,
+    /// End of a page span the entry must overlap (inclusive)
+    pub page_to: Option<u32>,
+    /// Approximate page: entries overlapping `page_near ± tolerance`, closest first
+    pub page_near: Option<u32>,
+    /// Pages either side of `page_near` (default 5, at most 50)
+    pub tolerance: Option<u32>,
+    /// Number of matches to skip
+    pub offset: Option<usize>,
+    /// Maximum number of matches to return
+    pub limit: Option<usize>,
+}
+
+/// What a search found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matches<T> {
+    /// Matching entries (the requested window when paginated)
+    pub results: Vec<T>,
+    /// Number of matches before pagination
+    pub total: usize,
+    /// Matches in each volume, ignoring the volume filter, as (volume, count)
+    pub volume_counts: Vec<(u32, usize)>,
+    /// Closest titles when the query matched nothing ("did you mean")
+    pub suggestions: Vec<String>,
+}
+
+/// Search `entries` (the catalog, or a part of it such as the favorites)
+pub fn search_entries<T: Entry + Clone>(entries: &[T], filter: &SearchFilter) -> Matches<T> {
+    let mut results: Vec<T> = entries.to_vec();
+
+    // Filter by text query
+    if let Some(q) = filter.query
+        && !q.is_empty() {
+        results.retain(|entry| entry.matches(q));
+    }
+
+    // Refine within previous queries (AND semantics)
+    for term in filter.within.iter().filter(|term| !term.is_empty()) {
+        results.retain(|entry| entry.matches(term));
+    }
+
+    // Filter by page (entry must contain this page)
+    if let Some(p) = filter.page {
+        results.retain(|entry| entry.page_distance(p) == 0);
+    }
+
+    // Filter by page span (entry must overlap page_from..=page_to)
+    if filter.page_from.is_some() || filter.page_to.is_some() {
+        let from = filter.page_from.unwrap_or(u32::MIN);
+        let to = filter.page_to.unwrap_or(u32::MAX);
+        results.retain(|entry| entry.pages().1 >= from && entry.pages().0 <= to);
+        results.sort_by_key(|entry| (entry.volume(), entry.pages().0));
+    }
+
+    // Filter by approximate page (entry must overlap the window around
+    // page_near), closest to it first
+    if let Some(near) = filter.page_near {
+        let tolerance = filter.tolerance.unwrap_or(DEFAULT_PAGE_TOLERANCE).min(MAX_PAGE_TOLERANCE);
+        results.retain(|entry| entry.page_distance(near) <= tolerance);
+        results.sort_by_key(|entry| (entry.page_distance(near), entry.volume(), entry.pages().0));
+    }
+
+    // Filter by index letter (A-Z strip)
+    if let Some(letter) = filter.initial {
+        let letter = letter.to_ascii_uppercase();
+        results.retain(|entry| entry.initial() == letter);
+    }
+
+    // Count matches per volume before the volume filter, so a client that
+    // narrows to one volume can still show (and switch to) the others
+    let volume_counts = count_by_volume(&results);
+
+    // Filter by volume
+    if let Some(vol) = filter.volume {
+        results.retain(|entry| entry.volume() == vol);
+    }
+
+    let total = results.len();
+
+    // Nothing matched: suggest close titles (within the selected volume)
+    let suggestions = match filter.query {
+        Some(q) if total == 0 && !q.is_empty() => closest_titles(
+            entries.iter().filter(|entry| filter.volume.is_none_or(|vol| entry.volume() == vol)),
+            q,
+        ),
+        _ => Vec::new(),
+    };
+
+    // Paginate after counting so clients can tell whether more remain
+    let results = results
+        .into_iter()
+        .skip(filter.offset.unwrap_or(0))
+        .take(filter.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Matches { results, total, volume_counts, suggestions }
+}
+
+/// Entries per volume as (volume, count), sorted by volume number
+pub fn count_by_volume<T: Entry>(entries: &[T]) -> Vec<(u32, usize)> {
+    let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(entry.volume()).or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
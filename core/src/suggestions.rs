@@ -0,0 +1,180 @@
+//! "Did you mean" titles and autocomplete
+
+use crate::Entry;
+
+/// Maximum number of "did you mean" suggestions returned
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Maximum number of autocomplete completions returned
+pub const MAX_COMPLETIONS: usize = 8;
+
+/// Levenshtein edit distance between two strings (by chars)
+///
+/// Uses the single-row dynamic programming formulation, so memory is
+/// proportional to the length of `b` only.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b_chars.len()]
+}
+
+/// Find the titles closest to a query that matched nothing
+///
+/// Comparison is case-insensitive. Only titles within a third of the
+/// query's length in edits (minimum 2) are considered close enough to
+/// suggest, so gibberish queries produce no suggestions.
+pub fn closest_titles<'a, T: Entry + 'a>(entries: impl Iterator<Item = &'a T>, query: &str) -> Vec<String> {
+    let query = query.trim().to_lowercase();
+    let max_distance = (query.chars().count() / 3).max(2);
+
+    let mut candidates: Vec<(usize, &str)> = entries
+        .map(|entry| (edit_distance(&query, &entry.title().to_lowercase()), entry.title()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+
+    candidates.sort_by_key(|(distance, _)| *distance);
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for (_, title) in candidates {
+        if !suggestions.iter().any(|s| s.eq_ignore_ascii_case(title)) {
+            suggestions.push(title.to_string());
+        }
+        if suggestions.len() == MAX_SUGGESTIONS {
+            break;
+        }
+    }
+
+    suggestions
+}
+
+/// Titles completing a partly typed query, for the search box's autocomplete
+///
+/// Case-insensitive. Titles starting with the query come first, then titles
+/// with a word starting with it, then any other title containing it; each
+/// group is alphabetical, and titles found in several volumes appear once.
+pub fn completions<'a, T: Entry + 'a>(entries: impl Iterator<Item = &'a T>, query: &str) -> Vec<String> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<(u8, String, &str)> = entries
+        .filter_map(|entry| {
+            let title = entry.title().to_lowercase();
+            let rank = if title.starts_with(&query) {
+                0
+            } else if title.split_whitespace().any(|word| word.starts_with(&query)) {
+                1
+            } else if title.contains(&query) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, title, entry.title()))
+        })
+        .collect();
+
+    candidates.sort();
+
+    let mut completions: Vec<String> = Vec::new();
+    for (_, _, title) in candidates {
+        if !completions.iter().any(|c| c.eq_ignore_ascii_case(title)) {
+            completions.push(title.to_string());
+        }
+        if completions.len() == MAX_COMPLETIONS {
+            break;
+        }
+    }
+
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Song(&'static str);
+
+    impl Entry for Song {
+        fn title(&self) -> &str {
+            self.0
+        }
+
+        fn volume(&self) -> u32 {
+            1
+        }
+
+        fn pages(&self) -> (u32, u32) {
+            (1, 1)
+        }
+    }
+
+    fn songs(titles: &[&'static str]) -> Vec<Song> {
+        titles.iter().map(|title| Song(title)).collect()
+    }
+
+    #[test]
+    fn edit_distance_counts_single_char_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("solar", "solar"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("sloar", "solar"), 2);
+        // Chars, not bytes
+        assert_eq!(edit_distance("café", "cafe"), 1);
+        assert_eq!(edit_distance("flintstone", "stone"), edit_distance("stone", "flintstone"));
+    }
+
+    #[test]
+    fn close_titles_are_suggested_closest_first() {
+        let catalog = songs(&["Solar", "Polka Dots And Moonbeams", "Sola", "Soldier", "Blue In Green"]);
+        assert_eq!(closest_titles(catalog.iter(), "  SOLAT "), vec!["Solar", "Sola"]);
+        assert_eq!(closest_titles(catalog.iter(), "blue in grean"), vec!["Blue In Green"]);
+    }
+
+    #[test]
+    fn gibberish_gets_no_suggestions() {
+        let catalog = songs(&["Solar", "Nardis"]);
+        assert!(closest_titles(catalog.iter(), "xqzvw").is_empty());
+    }
+
+    #[test]
+    fn suggestions_are_deduplicated_and_capped() {
+        let catalog = songs(&["Nardis", "NARDIS", "Nardi", "Narxis", "Nardiss", "Narcis"]);
+        let suggestions = closest_titles(catalog.iter(), "nardis");
+        assert_eq!(suggestions.len(), MAX_SUGGESTIONS);
+        assert_eq!(suggestions[0], "Nardis");
+        assert!(!suggestions.contains(&"NARDIS".to_string()));
+    }
+
+    #[test]
+    fn completions_rank_prefixes_then_words_then_anywhere() {
+        let catalog = songs(&["All The Things You Are", "Blue Train", "Trane's Blues", "Tracing", "Au Privave"]);
+        assert_eq!(completions(catalog.iter(), "tra"), vec!["Tracing", "Trane's Blues", "Blue Train"]);
+        assert_eq!(completions(catalog.iter(), "ing"), vec!["All The Things You Are", "Tracing"]);
+        assert!(completions(catalog.iter(), "   ").is_empty());
+        assert!(completions(catalog.iter(), "zz").is_empty());
+    }
+
+    #[test]
+    fn completions_list_each_title_once_and_are_capped() {
+        let catalog = songs(&["Blues For Alice", "blues for alice", "Blue Bossa"]);
+        assert_eq!(completions(catalog.iter(), "BLUE"), vec!["Blue Bossa", "Blues For Alice"]);
+
+        let many: Vec<Song> = ["A1", "A2", "A3", "A4", "A5", "A6", "A7", "A8", "A9", "A10"].into_iter().map(Song).collect();
+        assert_eq!(completions(many.iter(), "a").len(), MAX_COMPLETIONS);
+    }
+}
@@ -0,0 +1,35 @@
+#!/usr/bin/env perl
+# Trunk post-build hook (Trunk.toml): moves the WASM loader Trunk inlines into
+# index.html out to a script file of its own, so the Content-Security-Policy
+# (api/src/security.rs) needn't allow inline scripts. The loader names the
+# bundle's hashed files, so its file is named by a hash of its contents too.
+use strict;
+use warnings;
+use Digest::MD5 qw(md5_hex);
+
+my $dir = $ENV{TRUNK_STAGING_DIR}
+    or die "TRUNK_STAGING_DIR is not set; this runs as Trunk's post_build hook\n";
+my $index = "$dir/index.html";
+my $public_url = $ENV{TRUNK_PUBLIC_URL} // '/';
+
+open(my $in, '<', $index) or die "Can't read $index: $!\n";
+my $html = do { local $/; <$in> };
+close($in);
+
+# Module scripts with a body and no src: Trunk's loader (`--minify` may drop
+# the attribute's quotes)
+my $moved = $html =~ s{<script type=(?:"module"|module)((?:(?!\bsrc=)[^>])*)>(.*?)</script>}{external($1, $2)}gse;
+die "No inline loader found in $index\n" unless $moved;
+
+open(my $out, '>', $index) or die "Can't write $index: $!\n";
+print $out $html;
+close($out);
+
+sub external {
+    my ($attributes, $code) = @_;
+    my $name = 'loader-' . substr(md5_hex($code), 0, 16) . '.js';
+    open(my $file, '>', "$dir/$name") or die "Can't write $dir/$name: $!\n";
+    print $file $code;
+    close($file);
+    return qq{<script type="module"$attributes src="$public_url$name"></script>};
+}
@@ -0,0 +1,5 @@
+// Registers the service worker (sw.js). Kept out of index.html so the
+// Content-Security-Policy doesn't have to allow inline scripts.
+if ("serviceWorker" in navigator) {
+  window.addEventListener("load", () => navigator.serviceWorker.register("/sw.js"));
+}
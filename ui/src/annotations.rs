@@ -0,0 +1,186 @@
+//! Per-song drawings and markers over sheet pages (cuts, roadmaps, fingerings)
+//!
+//! Annotations are stored in localStorage under `realbook.annotations.<entry id>`,
+//! keyed by page number. Points are fractions of the page's width and height, so
+//! strokes and stickers line up at any zoom level or rotation and on any screen.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use crate::storage;
+
+/// Drawing tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tool {
+    Pen,
+    Highlighter,
+    /// Erases pen and highlighter ink (never the scan underneath)
+    Eraser,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 3] = [Tool::Pen, Tool::Highlighter, Tool::Eraser];
+
+    /// Line width as a fraction of the page width
+    fn width(self) -> f64 {
+        match self {
+            Tool::Pen => 0.004,
+            Tool::Highlighter => 0.02,
+            Tool::Eraser => 0.03,
+        }
+    }
+}
+
+/// One freehand stroke
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stroke {
+    pub tool: Tool,
+    /// (x, y) as fractions of the page width and height
+    pub points: Vec<(f32, f32)>,
+}
+
+/// Common chart markers offered as stickers: (name, text shown on the page)
+pub const MARKERS: [(&str, &str); 8] = [
+    ("Coda", "𝄌"),
+    ("Segno", "𝄋"),
+    ("Open", "Open"),
+    ("A", "A"),
+    ("B", "B"),
+    ("C", "C"),
+    ("D", "D"),
+    ("Fine", "Fine"),
+];
+
+/// A text label or marker placed on a page (rendered as text, so it stays sharp
+/// at any zoom)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sticker {
+    pub text: String,
+    /// Center of the label as fractions of the page width and height
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A named jump point on a page ("bridge", "shout chorus")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    /// Height on the page as a fraction of the page height
+    pub y: f32,
+}
+
+/// Everything drawn or placed on one page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageAnnotations {
+    /// In drawing order (eraser strokes only affect the strokes before them)
+    pub strokes: Vec<Stroke>,
+    pub stickers: Vec<Sticker>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl PageAnnotations {
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty() && self.stickers.is_empty() && self.bookmarks.is_empty()
+    }
+}
+
+/// Something waiting to be put on a page by the next tap
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placement {
+    /// A sticker with this text
+    Sticker(String),
+    /// A bookmark with this name
+    Bookmark(String),
+}
+
+/// Element id of a bookmark's flag on the page (the viewer scrolls to it)
+pub fn bookmark_id(page: u32, index: usize) -> String {
+    format!("bookmark-{}-{}", page, index)
+}
+
+/// A song's annotations by page number
+pub type SongAnnotations = BTreeMap<u32, PageAnnotations>;
+
+/// Prefix of the per-song localStorage keys
+const STORAGE_PREFIX: &str = "realbook.annotations.";
+
+fn storage_key(entry_id: u32) -> String {
+    format!("{}{}", STORAGE_PREFIX, entry_id)
+}
+
+/// Annotations saved for a song (empty if there are none)
+pub fn load(entry_id: u32) -> SongAnnotations {
+    storage::load(&storage_key(entry_id)).unwrap_or_default()
+}
+
+/// Every annotated song's annotations, by entry id
+pub fn all() -> BTreeMap<u32, SongAnnotations> {
+    storage::keys(STORAGE_PREFIX)
+        .iter()
+        .filter_map(|key| key[STORAGE_PREFIX.len()..].parse().ok())
+        .map(|entry_id| (entry_id, load(entry_id)))
+        .filter(|(_, annotations)| !annotations.is_empty())
+        .collect()
+}
+
+/// Save a song's annotations, dropping blank pages (and the key once none are left)
+pub fn save(entry_id: u32, annotations: &SongAnnotations) {
+    let annotations: SongAnnotations = annotations
+        .iter()
+        .filter(|(_, page)| !page.is_empty())
+        .map(|(page, notes)| (*page, notes.clone()))
+        .collect();
+    if annotations.is_empty() {
+        storage::remove(&storage_key(entry_id));
+    } else {
+        storage::save(&storage_key(entry_id), &annotations);
+    }
+}
+
+/// Clear `canvas` and draw `strokes` onto it, in order
+pub fn draw<'a>(canvas: &web_sys::HtmlCanvasElement, strokes: impl IntoIterator<Item = &'a Stroke>) {
+    let Some(context) = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<web_sys::CanvasRenderingContext2d>().ok())
+    else {
+        return;
+    };
+    let (width, height) = (f64::from(canvas.width()), f64::from(canvas.height()));
+
+    context.clear_rect(0.0, 0.0, width, height);
+    context.set_line_cap("round");
+    context.set_line_join("round");
+
+    for stroke in strokes {
+        let Some(&(x, y)) = stroke.points.first() else {
+            continue;
+        };
+        let (operation, color, alpha) = match stroke.tool {
+            Tool::Pen => ("source-over", "#c62828", 1.0),
+            Tool::Highlighter => ("source-over", "#fdd835", 0.4),
+            Tool::Eraser => ("destination-out", "#000", 1.0),
+        };
+        let _ = context.set_global_composite_operation(operation);
+        context.set_global_alpha(alpha);
+        context.set_stroke_style_str(color);
+        context.set_line_width(stroke.tool.width() * width);
+
+        context.begin_path();
+        context.move_to(f64::from(x) * width, f64::from(y) * height);
+        if stroke.points.len() == 1 {
+            // A single tap still leaves a dot (a zero-length segment with round caps)
+            context.line_to(f64::from(x) * width, f64::from(y) * height);
+        }
+        for &(x, y) in &stroke.points[1..] {
+            context.line_to(f64::from(x) * width, f64::from(y) * height);
+        }
+        context.stroke();
+    }
+
+    let _ = context.set_global_composite_operation("source-over");
+    context.set_global_alpha(1.0);
+}
@@ -1,8 +1,51 @@
-use crate::models::{RealBookEntry, SearchResponse};
+use gloo_timers::future::TimeoutFuture;
+use serde::de::DeserializeOwned;
+use wasm_bindgen::JsCast;
+use crate::models::{ApiErrorResponse, Attachments, CatalogSnapshot, CatalogStats, Changelog, CorrectionRequest, ImportSummary, LinkCode, PageProblem, PageReport, Passkey, RealBookEntry, SearchParams, SearchResponse, SessionSummary, SongOfTheDay, SuggestResponse, SyncRequest, SyncResponse};
+use webauthn_rs_proto::{CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse};
 
-/// Base URL for the API
+/// Base URL for the API (pinned to a version so older bundles keep working
+/// against newer servers)
 /// During development with Trunk, this will be proxied through localhost:8080
-const API_BASE_URL: &str = "http://localhost:8080/api";
+const API_BASE_URL: &str = "http://localhost:8080/api/v1";
+
+/// URL of a sheet music page, served through the API's mirror-failover image proxy
+pub fn image_url(volume: u32, page: u32) -> String {
+    format!("{}/images/{}/{}", API_BASE_URL, volume, page)
+}
+
+/// URL of a tiny version of a page, shown blurred while the full page loads
+pub fn thumbnail_url(volume: u32, page: u32) -> String {
+    format!("{}/images/{}/{}/thumb", API_BASE_URL, volume, page)
+}
+
+/// URL of an entry's pages as a single PDF download (assembled by the API)
+pub fn entry_pdf_url(id: u32) -> String {
+    format!("{}/entries/{}/pdf", API_BASE_URL, id)
+}
+
+/// URL of a printable one-page PDF index of a setlist (assembled by the API)
+pub fn setlist_index_pdf_url(name: &str, ids: &[u32]) -> String {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    format!("{}/setlists/index.pdf?name={}&ids={}", API_BASE_URL, urlencoding::encode(name), ids.join(","))
+}
+
+/// URL of a whole setlist as one PDF for printing: the index, then every page
+/// of every song (assembled by the API)
+pub fn setlist_book_pdf_url(name: &str, ids: &[u32]) -> String {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    format!("{}/setlists/book.pdf?name={}&ids={}", API_BASE_URL, urlencoding::encode(name), ids.join(","))
+}
+
+/// Prefer compact MessagePack bodies (cheaper to decode in WASM than JSON);
+/// routes that don't negotiate still answer with JSON
+const ACCEPT_HEADER: &str = "application/msgpack, application/json;q=0.9";
+
+/// Total tries for a request before a transient failure is surfaced
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each subsequent one
+const BASE_BACKOFF_MS: u32 = 200;
 
 /// Error type for API operations
 #[derive(Debug, Clone)]
@@ -24,55 +67,399 @@ impl From<String> for ApiError {
     }
 }
 
-/// Search for Real Book entries
-pub async fn search(
-    query: Option<String>,
-    volume: Option<u32>,
-    page: Option<u32>,
-) -> Result<SearchResponse, ApiError> {
-    let mut url = format!("{}/search", API_BASE_URL);
+impl From<ApiErrorResponse> for ApiError {
+    /// Fold per-field details into the message so the UI can show them inline,
+    /// e.g. "One or more query parameters are invalid (volume: must be between 1 and 3)"
+    fn from(body: ApiErrorResponse) -> Self {
+        let details: Vec<String> = body
+            .details
+            .iter()
+            .map(|detail| match &detail.field {
+                Some(field) => format!("{}: {}", field, detail.message),
+                None => detail.message.clone(),
+            })
+            .collect();
+
+        let message = if details.is_empty() {
+            body.message
+        } else {
+            format!("{} ({})", body.message, details.join("; "))
+        };
+
+        ApiError { message }
+    }
+}
+
+/// Turn a non-success response into an ApiError, using the API's error body
+/// when it has one and falling back to the bare status otherwise
+async fn error_from_response(response: reqwest::Response) -> ApiError {
+    let status = response.status();
+    match response.json::<ApiErrorResponse>().await {
+        Ok(body) => body.into(),
+        Err(_) => ApiError {
+            message: format!("API returned status: {}", status),
+        },
+    }
+}
+
+/// Whether a response body is MessagePack (per its Content-Type)
+fn is_msgpack(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type.starts_with("application/msgpack")
+                || content_type.starts_with("application/x-msgpack")
+        })
+}
+
+/// Outcome of a single failed attempt
+enum Failure {
+    /// Worth retrying: network errors and 502/503/504 from the proxy or API
+    Transient(ApiError),
+    /// Retrying won't help (bad parameters, not found, undecodable body)
+    Permanent(ApiError),
+}
+
+/// Whether a status indicates an upstream hiccup rather than a bad request
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::BAD_GATEWAY
+            | reqwest::StatusCode::SERVICE_UNAVAILABLE
+            | reqwest::StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Backoff before retry number `attempt` (1-based): exponential, with the
+/// upper half jittered so clients that failed together don't retry together
+fn backoff_ms(attempt: u32) -> u32 {
+    let delay = BASE_BACKOFF_MS << (attempt - 1);
+    delay / 2 + (js_sys::Math::random() * f64::from(delay / 2)) as u32
+}
+
+/// GET a URL once and deserialize its body, negotiating MessagePack with a JSON fallback
+async fn get_once<T: DeserializeOwned>(url: &str) -> Result<T, Failure> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .header(reqwest::header::ACCEPT, ACCEPT_HEADER)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_request() || e.is_timeout() {
+                Failure::Transient(e.into())
+            } else {
+                Failure::Permanent(e.into())
+            }
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error = error_from_response(response).await;
+        return Err(if is_transient_status(status) {
+            Failure::Transient(error)
+        } else {
+            Failure::Permanent(error)
+        });
+    }
+
+    if is_msgpack(&response) {
+        let bytes = response.bytes().await.map_err(|e| Failure::Transient(e.into()))?;
+        return rmp_serde::from_slice(&bytes).map_err(|e| {
+            Failure::Permanent(ApiError {
+                message: format!("Failed to decode response: {}", e),
+            })
+        });
+    }
+
+    response.json::<T>().await.map_err(|e| Failure::Permanent(e.into()))
+}
+
+/// GET a URL and deserialize its body, retrying transient failures with backoff
+async fn get_data<T: DeserializeOwned>(url: &str) -> Result<T, ApiError> {
+    let mut attempt = 1;
+    loop {
+        match get_once(url).await {
+            Ok(data) => return Ok(data),
+            Err(Failure::Permanent(error)) => return Err(error),
+            Err(Failure::Transient(error)) if attempt >= MAX_ATTEMPTS => {
+                return Err(ApiError {
+                    message: format!("{} (gave up after {} attempts)", error.message, attempt),
+                });
+            }
+            Err(Failure::Transient(_)) => {
+                TimeoutFuture::new(backoff_ms(attempt)).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// The API's CSRF token from its `realbook_csrf` cookie (see `api/src/csrf.rs`)
+fn csrf_token() -> Option<String> {
+    let document: web_sys::HtmlDocument = web_sys::window()?.document()?.dyn_into().ok()?;
+    let cookies = document.cookie().ok()?;
+    cookies.split("; ").find_map(|cookie| cookie.strip_prefix("realbook_csrf=")).map(str::to_string)
+}
+
+/// Send a request that changes state on the server, failing on an error
+/// status; not retried, since a retry could apply the change twice. The one
+/// exception: the API refuses (without applying) a request whose CSRF token
+/// is missing or stale, as the first one of a visit can be, and sets a new
+/// token; the request is then repeated once with it.
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, ApiError> {
+    let with_token = |request: reqwest::RequestBuilder, token: &Option<String>| {
+        let request = request.header(reqwest::header::ACCEPT, "application/json");
+        match token {
+            Some(token) => request.header("X-CSRF-Token", token),
+            None => request,
+        }
+    };
+    let token = csrf_token();
+    let retry = request.try_clone();
+    let mut response = with_token(request, &token).send().await?;
+    if response.status() == reqwest::StatusCode::FORBIDDEN
+        && let Some(retry) = retry
+        && let fresh @ Some(_) = csrf_token()
+        && fresh != token
+    {
+        response = with_token(retry, &fresh).send().await?;
+    }
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+    Ok(response)
+}
+
+/// `url` with the search parameters appended as a query string
+/// (None fields are omitted)
+fn with_search_params(mut url: String, search: &SearchParams) -> String {
     let mut params = vec![];
 
-    if let Some(q) = query
+    if let Some(q) = &search.query
         && !q.is_empty() {
-        params.push(format!("query={}", urlencoding::encode(&q)));
+        params.push(format!("query={}", urlencoding::encode(q)));
     }
-    if let Some(v) = volume {
+    for term in &search.within {
+        params.push(format!("within={}", urlencoding::encode(term)));
+    }
+    if let Some(letter) = search.initial {
+        params.push(format!("initial={}", urlencoding::encode(&letter.to_string())));
+    }
+    if let Some(v) = search.volume {
         params.push(format!("volume={}", v));
     }
-    if let Some(p) = page {
+    if let Some(p) = search.page {
         params.push(format!("page={}", p));
     }
+    if let Some(p) = search.page_from {
+        params.push(format!("page_from={}", p));
+    }
+    if let Some(p) = search.page_to {
+        params.push(format!("page_to={}", p));
+    }
+    if let Some(p) = search.page_near {
+        params.push(format!("page_near={}", p));
+        if let Some(t) = search.tolerance {
+            params.push(format!("tolerance={}", t));
+        }
+    }
+    if let Some(o) = search.offset {
+        params.push(format!("offset={}", o));
+    }
+    if let Some(l) = search.limit {
+        params.push(format!("limit={}", l));
+    }
 
     if !params.is_empty() {
         url.push('?');
         url.push_str(&params.join("&"));
     }
+    url
+}
 
-    let response = reqwest::get(&url).await?;
+/// Search for Real Book entries
+///
+/// Dropping the returned future cancels the request (on wasm reqwest aborts
+/// the underlying fetch), so callers can abandon searches that went stale.
+pub async fn search(search: &SearchParams) -> Result<SearchResponse, ApiError> {
+    get_data(&with_search_params(format!("{}/search", API_BASE_URL), search)).await
+}
 
-    if !response.status().is_success() {
-        return Err(ApiError {
-            message: format!("API returned status: {}", response.status()),
-        });
+/// Get titles completing a partly typed query (optionally within one volume)
+pub async fn suggest(query: &str, volume: Option<u32>) -> Result<Vec<String>, ApiError> {
+    let mut url = format!("{}/suggest?query={}", API_BASE_URL, urlencoding::encode(query));
+    if let Some(v) = volume {
+        url.push_str(&format!("&volume={}", v));
     }
+    let response: SuggestResponse = get_data(&url).await?;
+    Ok(response.suggestions)
+}
 
-    let data = response.json::<SearchResponse>().await?;
-    Ok(data)
+/// Get one entry by id
+pub async fn get_entry(id: u32) -> Result<RealBookEntry, ApiError> {
+    let url = format!("{}/entries/{}", API_BASE_URL, id);
+    get_data(&url).await
 }
 
-/// Get a random Real Book entry
-pub async fn get_random() -> Result<RealBookEntry, ApiError> {
-    let url = format!("{}/random", API_BASE_URL);
-    let response = reqwest::get(&url).await?;
+/// Get the extras attached to an entry (reference recording, ...)
+pub async fn get_attachments(id: u32) -> Result<Attachments, ApiError> {
+    let url = format!("{}/entries/{}/attachments", API_BASE_URL, id);
+    get_data(&url).await
+}
 
-    if !response.status().is_success() {
-        return Err(ApiError {
-            message: format!("API returned status: {}", response.status()),
-        });
+/// Get a random Real Book entry among those matching `filters`, passing over
+/// the ids in `exclude` unless nothing else matches
+pub async fn get_random(filters: &SearchParams, exclude: &[u32]) -> Result<RealBookEntry, ApiError> {
+    let mut url = with_search_params(format!("{}/random", API_BASE_URL), filters);
+    if !exclude.is_empty() {
+        let ids: Vec<String> = exclude.iter().map(u32::to_string).collect();
+        let separator = if url.contains('?') { '&' } else { '?' };
+        url = format!("{}{}exclude={}", url, separator, ids.join(","));
     }
+    get_data(&url).await
+}
+
+/// Deal `count` different random entries among those matching `filters`
+/// (fewer if fewer match)
+pub async fn shuffle(filters: &SearchParams, count: usize) -> Result<Vec<RealBookEntry>, ApiError> {
+    let url = with_search_params(format!("{}/shuffle", API_BASE_URL), filters);
+    let separator = if url.contains('?') { '&' } else { '?' };
+    get_data(&format!("{}{}count={}", url, separator, count)).await
+}
+
+/// Get the song of the day for `date` (YYYY-MM-DD, the user's local date)
+pub async fn song_of_the_day(date: &str) -> Result<SongOfTheDay, ApiError> {
+    let url = format!("{}/song-of-the-day?date={}", API_BASE_URL, urlencoding::encode(date));
+    get_data(&url).await
+}
+
+/// Report a sheet image that doesn't load or shows a bad scan
+pub async fn report_page(volume: u32, page: u32, problem: PageProblem) -> Result<(), ApiError> {
+    let url = format!("{}/images/{}/{}/reports", API_BASE_URL, volume, page);
+    send(reqwest::Client::new().post(url).json(&PageReport { problem })).await?;
+    Ok(())
+}
+
+/// Report a mistake in an entry, for a moderator to review
+pub async fn submit_correction(id: u32, correction: &CorrectionRequest) -> Result<(), ApiError> {
+    let url = format!("{}/entries/{}/corrections", API_BASE_URL, id);
+    send(reqwest::Client::new().post(url).json(correction)).await?;
+    Ok(())
+}
+
+/// Get what this browser's anonymous session remembers (the session cookie
+/// rides along with same-origin requests)
+pub async fn get_session() -> Result<SessionSummary, ApiError> {
+    get_data(&format!("{}/session", API_BASE_URL)).await
+}
+
+/// Note in the session that an entry was opened; returns the updated session
+pub async fn record_view(id: u32) -> Result<SessionSummary, ApiError> {
+    let url = format!("{}/session/viewed/{}", API_BASE_URL, id);
+    Ok(send(reqwest::Client::new().post(url)).await?.json().await?)
+}
+
+/// Get the part of the user's data archive kept on the server (see `archive.rs`)
+pub async fn export_archive() -> Result<serde_json::Map<String, serde_json::Value>, ApiError> {
+    get_data(&format!("{}/me/export", API_BASE_URL)).await
+}
+
+/// Have the API check an exported archive and merge its server-held part into
+/// the session by `strategy` ("merge", "overwrite", or "replace")
+pub async fn import_archive(contents: &str, strategy: &str) -> Result<ImportSummary, ApiError> {
+    let url = format!("{}/me/import?strategy={}", API_BASE_URL, strategy);
+    let request = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(contents.to_string());
+    Ok(send(request).await?.json().await?)
+}
+
+/// Exchange changed records with the other devices on the session
+pub async fn sync(request: &SyncRequest) -> Result<SyncResponse, ApiError> {
+    let url = format!("{}/sync", API_BASE_URL);
+    Ok(send(reqwest::Client::new().post(url).json(request)).await?.json().await?)
+}
+
+/// Get a code another device can enter to join this browser's session
+pub async fn link_session() -> Result<LinkCode, ApiError> {
+    let url = format!("{}/session/link", API_BASE_URL);
+    Ok(send(reqwest::Client::new().post(url)).await?.json().await?)
+}
+
+/// Join the session a link code was made for
+pub async fn join_session(code: &str) -> Result<(), ApiError> {
+    let url = format!("{}/session/join/{}", API_BASE_URL, urlencoding::encode(code));
+    send(reqwest::Client::new().post(url)).await?;
+    Ok(())
+}
+
+/// Start adding a passkey named `name` to this browser's session
+pub async fn passkey_challenge(name: &str) -> Result<CreationChallengeResponse, ApiError> {
+    let url = format!("{}/passkeys/challenge?name={}", API_BASE_URL, urlencoding::encode(name));
+    Ok(send(reqwest::Client::new().post(url)).await?.json().await?)
+}
+
+/// Finish adding a passkey with what the authenticator made
+pub async fn register_passkey(credential: &RegisterPublicKeyCredential) -> Result<Passkey, ApiError> {
+    let url = format!("{}/passkeys", API_BASE_URL);
+    Ok(send(reqwest::Client::new().post(url).json(credential)).await?.json().await?)
+}
+
+/// Get the passkeys that sign in to this browser's session
+pub async fn get_passkeys() -> Result<Vec<Passkey>, ApiError> {
+    get_data(&format!("{}/passkeys", API_BASE_URL)).await
+}
+
+/// Remove one of the session's passkeys
+pub async fn delete_passkey(id: &str) -> Result<(), ApiError> {
+    let url = format!("{}/passkeys/{}", API_BASE_URL, urlencoding::encode(id));
+    send(reqwest::Client::new().delete(url)).await?;
+    Ok(())
+}
+
+/// Start signing in with a passkey
+pub async fn sign_in_challenge() -> Result<RequestChallengeResponse, ApiError> {
+    let url = format!("{}/session/passkey/challenge", API_BASE_URL);
+    Ok(send(reqwest::Client::new().post(url)).await?.json().await?)
+}
+
+/// Sign in to a passkey's session with its signed challenge
+pub async fn passkey_sign_in(credential: &PublicKeyCredential) -> Result<Passkey, ApiError> {
+    let url = format!("{}/session/passkey", API_BASE_URL);
+    Ok(send(reqwest::Client::new().post(url).json(credential)).await?.json().await?)
+}
 
-    let data = response.json::<RealBookEntry>().await?;
-    Ok(data)
+/// Leave the session on this browser (it stays for the other devices)
+pub async fn sign_out() -> Result<(), ApiError> {
+    let url = format!("{}/session/sign-out", API_BASE_URL);
+    send(reqwest::Client::new().post(url)).await?;
+    Ok(())
 }
 
+/// Forget this browser's session on the server
+pub async fn forget_session() -> Result<(), ApiError> {
+    let url = format!("{}/session", API_BASE_URL);
+    send(reqwest::Client::new().delete(url)).await?;
+    Ok(())
+}
+
+/// Get the catalog's releases (for the what's-new banner)
+pub async fn get_changelog() -> Result<Changelog, ApiError> {
+    let url = format!("{}/changelog", API_BASE_URL);
+    get_data(&url).await
+}
+
+/// Get the full catalog (for the client-side search index)
+pub async fn get_catalog() -> Result<CatalogSnapshot, ApiError> {
+    let url = format!("{}/catalog", API_BASE_URL);
+    get_data(&url).await
+}
+
+/// Get catalog statistics
+pub async fn get_stats() -> Result<CatalogStats, ApiError> {
+    let url = format!("{}/stats", API_BASE_URL);
+    get_data(&url).await
+}
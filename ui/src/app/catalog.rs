@@ -0,0 +1,95 @@
+//! The cached catalog, and what the landing page shows from it
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::models::{RealBookEntry, Release};
+use crate::search_index::SearchIndex;
+use crate::{api, changelog, utils};
+
+/// The cached catalog, shared with the callbacks that search it (None until
+/// it's cached)
+pub type SharedIndex = Rc<RefCell<Option<Rc<SearchIndex>>>>;
+
+/// Cached catalog for instant local search, fetched on startup if the cache
+/// can't serve it
+#[hook]
+pub fn use_search_index() -> SharedIndex {
+    let search_index = use_mut_ref(|| SearchIndex::load().map(Rc::new));
+    {
+        let search_index = search_index.clone();
+        use_effect_with((), move |_| {
+            let needs_refresh = search_index.borrow().as_ref().is_none_or(|index| index.is_stale());
+            if needs_refresh {
+                spawn_local(async move {
+                    // On failure keep whatever we had; searches go to the API meanwhile
+                    if let Ok(snapshot) = api::get_catalog().await {
+                        let index = SearchIndex::store(snapshot);
+                        *search_index.borrow_mut() = Some(Rc::new(index));
+                    }
+                });
+            }
+            || ()
+        });
+    }
+    search_index
+}
+
+/// Catalog releases since the last visit, for the what's-new banner, and the
+/// callback dismissing them; a cached catalog from before the newest one is
+/// fetched again (a missing or stale cache is already being refreshed by
+/// `use_search_index`)
+#[hook]
+pub fn use_whats_new(search_index: SharedIndex) -> (Vec<Release>, Callback<()>) {
+    let whats_new = use_state(Vec::<Release>::new);
+    {
+        let whats_new = whats_new.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let Ok(changelog) = api::get_changelog().await else {
+                    return;
+                };
+                let outdated = search_index
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|index| !index.is_stale() && index.version != changelog.catalog_version);
+                if outdated && let Ok(snapshot) = api::get_catalog().await {
+                    let index = SearchIndex::store(snapshot);
+                    *search_index.borrow_mut() = Some(Rc::new(index));
+                }
+                whats_new.set(changelog::unseen(&changelog));
+            });
+            || ()
+        });
+    }
+    let on_dismiss = {
+        let whats_new = whats_new.clone();
+        Callback::from(move |_: ()| {
+            if let Some(newest) = whats_new.first() {
+                changelog::mark_seen(newest.version);
+            }
+            whats_new.set(Vec::new());
+        })
+    };
+    ((*whats_new).clone(), on_dismiss)
+}
+
+/// Song of the day for the landing placeholder (for the local date, so it
+/// changes at the user's midnight); it's a nicety, so failures show nothing
+#[hook]
+pub fn use_song_of_the_day() -> Option<RealBookEntry> {
+    let song_of_the_day = use_state(|| Option::<RealBookEntry>::None);
+    {
+        let song_of_the_day = song_of_the_day.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                if let Ok(pick) = api::song_of_the_day(&utils::local_date()).await {
+                    song_of_the_day.set(Some(pick.entry));
+                }
+            });
+            || ()
+        });
+    }
+    (*song_of_the_day).clone()
+}
@@ -0,0 +1,172 @@
+//! The user's songs: favorites, tags, repertoire, and setlists
+
+use yew::prelude::*;
+use crate::components::use_toaster;
+use crate::models::RealBookEntry;
+use crate::repertoire::{Repertoire, Status};
+use crate::setlists::Setlist;
+use crate::tags::Tags;
+use crate::{favorites, repertoire, setlists, tags, utils};
+
+/// The stored song lists, and the callbacks changing them
+#[derive(Clone)]
+pub struct Library {
+    /// Starred songs (persisted)
+    pub favorites: UseStateHandle<Vec<RealBookEntry>>,
+    /// User tags on songs (persisted)
+    pub tags: UseStateHandle<Tags>,
+    /// Songs' learning status and star ratings (persisted)
+    pub repertoire: UseStateHandle<Repertoire>,
+    /// Saved setlists (persisted)
+    pub setlists: UseStateHandle<Vec<Setlist>>,
+    /// Reload them after an import or a sync may have changed them
+    pub reload: Callback<()>,
+    /// Star or unstar a song
+    pub on_toggle_favorite: Callback<RealBookEntry>,
+    /// Replace a song's tags
+    pub on_tags_change: Callback<(RealBookEntry, Vec<String>)>,
+    /// Rename a tag on every song (the tag filter follows it)
+    pub on_rename_tag: Callback<(String, String)>,
+    /// Remove a tag from every song (turning its filter off)
+    pub on_delete_tag: Callback<String>,
+    /// Set a song's repertoire status and star rating
+    pub on_progress_change: Callback<(RealBookEntry, Option<Status>, Option<u8>)>,
+    /// Store changed setlists
+    pub on_setlists_change: Callback<Vec<Setlist>>,
+    /// Add a song to a setlist (None: to a new setlist, named by the user)
+    pub on_add_to_setlist: Callback<(Option<String>, RealBookEntry)>,
+}
+
+/// The stored song lists; `tag_filter` follows renamed and deleted tags
+#[hook]
+pub fn use_library(tag_filter: UseStateHandle<Option<String>>) -> Library {
+    let toaster = use_toaster();
+    let favorites = use_state(favorites::load);
+    let tags = use_state(tags::load);
+    let repertoire = use_state(repertoire::load);
+    let setlists = use_state(setlists::load);
+
+    // Keep what's stored in step with the app's other tabs and windows (say, a
+    // second window on the stage monitor): starring a song or editing a setlist
+    // in one shows up in the rest
+    {
+        let favorites = favorites.clone();
+        let tags = tags.clone();
+        let repertoire = repertoire.clone();
+        let setlists = setlists.clone();
+        use_effect_with((), move |_| {
+            let listeners = [
+                favorites::watch(move |songs| favorites.set(songs)),
+                tags::watch(move |tagged| tags.set(tagged)),
+                repertoire::watch(move |songs| repertoire.set(songs)),
+                setlists::watch(move |lists| setlists.set(lists)),
+            ];
+            move || drop(listeners)
+        });
+    }
+
+    let reload = {
+        let favorites = favorites.clone();
+        let tags = tags.clone();
+        let repertoire = repertoire.clone();
+        let setlists = setlists.clone();
+        Callback::from(move |_: ()| {
+            favorites.set(favorites::load());
+            tags.set(tags::load());
+            repertoire.set(repertoire::load());
+            setlists.set(setlists::load());
+        })
+    };
+
+    // Callback: Star or unstar a song
+    let on_toggle_favorite = {
+        let favorites = favorites.clone();
+        Callback::from(move |entry: RealBookEntry| {
+            favorites.set(favorites::toggle(&favorites, &entry));
+        })
+    };
+
+    // Callback: Replace a song's tags
+    let on_tags_change = {
+        let tags = tags.clone();
+        Callback::from(move |(entry, song_tags): (RealBookEntry, Vec<String>)| {
+            tags.set(tags::set(&tags, &entry, &song_tags));
+        })
+    };
+
+    // Callback: Rename a tag on every song (the filter follows it)
+    let on_rename_tag = {
+        let tags = tags.clone();
+        let tag_filter = tag_filter.clone();
+        Callback::from(move |(from, to): (String, String)| {
+            let Some(to) = tags::normalize(&to) else {
+                return;
+            };
+            tags.set(tags::rename(&tags, &from, &to));
+            if tag_filter.as_deref() == Some(from.as_str()) {
+                tag_filter.set(Some(to));
+            }
+        })
+    };
+
+    // Callback: Remove a tag from every song (turning its filter off)
+    let on_delete_tag = {
+        let tags = tags.clone();
+        Callback::from(move |tag: String| {
+            tags.set(tags::delete(&tags, &tag));
+            if tag_filter.as_deref() == Some(tag.as_str()) {
+                tag_filter.set(None);
+            }
+        })
+    };
+
+    // Callback: Set a song's repertoire status and star rating
+    let on_progress_change = {
+        let repertoire = repertoire.clone();
+        Callback::from(move |(entry, status, rating): (RealBookEntry, Option<Status>, Option<u8>)| {
+            repertoire.set(repertoire::set(&repertoire, &entry, status, rating));
+        })
+    };
+
+    // Callback: Store changed setlists
+    let on_setlists_change = {
+        let setlists = setlists.clone();
+        Callback::from(move |updated: Vec<Setlist>| setlists.set(setlists::save(updated)))
+    };
+
+    // Callback: Add a song to a setlist (None: to a new setlist, named by the user)
+    let on_add_to_setlist = {
+        let setlists = setlists.clone();
+        let on_setlists_change = on_setlists_change.clone();
+        Callback::from(move |(setlist_id, entry): (Option<String>, RealBookEntry)| {
+            let mut updated = (*setlists).clone();
+            let index = match setlist_id {
+                Some(id) => updated.iter().position(|setlist| setlist.id == id),
+                None => utils::prompt_text("Setlist name").map(|name| {
+                    updated.push(setlists::create(&name));
+                    updated.len() - 1
+                }),
+            };
+            if let Some(index) = index {
+                toaster.success(format!("Added \"{}\" to {}", entry.title, updated[index].name));
+                updated[index].songs.push(entry);
+                on_setlists_change.emit(updated);
+            }
+        })
+    };
+
+    Library {
+        favorites,
+        tags,
+        repertoire,
+        setlists,
+        reload,
+        on_toggle_favorite,
+        on_tags_change,
+        on_rename_tag,
+        on_delete_tag,
+        on_progress_change,
+        on_setlists_change,
+        on_add_to_setlist,
+    }
+}
@@ -0,0 +1,406 @@
+//! The App component and the hooks holding its state
+//!
+//! App only wires the features together and lays out the page; each feature
+//! keeps its state and callbacks in a custom hook:
+//! - `catalog`: the cached catalog, what's new in it, and the song of the day
+//! - `search/`: the query and filters, live search, recent and saved searches
+//! - `navigation`: the URL (open chart and setlist, Back/Forward)
+//! - `library`: favorites, tags, repertoire, and setlists
+//! - `practice/`: the practice log and dealt practice sets
+//! - `viewer/`: Random, the songs either side, offline pages, MIDI pedals
+//! - `settings/`: preferences, and the account (sync, passkeys, archives)
+//! - `resume`: picking up where the last visit left off
+//! - `shortcuts`: global keyboard shortcuts
+
+mod catalog;
+mod library;
+mod navigation;
+mod practice;
+mod resume;
+mod search;
+mod settings;
+mod shortcuts;
+mod viewer;
+
+use yew::prelude::*;
+use crate::components::{AboutPanel, AdvancedSearch, Header, OfflineIndicator, PracticePanel, ResultsList, SearchInput, SettingsPanel, SetlistPanel, SheetViewer, WhatsNew, use_toaster};
+use crate::hooks;
+use crate::i18n::Locale;
+use crate::models::RealBookEntry;
+use crate::repertoire::{self, RepertoireFilter};
+use crate::{review, tags};
+use shortcuts::{ShortcutActions, ShortcutTargets};
+
+/// Screens at most this wide show one pane at a time (results or viewer)
+const NARROW_SCREEN: &str = "(max-width: 767px)";
+
+/// Which pane is shown on narrow screens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pane {
+    Results,
+    Viewer,
+}
+
+/// Main App component
+///
+/// The search page: the search box and filters, the results beside the sheet
+/// viewer, and the setlist, practice, about, and settings panels below. Its
+/// own state is only what the layout needs: the chart open in the viewer,
+/// which pane shows on narrow screens, and whether the results are collapsed
+/// beside the chart on wide ones.
+#[function_component(App)]
+pub fn app() -> Html {
+    let restored = resume::use_restored();
+    let toaster = use_toaster();
+    let search_index = catalog::use_search_index();
+    let (whats_new, on_dismiss_whats_new) = catalog::use_whats_new(search_index.clone());
+    let song_of_the_day = catalog::use_song_of_the_day();
+    let preferences = settings::use_preferences();
+    let inputs = search::use_search_inputs(restored.clone());
+    // Entry selected for viewing sheet music
+    let selected_entry = use_state(|| Option::<RealBookEntry>::None);
+    // Pane shown on narrow screens (results list or full-screen viewer)
+    let mobile_pane = use_state(|| Pane::Results);
+    // Whether the results column is hidden while a song is open (wide screens)
+    let results_collapsed = use_state(|| false);
+    let narrow = hooks::use_media_query(NARROW_SCREEN);
+
+    let navigation = navigation::use_navigation(
+        restored.clone(),
+        inputs.clone(),
+        selected_entry.clone(),
+        mobile_pane.clone(),
+        search_index.clone(),
+    );
+    let open_entry = navigation.open_entry.clone();
+    let library = library::use_library(inputs.tag_filter.clone());
+    let (practice_log, on_practice_logged) = practice::use_practice_log();
+
+    // Reload the stored data an import or a sync may have changed
+    let reload_stores = {
+        let reload_library = library.reload.clone();
+        let reload_practice_log = on_practice_logged.clone();
+        Callback::from(move |_: ()| {
+            reload_library.emit(());
+            reload_practice_log.emit(());
+        })
+    };
+    let account = settings::use_account(reload_stores, selected_entry.as_ref().map(|entry| entry.id));
+
+    let history = search::use_search_history(
+        inputs.clone(),
+        selected_entry.clone(),
+        navigation.open_setlist.clone(),
+        navigation.on_close_setlist.clone(),
+    );
+    let local_songs = inputs.local_songs(navigation.open_setlist.as_ref(), &library);
+    let search = search::use_search(
+        inputs.clone(),
+        local_songs,
+        search_index,
+        selected_entry.clone(),
+        open_entry.clone(),
+        history.record_search.clone(),
+    );
+    navigation::use_search_in_url(navigation.synced_search.clone(), selected_entry.clone(), search.debounced_params.clone());
+
+    let results_scroll = resume::use_session_saving(restored.clone(), inputs.session());
+    resume::use_page_scroll_restore(restored);
+
+    let random_filters = inputs.random_filters();
+    let (random_loading, on_random) = viewer::use_random(random_filters.clone(), selected_entry.clone(), open_entry.clone());
+    let offline_songs = viewer::use_offline_songs();
+    let on_adjacent_song =
+        viewer::adjacent_song(search.results.clone(), selected_entry.clone(), search.selected_index.clone(), open_entry.clone());
+    let pedals = viewer::use_pedals(selected_entry.clone(), search.results.clone(), open_entry.clone(), on_adjacent_song.clone());
+    let deal = practice::use_deal(
+        random_filters,
+        library.setlists.clone(),
+        library.on_setlists_change.clone(),
+        navigation.on_open_setlist.clone(),
+    );
+
+    shortcuts::use_shortcuts(
+        ShortcutTargets {
+            results: (*search.results).clone(),
+            selected_index: *search.selected_index,
+            keymap: preferences.keymap.clone(),
+            chart_open: selected_entry.is_some(),
+            query_empty: inputs.query.is_empty(),
+            reading_mode: !narrow && *results_collapsed,
+        },
+        search.selected_index.clone(),
+        results_collapsed.clone(),
+        ShortcutActions {
+            open_entry: open_entry.clone(),
+            on_random: on_random.clone(),
+            on_close_viewer: navigation.on_close_viewer.clone(),
+            on_query_change: search.on_query_change.clone(),
+        },
+    );
+
+    // Callback: Handle when user clicks on a search result
+    let on_entry_click = {
+        let open_entry = open_entry.clone();
+        let record_search = history.record_search.clone();
+        Callback::from(move |entry: RealBookEntry| {
+            record_search.emit(());
+            open_entry.emit(entry);
+        })
+    };
+
+    // Callback: Switch panes from the narrow-screen tab bar
+    let on_pane_select = {
+        let mobile_pane = mobile_pane.clone();
+        Callback::from(move |pane: Pane| mobile_pane.set(pane))
+    };
+
+    let next_entry = viewer::next_entry((*selected_entry).as_ref(), (*search.results).as_ref());
+
+    // On narrow screens a selected chart takes over the whole screen
+    let viewer_fullscreen = narrow && selected_entry.is_some() && *mobile_pane == Pane::Viewer;
+    // Wide screens only: the results stay mounted (keeping their scroll
+    // position) and are hidden by CSS
+    let reading_mode = !narrow && selected_entry.is_some() && *results_collapsed;
+
+    // Tags in use (offered by the tag filter and the viewer), and how many songs have each
+    let tag_counts = tags::counts(&library.tags);
+    let tag_names: Vec<String> = tag_counts.iter().map(|(tag, _)| tag.clone()).collect();
+
+    // Render the UI
+    // The html! macro lets us write JSX-like syntax
+    html! {
+        <ContextProvider<Locale> context={preferences.locale}>
+            // Pico CSS styles <main> as the main container
+            <main class="container">
+                if !viewer_fullscreen {
+                    // Header component (with the sync indicator)
+                    <Header sync_status={account.sync_status.clone()} on_sync={account.run_sync.clone()} />
+                }
+
+                // Banner while the browser has no connectivity
+                <OfflineIndicator />
+
+                // What changed in the catalog since the last visit
+                <WhatsNew releases={whats_new} on_dismiss={on_dismiss_whats_new} />
+
+                if !viewer_fullscreen {
+                    // SearchInput component (controlled component with callbacks)
+                    // Search happens automatically as user types
+                    <SearchInput
+                        query={(*inputs.query).clone()}
+                        refine_terms={(*inputs.refine_terms).clone()}
+                        selected_volume={*inputs.volume}
+                        browse_mode={*inputs.browse_mode}
+                        page_input={(*inputs.page_input).clone()}
+                        random_loading={random_loading}
+                        favorites_only={*inputs.favorites_only}
+                        tags={tag_names.clone()}
+                        tag_filter={(*inputs.tag_filter).clone()}
+                        repertoire_filter={*inputs.repertoire_filter}
+                        on_repertoire_filter={
+                            let repertoire_filter = inputs.repertoire_filter.clone();
+                            Callback::from(move |filter: Option<RepertoireFilter>| repertoire_filter.set(filter))
+                        }
+                        on_tag_filter={
+                            let tag_filter = inputs.tag_filter.clone();
+                            Callback::from(move |tag: Option<String>| tag_filter.set(tag))
+                        }
+                        setlist_name={
+                            (*navigation.open_setlist).as_ref()
+                                .and_then(|id| library.setlists.iter().find(|setlist| &setlist.id == id))
+                                .map(|setlist| setlist.name.clone())
+                        }
+                        completions={search.completions.clone()}
+                        recent_searches={history.recent.clone()}
+                        saved_searches={history.saved.clone()}
+                        on_query_change={search.on_query_change.clone()}
+                        on_volume_change={search.on_volume_change.clone()}
+                        on_browse_toggle={search.on_browse_toggle.clone()}
+                        on_page_change={search.on_page_change.clone()}
+                        on_refine={search.on_refine.clone()}
+                        on_remove_refine={search.on_remove_refine.clone()}
+                        on_random={on_random}
+                        on_favorites_toggle={
+                            let favorites_only = inputs.favorites_only.clone();
+                            Callback::from(move |enabled: bool| favorites_only.set(enabled))
+                        }
+                        on_close_setlist={navigation.on_close_setlist.clone()}
+                        on_navigate={search.on_navigate.clone()}
+                        on_enter={search.on_enter.clone()}
+                        on_completion_select={search.on_suggestion_click.clone()}
+                        on_clear_recent={history.on_clear_recent.clone()}
+                        on_save_search={history.on_save_search.clone()}
+                        on_run_saved_search={history.on_run_saved_search.clone()}
+                        on_delete_saved_search={history.on_delete_saved_search.clone()}
+                    />
+                    // Structured filters for those who'd rather not type "100-120"
+                    <AdvancedSearch
+                        page_input={(*inputs.page_input).clone()}
+                        on_page_change={search.on_page_change.clone()}
+                    />
+                }
+
+                // Narrow screens: tabs to switch between the results and the open chart
+                if narrow && selected_entry.is_some() {
+                    <div class="pane-tabs" role="tablist">
+                        <button
+                            role="tab"
+                            aria-selected={(!viewer_fullscreen).to_string()}
+                            class={if viewer_fullscreen { "secondary outline" } else { "" }}
+                            onclick={on_pane_select.reform(|_: MouseEvent| Pane::Results)}
+                        >
+                            { "Results" }
+                        </button>
+                        <button
+                            role="tab"
+                            aria-selected={viewer_fullscreen.to_string()}
+                            class={if viewer_fullscreen { "" } else { "secondary outline" }}
+                            onclick={on_pane_select.reform(|_: MouseEvent| Pane::Viewer)}
+                        >
+                            { "Sheet" }
+                        </button>
+                    </div>
+                }
+
+                // Content grid: results on left, viewer on right
+                // (one pane at a time on narrow screens)
+                // (or the viewer alone once the results are collapsed)
+                <div class={classes!(
+                    "content-grid",
+                    narrow.then_some("single-pane"),
+                    reading_mode.then_some("results-collapsed"),
+                )}>
+                    if !viewer_fullscreen {
+                        // ResultsList component - shows skeleton rows while searching
+                        // selected_index tracks which result is highlighted via keyboard navigation
+                        <ResultsList
+                            results={(*search.results).clone()}
+                            loading={search.loading}
+                            row_height={preferences.appearance.row_height()}
+                            selected_index={*search.selected_index}
+                            favorite_ids={library.favorites.iter().map(|song| song.id).collect::<Vec<_>>()}
+                            on_entry_click={on_entry_click}
+                            on_toggle_favorite={library.on_toggle_favorite.clone()}
+                            on_suggestion_click={search.on_suggestion_click.clone()}
+                            on_load_more={search.on_load_more.clone()}
+                            sort_order={*inputs.sort_order}
+                            selected_volume={*inputs.volume}
+                            on_volume_click={search.on_volume_change.clone()}
+                            initial_scroll={*results_scroll.borrow()}
+                            review_queue={review::queue(&library.repertoire, &practice_log, preferences.review_intervals)}
+                            song_of_the_day={song_of_the_day}
+                            recently_viewed={account.recently_viewed.clone()}
+                            on_clear_recently_viewed={account.on_clear_recently_viewed.clone()}
+                            on_scroll_top={
+                                let results_scroll = results_scroll.clone();
+                                Callback::from(move |top: f64| *results_scroll.borrow_mut() = top)
+                            }
+                            initial={*inputs.title_initial}
+                            on_initial_select={
+                                let title_initial = inputs.title_initial.clone();
+                                Callback::from(move |letter: Option<char>| title_initial.set(letter))
+                            }
+                            on_sort_change={search.on_sort_change.clone()}
+                        />
+                    }
+
+                    if !narrow || viewer_fullscreen {
+                        // SheetViewer component - displays selected sheet music
+                        <SheetViewer
+                            entry={(*selected_entry).clone()}
+                            half_page_turns={preferences.appearance.half_page_turns}
+                            loading={random_loading}
+                            saved_offline={(*selected_entry).as_ref().is_some_and(|entry| offline_songs.contains(entry))}
+                            saving_offline={offline_songs.saving}
+                            favorite={
+                                (*selected_entry).as_ref()
+                                    .is_some_and(|entry| library.favorites.iter().any(|song| song.id == entry.id))
+                            }
+                            on_save_offline={offline_songs.on_save.clone()}
+                            on_toggle_favorite={library.on_toggle_favorite.clone()}
+                            tags={(*selected_entry).as_ref().map(|entry| tags::of(&library.tags, entry.id)).unwrap_or_default()}
+                            all_tags={tag_names}
+                            on_tags_change={library.on_tags_change.clone()}
+                            progress={(*selected_entry).as_ref().map(|entry| repertoire::of(&library.repertoire, entry.id)).unwrap_or_default()}
+                            on_progress_change={library.on_progress_change.clone()}
+                            on_practice_logged={on_practice_logged}
+                            setlists={(*library.setlists).clone()}
+                            keymap={preferences.keymap.clone()}
+                            page_turn={pedals.page_turn}
+                            on_add_to_setlist={library.on_add_to_setlist.clone()}
+                            on_close={navigation.on_close_viewer.clone()}
+                            on_adjacent_song={on_adjacent_song}
+                            {next_entry}
+                            modal={viewer_fullscreen}
+                            results_collapsed={(!narrow && selected_entry.is_some()).then_some(*results_collapsed)}
+                            on_toggle_results={
+                                let results_collapsed = results_collapsed.clone();
+                                Callback::from(move |()| results_collapsed.set(!*results_collapsed))
+                            }
+                        />
+                    }
+                </div>
+
+                if !viewer_fullscreen {
+                    // Setlists: open, reorder, export/import (collapsed by default)
+                    <SetlistPanel
+                        setlists={(*library.setlists).clone()}
+                        on_change={library.on_setlists_change.clone()}
+                        on_open={navigation.on_open_setlist.clone()}
+                        dealt_set={deal.set.clone()}
+                        dealing={deal.dealing}
+                        on_deal={deal.on_deal.clone()}
+                        on_reroll={deal.on_reroll.clone()}
+                        on_keep_dealt={deal.on_keep.clone()}
+                        on_discard_dealt={deal.on_discard.clone()}
+                        on_song_click={open_entry}
+                        on_error={Callback::from(move |message: String| toaster.error(message))}
+                    />
+
+                    // This week's practice time (collapsed by default)
+                    <PracticePanel log={(*practice_log).clone()} />
+
+                    // Catalog statistics (collapsed by default)
+                    <AboutPanel repertoire={repertoire::summarize(&library.repertoire)} />
+
+                    // Offline storage usage and eviction (collapsed by default)
+                    <SettingsPanel
+                        appearance={preferences.appearance}
+                        on_appearance_change={preferences.on_appearance_change.clone()}
+                        locale={preferences.locale}
+                        on_locale_change={preferences.on_locale_change.clone()}
+                        keymap={preferences.keymap.clone()}
+                        on_keymap_change={preferences.on_keymap_change.clone()}
+                        midi_status={pedals.status.clone()}
+                        midi_map={pedals.map.clone()}
+                        midi_learning={pedals.learning}
+                        on_midi_connect={pedals.on_connect.clone()}
+                        on_midi_learn={pedals.on_learn.clone()}
+                        on_midi_map_change={pedals.on_map_change.clone()}
+                        offline_songs={offline_songs.songs.clone()}
+                        on_remove_offline={offline_songs.on_remove.clone()}
+                        on_clear_offline={offline_songs.on_clear.clone()}
+                        {tag_counts}
+                        on_rename_tag={library.on_rename_tag.clone()}
+                        on_delete_tag={library.on_delete_tag.clone()}
+                        review_intervals={preferences.review_intervals}
+                        on_review_intervals_change={preferences.on_review_intervals_change.clone()}
+                        on_export_data={account.on_export_data.clone()}
+                        on_import_data={account.on_import_data.clone()}
+                        sync_status={account.sync_status.clone()}
+                        link_code={account.link_code.clone()}
+                        on_link_device={account.on_link_device.clone()}
+                        on_join_device={account.on_join_device.clone()}
+                        on_stop_sync={account.on_stop_sync.clone()}
+                        passkeys={account.passkeys.clone()}
+                        on_create_passkey={account.on_create_passkey.clone()}
+                        on_passkey_sign_in={account.on_passkey_sign_in.clone()}
+                        on_remove_passkey={account.on_remove_passkey.clone()}
+                        on_sign_out={account.on_sign_out.clone()}
+                    />
+                }
+            </main>
+        </ContextProvider<Locale>>
+    }
+}
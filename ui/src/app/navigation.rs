@@ -0,0 +1,212 @@
+//! The URL: opening and closing charts and setlists, and Back/Forward
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use gloo_events::EventListener;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::app::Pane;
+use crate::app::catalog::SharedIndex;
+use crate::app::resume::Restored;
+use crate::app::search::SearchInputs;
+use crate::components::use_toaster;
+use crate::models::{RealBookEntry, SearchParams};
+use crate::router::{self, Route, SearchRoute};
+use crate::{api, setlists};
+
+/// The open chart and setlist as the URL has them, and the callbacks moving between them
+#[derive(Clone)]
+pub struct Navigation {
+    /// Id of the setlist shown as the results (`/setlists/:id`)
+    pub open_setlist: UseStateHandle<Option<String>>,
+    /// The search last written to (or read from) the URL
+    pub synced_search: Rc<RefCell<SearchRoute>>,
+    /// Show an entry in the viewer and record it in the URL
+    pub open_entry: Callback<RealBookEntry>,
+    /// Close the viewer and return to the results
+    pub on_close_viewer: Callback<()>,
+    /// Show a setlist's songs as the results
+    pub on_open_setlist: Callback<String>,
+    /// Leave the setlist and go back to searching the catalog
+    pub on_close_setlist: Callback<()>,
+}
+
+/// Keep `selected_entry` and the search `inputs` in step with the URL: apply
+/// it on load and on Back/Forward, and record each chart opened
+#[hook]
+pub fn use_navigation(
+    restored: Restored,
+    inputs: SearchInputs,
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    mobile_pane: UseStateHandle<Pane>,
+    search_index: SharedIndex,
+) -> Navigation {
+    let toaster = use_toaster();
+    let open_setlist = use_state(|| Option::<String>::None);
+
+    // The search last written to (or read from) the URL, so URL changes we
+    // apply ourselves aren't pushed back as new history entries
+    let synced_search = use_mut_ref(|| restored.search.without_song());
+
+    // Whether the open chart got its own history entry from inside the app,
+    // so closing it can simply go Back to the results
+    let song_pushed = use_mut_ref(|| false);
+
+    // Callback: Show an entry in the viewer and record it in the URL
+    // Opening a chart from the results adds a history entry (Back returns to the
+    // list); switching charts while one is open replaces it, so Back still does
+    let open_entry = {
+        let selected_entry = selected_entry.clone();
+        let synced_search = synced_search.clone();
+        let song_pushed = song_pushed.clone();
+        Callback::from(move |entry: RealBookEntry| {
+            mobile_pane.set(Pane::Viewer);
+            let search = SearchRoute { song: Some(entry.id), ..synced_search.borrow().clone() };
+            let route = Route::for_search(search);
+            if selected_entry.is_some() {
+                route.replace();
+            } else {
+                route.push();
+                *song_pushed.borrow_mut() = true;
+            }
+            selected_entry.set(Some(entry));
+        })
+    };
+
+    // Callback: Close the viewer and return to the results
+    let on_close_viewer = {
+        let selected_entry = selected_entry.clone();
+        let synced_search = synced_search.clone();
+        let song_pushed = song_pushed.clone();
+        Callback::from(move |_: ()| {
+            if *song_pushed.borrow() {
+                // popstate clears the selection
+                router::back();
+            } else {
+                // Arrived via a shared link: there's no in-app entry to go back to
+                Route::for_search(synced_search.borrow().clone()).push();
+                selected_entry.set(None);
+            }
+        })
+    };
+
+    // Callback: Restore state from a route (on load and on Back/Forward)
+    let on_route = {
+        let synced_search = synced_search.clone();
+        let open_setlist = open_setlist.clone();
+        Callback::from(move |route: Route| {
+            match &route {
+                // Read from storage: this callback outlives the render it was made in
+                Route::Setlist(id) => {
+                    if setlists::load().iter().any(|setlist| &setlist.id == id) {
+                        open_setlist.set(Some(id.clone()));
+                        selected_entry.set(None);
+                    } else {
+                        toaster.error(format!("Setlist \"{}\" not found", id));
+                    }
+                    return;
+                }
+                Route::NotFound => {
+                    toaster.error("Page not found");
+                    return;
+                }
+                // The stage view renders in place of the App (see Root)
+                Route::Home | Route::Song(_) | Route::Search(_) | Route::Stage => open_setlist.set(None),
+            }
+
+            let search = route.search();
+            inputs.query.set(search.q.clone().unwrap_or_default());
+            inputs.volume.set(search.vol);
+            inputs.page_input.set(search.page.clone().unwrap_or_default());
+            *synced_search.borrow_mut() = search.without_song();
+
+            let Some(id) = search.song else {
+                selected_entry.set(None);
+                *song_pushed.borrow_mut() = false;
+                return;
+            };
+            let cached = search_index.borrow().as_ref()
+                .and_then(|index| index.entries.iter().find(|entry| entry.id == id).cloned());
+            if let Some(entry) = cached {
+                selected_entry.set(Some(entry));
+            } else {
+                let selected_entry = selected_entry.clone();
+                let toaster = toaster.clone();
+                spawn_local(async move {
+                    match api::get_entry(id).await {
+                        Ok(entry) => selected_entry.set(Some(entry)),
+                        Err(e) => toaster.error(e.message),
+                    }
+                });
+            }
+        })
+    };
+
+    // Apply the URL on load, and again whenever Back/Forward changes it
+    {
+        let on_route = on_route.clone();
+        use_effect_with((), move |_| {
+            // Reopened at `/`: pick up where the last session left off
+            if let Some(session) = &*restored.session
+                && Route::current() == Route::Home {
+                session.route().replace();
+            }
+            on_route.emit(Route::current());
+
+            let window = web_sys::window().unwrap();
+            let listener = EventListener::new(&window, "popstate", move |_| {
+                on_route.emit(Route::current());
+            });
+            move || drop(listener)
+        });
+    }
+
+    // Callback: Show a setlist's songs as the results
+    let on_open_setlist = Callback::from(move |id: String| {
+        let route = Route::Setlist(id);
+        route.push();
+        on_route.emit(route);
+    });
+
+    // Callback: Leave the setlist and go back to searching the catalog
+    let on_close_setlist = {
+        let open_setlist = open_setlist.clone();
+        let synced_search = synced_search.clone();
+        Callback::from(move |_: ()| {
+            Route::for_search(synced_search.borrow().clone()).push();
+            open_setlist.set(None);
+        })
+    };
+
+    Navigation { open_setlist, synced_search, open_entry, on_close_viewer, on_open_setlist, on_close_setlist }
+}
+
+/// Record each settled search (`debounced_params`) in the URL, keeping the
+/// open chart (if any)
+#[hook]
+pub fn use_search_in_url(
+    synced_search: Rc<RefCell<SearchRoute>>,
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    debounced_params: SearchParams,
+) {
+    use_effect_with(debounced_params, move |params| {
+        let search = SearchRoute {
+            q: params.query.clone().filter(|q| !q.is_empty()),
+            vol: params.volume,
+            page: params.page_input(),
+            song: None,
+        };
+        if *synced_search.borrow() != search {
+            let song = selected_entry.as_ref().map(|entry| entry.id);
+            let route = Route::for_search(SearchRoute { song, ..search.clone() });
+            // Refining a search updates its entry in place; starting one adds an entry
+            if synced_search.borrow().is_empty() {
+                route.push();
+            } else {
+                route.replace();
+            }
+            *synced_search.borrow_mut() = search;
+        }
+        || ()
+    });
+}
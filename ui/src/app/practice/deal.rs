@@ -0,0 +1,121 @@
+//! Random practice sets, dealt from the songs at hand
+
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::components::use_toaster;
+use crate::models::{RealBookEntry, SearchParams};
+use crate::setlists::{self, Setlist};
+use crate::{api, utils};
+
+/// Number of songs in a dealt practice set
+const DEAL_SIZE: usize = 5;
+
+/// A dealt set waiting to be kept or discarded, and the callbacks handling it
+#[derive(Clone)]
+pub struct Deal {
+    /// The set, until it's kept or discarded
+    pub set: Option<Vec<RealBookEntry>>,
+    /// Whether a set (or a replacement song) is being dealt
+    pub dealing: bool,
+    /// Deal a fresh set
+    pub on_deal: Callback<()>,
+    /// Swap the song at a position for another not already in the set
+    pub on_reroll: Callback<usize>,
+    /// Keep the set as a setlist (named by the user) and open it
+    pub on_keep: Callback<()>,
+    pub on_discard: Callback<()>,
+}
+
+/// Sets dealt from the songs `filters` match; a kept set is added to
+/// `setlists` with `on_setlists_change` and opened with `on_open_setlist`
+#[hook]
+pub fn use_deal(
+    filters: SearchParams,
+    setlists: UseStateHandle<Vec<Setlist>>,
+    on_setlists_change: Callback<Vec<Setlist>>,
+    on_open_setlist: Callback<String>,
+) -> Deal {
+    let toaster = use_toaster();
+    let dealt_set = use_state(|| Option::<Vec<RealBookEntry>>::None);
+    let dealing = use_state(|| false);
+
+    // Callback: Deal a fresh practice set
+    let on_deal = {
+        let filters = filters.clone();
+        let dealt_set = dealt_set.clone();
+        let dealing = dealing.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |_: ()| {
+            let filters = filters.clone();
+            let dealt_set = dealt_set.clone();
+            let dealing = dealing.clone();
+            let toaster = toaster.clone();
+            dealing.set(true);
+            spawn_local(async move {
+                match api::shuffle(&filters, DEAL_SIZE).await {
+                    Ok(songs) if songs.is_empty() => toaster.error("No songs match these filters"),
+                    Ok(songs) => dealt_set.set(Some(songs)),
+                    Err(e) => toaster.error(e.message),
+                }
+                dealing.set(false);
+            });
+        })
+    };
+
+    // Callback: Swap one song of the dealt set for another not already in it
+    let on_reroll = {
+        let dealt_set = dealt_set.clone();
+        let dealing = dealing.clone();
+        Callback::from(move |position: usize| {
+            let Some(songs) = (*dealt_set).clone() else {
+                return;
+            };
+            let filters = filters.clone();
+            let dealt_set = dealt_set.clone();
+            let dealing = dealing.clone();
+            let toaster = toaster.clone();
+            dealing.set(true);
+            spawn_local(async move {
+                // One more than the set holds guarantees a newcomer if there is one
+                match api::shuffle(&filters, songs.len() + 1).await {
+                    Ok(candidates) => {
+                        if let Some(song) = candidates.into_iter().find(|song| songs.iter().all(|dealt| dealt.id != song.id)) {
+                            let mut songs = songs;
+                            songs[position] = song;
+                            dealt_set.set(Some(songs));
+                        }
+                    }
+                    Err(e) => toaster.error(e.message),
+                }
+                dealing.set(false);
+            });
+        })
+    };
+
+    // Callback: Keep the dealt set as a setlist (named by the user) and open it
+    let on_keep = {
+        let dealt_set = dealt_set.clone();
+        Callback::from(move |_: ()| {
+            let Some(songs) = (*dealt_set).clone() else {
+                return;
+            };
+            let Some(name) = utils::prompt_text("Setlist name") else {
+                return;
+            };
+            let setlist = Setlist { songs, ..setlists::create(&name) };
+            let id = setlist.id.clone();
+            let mut updated = (*setlists).clone();
+            updated.push(setlist);
+            on_setlists_change.emit(updated);
+            dealt_set.set(None);
+            on_open_setlist.emit(id);
+        })
+    };
+
+    let on_discard = {
+        let dealt_set = dealt_set.clone();
+        Callback::from(move |_: ()| dealt_set.set(None))
+    };
+
+    Deal { set: (*dealt_set).clone(), dealing: *dealing, on_deal, on_reroll, on_keep, on_discard }
+}
@@ -0,0 +1,28 @@
+//! Practice: the log kept by the viewer's timer, and dealt practice sets
+
+mod deal;
+
+pub use deal::use_deal;
+
+use yew::prelude::*;
+use crate::practice::{self, PracticeSession};
+
+/// Sessions logged by the viewer's practice timer (persisted), and the
+/// callback reloading them once the timer (or an import or a sync) logs more
+#[hook]
+pub fn use_practice_log() -> (UseStateHandle<Vec<PracticeSession>>, Callback<()>) {
+    let practice_log = use_state(practice::load);
+    {
+        let practice_log = practice_log.clone();
+        use_effect_with((), move |_| {
+            // Kept in step with the app's other tabs and windows
+            let listener = practice::watch(move |sessions| practice_log.set(sessions));
+            move || drop(listener)
+        });
+    }
+    let reload = {
+        let practice_log = practice_log.clone();
+        Callback::from(move |_: ()| practice_log.set(practice::load()))
+    };
+    (practice_log, reload)
+}
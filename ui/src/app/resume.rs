@@ -0,0 +1,115 @@
+//! Picking up where the last visit left off (see `session.rs`)
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use gloo_events::{EventListener, EventListenerOptions};
+use yew::prelude::*;
+use crate::router::{Route, SearchRoute};
+use crate::session::{self, Session};
+
+/// What the app starts from
+#[derive(Clone)]
+pub struct Restored {
+    /// The last session, if the app picks up where it left off
+    pub session: Rc<Option<Session>>,
+    /// Search to start with: the URL's, or the last session's when reopened at `/`
+    pub search: Rc<SearchRoute>,
+}
+
+/// Search state starts from the URL, so reloads and shared links restore it;
+/// the last session fills in the rest (and the URL, when reopened at `/`)
+#[hook]
+pub fn use_restored() -> Restored {
+    let session = use_memo((), |_| session::restore(&Route::current()));
+    let search = use_memo((), {
+        let session = session.clone();
+        move |_| match &*session {
+            Some(session) => session.route().search(),
+            None => Route::current().search(),
+        }
+    });
+    Restored { session, search }
+}
+
+/// Save the session whenever the page is hidden: a reload, closing the tab,
+/// or switching apps (a phone may discard a hidden page without warning)
+///
+/// `filters` are the ones the URL doesn't carry. Answers with how far the
+/// results list is scrolled, for the list to keep current and to put back
+/// when it's mounted again.
+#[hook]
+pub fn use_session_saving(restored: Restored, filters: Session) -> Rc<RefCell<f64>> {
+    let results_scroll = use_mut_ref(|| (*restored.session).as_ref().map_or(0.0, |session| session.results_scroll));
+
+    // Filters kept current for saving
+    let session_filters = use_mut_ref(Session::default);
+    {
+        let session_filters = session_filters.clone();
+        use_effect_with(filters, move |filters| {
+            *session_filters.borrow_mut() = filters.clone();
+            || ()
+        });
+    }
+
+    {
+        let results_scroll = results_scroll.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().unwrap();
+            let document = window.document().unwrap();
+            let save = Rc::new(move || {
+                let page_scroll = web_sys::window().and_then(|window| window.scroll_y().ok()).unwrap_or_default();
+                session::save(Session {
+                    results_scroll: *results_scroll.borrow(),
+                    page_scroll,
+                    ..session_filters.borrow().clone()
+                });
+            });
+            let on_pagehide = {
+                let save = save.clone();
+                EventListener::new(&window, "pagehide", move |_| save())
+            };
+            let on_visibility = EventListener::new(&document.clone(), "visibilitychange", move |_| {
+                if document.visibility_state() == web_sys::VisibilityState::Hidden {
+                    save();
+                }
+            });
+            move || drop((on_pagehide, on_visibility))
+        });
+    }
+
+    results_scroll
+}
+
+/// Scroll the page (the open chart) back to where the session left it. The
+/// pages load after the app starts, so this retries as each image arrives
+/// until the page is tall enough, or the user scrolls for themselves.
+#[hook]
+pub fn use_page_scroll_restore(restored: Restored) {
+    let page_scroll = (*restored.session).as_ref().map(|session| session.page_scroll).filter(|top| *top > 0.0);
+    use_effect_with((), move |_| {
+        let pending = Rc::new(Cell::new(page_scroll));
+        let window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+        // Image load events don't bubble, so listen in the capture phase
+        let on_load = {
+            let pending = pending.clone();
+            let window = window.clone();
+            EventListener::new_with_options(&document, "load", EventListenerOptions::run_in_capture_phase(), move |_| {
+                if let Some(top) = pending.get() {
+                    window.scroll_to_with_x_and_y(0.0, top);
+                    if window.scroll_y().is_ok_and(|scrolled| scrolled >= top - 1.0) {
+                        pending.set(None);
+                    }
+                }
+            })
+        };
+        let user_scrolls: Vec<EventListener> = ["wheel", "touchstart", "keydown"]
+            .into_iter()
+            .map(|event| {
+                let pending = pending.clone();
+                EventListener::new(&window, event, move |_| pending.set(None))
+            })
+            .collect();
+        move || drop((on_load, user_scrolls))
+    });
+}
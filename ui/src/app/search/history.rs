@@ -0,0 +1,127 @@
+//! Recent and saved searches
+
+use yew::prelude::*;
+use crate::app::search::SearchInputs;
+use crate::models::RealBookEntry;
+use crate::saved_searches::SavedSearch;
+use crate::{recent_searches, saved_searches, utils};
+
+/// Recent and saved searches, and the callbacks managing them
+#[derive(Clone)]
+pub struct SearchHistory {
+    /// Recently submitted queries (persisted)
+    pub recent: Vec<String>,
+    /// Named query + filter combinations (persisted)
+    pub saved: Vec<SavedSearch>,
+    /// Remember the current query as a recent search
+    pub record_search: Callback<()>,
+    pub on_clear_recent: Callback<()>,
+    /// Name and save the current query and filters
+    pub on_save_search: Callback<()>,
+    /// Run a saved search again (in the whole catalog)
+    pub on_run_saved_search: Callback<SavedSearch>,
+    pub on_delete_saved_search: Callback<usize>,
+}
+
+/// Recent and saved searches of `inputs`; running a saved one closes the open
+/// chart and any open setlist (`on_close_setlist`)
+#[hook]
+pub fn use_search_history(
+    inputs: SearchInputs,
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    open_setlist: UseStateHandle<Option<String>>,
+    on_close_setlist: Callback<()>,
+) -> SearchHistory {
+    let recent_searches = use_state(recent_searches::load);
+    let saved_searches = use_state(saved_searches::load);
+
+    // Keep them in step with the app's other tabs and windows
+    {
+        let recent_searches = recent_searches.clone();
+        let saved_searches = saved_searches.clone();
+        use_effect_with((), move |_| {
+            let listeners = [
+                recent_searches::watch(move |queries| recent_searches.set(queries)),
+                saved_searches::watch(move |searches| saved_searches.set(searches)),
+            ];
+            move || drop(listeners)
+        });
+    }
+
+    // Callback: Remember the current query as a recent search
+    let record_search = {
+        let search_query = inputs.query.clone();
+        let recent_searches = recent_searches.clone();
+        Callback::from(move |_: ()| {
+            if !search_query.trim().is_empty() {
+                recent_searches.set(recent_searches::add(&recent_searches, &search_query));
+            }
+        })
+    };
+
+    // Callback: Forget the recent searches
+    let on_clear_recent = {
+        let recent_searches = recent_searches.clone();
+        Callback::from(move |_: ()| {
+            recent_searches::clear();
+            recent_searches.set(Vec::new());
+        })
+    };
+
+    // Callback: Name and save the current query and filters
+    let on_save_search = {
+        let saved_searches = saved_searches.clone();
+        let inputs = inputs.clone();
+        Callback::from(move |_: ()| {
+            let Some(name) = utils::prompt_text("Name this search") else {
+                return;
+            };
+            let mut updated = (*saved_searches).clone();
+            updated.push(SavedSearch {
+                name,
+                query: inputs.query.trim().to_string(),
+                within: (*inputs.refine_terms).clone(),
+                volume: *inputs.volume,
+                page_input: inputs.page_input.trim().to_string(),
+            });
+            saved_searches.set(saved_searches::save(updated));
+        })
+    };
+
+    // Callback: Run a saved search again (in the whole catalog)
+    let on_run_saved_search = Callback::from(move |saved: SavedSearch| {
+        if open_setlist.is_some() {
+            on_close_setlist.emit(());
+        }
+        inputs.favorites_only.set(false);
+        inputs.tag_filter.set(None);
+        inputs.repertoire_filter.set(None);
+        inputs.query.set(saved.query);
+        inputs.refine_terms.set(saved.within);
+        inputs.volume.set(saved.volume);
+        inputs.page_input.set(saved.page_input);
+        selected_entry.set(None);
+    });
+
+    // Callback: Delete a saved search
+    let on_delete_saved_search = {
+        let saved_searches = saved_searches.clone();
+        Callback::from(move |index: usize| {
+            let mut updated = (*saved_searches).clone();
+            if index < updated.len() {
+                updated.remove(index);
+                saved_searches.set(saved_searches::save(updated));
+            }
+        })
+    };
+
+    SearchHistory {
+        recent: (*recent_searches).clone(),
+        saved: (*saved_searches).clone(),
+        record_search,
+        on_clear_recent,
+        on_save_search,
+        on_run_saved_search,
+        on_delete_saved_search,
+    }
+}
@@ -0,0 +1,532 @@
+//! The query, its filters, and the live search over them
+
+mod history;
+
+pub use history::use_search_history;
+
+use futures::future::abortable;
+use gloo_timers::callback::Timeout;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::app::catalog::SharedIndex;
+use crate::app::library::Library;
+use crate::app::resume::Restored;
+use crate::components::use_toaster;
+use crate::models::{RealBookEntry, SearchParams, SearchResponse, SortOrder};
+use crate::repertoire::{self, RepertoireFilter};
+use crate::session::Session;
+use crate::{notes, search_index, storage, tags, utils};
+
+/// Number of entries fetched per page in browse mode
+const BROWSE_PAGE_SIZE: usize = 50;
+
+/// Number of matches fetched per page for searches (more stream in as the
+/// results list is scrolled)
+const RESULTS_PAGE_SIZE: usize = 100;
+
+/// localStorage key for the results sort order
+const SORT_STORAGE_KEY: &str = "realbook.sort";
+
+/// Quiet period after the last keystroke before a live search is sent
+const SEARCH_DEBOUNCE_MS: u32 = 200;
+
+/// What the user is searching for
+#[derive(Clone)]
+pub struct SearchInputs {
+    /// Current search text
+    pub query: UseStateHandle<String>,
+    /// Earlier queries the current query refines
+    pub refine_terms: UseStateHandle<Vec<String>>,
+    /// Volume filter (or None for "All")
+    pub volume: UseStateHandle<Option<u32>>,
+    /// Page filter text, a page ("134"), a span ("100-120"), or a page to
+    /// search near ("~300±3")
+    pub page_input: UseStateHandle<String>,
+    /// Index letter picked in the A-Z strip, if any
+    pub title_initial: UseStateHandle<Option<char>>,
+    /// Whether an empty query lists the selected volume (paginated)
+    pub browse_mode: UseStateHandle<bool>,
+    /// Whether searches are limited to starred songs
+    pub favorites_only: UseStateHandle<bool>,
+    /// Tag the searches are limited to, if any
+    pub tag_filter: UseStateHandle<Option<String>>,
+    /// Part of the user's repertoire the searches are limited to, if any
+    pub repertoire_filter: UseStateHandle<Option<RepertoireFilter>>,
+    /// Order of the results list (persisted)
+    pub sort_order: UseStateHandle<SortOrder>,
+}
+
+impl SearchInputs {
+    /// Current search parameters, derived from the inputs
+    ///
+    /// In browse mode, an empty search with a volume selected lists that volume
+    /// page by page. Searches are paged too, except when sorted by title or
+    /// page: sorting only a first page would put the wrong songs at the top.
+    pub fn params(&self) -> SearchParams {
+        let mut params = SearchParams {
+            query: Some((*self.query).clone()),
+            within: (*self.refine_terms).clone(),
+            initial: *self.title_initial,
+            volume: *self.volume,
+            // Filters on metadata the catalog doesn't record (kept from an
+            // earlier session) would match nothing
+            ..Default::default()
+        }
+        .with_page_input(&self.page_input);
+
+        if params.has_criteria() {
+            if *self.sort_order == SortOrder::Relevance {
+                params.limit = Some(RESULTS_PAGE_SIZE);
+            }
+        } else if *self.browse_mode && params.volume.is_some() {
+            params.limit = Some(BROWSE_PAGE_SIZE);
+        }
+        params
+    }
+
+    /// Random songs (the Random button, dealt sets) come from the current volume
+    /// and page and letter filters (not the query), so they're from the books at hand
+    pub fn random_filters(&self) -> SearchParams {
+        SearchParams {
+            query: None,
+            within: Vec::new(),
+            offset: None,
+            limit: None,
+            ..self.params()
+        }
+    }
+
+    /// Filters the URL doesn't carry, for saving the session
+    pub fn session(&self) -> Session {
+        Session {
+            refine_terms: (*self.refine_terms).clone(),
+            title_initial: *self.title_initial,
+            browse_mode: *self.browse_mode,
+            favorites_only: *self.favorites_only,
+            tag: (*self.tag_filter).clone(),
+            repertoire: *self.repertoire_filter,
+            ..Default::default()
+        }
+    }
+
+    /// Songs searched locally instead of the catalog: the open setlist's, or
+    /// those the song filters that are on (Favorites, a tag, part of the
+    /// repertoire) all keep, in the order of the first
+    pub fn local_songs(&self, open_setlist: Option<&String>, library: &Library) -> Option<Vec<RealBookEntry>> {
+        match open_setlist {
+            Some(id) => library.setlists.iter().find(|setlist| &setlist.id == id).map(|setlist| setlist.songs.clone()),
+            None => [
+                self.favorites_only.then(|| (*library.favorites).clone()),
+                self.tag_filter.as_ref().map(|tag| tags::songs_with(&library.tags, tag)),
+                self.repertoire_filter.map(|filter| repertoire::songs(&library.repertoire, filter)),
+            ]
+            .into_iter()
+            .flatten()
+            .reduce(|kept, songs| kept.into_iter().filter(|entry| songs.iter().any(|song| song.id == entry.id)).collect()),
+        }
+    }
+}
+
+/// The search inputs, starting from the URL and the restored session
+#[hook]
+pub fn use_search_inputs(restored: Restored) -> SearchInputs {
+    let session = restored.session;
+    let search = restored.search;
+    SearchInputs {
+        query: use_state(|| search.q.clone().unwrap_or_default()),
+        refine_terms: use_state(|| (*session).as_ref().map(|session| session.refine_terms.clone()).unwrap_or_default()),
+        volume: use_state(|| search.vol),
+        page_input: use_state(|| search.page.clone().unwrap_or_default()),
+        title_initial: use_state(|| (*session).as_ref().and_then(|session| session.title_initial)),
+        browse_mode: use_state(|| (*session).as_ref().is_some_and(|session| session.browse_mode)),
+        favorites_only: use_state(|| (*session).as_ref().is_some_and(|session| session.favorites_only)),
+        tag_filter: use_state(|| (*session).as_ref().and_then(|session| session.tag.clone())),
+        repertoire_filter: use_state(|| (*session).as_ref().and_then(|session| session.repertoire)),
+        sort_order: use_state(|| storage::load::<SortOrder>(SORT_STORAGE_KEY).unwrap_or_default()),
+    }
+}
+
+/// The live search and the callbacks driving it
+#[derive(Clone)]
+pub struct Search {
+    /// Results from the last search
+    pub results: UseStateHandle<Option<SearchResponse>>,
+    /// Whether a search is in progress
+    pub loading: bool,
+    /// Index of the keyboard-selected result
+    pub selected_index: UseStateHandle<Option<usize>>,
+    /// Search parameters once typing pauses (what live search runs)
+    pub debounced_params: SearchParams,
+    /// Autocomplete titles for the current query
+    pub completions: Vec<String>,
+    /// Typing in the search box (which closes the chart)
+    pub on_query_change: Callback<String>,
+    /// Pin the current query and search within its results
+    pub on_refine: Callback<()>,
+    /// Drop one refinement term
+    pub on_remove_refine: Callback<usize>,
+    pub on_volume_change: Callback<Option<u32>>,
+    pub on_page_change: Callback<String>,
+    pub on_browse_toggle: Callback<bool>,
+    /// A "did you mean" suggestion or an autocomplete title picked
+    pub on_suggestion_click: Callback<String>,
+    pub on_sort_change: Callback<SortOrder>,
+    /// Arrow keys in the search box
+    pub on_navigate: Callback<String>,
+    /// Enter in the search box
+    pub on_enter: Callback<()>,
+    /// Fetch the next page of paginated results and append it
+    pub on_load_more: Callback<()>,
+}
+
+/// Search as the user types: `local_songs` (see `SearchInputs::local_songs`)
+/// when there are any, else the cached catalog or the API
+#[hook]
+pub fn use_search(
+    inputs: SearchInputs,
+    local_songs: Option<Vec<RealBookEntry>>,
+    search_index: SharedIndex,
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    open_entry: Callback<RealBookEntry>,
+    record_search: Callback<()>,
+) -> Search {
+    let toaster = use_toaster();
+    let search_results = use_state(|| Option::<SearchResponse>::None);
+    let search_loading = use_state(|| false);
+    // Track which result is currently selected via keyboard navigation
+    let selected_index = use_state(|| Option::<usize>::None);
+    // Bumped on every new search so late "load more" pages for an older
+    // search are discarded instead of appended to the new results
+    let search_generation = use_mut_ref(|| 0u64);
+    let completions = use_state(Vec::<String>::new);
+    let search_params = inputs.params();
+    let sort_order = *inputs.sort_order;
+
+    // Parameters the live search actually runs with; they trail search_params
+    // by SEARCH_DEBOUNCE_MS so fast typing doesn't fire a request per keystroke
+    let debounced_params = use_state(|| search_params.clone());
+    {
+        let debounced_params = debounced_params.clone();
+        use_effect_with(search_params.clone(), move |params| {
+            let params = params.clone();
+            let timeout = Timeout::new(SEARCH_DEBOUNCE_MS, move || debounced_params.set(params));
+            // Dropping the timeout cancels it, restarting the wait on every change
+            move || drop(timeout)
+        });
+    }
+
+    // Autocomplete on every keystroke (local, so no debounce; the API fallback
+    // is aborted when the query changes again, like live search)
+    // With a setlist open or the Favorites filter on, only those songs are offered
+    {
+        let completions = completions.clone();
+        let search_index = search_index.clone();
+        use_effect_with(
+            ((*inputs.query).clone(), *inputs.volume, local_songs.clone()),
+            move |(query, volume, local_songs)| {
+                let mut abort_handle = None;
+                if let Some(songs) = local_songs {
+                    let songs = songs.iter().filter(|entry| volume.is_none_or(|vol| entry.volume == vol));
+                    completions.set(search_index::completions(songs, query));
+                } else if query.trim().is_empty() {
+                    completions.set(Vec::new());
+                } else {
+                    let (query, volume) = (query.clone(), *volume);
+                    let index = search_index.borrow().clone();
+                    let (request, handle) = abortable(async move {
+                        search_index::suggest(index.as_deref(), &query, volume).await
+                    });
+                    abort_handle = Some(handle);
+                    spawn_local(async move {
+                        // Autocomplete is a nicety: on failure just show nothing
+                        if let Ok(result) = request.await {
+                            completions.set(result.unwrap_or_default());
+                        }
+                    });
+                }
+                move || {
+                    if let Some(handle) = abort_handle {
+                        handle.abort();
+                    }
+                }
+            },
+        );
+    }
+
+    // Live search: trigger search whenever the (debounced) search parameters change
+    // Each search is abortable; the effect cleanup (run when the parameters
+    // change again or on unmount) aborts it, so a slow response for an older
+    // query can never overwrite newer results
+    // With a setlist open or the Favorites filter on, those songs are searched
+    // locally instead (and listed in full when there's nothing to search for)
+    // Changing the sort order searches again, so going back to Relevance
+    // restores the search's own order
+    // Songs whose personal notes match the query are added to the first page
+    {
+        let search_results = search_results.clone();
+        let search_loading = search_loading.clone();
+        let toaster = toaster.clone();
+        let selected_index = selected_index.clone();
+        let search_generation = search_generation.clone();
+        let search_index = search_index.clone();
+
+        use_effect_with(((*debounced_params).clone(), local_songs.clone(), sort_order), move |(params, local_songs, sort_order)| {
+            let params = params.clone();
+            let sort_order = *sort_order;
+            let results = search_results.clone();
+            let loading = search_loading.clone();
+            let toaster = toaster.clone();
+            let selected_index = selected_index.clone();
+            *search_generation.borrow_mut() += 1;
+            let mut abort_handle = None;
+
+            if let Some(songs) = local_songs {
+                let mut response = search_index::search_entries(songs, &params);
+                notes::add_matches(&mut response, &params, Some(songs));
+                sort_order.sort(&mut response.results);
+                selected_index.set((!response.results.is_empty()).then_some(0));
+                results.set(Some(response));
+                loading.set(false);
+            }
+            // Only search if there is a query or a page filter
+            // (a page alone lists every chart on that page), or when browsing
+            else if params.has_criteria() || params.limit.is_some() {
+                loading.set(true);
+
+                let index = search_index.borrow().clone();
+                let (request, handle) = abortable(async move {
+                    search_index::search(index.as_deref(), &params).await.map(|mut response| {
+                        notes::add_matches(&mut response, &params, None);
+                        response
+                    })
+                });
+                abort_handle = Some(handle);
+
+                spawn_local(async move {
+                    // Aborted: a newer search owns the loading/results state now
+                    let Ok(result) = request.await else {
+                        return;
+                    };
+                    match result {
+                        Ok(mut response) => {
+                            sort_order.sort(&mut response.results);
+                            results.set(Some(response.clone()));
+                            // Auto-highlight first result if results exist
+                            if !response.results.is_empty() {
+                                selected_index.set(Some(0));
+                            } else {
+                                selected_index.set(None);
+                            }
+                        }
+                        Err(e) => {
+                            toaster.error(e.message);
+                            selected_index.set(None);
+                        }
+                    }
+                    loading.set(false);
+                });
+            } else {
+                // Clear results if there is nothing to search for
+                results.set(None);
+                selected_index.set(None);
+                loading.set(false);
+            }
+
+            move || {
+                if let Some(handle) = abort_handle {
+                    handle.abort();
+                }
+            }
+        });
+    }
+
+    // Callback: Fetch the next page of paginated results and append it
+    let on_load_more = {
+        let search_params = (*debounced_params).clone();
+        let search_results = search_results.clone();
+        let search_loading = search_loading.clone();
+        let toaster = toaster.clone();
+        let search_generation = search_generation.clone();
+        let search_index = search_index.clone();
+        let local_songs = local_songs.clone();
+        Callback::from(move |_: ()| {
+            let Some(current) = (*search_results).clone() else {
+                return;
+            };
+            let params = SearchParams {
+                offset: Some(current.results.len()),
+                ..search_params.clone()
+            };
+            let results = search_results.clone();
+            let loading = search_loading.clone();
+            let toaster = toaster.clone();
+            let search_generation = search_generation.clone();
+            let generation = *search_generation.borrow();
+            let index = search_index.borrow().clone();
+            let local_songs = local_songs.clone();
+
+            loading.set(true);
+            spawn_local(async move {
+                let result = match &local_songs {
+                    Some(songs) => Ok(search_index::search_entries(songs, &params)),
+                    None => search_index::search(index.as_deref(), &params).await,
+                };
+                if *search_generation.borrow() != generation {
+                    return;
+                }
+                match result {
+                    Ok(page) => {
+                        let mut merged = current;
+                        merged.results.extend(page.results);
+                        merged.total = page.total;
+                        merged.volume_counts = page.volume_counts;
+                        sort_order.sort(&mut merged.results);
+                        results.set(Some(merged));
+                    }
+                    Err(e) => {
+                        toaster.error(e.message);
+                    }
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    // Callback: Handle when user types in the search box
+    // This triggers live search and clears the sheet viewer
+    let on_query_change = {
+        let search_query = inputs.query.clone();
+        let selected_entry = selected_entry.clone();
+        Callback::from(move |new_query: String| {
+            search_query.set(new_query);
+            // Clear sheet viewer when typing - user must press Enter to view
+            selected_entry.set(None);
+        })
+    };
+
+    // Callback: Pin the current query and search within its results
+    let on_refine = {
+        let search_query = inputs.query.clone();
+        let refine_terms = inputs.refine_terms.clone();
+        Callback::from(move |_: ()| {
+            let query = search_query.trim().to_string();
+            if !query.is_empty() {
+                let mut terms = (*refine_terms).clone();
+                terms.push(query);
+                refine_terms.set(terms);
+                search_query.set(String::new());
+            }
+        })
+    };
+
+    // Callback: Drop one refinement term
+    let on_remove_refine = {
+        let refine_terms = inputs.refine_terms.clone();
+        Callback::from(move |index: usize| {
+            let mut terms = (*refine_terms).clone();
+            if index < terms.len() {
+                terms.remove(index);
+                refine_terms.set(terms);
+            }
+        })
+    };
+
+    // Callback: Handle when user changes the volume dropdown
+    let on_volume_change = {
+        let selected_volume = inputs.volume.clone();
+        Callback::from(move |new_volume: Option<u32>| {
+            selected_volume.set(new_volume);
+        })
+    };
+
+    // Callback: Handle when user types in the page filter
+    let on_page_change = {
+        let page_input = inputs.page_input.clone();
+        Callback::from(move |new_input: String| {
+            page_input.set(new_input);
+        })
+    };
+
+    // Callback: Handle when user toggles browse mode
+    let on_browse_toggle = {
+        let browse_mode = inputs.browse_mode.clone();
+        Callback::from(move |enabled: bool| {
+            browse_mode.set(enabled);
+        })
+    };
+
+    // Callback: Handle when user clicks a "did you mean" suggestion
+    // (also used when an autocomplete title is picked)
+    let on_suggestion_click = {
+        let search_query = inputs.query.clone();
+        Callback::from(move |title: String| {
+            search_query.set(title);
+        })
+    };
+
+    // Callback: Change (and persist) the results order
+    let on_sort_change = {
+        let sort_order = inputs.sort_order.clone();
+        Callback::from(move |order: SortOrder| {
+            storage::save(SORT_STORAGE_KEY, &order);
+            sort_order.set(order);
+        })
+    };
+
+    // Callback: Handle arrow key navigation from input field
+    let on_navigate = {
+        let selected_index = selected_index.clone();
+        let search_results = search_results.clone();
+        Callback::from(move |direction: String| {
+            if let Some(response) = (*search_results).as_ref() {
+                let total = response.results.len();
+                if total > 0 {
+                    let new_index = if direction == "down" {
+                        utils::next_result_index(*selected_index, total)
+                    } else {
+                        utils::prev_result_index(*selected_index, total)
+                    };
+                    selected_index.set(Some(new_index));
+                }
+            }
+        })
+    };
+
+    // Callback: Handle Enter key from input field
+    // Runs a search still waiting out the debounce right away; otherwise views
+    // the highlighted result
+    let on_enter = {
+        let selected_index = selected_index.clone();
+        let search_results = search_results.clone();
+        let debounced_params = debounced_params.clone();
+        Callback::from(move |_: ()| {
+            record_search.emit(());
+            if *debounced_params != search_params {
+                debounced_params.set(search_params.clone());
+            } else if let Some(response) = (*search_results).as_ref()
+                && let Some(idx) = *selected_index
+                && idx < response.results.len() {
+                open_entry.emit(response.results[idx].clone());
+            }
+        })
+    };
+
+    Search {
+        results: search_results,
+        loading: *search_loading,
+        selected_index,
+        debounced_params: (*debounced_params).clone(),
+        completions: (*completions).clone(),
+        on_query_change,
+        on_refine,
+        on_remove_refine,
+        on_volume_change,
+        on_page_change,
+        on_browse_toggle,
+        on_suggestion_click,
+        on_sort_change,
+        on_navigate,
+        on_enter,
+        on_load_more,
+    }
+}
@@ -0,0 +1,399 @@
+//! The account: the anonymous session and its recently viewed songs, sync
+//! with the user's other devices, passkeys, and data archives
+
+use gloo_events::EventListener;
+use gloo_timers::callback::Interval;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::components::use_toaster;
+use crate::models::{LinkCode, Passkey, RealBookEntry};
+use crate::sync::{self, SyncStatus};
+use crate::{api, archive, passkeys};
+
+/// The session's state for the settings panel and the results list, and the
+/// callbacks managing it
+#[derive(Clone)]
+pub struct Account {
+    /// Songs opened lately, from the anonymous session on the API
+    pub recently_viewed: Vec<RealBookEntry>,
+    /// Where syncing the local data with other devices stands
+    pub sync_status: SyncStatus,
+    /// Sync now
+    pub run_sync: Callback<()>,
+    /// Code made for another device to join this one's sync, if any
+    pub link_code: Option<LinkCode>,
+    /// Passkeys that sign in to this browser's session
+    pub passkeys: Vec<Passkey>,
+    /// Forget the session (and with it the recently viewed songs)
+    pub on_clear_recently_viewed: Callback<()>,
+    pub on_export_data: Callback<()>,
+    /// Restore a data archive
+    pub on_import_data: Callback<(web_sys::File, archive::ImportStrategy)>,
+    /// Make a link code and start syncing this device
+    pub on_link_device: Callback<()>,
+    /// Join another device's session with its code
+    pub on_join_device: Callback<String>,
+    /// Stop syncing this device (its data stays)
+    pub on_stop_sync: Callback<()>,
+    pub on_create_passkey: Callback<String>,
+    pub on_passkey_sign_in: Callback<()>,
+    pub on_remove_passkey: Callback<String>,
+    /// Leave the session on this browser
+    pub on_sign_out: Callback<()>,
+}
+
+/// The session, recording each song `viewing` opens; `reload_stores` reloads
+/// the stored data an import, a sync, or signing out may have changed
+#[hook]
+pub fn use_account(reload_stores: Callback<()>, viewing: Option<u32>) -> Account {
+    let toaster = use_toaster();
+
+    // Songs this browser opened lately, kept in its anonymous session on the
+    // server; like the song of the day, failures just leave the list empty
+    let recently_viewed = use_state(Vec::<RealBookEntry>::new);
+    {
+        let recently_viewed = recently_viewed.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                if let Ok(session) = api::get_session().await {
+                    recently_viewed.set(session.recently_viewed);
+                }
+            });
+            || ()
+        });
+    }
+
+    // Record each song opened in the session
+    {
+        let recently_viewed = recently_viewed.clone();
+        use_effect_with(viewing, move |id| {
+            if let Some(id) = *id {
+                spawn_local(async move {
+                    if let Ok(session) = api::record_view(id).await {
+                        recently_viewed.set(session.recently_viewed);
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
+    // Callback: Forget the session (and with it the recently viewed songs)
+    let on_clear_recently_viewed = {
+        let recently_viewed = recently_viewed.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |_: ()| {
+            let recently_viewed = recently_viewed.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match api::forget_session().await {
+                    Ok(()) => recently_viewed.set(Vec::new()),
+                    Err(e) => toaster.error(e.message),
+                }
+            });
+        })
+    };
+
+    // Callback: Restore a data archive, then reload everything it may have changed
+    let on_import_data = {
+        let reload_stores = reload_stores.clone();
+        let recently_viewed = recently_viewed.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |(file, strategy): (web_sys::File, archive::ImportStrategy)| {
+            let reload_stores = reload_stores.clone();
+            let recently_viewed = recently_viewed.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match archive::import(file, strategy).await {
+                    Ok(message) => {
+                        reload_stores.emit(());
+                        if let Ok(session) = api::get_session().await {
+                            recently_viewed.set(session.recently_viewed);
+                        }
+                        toaster.success(message);
+                    }
+                    Err(message) => toaster.error(message),
+                }
+            });
+        })
+    };
+
+    // Sync with the user's other devices (see sync.rs): on startup, every
+    // SYNC_INTERVAL_MS, when the connection comes back, and on request
+    let sync_status = use_state(|| if sync::is_enabled() { SyncStatus::Synced(None) } else { SyncStatus::Off });
+    let sync_running = use_mut_ref(|| false);
+    let run_sync = {
+        let sync_status = sync_status.clone();
+        let sync_running = sync_running.clone();
+        let reload_stores = reload_stores.clone();
+        Callback::from(move |_: ()| {
+            if !sync::is_enabled() {
+                sync_status.set(SyncStatus::Off);
+                return;
+            }
+            if !sync::is_online() {
+                sync_status.set(SyncStatus::Offline);
+                return;
+            }
+            if sync_running.replace(true) {
+                return;
+            }
+            sync_status.set(SyncStatus::Syncing);
+            let sync_status = sync_status.clone();
+            let sync_running = sync_running.clone();
+            let reload_stores = reload_stores.clone();
+            spawn_local(async move {
+                let result = sync::sync().await;
+                *sync_running.borrow_mut() = false;
+                match result {
+                    Ok(changed) => {
+                        if changed {
+                            reload_stores.emit(());
+                        }
+                        sync_status.set(SyncStatus::Synced(Some(js_sys::Date::now())));
+                    }
+                    Err(_) if !sync::is_online() => sync_status.set(SyncStatus::Offline),
+                    Err(message) => sync_status.set(SyncStatus::Failed(message)),
+                }
+            });
+        })
+    };
+    {
+        let run_sync = run_sync.clone();
+        use_effect_with((), move |_| {
+            run_sync.emit(());
+            let interval = {
+                let run_sync = run_sync.clone();
+                Interval::new(sync::SYNC_INTERVAL_MS, move || run_sync.emit(()))
+            };
+            let window = web_sys::window().expect("no window");
+            let listeners: Vec<EventListener> = ["online", "offline"]
+                .into_iter()
+                .map(|event| {
+                    let run_sync = run_sync.clone();
+                    EventListener::new(&window, event, move |_| run_sync.emit(()))
+                })
+                .collect();
+            move || drop((interval, listeners))
+        });
+    }
+
+    // Code shown in settings for another device to join with
+    let link_code = use_state(|| Option::<LinkCode>::None);
+
+    // Callback: Make a link code and start syncing this device
+    let on_link_device = {
+        let link_code = link_code.clone();
+        let run_sync = run_sync.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |_: ()| {
+            let link_code = link_code.clone();
+            let run_sync = run_sync.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match api::link_session().await {
+                    Ok(code) => {
+                        link_code.set(Some(code));
+                        if !sync::is_enabled() {
+                            sync::enable(false);
+                        }
+                        run_sync.emit(());
+                    }
+                    Err(e) => toaster.error(e.message),
+                }
+            });
+        })
+    };
+
+    // Callback: Join another device's session with its code and sync with it
+    let on_join_device = {
+        let recently_viewed = recently_viewed.clone();
+        let run_sync = run_sync.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |code: String| {
+            let recently_viewed = recently_viewed.clone();
+            let run_sync = run_sync.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match api::join_session(&code).await {
+                    Ok(()) => {
+                        sync::enable(true);
+                        run_sync.emit(());
+                        if let Ok(session) = api::get_session().await {
+                            recently_viewed.set(session.recently_viewed);
+                        }
+                        toaster.success("Joined; this device now syncs with the other one");
+                    }
+                    Err(e) => toaster.error(e.message),
+                }
+            });
+        })
+    };
+
+    // Callback: Stop syncing this device (its data stays)
+    let on_stop_sync = {
+        let sync_status = sync_status.clone();
+        let link_code = link_code.clone();
+        Callback::from(move |_: ()| {
+            sync::disable();
+            link_code.set(None);
+            sync_status.set(SyncStatus::Off);
+        })
+    };
+
+    // Passkeys that sign in to this session; like the recently viewed songs,
+    // failures leave the list empty
+    let passkey_list = use_state(Vec::<Passkey>::new);
+    {
+        let passkey_list = passkey_list.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                if let Ok(list) = api::get_passkeys().await {
+                    passkey_list.set(list);
+                }
+            });
+            || ()
+        });
+    }
+
+    // Callback: Make a passkey for this session (and sync it, so signing in
+    // elsewhere brings the data along)
+    let on_create_passkey = {
+        let passkey_list = passkey_list.clone();
+        let run_sync = run_sync.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |name: String| {
+            let passkey_list = passkey_list.clone();
+            let run_sync = run_sync.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match passkeys::register(&name).await {
+                    Ok(passkey) => {
+                        let mut list = (*passkey_list).clone();
+                        list.push(passkey);
+                        passkey_list.set(list);
+                        if !sync::is_enabled() {
+                            sync::enable(false);
+                        }
+                        run_sync.emit(());
+                        toaster.success("Passkey saved; use it to sign in on any device");
+                    }
+                    Err(message) => toaster.error(message),
+                }
+            });
+        })
+    };
+
+    // Callback: Sign in to a passkey's session and sync with it
+    let on_passkey_sign_in = {
+        let passkey_list = passkey_list.clone();
+        let recently_viewed = recently_viewed.clone();
+        let run_sync = run_sync.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |_: ()| {
+            let passkey_list = passkey_list.clone();
+            let recently_viewed = recently_viewed.clone();
+            let run_sync = run_sync.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match passkeys::sign_in().await {
+                    Ok(passkey) => {
+                        sync::enable(true);
+                        run_sync.emit(());
+                        if let Ok(list) = api::get_passkeys().await {
+                            passkey_list.set(list);
+                        }
+                        if let Ok(session) = api::get_session().await {
+                            recently_viewed.set(session.recently_viewed);
+                        }
+                        toaster.success(format!("Signed in as {}", passkey.name));
+                    }
+                    Err(message) => toaster.error(message),
+                }
+            });
+        })
+    };
+
+    // Callback: Remove one of the session's passkeys
+    let on_remove_passkey = {
+        let passkey_list = passkey_list.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |id: String| {
+            let passkey_list = passkey_list.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match api::delete_passkey(&id).await {
+                    Ok(()) => passkey_list.set(passkey_list.iter().filter(|passkey| passkey.id != id).cloned().collect()),
+                    Err(e) => toaster.error(e.message),
+                }
+            });
+        })
+    };
+
+    // Callback: Leave the session on this browser (it carries on for the
+    // other devices). On a shared tablet the next person shouldn't inherit
+    // this one's data, so it's synced a last time and cleared from here.
+    let on_sign_out = {
+        let passkey_list = passkey_list.clone();
+        let recently_viewed = recently_viewed.clone();
+        let link_code = link_code.clone();
+        let sync_status = sync_status.clone();
+        let reload_stores = reload_stores.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |_: ()| {
+            let passkey_list = passkey_list.clone();
+            let recently_viewed = recently_viewed.clone();
+            let link_code = link_code.clone();
+            let sync_status = sync_status.clone();
+            let reload_stores = reload_stores.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                if let Err(message) = sync::sync().await {
+                    toaster.error(format!("Couldn't sync before signing out, so nothing was changed: {}", message));
+                    return;
+                }
+                match api::sign_out().await {
+                    Ok(()) => {
+                        sync::disable();
+                        archive::clear_local();
+                        reload_stores.emit(());
+                        sync_status.set(SyncStatus::Off);
+                        link_code.set(None);
+                        passkey_list.set(Vec::new());
+                        recently_viewed.set(Vec::new());
+                        toaster.success("Signed out; your data stays with your passkey");
+                    }
+                    Err(e) => toaster.error(e.message),
+                }
+            });
+        })
+    };
+
+    // Callback: Download everything as an archive
+    let on_export_data = Callback::from(move |_: ()| {
+        let toaster = toaster.clone();
+        spawn_local(async move {
+            if let Err(message) = archive::export().await {
+                toaster.error(message);
+            }
+        });
+    });
+
+    Account {
+        recently_viewed: (*recently_viewed).clone(),
+        sync_status: (*sync_status).clone(),
+        run_sync,
+        link_code: (*link_code).clone(),
+        passkeys: (*passkey_list).clone(),
+        on_clear_recently_viewed,
+        on_export_data,
+        on_import_data,
+        on_link_device,
+        on_join_device,
+        on_stop_sync,
+        on_create_passkey,
+        on_passkey_sign_in,
+        on_remove_passkey,
+        on_sign_out,
+    }
+}
@@ -0,0 +1,80 @@
+//! Settings: preferences, and the account (sync, passkeys, data archives)
+
+mod account;
+
+pub use account::use_account;
+
+use yew::prelude::*;
+use crate::i18n::Locale;
+use crate::keymap::Keymap;
+use crate::review::ReviewIntervals;
+use crate::theme::Appearance;
+
+/// The user's preferences (all persisted), and the callbacks changing them
+#[derive(Clone)]
+pub struct Preferences {
+    /// Color scheme, accent color, density, text size, and sheet inversion
+    pub appearance: Appearance,
+    /// UI language, provided to the components as a context
+    pub locale: Locale,
+    /// Keyboard shortcut bindings
+    pub keymap: Keymap,
+    /// Days between reviews for each repertoire status
+    pub review_intervals: ReviewIntervals,
+    pub on_appearance_change: Callback<Appearance>,
+    pub on_locale_change: Callback<Locale>,
+    pub on_keymap_change: Callback<Keymap>,
+    pub on_review_intervals_change: Callback<ReviewIntervals>,
+}
+
+#[hook]
+pub fn use_preferences() -> Preferences {
+    let appearance = use_state(Appearance::load);
+    let locale = use_state(Locale::load);
+    let keymap = use_state(Keymap::load);
+    let review_intervals = use_state(ReviewIntervals::load);
+
+    // Apply (and persist) appearance preferences whenever they change
+    use_effect_with(*appearance, |appearance| {
+        appearance.apply();
+        || ()
+    });
+    use_effect_with(*locale, |locale| {
+        locale.apply();
+        || ()
+    });
+
+    let on_appearance_change = {
+        let appearance = appearance.clone();
+        Callback::from(move |value: Appearance| appearance.set(value))
+    };
+    let on_locale_change = {
+        let locale = locale.clone();
+        Callback::from(move |value: Locale| locale.set(value))
+    };
+    let on_keymap_change = {
+        let keymap = keymap.clone();
+        Callback::from(move |value: Keymap| {
+            value.save();
+            keymap.set(value);
+        })
+    };
+    let on_review_intervals_change = {
+        let review_intervals = review_intervals.clone();
+        Callback::from(move |value: ReviewIntervals| {
+            value.save();
+            review_intervals.set(value);
+        })
+    };
+
+    Preferences {
+        appearance: *appearance,
+        locale: *locale,
+        keymap: (*keymap).clone(),
+        review_intervals: *review_intervals,
+        on_appearance_change,
+        on_locale_change,
+        on_keymap_change,
+        on_review_intervals_change,
+    }
+}
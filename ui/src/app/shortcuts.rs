@@ -0,0 +1,157 @@
+//! Global keyboard shortcuts
+//!
+//! Arrow keys and Enter work both in the search box (its own `onkeydown`) and
+//! globally; the keys come from the user's keymap (Settings > Keyboard
+//! shortcuts). "/" (focus the search box) and Escape (restore collapsed
+//! results, else close the chart, else clear the query) are fixed, like
+//! GitHub's and Gmail's; Escape also works from the search box.
+
+use gloo_events::EventListener;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use crate::components::search_input::SEARCH_INPUT_ID;
+use crate::keymap::{Action, KeyPress, Keymap};
+use crate::models::{RealBookEntry, SearchResponse};
+use crate::utils;
+
+/// How long a multi-key shortcut (like `g g`) waits for its next key
+const KEY_SEQUENCE_TIMEOUT_MS: f64 = 1000.0;
+
+/// What the shortcuts act on
+#[derive(Clone, PartialEq)]
+pub struct ShortcutTargets {
+    pub results: Option<SearchResponse>,
+    pub selected_index: Option<usize>,
+    pub keymap: Keymap,
+    pub chart_open: bool,
+    pub query_empty: bool,
+    /// Whether the results are collapsed beside the chart (wide screens)
+    pub reading_mode: bool,
+}
+
+/// What the shortcuts do
+#[derive(Clone)]
+pub struct ShortcutActions {
+    /// Open the highlighted result
+    pub open_entry: Callback<RealBookEntry>,
+    pub on_random: Callback<()>,
+    pub on_close_viewer: Callback<()>,
+    /// Clear the query (Escape)
+    pub on_query_change: Callback<String>,
+}
+
+/// Listen for the shortcuts while the app is mounted
+#[hook]
+pub fn use_shortcuts(
+    targets: ShortcutTargets,
+    selected_index: UseStateHandle<Option<usize>>,
+    results_collapsed: UseStateHandle<bool>,
+    actions: ShortcutActions,
+) {
+    // Unfinished key sequence and when its last key was pressed
+    let pending_keys = use_mut_ref(|| (String::new(), 0.0));
+    use_effect_with(targets, move |targets| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let current_results = targets.results.clone();
+        let current_index = targets.selected_index;
+        let keymap = targets.keymap.clone();
+        let (chart_open, query_empty, reading_mode) = (targets.chart_open, targets.query_empty, targets.reading_mode);
+
+        let listener = EventListener::new(&document, "keydown", move |event| {
+            let keyboard_event = event.dyn_ref::<web_sys::KeyboardEvent>().unwrap();
+
+            // Leave browser and OS shortcuts alone
+            if keyboard_event.ctrl_key() || keyboard_event.meta_key() || keyboard_event.alt_key() {
+                return;
+            }
+
+            let target = keyboard_event.target().and_then(|target| target.dyn_into::<web_sys::Element>().ok());
+            let in_search_box = target.as_ref().is_some_and(|element| element.id() == SEARCH_INPUT_ID);
+            if keyboard_event.key() == "Escape" && (in_search_box || !utils::is_typing(keyboard_event)) {
+                if chart_open && reading_mode {
+                    // Bring the results back first; the next Escape closes the chart
+                    results_collapsed.set(false);
+                } else if chart_open {
+                    actions.on_close_viewer.emit(());
+                } else if !query_empty {
+                    actions.on_query_change.emit(String::new());
+                } else if let Some(element) = target.and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok()) {
+                    // Nothing left to clear: step out of the search box so the
+                    // single-letter shortcuts work
+                    let _ = element.blur();
+                }
+                return;
+            }
+
+            // Skip if user is typing in input/textarea
+            // (these shortcuts are handled by the input's onkeydown)
+            if utils::is_typing(keyboard_event) {
+                return;
+            }
+
+            if keyboard_event.key() == "/" {
+                // Keep the "/" out of the box (and Firefox's quick find closed)
+                keyboard_event.prevent_default();
+                utils::focus_input(SEARCH_INPUT_ID);
+                return;
+            }
+
+            let key = keyboard_event.key();
+            let now = js_sys::Date::now();
+            let pending = {
+                let (keys, at) = &*pending_keys.borrow();
+                if now - at < KEY_SEQUENCE_TIMEOUT_MS { keys.clone() } else { String::new() }
+            };
+            let press = keymap.press(&pending, &key);
+            *pending_keys.borrow_mut() = match press {
+                KeyPress::Prefix if pending.is_empty() => (key, now),
+                KeyPress::Prefix => (format!("{} {}", pending, key), now),
+                _ => (String::new(), 0.0),
+            };
+            let KeyPress::Action(action) = press else {
+                return;
+            };
+
+            let total = current_results.as_ref().map_or(0, |response| response.results.len());
+            match action {
+                // Navigate to next result
+                Action::NextResult if total > 0 => {
+                    keyboard_event.prevent_default();
+                    selected_index.set(Some(utils::next_result_index(current_index, total)));
+                }
+                // Navigate to previous result
+                Action::PrevResult if total > 0 => {
+                    keyboard_event.prevent_default();
+                    selected_index.set(Some(utils::prev_result_index(current_index, total)));
+                }
+                // Jump to the first/last result
+                Action::FirstResult if total > 0 => {
+                    keyboard_event.prevent_default();
+                    selected_index.set(Some(0));
+                }
+                Action::LastResult if total > 0 => {
+                    keyboard_event.prevent_default();
+                    selected_index.set(Some(utils::last_result_index(total)));
+                }
+                // View the currently selected result
+                Action::OpenResult => {
+                    if let Some(response) = &current_results
+                        && let Some(idx) = current_index
+                        && idx < response.results.len() {
+                        keyboard_event.prevent_default();
+                        actions.open_entry.emit(response.results[idx].clone());
+                    }
+                }
+                Action::Random => {
+                    keyboard_event.prevent_default();
+                    actions.on_random.emit(());
+                }
+                // Page turns and auto-scroll are handled by the viewer
+                _ => {}
+            }
+        });
+
+        // Dropping the listener (on new targets, or unmount) stops it
+        move || drop(listener)
+    });
+}
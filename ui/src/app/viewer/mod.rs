@@ -0,0 +1,106 @@
+//! The sheet viewer's helpers: Random, the songs either side, offline pages,
+//! and MIDI pedals
+
+mod offline;
+mod pedals;
+
+pub use offline::use_offline_songs;
+pub use pedals::use_pedals;
+
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::api;
+use crate::components::use_toaster;
+use crate::models::{RealBookEntry, SearchParams, SearchResponse};
+
+/// Recent Random picks the next press avoids
+const RECENT_RANDOM_PICKS: usize = 20;
+
+/// The Random button: whether a pick is loading, and the callback picking one
+/// from the songs `filters` match
+#[hook]
+pub fn use_random(
+    filters: SearchParams,
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    open_entry: Callback<RealBookEntry>,
+) -> (bool, Callback<()>) {
+    let toaster = use_toaster();
+    let random_loading = use_state(|| false);
+    // Ids of this session's latest Random picks, oldest first
+    let recent_randoms = use_mut_ref(Vec::<u32>::new);
+
+    let on_random = {
+        let random_loading = random_loading.clone();
+        Callback::from(move |_: ()| {
+            let entry = selected_entry.clone();
+            let open_entry = open_entry.clone();
+            let loading = random_loading.clone();
+            let recent_randoms = recent_randoms.clone();
+            let toaster = toaster.clone();
+            let filters = filters.clone();
+
+            // Show the loading skeleton before clearing entry to avoid placeholder flash
+            loading.set(true);
+            entry.set(None);
+
+            spawn_local(async move {
+                // Ensure the skeleton shows for at least 300ms for better UX
+                let min_duration = gloo_timers::future::TimeoutFuture::new(300);
+
+                // Skip the last few picks so repeated presses keep turning up new songs
+                let exclude = recent_randoms.borrow().clone();
+                let result = api::get_random(&filters, &exclude).await;
+
+                // Wait for minimum duration
+                min_duration.await;
+
+                match result {
+                    Ok(random_entry) => {
+                        let mut recent = recent_randoms.borrow_mut();
+                        recent.retain(|id| *id != random_entry.id);
+                        recent.push(random_entry.id);
+                        let excess = recent.len().saturating_sub(RECENT_RANDOM_PICKS);
+                        recent.drain(..excess);
+                        drop(recent);
+                        open_entry.emit(random_entry);
+                    }
+                    Err(e) => toaster.error(e.message),
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    (*random_loading, on_random)
+}
+
+/// Callback opening the next/previous song in the results (swiping past a
+/// chart's ends, or a MIDI pedal)
+pub fn adjacent_song(
+    search_results: UseStateHandle<Option<SearchResponse>>,
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    selected_index: UseStateHandle<Option<usize>>,
+    open_entry: Callback<RealBookEntry>,
+) -> Callback<i32> {
+    Callback::from(move |step: i32| {
+        let (Some(response), Some(current)) = ((*search_results).as_ref(), (*selected_entry).as_ref()) else {
+            return;
+        };
+        let Some(position) = response.results.iter().position(|entry| entry.id == current.id) else {
+            return;
+        };
+        let target = position as i32 + step;
+        if let Some(entry) = usize::try_from(target).ok().and_then(|index| response.results.get(index)) {
+            selected_index.set(Some(target as usize));
+            open_entry.emit(entry.clone());
+        }
+    })
+}
+
+/// The song after the open one in the results (the next in the setlist when
+/// one is open), which the viewer preloads so it opens without a wait
+pub fn next_entry(selected_entry: Option<&RealBookEntry>, results: Option<&SearchResponse>) -> Option<RealBookEntry> {
+    let (current, response) = selected_entry.zip(results)?;
+    let position = response.results.iter().position(|entry| entry.id == current.id)?;
+    response.results.get(position + 1).cloned()
+}
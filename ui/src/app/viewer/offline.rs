@@ -0,0 +1,98 @@
+//! Songs saved for offline use
+
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use crate::components::use_toaster;
+use crate::models::RealBookEntry;
+use crate::offline;
+
+/// Songs whose pages are saved for offline use, and the callbacks saving and
+/// removing them
+#[derive(Clone)]
+pub struct OfflineSongs {
+    pub songs: Vec<RealBookEntry>,
+    /// Whether pages are being downloaded
+    pub saving: bool,
+    /// Download a song's pages
+    pub on_save: Callback<RealBookEntry>,
+    /// Remove one song from offline storage
+    pub on_remove: Callback<RealBookEntry>,
+    /// Remove every offline song
+    pub on_clear: Callback<()>,
+}
+
+impl OfflineSongs {
+    /// Whether `entry`'s pages are saved
+    pub fn contains(&self, entry: &RealBookEntry) -> bool {
+        self.songs.iter().any(|song| song.id == entry.id)
+    }
+}
+
+#[hook]
+pub fn use_offline_songs() -> OfflineSongs {
+    let toaster = use_toaster();
+    let offline_songs = use_state(offline::saved_songs);
+    let saving_offline = use_state(|| false);
+
+    // Callback: Download a song's pages for offline use
+    let on_save = {
+        let offline_songs = offline_songs.clone();
+        let saving_offline = saving_offline.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |entry: RealBookEntry| {
+            let offline_songs = offline_songs.clone();
+            let saving = saving_offline.clone();
+            let toaster = toaster.clone();
+            saving.set(true);
+            spawn_local(async move {
+                match offline::save_songs(std::slice::from_ref(&entry)).await {
+                    Ok(saved) => {
+                        offline_songs.set(saved);
+                        toaster.success(format!("\"{}\" is available offline", entry.title));
+                    }
+                    Err(message) => toaster.error(message),
+                }
+                saving.set(false);
+            });
+        })
+    };
+
+    // Callback: Remove one song from offline storage
+    let on_remove = {
+        let offline_songs = offline_songs.clone();
+        let toaster = toaster.clone();
+        Callback::from(move |entry: RealBookEntry| {
+            let offline_songs = offline_songs.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match offline::remove_song(&entry).await {
+                    Ok(saved) => {
+                        offline_songs.set(saved);
+                        toaster.info(format!("Removed \"{}\" from offline songs", entry.title));
+                    }
+                    Err(message) => toaster.error(message),
+                }
+            });
+        })
+    };
+
+    // Callback: Remove every offline song
+    let on_clear = {
+        let offline_songs = offline_songs.clone();
+        Callback::from(move |_: ()| {
+            let offline_songs = offline_songs.clone();
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                match offline::clear().await {
+                    Ok(()) => {
+                        offline_songs.set(Vec::new());
+                        toaster.info("Cleared all offline songs");
+                    }
+                    Err(message) => toaster.error(message),
+                }
+            });
+        })
+    };
+
+    OfflineSongs { songs: (*offline_songs).clone(), saving: *saving_offline, on_save, on_remove, on_clear }
+}
@@ -0,0 +1,145 @@
+//! MIDI pedals: connecting, learning messages, and acting on them
+
+use yew::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::components::PageTurn;
+use crate::midi::{self, MidiAction, MidiConnection, MidiMap, MidiMessage, MidiStatus};
+use crate::models::{RealBookEntry, SearchResponse};
+
+/// Pedal state for the settings panel and the viewer, and the callbacks changing it
+#[derive(Clone)]
+pub struct Pedals {
+    /// Whether MIDI is connected, and to how many inputs
+    pub status: MidiStatus,
+    /// Messages learned for each action (persisted)
+    pub map: MidiMap,
+    /// Action waiting for a pedal press to learn
+    pub learning: Option<MidiAction>,
+    /// Latest pedal page turn, passed to the viewer
+    pub page_turn: PageTurn,
+    /// Ask for MIDI access and start listening to every input
+    pub on_connect: Callback<()>,
+    pub on_learn: Callback<Option<MidiAction>>,
+    pub on_map_change: Callback<MidiMap>,
+}
+
+/// MIDI pedals turning the open chart's pages, or stepping through the
+/// results with `on_adjacent_song` (opening the first with `open_entry` when
+/// no chart is open)
+#[hook]
+pub fn use_pedals(
+    selected_entry: UseStateHandle<Option<RealBookEntry>>,
+    search_results: UseStateHandle<Option<SearchResponse>>,
+    open_entry: Callback<RealBookEntry>,
+    on_adjacent_song: Callback<i32>,
+) -> Pedals {
+    let midi_map = use_state(MidiMap::load);
+    let midi_status = use_state(|| MidiStatus::Off);
+    let midi_learning = use_state(|| Option::<MidiAction>::None);
+    // Latest pedal message, numbered so repeats register
+    let midi_message = use_state(|| Option::<(u32, MidiMessage)>::None);
+    let page_turn = use_state(PageTurn::default);
+    // Kept for the app's lifetime once connected (dropping it stops the messages)
+    let midi_connection = use_mut_ref(|| Option::<MidiConnection>::None);
+    let midi_serial = use_mut_ref(|| 0u32);
+
+    // Callback: Ask for MIDI access and start listening to every input
+    let on_connect = {
+        let midi_status = midi_status.clone();
+        let midi_message = midi_message.clone();
+        Callback::from(move |_: ()| {
+            if midi_connection.borrow().is_some() {
+                return;
+            }
+            midi_status.set(MidiStatus::Connecting);
+            let on_message = {
+                let midi_message = midi_message.clone();
+                let midi_serial = midi_serial.clone();
+                Callback::from(move |message: MidiMessage| {
+                    *midi_serial.borrow_mut() += 1;
+                    midi_message.set(Some((*midi_serial.borrow(), message)));
+                })
+            };
+            let on_inputs = {
+                let midi_status = midi_status.clone();
+                Callback::from(move |count: u32| midi_status.set(MidiStatus::Connected(count)))
+            };
+            let midi_status = midi_status.clone();
+            let midi_connection = midi_connection.clone();
+            spawn_local(async move {
+                match midi::connect(on_message, on_inputs).await {
+                    Ok(connection) => *midi_connection.borrow_mut() = Some(connection),
+                    Err(message) => midi_status.set(MidiStatus::Unavailable(message)),
+                }
+            });
+        })
+    };
+
+    // Reconnect on startup once a pedal has been learned (the browser remembers
+    // the permission, so this doesn't prompt again)
+    {
+        let on_connect = on_connect.clone();
+        let learned = !midi_map.is_empty();
+        use_effect_with((), move |_| {
+            if learned {
+                on_connect.emit(());
+            }
+            || ()
+        });
+    }
+
+    // Act on each pedal message: learn it for the action waiting in Settings,
+    // or turn the page / step through the songs
+    {
+        let midi_map = midi_map.clone();
+        let midi_learning = midi_learning.clone();
+        let page_turn = page_turn.clone();
+        use_effect_with(*midi_message, move |message| {
+            if let Some((serial, message)) = *message {
+                if let Some(action) = *midi_learning {
+                    let learned = midi_map.learn(action, message);
+                    learned.save();
+                    midi_map.set(learned);
+                    midi_learning.set(None);
+                } else if let Some(action) = midi_map.action_for(&message) {
+                    match action {
+                        MidiAction::NextPage | MidiAction::PrevPage => {
+                            page_turn.set(PageTurn { serial, step: action.step() });
+                        }
+                        // With no chart open yet, a song pedal starts at the top of the list
+                        MidiAction::NextSong | MidiAction::PrevSong if selected_entry.is_none() => {
+                            if let Some(first) = (*search_results).as_ref().and_then(|response| response.results.first()) {
+                                open_entry.emit(first.clone());
+                            }
+                        }
+                        MidiAction::NextSong | MidiAction::PrevSong => on_adjacent_song.emit(action.step()),
+                    }
+                }
+            }
+            || ()
+        });
+    }
+
+    let on_learn = {
+        let midi_learning = midi_learning.clone();
+        Callback::from(move |action: Option<MidiAction>| midi_learning.set(action))
+    };
+
+    let on_map_change = {
+        let midi_map = midi_map.clone();
+        Callback::from(move |value: MidiMap| {
+            value.save();
+            midi_map.set(value);
+        })
+    };
+
+    Pedals {
+        status: (*midi_status).clone(),
+        map: (*midi_map).clone(),
+        learning: *midi_learning,
+        page_turn: *page_turn,
+        on_connect,
+        on_learn,
+        on_map_change,
+    }
+}
@@ -0,0 +1,251 @@
+//! User data archives: everything the user has built up, as one JSON file
+//!
+//! The API's `/me/export` supplies the archive's header and the part kept on
+//! the server (the anonymous session's recently viewed songs); the sections
+//! kept in this browser are added here, each under its own key. If the API
+//! can't be reached the archive is made from the local sections alone, so a
+//! backup works offline too.
+//!
+//! Importing reads every local section first (so a damaged file changes
+//! nothing), has `/me/import` check the archive and merge the server's part,
+//! then merges each local section present in the archive by the chosen
+//! strategy. Sections missing from the archive are left alone, even when
+//! replacing.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::annotations::SongAnnotations;
+use crate::models::RealBookEntry;
+use crate::notes::Note;
+use crate::practice::PracticeSession;
+use crate::repertoire::Repertoire;
+use crate::setlists::Setlist;
+use crate::tags::Tags;
+use crate::{annotations, api, favorites, notes, practice, repertoire, setlists, tags, utils};
+
+/// Archive format tag and version (as in `api/src/archive.rs`)
+const ARCHIVE_FORMAT: &str = "realbook-archive";
+const ARCHIVE_VERSION: u32 = 1;
+
+fn section(value: impl Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// The sections kept in this browser, by archive key
+pub fn local_sections() -> Map<String, Value> {
+    Map::from_iter([
+        ("favorites".to_string(), section(favorites::load())),
+        ("setlists".to_string(), section(setlists::load())),
+        ("annotations".to_string(), section(annotations::all())),
+        ("notes".to_string(), section(notes::load())),
+        ("practice".to_string(), section(practice::load())),
+        ("tags".to_string(), section(tags::load())),
+        ("repertoire".to_string(), section(repertoire::load())),
+    ])
+}
+
+/// Store a whole section as given (replacing what's there); false if `value`
+/// doesn't have the section's shape or `name` isn't a section
+pub fn save_section(name: &str, value: Value) -> bool {
+    fn parse<T: serde::de::DeserializeOwned>(value: Value) -> Option<T> {
+        serde_json::from_value(value).ok()
+    }
+    match name {
+        "favorites" => parse(value).map(|songs: Vec<RealBookEntry>| favorites::save_all(&songs)),
+        "setlists" => parse(value).map(|lists| drop(setlists::save(lists))),
+        "annotations" => parse(value).map(|songs: BTreeMap<u32, SongAnnotations>| {
+            // Saving a song's annotations as empty removes them
+            for id in annotations::all().keys().filter(|id| !songs.contains_key(id)) {
+                annotations::save(*id, &SongAnnotations::new());
+            }
+            for (id, song) in &songs {
+                annotations::save(*id, song);
+            }
+        }),
+        "notes" => parse(value).map(|all: BTreeMap<u32, Note>| notes::save_all(&all)),
+        "practice" => parse(value).map(practice::save_all),
+        "tags" => parse(value).map(|all| drop(tags::save(all))),
+        "repertoire" => parse(value).map(|all: Repertoire| repertoire::save_all(&all)),
+        _ => None,
+    }
+    .is_some()
+}
+
+/// Empty every local section (signing out of a shared device)
+pub fn clear_local() {
+    for (name, section) in local_sections() {
+        let empty = if section.is_array() { Value::Array(Vec::new()) } else { Value::Object(Map::new()) };
+        save_section(&name, empty);
+    }
+}
+
+/// Put together the archive and offer it as a download
+pub async fn export() -> Result<(), String> {
+    let mut archive = api::export_archive().await.unwrap_or_else(|_| {
+        Map::from_iter([
+            ("format".to_string(), Value::from(ARCHIVE_FORMAT)),
+            ("version".to_string(), Value::from(ARCHIVE_VERSION)),
+            ("exported_at".to_string(), Value::from(utils::local_date())),
+        ])
+    });
+    archive.extend(local_sections());
+    let contents = serde_json::to_string_pretty(&archive).map_err(|e| e.to_string())?;
+    let filename = format!("realbook-archive-{}.json", utils::local_date());
+    setlists::download(&filename, &contents, "application/json")
+}
+
+/// How an imported archive combines with the data already here
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportStrategy {
+    /// Keep everything; where both have the same item, keep the one here
+    #[default]
+    Merge,
+    /// Keep everything; where both have the same item, take the archive's
+    Overwrite,
+    /// Swap each section in the archive for the archive's version
+    Replace,
+}
+
+impl ImportStrategy {
+    pub const ALL: [ImportStrategy; 3] = [ImportStrategy::Merge, ImportStrategy::Overwrite, ImportStrategy::Replace];
+
+    /// The API's name for it (also used as the select value)
+    pub fn value(self) -> &'static str {
+        match self {
+            ImportStrategy::Merge => "merge",
+            ImportStrategy::Overwrite => "overwrite",
+            ImportStrategy::Replace => "replace",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|strategy| strategy.value() == value)
+    }
+}
+
+/// The local sections of an archive, each None if it's missing
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LocalSections {
+    favorites: Option<Vec<RealBookEntry>>,
+    setlists: Option<Vec<Setlist>>,
+    annotations: Option<BTreeMap<u32, SongAnnotations>>,
+    notes: Option<BTreeMap<u32, Note>>,
+    practice: Option<Vec<PracticeSession>>,
+    tags: Option<Tags>,
+    repertoire: Option<Repertoire>,
+}
+
+/// Combine two lists, matching items by `key`
+fn merge_list<T, K: PartialEq>(current: Vec<T>, incoming: Vec<T>, strategy: ImportStrategy, key: impl Fn(&T) -> K) -> Vec<T> {
+    if strategy == ImportStrategy::Replace {
+        return incoming;
+    }
+    let mut merged = current;
+    for item in incoming {
+        match merged.iter().position(|existing| key(existing) == key(&item)) {
+            Some(index) if strategy == ImportStrategy::Overwrite => merged[index] = item,
+            Some(_) => {}
+            None => merged.push(item),
+        }
+    }
+    merged
+}
+
+/// Combine two maps by entry id
+fn merge_map<V>(current: BTreeMap<u32, V>, incoming: BTreeMap<u32, V>, strategy: ImportStrategy) -> BTreeMap<u32, V> {
+    match strategy {
+        ImportStrategy::Replace => incoming,
+        ImportStrategy::Overwrite => current.into_iter().chain(incoming).collect(),
+        ImportStrategy::Merge => incoming.into_iter().chain(current).collect(),
+    }
+}
+
+/// Restore an archive file into this browser (and the server's session);
+/// answers with a summary to show
+pub async fn import(file: web_sys::File, strategy: ImportStrategy) -> Result<String, String> {
+    let contents = setlists::read_file(file).await?;
+    let sections: LocalSections =
+        serde_json::from_str(&contents).map_err(|e| format!("This archive can't be read: {}", e))?;
+    let summary = api::import_archive(&contents, strategy.value()).await.map_err(|e| e.message)?;
+
+    if let Some(incoming) = sections.favorites {
+        favorites::save_all(&merge_list(favorites::load(), incoming, strategy, |song| song.id));
+    }
+    if let Some(incoming) = sections.setlists {
+        setlists::save(merge_list(setlists::load(), incoming, strategy, |setlist| setlist.id.clone()));
+    }
+    if let Some(incoming) = sections.annotations {
+        save_section("annotations", section(merge_map(annotations::all(), incoming, strategy)));
+    }
+    if let Some(incoming) = sections.notes {
+        notes::save_all(&merge_map(notes::load(), incoming, strategy));
+    }
+    if let Some(incoming) = sections.practice {
+        let mut log = merge_list(practice::load(), incoming, strategy, |session| {
+            (session.entry_id, session.started_at.to_bits())
+        });
+        log.sort_by(|a, b| a.started_at.total_cmp(&b.started_at));
+        practice::save_all(log);
+    }
+    if let Some(incoming) = sections.tags {
+        tags::save(merge_map(tags::load(), incoming, strategy));
+    }
+    if let Some(incoming) = sections.repertoire {
+        repertoire::save_all(&merge_map(repertoire::load(), incoming, strategy));
+    }
+
+    let mut message = if summary.sections.is_empty() {
+        "Imported the archive (it had no saved data)".to_string()
+    } else {
+        format!("Imported {}", summary.sections.join(", "))
+    };
+    if !summary.unknown_entries.is_empty() {
+        message.push_str(&format!("; skipped {} recently viewed songs not in this catalog", summary.unknown_entries.len()));
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(strategy: ImportStrategy) -> Vec<(u32, &'static str)> {
+        merge_list(vec![(1, "here"), (2, "here")], vec![(2, "archive"), (3, "archive")], strategy, |item| item.0)
+    }
+
+    fn map(strategy: ImportStrategy) -> BTreeMap<u32, &'static str> {
+        merge_map(
+            BTreeMap::from([(1, "here"), (2, "here")]),
+            BTreeMap::from([(2, "archive"), (3, "archive")]),
+            strategy,
+        )
+    }
+
+    #[test]
+    fn merge_keeps_what_is_here() {
+        assert_eq!(list(ImportStrategy::Merge), vec![(1, "here"), (2, "here"), (3, "archive")]);
+        assert_eq!(map(ImportStrategy::Merge), BTreeMap::from([(1, "here"), (2, "here"), (3, "archive")]));
+    }
+
+    #[test]
+    fn overwrite_takes_the_archives_version_in_place() {
+        assert_eq!(list(ImportStrategy::Overwrite), vec![(1, "here"), (2, "archive"), (3, "archive")]);
+        assert_eq!(map(ImportStrategy::Overwrite), BTreeMap::from([(1, "here"), (2, "archive"), (3, "archive")]));
+    }
+
+    #[test]
+    fn replace_keeps_only_the_archive() {
+        assert_eq!(list(ImportStrategy::Replace), vec![(2, "archive"), (3, "archive")]);
+        assert_eq!(map(ImportStrategy::Replace), BTreeMap::from([(2, "archive"), (3, "archive")]));
+    }
+
+    #[test]
+    fn strategies_round_trip_through_their_names() {
+        for strategy in ImportStrategy::ALL {
+            assert_eq!(ImportStrategy::from_value(strategy.value()), Some(strategy));
+        }
+        assert_eq!(ImportStrategy::from_value("append"), None);
+    }
+}
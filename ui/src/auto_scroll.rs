@@ -0,0 +1,91 @@
+//! Hands-free scrolling through a chart
+//!
+//! The page is scrolled a little every frame-ish tick at the chosen speed,
+//! keeping the position as a float so slow speeds (less than a pixel a tick)
+//! still move. Scrolling by hand while it runs is respected: the next tick
+//! carries on from wherever the user left the page. It stops itself at the
+//! bottom of the page.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use gloo_timers::callback::Interval;
+use crate::storage;
+
+/// localStorage key for the speed
+const STORAGE_KEY: &str = "realbook.autoscroll_speed";
+
+/// Speed levels offered, and pixels per second for each level
+pub const MIN_SPEED: u32 = 1;
+pub const MAX_SPEED: u32 = 10;
+const PIXELS_PER_SECOND_PER_LEVEL: f64 = 12.0;
+const DEFAULT_SPEED: u32 = 3;
+
+/// Time between scroll steps, in milliseconds (about 60 per second)
+const TICK_MS: u32 = 16;
+
+/// The saved speed level (the default if none)
+pub fn load_speed() -> u32 {
+    storage::load(STORAGE_KEY).unwrap_or(DEFAULT_SPEED).clamp(MIN_SPEED, MAX_SPEED)
+}
+
+/// Remember the speed level
+pub fn save_speed(speed: u32) {
+    storage::save(STORAGE_KEY, &speed);
+}
+
+/// Move `speed` one level up (`step` = 1) or down (-1), within the limits
+pub fn step_speed(speed: u32, step: i32) -> u32 {
+    speed.saturating_add_signed(step).clamp(MIN_SPEED, MAX_SPEED)
+}
+
+/// A running auto-scroll; dropping it stops scrolling
+pub struct AutoScroller {
+    speed: Rc<Cell<u32>>,
+    _interval: Interval,
+}
+
+impl AutoScroller {
+    /// Start scrolling the page at `speed`; `on_end` is called once the bottom
+    /// is reached (and nothing more happens until it's dropped)
+    pub fn start(speed: u32, on_end: impl Fn() + 'static) -> Self {
+        let speed = Rc::new(Cell::new(speed));
+        let window = web_sys::window().expect("no window");
+        // Where the last tick left the page, with its fractional part
+        let position = Cell::new(window.scroll_y().unwrap_or_default());
+        let ended = Cell::new(false);
+        let interval = {
+            let speed = speed.clone();
+            Interval::new(TICK_MS, move || {
+                if ended.get() {
+                    return;
+                }
+                let scrolled = window.scroll_y().unwrap_or_default();
+                // Scrolled by hand since the last tick: carry on from there
+                if (scrolled - position.get().floor()).abs() > 1.0 {
+                    position.set(scrolled);
+                }
+
+                let step = f64::from(speed.get()) * PIXELS_PER_SECOND_PER_LEVEL * f64::from(TICK_MS) / 1000.0;
+                position.set(position.get() + step);
+                window.scroll_to_with_x_and_y(window.scroll_x().unwrap_or_default(), position.get().floor());
+
+                let bottom = window
+                    .document()
+                    .and_then(|document| document.document_element())
+                    .map(|root| f64::from(root.scroll_height()))
+                    .unwrap_or_default();
+                let height = window.inner_height().ok().and_then(|height| height.as_f64()).unwrap_or_default();
+                if position.get() + height >= bottom {
+                    ended.set(true);
+                    on_end();
+                }
+            })
+        };
+        AutoScroller { speed, _interval: interval }
+    }
+
+    /// Change the speed without restarting
+    pub fn set_speed(&self, speed: u32) {
+        self.speed.set(speed);
+    }
+}
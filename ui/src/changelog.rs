@@ -0,0 +1,35 @@
+//! What's new in the catalog since this browser last looked
+//!
+//! The API's changelog (`/api/v1/changelog`) numbers each catalog release. The
+//! newest one seen here is kept in localStorage; releases after it are shown
+//! in a banner until it's dismissed. A first visit has nothing to catch up on,
+//! so it only records the current release.
+
+use crate::models::{Changelog, Release};
+use crate::storage;
+
+/// localStorage key for the newest release seen
+const STORAGE_KEY: &str = "realbook.changelog_seen";
+
+/// Remember that the releases up to `version` have been seen
+pub fn mark_seen(version: u32) {
+    storage::save(STORAGE_KEY, &version);
+}
+
+/// Releases to announce, newest first (empty if there's nothing new)
+pub fn unseen(changelog: &Changelog) -> Vec<Release> {
+    let Some(latest) = changelog.latest else {
+        return Vec::new();
+    };
+    match storage::load::<u32>(STORAGE_KEY) {
+        Some(seen) if seen < latest => {
+            changelog.releases.iter().filter(|release| release.version > seen).cloned().collect()
+        }
+        // First visit, or a changelog that was started over
+        Some(seen) if seen == latest => Vec::new(),
+        _ => {
+            mark_seen(latest);
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use yew::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::api;
+use crate::models::{CatalogStats, RealBookEntry};
+use crate::repertoire::Summary;
+
+/// Format a chart as "Title (Vol. N, p. X-Y)" for the stats list
+fn describe_chart(entry: &RealBookEntry) -> String {
+    format!("{} (Vol. {}, p. {})", entry.title, entry.volume, entry.page_range())
+}
+
+/// Describe the user's book, against the catalog's size when known:
+/// "12 of 1200 songs (1%): 4 learning, 5 learned, 3 performance-ready"
+fn describe_book(book: &Summary, catalog_songs: Option<usize>) -> String {
+    let size = match catalog_songs {
+        Some(total) if total > 0 => {
+            format!("{} of {} songs ({:.0}%)", book.total(), total, book.total() as f64 * 100.0 / total as f64)
+        }
+        _ => format!("{} songs", book.total()),
+    };
+    format!("{}: {} learning, {} learned, {} performance-ready", size, book.learning, book.learned, book.ready)
+}
+
+/// Props for the AboutPanel component
+#[derive(Properties, PartialEq)]
+pub struct AboutPanelProps {
+    /// How many songs the user has at each repertoire status
+    pub repertoire: Summary,
+}
+
+/// AboutPanel component - collapsible panel with catalog statistics
+///
+/// Statistics are fetched from `/api/stats` the first time the panel is opened,
+/// so users who never open it don't pay for the request. The user's own book
+/// (songs with a repertoire status) is set against the whole catalog.
+#[function_component(AboutPanel)]
+pub fn about_panel(props: &AboutPanelProps) -> Html {
+    let stats = use_state(|| Option::<CatalogStats>::None);
+    let loading = use_state(|| false);
+    let error = use_state(|| Option::<String>::None);
+
+    // Fetch stats on first open (native <details> toggle event)
+    let on_toggle = {
+        let stats = stats.clone();
+        let loading = loading.clone();
+        let error = error.clone();
+        Callback::from(move |_: Event| {
+            if stats.is_some() || *loading {
+                return;
+            }
+
+            let stats = stats.clone();
+            let loading = loading.clone();
+            let error = error.clone();
+            loading.set(true);
+
+            spawn_local(async move {
+                match api::get_stats().await {
+                    Ok(data) => {
+                        stats.set(Some(data));
+                        error.set(None);
+                    }
+                    Err(e) => {
+                        error.set(Some(e.message));
+                    }
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    html! {
+        <details class="about-panel" ontoggle={on_toggle}>
+            <summary>{ "About this catalog" }</summary>
+            <article aria-busy={loading.to_string()}>
+                {
+                    if let Some(stats) = &*stats {
+                        html! {
+                            <dl class="stats-list">
+                                <dt>{ "Songs" }</dt>
+                                <dd>{ stats.total_songs }</dd>
+
+                                <dt>{ "Songs per volume" }</dt>
+                                <dd>
+                                    {
+                                        stats.songs_per_volume.iter()
+                                            .map(|v| format!("Vol. {}: {}", v.volume, v.count))
+                                            .collect::<Vec<_>>()
+                                            .join(" · ")
+                                    }
+                                </dd>
+
+                                <dt>{ "Your book" }</dt>
+                                <dd>{ describe_book(&props.repertoire, Some(stats.total_songs)) }</dd>
+
+                                <dt>{ "Pages" }</dt>
+                                <dd>{ stats.total_pages }</dd>
+
+                                <dt>{ "Average pages per song" }</dt>
+                                <dd>{ format!("{:.2}", stats.average_pages_per_song) }</dd>
+
+                                if let Some(entry) = &stats.longest_chart {
+                                    <dt>{ "Longest chart" }</dt>
+                                    <dd>{ describe_chart(entry) }</dd>
+                                }
+
+                                if let Some(entry) = &stats.shortest_chart {
+                                    <dt>{ "Shortest chart" }</dt>
+                                    <dd>{ describe_chart(entry) }</dd>
+                                }
+                            </dl>
+                        }
+                    } else if let Some(error_msg) = &*error {
+                        html! {
+                            <>
+                                <p><mark>{ error_msg }</mark></p>
+                                <p>{ format!("Your book: {}", describe_book(&props.repertoire, None)) }</p>
+                            </>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </article>
+        </details>
+    }
+}
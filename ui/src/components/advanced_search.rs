@@ -0,0 +1,99 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use crate::models::SearchParams;
+
+/// Props for the AdvancedSearch component
+#[derive(Properties, PartialEq)]
+pub struct AdvancedSearchProps {
+    /// Page filter text as in the search bar: a page ("134"), a span
+    /// ("100-120"), or an approximate page ("~300±3")
+    pub page_input: String,
+
+    /// Callback fired when a field changes
+    /// Takes the page filter text the fields add up to
+    pub on_page_change: Callback<String>,
+}
+
+/// AdvancedSearch component - a collapsible form of the structured filters
+///
+/// The fields edit the same page filter text as the compact field in the
+/// search bar, so the two always agree and nothing new has to be threaded
+/// through to the search. Setting one kind of page filter (a single page, a
+/// span, or a page to search near) clears the others.
+#[function_component(AdvancedSearch)]
+pub fn advanced_search(props: &AdvancedSearchProps) -> Html {
+    let filter = SearchParams::default().with_page_input(&props.page_input);
+    let field_text = |page: Option<u32>| page.map(|page| page.to_string()).unwrap_or_default();
+
+    // Turn an edit of one field into the new page filter text
+    let on_field = |update: fn(&mut SearchParams, Option<u32>)| {
+        let filter = filter.clone();
+        props.on_page_change.reform(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut filter = filter.clone();
+            update(&mut filter, input.value().trim().parse().ok());
+            filter.page_input().unwrap_or_default()
+        })
+    };
+    let on_page = on_field(|filter, page| {
+        filter.page = page;
+        filter.page_from = None;
+        filter.page_to = None;
+        filter.page_near = None;
+    });
+    let on_from = on_field(|filter, page| {
+        filter.page = None;
+        filter.page_near = None;
+        filter.page_from = page;
+    });
+    let on_to = on_field(|filter, page| {
+        filter.page = None;
+        filter.page_near = None;
+        filter.page_to = page;
+    });
+    let on_near = on_field(|filter, page| {
+        filter.page = None;
+        filter.page_from = None;
+        filter.page_to = None;
+        filter.page_near = page;
+    });
+    let on_tolerance = on_field(|filter, pages| filter.tolerance = pages);
+
+    html! {
+        <details class="advanced-search">
+            <summary>{ "Advanced search" }</summary>
+            <div class="grid">
+                <label>
+                    { "On page" }
+                    <input type="number" min="1" placeholder="e.g. 134" value={field_text(filter.page)} oninput={on_page} />
+                </label>
+                <label>
+                    { "From page" }
+                    <input type="number" min="1" placeholder="e.g. 100" value={field_text(filter.page_from)} oninput={on_from} />
+                </label>
+                <label>
+                    { "To page" }
+                    <input type="number" min="1" placeholder="e.g. 120" value={field_text(filter.page_to)} oninput={on_to} />
+                </label>
+            </div>
+            <div class="grid">
+                <label>
+                    { "Near page" }
+                    <input type="number" min="1" placeholder="e.g. 300" value={field_text(filter.page_near)} oninput={on_near} />
+                </label>
+                <label>
+                    { "Give or take (pages)" }
+                    <input
+                        type="number"
+                        min="0"
+                        max="50"
+                        placeholder="5"
+                        disabled={filter.page_near.is_none()}
+                        value={field_text(filter.tolerance)}
+                        oninput={on_tolerance}
+                    />
+                </label>
+            </div>
+        </details>
+    }
+}
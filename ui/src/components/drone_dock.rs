@@ -0,0 +1,109 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use crate::components::use_toaster;
+use crate::drone::{DEFAULT_OCTAVE, DEFAULT_PITCH, DEFAULT_VOLUME, Drone, OCTAVES};
+use crate::i18n::use_messages;
+
+/// DroneDock component - a sustained reference pitch docked under the chart
+///
+/// Start/stop, note, octave, and volume; changing them while the drone sounds
+/// glides to the new setting. Closing the dock (unmounting it) stops the drone.
+#[function_component(DroneDock)]
+pub fn drone_dock() -> Html {
+    let t = use_messages();
+    let toaster = use_toaster();
+    let pitch = use_state(|| DEFAULT_PITCH);
+    let octave = use_state(|| DEFAULT_OCTAVE);
+    let volume = use_state(|| DEFAULT_VOLUME);
+    let running = use_mut_ref(|| Option::<Drone>::None);
+    let playing = use_state(|| false);
+
+    // Keep a sounding drone in step with the controls
+    {
+        let running = running.clone();
+        use_effect_with((*pitch, *octave, *volume), move |(pitch, octave, volume)| {
+            if let Some(drone) = running.borrow().as_ref() {
+                drone.set_pitch(*pitch, *octave);
+                drone.set_volume(*volume);
+            }
+            || ()
+        });
+    }
+
+    let on_toggle = {
+        let running = running.clone();
+        let playing = playing.clone();
+        let (pitch, octave, volume) = (*pitch, *octave, *volume);
+        let unavailable = t.audio_unavailable;
+        Callback::from(move |_: MouseEvent| {
+            let mut running = running.borrow_mut();
+            if running.take().is_none() {
+                *running = Drone::start(pitch, octave, volume);
+                if running.is_none() {
+                    toaster.error(unavailable);
+                }
+            }
+            playing.set(running.is_some());
+        })
+    };
+
+    let on_pitch_change = {
+        let pitch = pitch.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Ok(value) = select.value().parse() {
+                pitch.set(value);
+            }
+        })
+    };
+
+    let on_octave_change = {
+        let octave = octave.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Ok(value) = select.value().parse() {
+                octave.set(value);
+            }
+        })
+    };
+
+    let on_volume_input = {
+        let volume = volume.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(value) = input.value().parse() {
+                volume.set(value);
+            }
+        })
+    };
+
+    html! {
+        <div class="practice-dock" role="group" aria-label={t.drone}>
+            <button
+                class={classes!("secondary", (!*playing).then_some("outline"))}
+                aria-pressed={playing.to_string()}
+                onclick={on_toggle}
+            >
+                { if *playing { t.sound_stop } else { t.sound_start } }
+            </button>
+            <select aria-label={t.drone_pitch} onchange={on_pitch_change}>
+                {
+                    for t.note_names.iter().zip(0u8..).map(|(name, option)| html! {
+                        <option value={option.to_string()} selected={*pitch == option}>{ *name }</option>
+                    })
+                }
+            </select>
+            <select aria-label={t.drone_octave} onchange={on_octave_change}>
+                {
+                    for OCTAVES.iter().map(|&option| html! {
+                        <option value={option.to_string()} selected={*octave == option}>{ option }</option>
+                    })
+                }
+            </select>
+            <label class="drone-volume">
+                { t.drone_volume }
+                <input type="range" min="0" max="100" value={volume.to_string()} oninput={on_volume_input} />
+            </label>
+        </div>
+    }
+}
@@ -1,24 +1,73 @@
 use yew::prelude::*;
+use crate::i18n::use_messages;
+use crate::sync::SyncStatus;
+use crate::utils;
+
+/// Props for the Header component
+#[derive(Properties, PartialEq)]
+pub struct HeaderProps {
+    /// Where syncing with other devices stands
+    pub sync_status: SyncStatus,
+
+    /// Callback to sync right away (clicking the indicator)
+    pub on_sync: Callback<()>,
+}
 
 /// Header component - displays the application title
 ///
-/// This is a simple "presentational" component with no props or state.
+/// This is a "presentational" component with no state of its own (its text
+/// comes from the language context). While the device syncs with others, a
+/// small indicator next to the title shows how that's going; clicking it syncs
+/// right away, and a failure's reason shows as its tooltip.
 /// In Yew, we use the #[function_component] macro to define components as functions.
 ///
 /// The function returns Html, which is Yew's virtual DOM representation.
 /// We use the html! macro to write JSX-like syntax that compiles to Html.
 #[function_component(Header)]
-pub fn header() -> Html {
+pub fn header(props: &HeaderProps) -> Html {
+    let t = use_messages();
+
+    let sync_indicator = match &props.sync_status {
+        SyncStatus::Off => None,
+        SyncStatus::Syncing => Some(("syncing", t.sync_syncing, t.sync_now.to_string())),
+        SyncStatus::Synced(at) => Some((
+            "synced",
+            t.sync_synced,
+            at.map_or_else(|| t.sync_now.to_string(), |at| format!("{} ({})", t.sync_now, utils::local_time(at))),
+        )),
+        SyncStatus::Offline => Some(("offline", t.sync_offline, t.sync_now.to_string())),
+        SyncStatus::Failed(reason) => Some(("failed", t.sync_failed, reason.clone())),
+    };
+
     html! {
         // Pico CSS automatically styles <header> elements nicely
         <header>
-            <h1>{ "Real Book Search" }</h1>
-            <p>{ "Find jazz standards by title, volume, or page number" }</p>
+            <h1>
+                { "Real Book Search" }
+                if let Some((state, label, title)) = sync_indicator {
+                    <button
+                        class={classes!("sync-status", state)}
+                        title={title}
+                        disabled={props.sync_status == SyncStatus::Syncing}
+                        onclick={props.on_sync.reform(|_: MouseEvent| ())}
+                    >
+                        { label }
+                    </button>
+                }
+            </h1>
+            <p>{ t.tagline }</p>
             <p>
                 <small>
-                    { "Keyboard shortcuts: " }
-                    <kbd>{ "↑↓" }</kbd>{ " navigate, " }
-                    <kbd>{ "Enter" }</kbd>{ " view selected" }
+                    { t.shortcuts_intro }
+                    <kbd>{ "/" }</kbd>{ t.shortcut_search }
+                    <kbd>{ "Esc" }</kbd>{ t.shortcut_clear }
+                    <kbd>{ "↑↓" }</kbd>{ t.shortcut_or }<kbd>{ "j" }</kbd><kbd>{ "k" }</kbd>{ t.shortcut_navigate }
+                    <kbd>{ "g g" }</kbd><kbd>{ "G" }</kbd>{ t.shortcut_first_last }
+                    <kbd>{ "Enter" }</kbd>{ t.shortcut_or }<kbd>{ "o" }</kbd>{ t.shortcut_view }
+                    <kbd>{ "r" }</kbd>{ t.shortcut_random }
+                    <kbd>{ "←→" }</kbd>{ t.shortcut_or }<kbd>{ "PgUp" }</kbd><kbd>{ "PgDn" }</kbd>{ t.shortcut_turn_pages }
+                    <kbd>{ "+" }</kbd><kbd>{ "-" }</kbd><kbd>{ "0" }</kbd>{ t.shortcut_zoom }
+                    { t.shortcut_rebind }
                 </small>
             </p>
         </header>
@@ -0,0 +1,271 @@
+use std::cell::Cell;
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use crate::components::use_toaster;
+use crate::i18n::use_messages;
+use crate::metronome::{self, DEFAULT_BPM, Form, MAX_BARS, MAX_BEATS_PER_BAR, MAX_BPM, MIN_BPM, Metronome, Subdivision};
+
+/// Props for the MetronomeDock component
+#[derive(Properties, PartialEq)]
+pub struct MetronomeDockProps {
+    /// Id of the song shown, whose tempo and form are loaded and saved
+    pub entry_id: u32,
+
+    /// Pages in the song's chart (page turns are offered when there are several)
+    #[prop_or(1)]
+    pub page_count: u32,
+
+    /// Callback to show a page (0-based) of the chart, when turning pages in time
+    #[prop_or_default]
+    pub on_page_turn: Callback<u32>,
+}
+
+/// MetronomeDock component - a click track docked under the chart
+///
+/// Start/stop, tempo (typed, nudged, or tapped), and clicks per beat. The
+/// tempo is saved for the song whenever it's changed and loaded again when the
+/// song is opened; a running metronome follows along when the set moves to the
+/// next song. Closing the dock (unmounting it) stops the clicks.
+///
+/// For multi-page charts, "Turn pages" follows the click: given the song's
+/// form (bars per chorus and beats per bar, saved like the tempo), starting
+/// counts in one bar and then turns to each page a beat before its first bar,
+/// going back to the first page for every chorus.
+#[function_component(MetronomeDock)]
+pub fn metronome_dock(props: &MetronomeDockProps) -> Html {
+    let t = use_messages();
+    let toaster = use_toaster();
+    let bpm = use_state(|| metronome::load_tempo(props.entry_id).unwrap_or(DEFAULT_BPM));
+    let subdivision = use_state(Subdivision::default);
+    let running = use_mut_ref(|| Option::<Metronome>::None);
+    let playing = use_state(|| false);
+    let taps = use_mut_ref(Vec::<f64>::new);
+    let form = use_state(|| metronome::load_form(props.entry_id).unwrap_or_default());
+    let follow = use_state(|| false);
+    // What the beat callback turns pages by, kept current while it runs:
+    // the form and page count when following along, and the page last shown
+    let page_turns = use_mut_ref(|| Option::<(Form, u32)>::None);
+    let last_page = use_memo((), |_| Cell::new(0u32));
+    {
+        let page_turns = page_turns.clone();
+        let following = (*follow && props.page_count > 1).then_some((*form, props.page_count));
+        use_effect_with(following, move |following| {
+            *page_turns.borrow_mut() = *following;
+            || ()
+        });
+    }
+
+    // Switch to the tempo and form saved for a newly opened song
+    {
+        let bpm = bpm.clone();
+        let form = form.clone();
+        use_effect_with(props.entry_id, move |entry_id| {
+            bpm.set(metronome::load_tempo(*entry_id).unwrap_or(DEFAULT_BPM));
+            form.set(metronome::load_form(*entry_id).unwrap_or_default());
+            || ()
+        });
+    }
+
+    // Keep a running click track in step with the controls
+    {
+        let running = running.clone();
+        use_effect_with((*bpm, *subdivision), move |(bpm, subdivision)| {
+            if let Some(metronome) = running.borrow().as_ref() {
+                metronome.set_bpm(*bpm);
+                metronome.set_subdivision(*subdivision);
+            }
+            || ()
+        });
+    }
+
+    // Set and save the song's tempo
+    let set_bpm = {
+        let bpm = bpm.clone();
+        let entry_id = props.entry_id;
+        Callback::from(move |value: u32| {
+            let value = metronome::clamp_bpm(value);
+            metronome::save_tempo(entry_id, value);
+            bpm.set(value);
+        })
+    };
+
+    let on_toggle = {
+        let running = running.clone();
+        let playing = playing.clone();
+        let page_turns = page_turns.clone();
+        let on_page_turn = props.on_page_turn.clone();
+        let last_page = last_page.clone();
+        let (bpm, subdivision) = (*bpm, *subdivision);
+        let unavailable = t.audio_unavailable;
+        Callback::from(move |_: MouseEvent| {
+            let mut running = running.borrow_mut();
+            if running.take().is_none() {
+                // Following along: count in a bar, from the top of the chart
+                let count_in = page_turns.borrow().map_or(0, |(form, _)| form.clamped().beats_per_bar);
+                if count_in > 0 {
+                    last_page.set(0);
+                    on_page_turn.emit(0);
+                }
+                let on_beat = {
+                    let page_turns = page_turns.clone();
+                    let on_page_turn = on_page_turn.clone();
+                    let last_page = last_page.clone();
+                    move |beat: i64| {
+                        let Some((form, page_count)) = *page_turns.borrow() else {
+                            return;
+                        };
+                        let Ok(beat) = u64::try_from(beat) else {
+                            return;
+                        };
+                        let page = form.page_at(beat, page_count);
+                        if page != last_page.replace(page) {
+                            on_page_turn.emit(page);
+                        }
+                    }
+                };
+                *running = Metronome::start(bpm, subdivision, count_in, on_beat);
+                if running.is_none() {
+                    toaster.error(unavailable);
+                }
+            }
+            playing.set(running.is_some());
+        })
+    };
+
+    let on_bpm_input = set_bpm.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        input.value().trim().parse().unwrap_or(DEFAULT_BPM)
+    });
+
+    let on_tap = {
+        let set_bpm = set_bpm.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(tapped) = metronome::tap_tempo(&mut taps.borrow_mut(), js_sys::Date::now()) {
+                set_bpm.emit(tapped);
+            }
+        })
+    };
+
+    let on_subdivision_change = {
+        let subdivision = subdivision.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(value) = Subdivision::from_code(&select.value()) {
+                subdivision.set(value);
+            }
+        })
+    };
+
+    // Set and save the song's form
+    let set_form = {
+        let form = form.clone();
+        let entry_id = props.entry_id;
+        Callback::from(move |value: Form| {
+            let value = value.clamped();
+            metronome::save_form(entry_id, value);
+            form.set(value);
+        })
+    };
+    let on_bars_input = {
+        let current = *form;
+        set_form.reform(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Form { bars: input.value().trim().parse().unwrap_or(current.bars), ..current }
+        })
+    };
+    let on_beats_input = {
+        let current = *form;
+        set_form.reform(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Form { beats_per_bar: input.value().trim().parse().unwrap_or(current.beats_per_bar), ..current }
+        })
+    };
+    let on_follow_change = {
+        let follow = follow.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            follow.set(input.checked());
+        })
+    };
+
+    let current = *bpm;
+    html! {
+        <div class="practice-dock" role="group" aria-label={t.metronome}>
+            <button
+                class={classes!("secondary", (!*playing).then_some("outline"))}
+                aria-pressed={playing.to_string()}
+                onclick={on_toggle}
+            >
+                { if *playing { t.sound_stop } else { t.sound_start } }
+            </button>
+            <div class="tempo-controls">
+                <button
+                    class="secondary outline"
+                    onclick={set_bpm.reform(move |_: MouseEvent| current.saturating_sub(1))}
+                    disabled={current <= MIN_BPM}
+                    aria-label={t.tempo_slower}
+                >
+                    { "−" }
+                </button>
+                <input
+                    type="number"
+                    min={MIN_BPM.to_string()}
+                    max={MAX_BPM.to_string()}
+                    value={current.to_string()}
+                    onchange={on_bpm_input}
+                    aria-label={t.tempo}
+                    title={t.tempo_saved}
+                />
+                <button
+                    class="secondary outline"
+                    onclick={set_bpm.reform(move |_: MouseEvent| current + 1)}
+                    disabled={current >= MAX_BPM}
+                    aria-label={t.tempo_faster}
+                >
+                    { "+" }
+                </button>
+                <span class="tempo-unit">{ t.bpm }</span>
+            </div>
+            <button class="secondary outline" onclick={on_tap} title={t.tap_tempo_title}>
+                { t.tap_tempo }
+            </button>
+            <select aria-label={t.subdivision} onchange={on_subdivision_change}>
+                {
+                    for Subdivision::ALL.iter().map(|&option| html! {
+                        <option value={option.code()} selected={*subdivision == option}>
+                            { t.subdivision_label(option) }
+                        </option>
+                    })
+                }
+            </select>
+            if props.page_count > 1 {
+                <label class="follow-pages" title={t.turn_pages_title}>
+                    <input type="checkbox" role="switch" checked={*follow} onchange={on_follow_change} />
+                    { t.turn_pages }
+                </label>
+                if *follow {
+                    <label class="form-input">
+                        <input
+                            type="number"
+                            min="1"
+                            max={MAX_BARS.to_string()}
+                            value={form.bars.to_string()}
+                            onchange={on_bars_input}
+                        />
+                        { t.form_bars }
+                    </label>
+                    <label class="form-input">
+                        <input
+                            type="number"
+                            min="1"
+                            max={MAX_BEATS_PER_BAR.to_string()}
+                            value={form.beats_per_bar.to_string()}
+                            onchange={on_beats_input}
+                        />
+                        { t.beats_per_bar }
+                    </label>
+                }
+            }
+        </div>
+    }
+}
@@ -3,14 +3,44 @@
 
 pub mod header;
 pub mod search_input;
+pub mod advanced_search;
 pub mod results_list;
 pub mod sheet_viewer;
 pub mod sheet_image;
+pub mod metronome_dock;
+pub mod drone_dock;
+pub mod recording_player;
+pub mod report_problem;
+pub mod qr_dialog;
+pub mod stage_view;
+pub mod about_panel;
+pub mod practice_panel;
+pub mod practice_timer;
+pub mod offline_indicator;
+pub mod whats_new;
+pub mod settings_panel;
+pub mod setlist_panel;
+pub mod toasts;
 
 // Re-export components so they can be imported as:
 // use crate::components::{Header, SearchInput, etc.};
 pub use header::Header;
 pub use search_input::SearchInput;
+pub use advanced_search::AdvancedSearch;
 pub use results_list::ResultsList;
-pub use sheet_viewer::SheetViewer;
+pub use sheet_viewer::{PageTurn, SheetViewer};
 pub use sheet_image::SheetImage;
+pub use metronome_dock::MetronomeDock;
+pub use drone_dock::DroneDock;
+pub use recording_player::RecordingPlayer;
+pub use report_problem::ReportProblem;
+pub use qr_dialog::QrDialog;
+pub use stage_view::StageView;
+pub use about_panel::AboutPanel;
+pub use practice_panel::PracticePanel;
+pub use practice_timer::PracticeTimer;
+pub use offline_indicator::OfflineIndicator;
+pub use whats_new::WhatsNew;
+pub use settings_panel::SettingsPanel;
+pub use setlist_panel::SetlistPanel;
+pub use toasts::{ToastProvider, use_toaster};
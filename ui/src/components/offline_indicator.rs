@@ -0,0 +1,42 @@
+use yew::prelude::*;
+use gloo_events::EventListener;
+
+/// Whether the browser currently reports network connectivity
+fn is_online() -> bool {
+    web_sys::window().is_none_or(|window| window.navigator().on_line())
+}
+
+/// OfflineIndicator component - banner shown while the browser is offline
+///
+/// Tracks `navigator.onLine` through the window's online/offline events.
+/// While offline, searches run against the cached catalog and the service
+/// worker serves the app shell and any cached API responses.
+#[function_component(OfflineIndicator)]
+pub fn offline_indicator() -> Html {
+    let online = use_state(is_online);
+
+    {
+        let online = online.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().unwrap();
+            let on_online = {
+                let online = online.clone();
+                EventListener::new(&window, "online", move |_| online.set(true))
+            };
+            let on_offline = EventListener::new(&window, "offline", move |_| online.set(false));
+
+            // Listeners are removed when dropped (on unmount)
+            move || drop((on_online, on_offline))
+        });
+    }
+
+    if *online {
+        return html! {};
+    }
+
+    html! {
+        <p class="offline-indicator" role="status">
+            { "You're offline - searching the saved catalog. Sheets you've opened before still load." }
+        </p>
+    }
+}
@@ -0,0 +1,62 @@
+use yew::prelude::*;
+use crate::practice::{self, PracticeSession};
+
+/// Day names for the week chart, Monday first
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Songs listed in the week's summary
+const MAX_SONGS: usize = 10;
+
+/// Props for the PracticePanel component
+#[derive(Properties, PartialEq)]
+pub struct PracticePanelProps {
+    /// Every logged practice session
+    pub log: Vec<PracticeSession>,
+}
+
+/// PracticePanel component - collapsible summary of this week's practice
+///
+/// Totals the sessions logged by the viewer's practice timer since Monday: the
+/// week's time, a bar per day, and the most practiced songs.
+#[function_component(PracticePanel)]
+pub fn practice_panel(props: &PracticePanelProps) -> Html {
+    let week = practice::this_week(&props.log);
+    let busiest = week.days.iter().copied().max().unwrap_or_default().max(1);
+
+    html! {
+        <details class="practice-panel">
+            <summary>{ "Practice this week" }</summary>
+            <article>
+                if week.total() == 0 {
+                    <p class="placeholder">{ "Nothing logged this week yet. Start the Practice timer on a chart and sessions are logged here." }</p>
+                } else {
+                    <p>{ format!("{} practiced since Monday", practice::format_duration(week.total())) }</p>
+                    <dl class="practice-days">
+                        {
+                            for WEEKDAYS.iter().zip(week.days).map(|(day, seconds)| html! {
+                                <>
+                                    <dt>{ *day }</dt>
+                                    <dd>
+                                        <progress value={seconds.to_string()} max={busiest.to_string()} />
+                                        <small>{ if seconds > 0 { practice::format_duration(seconds) } else { String::new() } }</small>
+                                    </dd>
+                                </>
+                            })
+                        }
+                    </dl>
+                    <h3>{ "Most practiced" }</h3>
+                    <ol class="practice-songs">
+                        {
+                            for week.songs.iter().take(MAX_SONGS).map(|(title, seconds)| html! {
+                                <li>
+                                    <span>{ title }</span>
+                                    <small>{ practice::format_duration(*seconds) }</small>
+                                </li>
+                            })
+                        }
+                    </ol>
+                }
+            </article>
+        </details>
+    }
+}
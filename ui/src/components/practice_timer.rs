@@ -0,0 +1,120 @@
+use yew::prelude::*;
+use gloo_events::EventListener;
+use gloo_timers::callback::Interval;
+use crate::i18n::{self, use_messages};
+use crate::models::RealBookEntry;
+use crate::practice;
+
+/// Props for the PracticeTimer component
+#[derive(Properties, PartialEq)]
+pub struct PracticeTimerProps {
+    /// Song being practiced
+    pub entry: RealBookEntry,
+
+    /// Callback fired after a session is added to the practice log
+    pub on_logged: Callback<()>,
+}
+
+/// PracticeTimer component - start/stop timer for practicing the open chart
+///
+/// A session is logged (see `practice.rs`) when the timer is stopped, when
+/// another song opens or the viewer closes while it runs, and when the page is
+/// hidden (a phone may discard it without warning), in which case the timer
+/// carries on as a new session.
+#[function_component(PracticeTimer)]
+pub fn practice_timer(props: &PracticeTimerProps) -> Html {
+    let t = use_messages();
+    // The song being timed and when its session started (None when stopped)
+    let running = use_mut_ref(|| Option::<(RealBookEntry, f64)>::None);
+    // Seconds shown on the button (None when stopped)
+    let elapsed = use_state(|| Option::<u32>::None);
+
+    // Tick the clock once a second while running
+    {
+        let running = running.clone();
+        let elapsed = elapsed.clone();
+        use_effect_with(elapsed.is_some(), move |&on| {
+            let interval = on.then(|| {
+                Interval::new(1000, move || {
+                    if let Some((_, started)) = &*running.borrow() {
+                        elapsed.set(Some(((js_sys::Date::now() - started) / 1000.0) as u32));
+                    }
+                })
+            });
+            move || drop(interval)
+        });
+    }
+
+    // Another song (or none): log the running session and stop
+    {
+        let running = running.clone();
+        let elapsed = elapsed.clone();
+        let on_logged = props.on_logged.clone();
+        use_effect_with(props.entry.id, move |_| {
+            move || {
+                if let Some((entry, started)) = running.borrow_mut().take() {
+                    if practice::log(&entry, started) {
+                        on_logged.emit(());
+                    }
+                    elapsed.set(None);
+                }
+            }
+        });
+    }
+
+    // Page hidden: log what's been practiced so far and time on from here
+    {
+        let running = running.clone();
+        let on_logged = props.on_logged.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().unwrap();
+            let listener = EventListener::new(&window, "pagehide", move |_| {
+                if let Some((entry, started)) = &mut *running.borrow_mut() {
+                    if practice::log(entry, *started) {
+                        on_logged.emit(());
+                    }
+                    *started = js_sys::Date::now();
+                }
+            });
+            move || drop(listener)
+        });
+    }
+
+    let on_toggle = {
+        let running = running.clone();
+        let elapsed = elapsed.clone();
+        let entry = props.entry.clone();
+        let on_logged = props.on_logged.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut running = running.borrow_mut();
+            match running.take() {
+                Some((entry, started)) => {
+                    if practice::log(&entry, started) {
+                        on_logged.emit(());
+                    }
+                    elapsed.set(None);
+                }
+                None => {
+                    *running = Some((entry.clone(), js_sys::Date::now()));
+                    elapsed.set(Some(0));
+                }
+            }
+        })
+    };
+
+    html! {
+        <button
+            class={classes!("secondary", "print-button", "practice-timer", elapsed.is_none().then_some("outline"))}
+            aria-pressed={elapsed.is_some().to_string()}
+            title={t.practice_title}
+            onclick={on_toggle}
+        >
+            {
+                match *elapsed {
+                    Some(seconds) => i18n::fill(t.practice_stop, &[&practice::format_clock(seconds)]),
+                    None => t.practice.to_string(),
+                }
+            }
+        </button>
+    }
+}
@@ -0,0 +1,103 @@
+use yew::prelude::*;
+use qrcode::QrCode;
+use qrcode::render::svg;
+use wasm_bindgen::JsCast;
+use crate::hooks;
+use crate::i18n::use_messages;
+
+/// Side of the rendered code, in CSS pixels (scaled down on small screens)
+const QR_SIZE: u32 = 320;
+
+/// The QR code for `url` as an SVG data URL (None if it's too long to encode)
+fn qr_data_url(url: &str) -> Option<String> {
+    let svg = QrCode::new(url.as_bytes())
+        .ok()?
+        .render::<svg::Color>()
+        .min_dimensions(QR_SIZE, QR_SIZE)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build();
+    Some(format!("data:image/svg+xml,{}", urlencoding::encode(&svg)))
+}
+
+/// Props for the QrDialog component
+#[derive(Properties, PartialEq)]
+pub struct QrDialogProps {
+    /// Link the code opens
+    pub url: String,
+
+    /// Song title, shown above the code
+    pub title: String,
+
+    /// Callback fired when the dialog is dismissed (Escape, close button, or backdrop)
+    pub on_close: Callback<()>,
+}
+
+/// QrDialog component - a modal QR code of a chart's link
+///
+/// For the bandleader to hold up a tablet so everyone scans their way to the
+/// same tune. A native `<dialog>` opened with `showModal()`, so the page behind
+/// is inert and Escape closes it; key presses stop at the dialog so the
+/// viewer's shortcuts don't act behind it. Mount it to open, unmount to close.
+#[function_component(QrDialog)]
+pub fn qr_dialog(props: &QrDialogProps) -> Html {
+    let t = use_messages();
+    let dialog_ref = use_node_ref();
+    let qr = use_memo(props.url.clone(), |url| qr_data_url(url));
+    // Declared before showModal() moves focus, so focus goes back to the
+    // button that opened the dialog once it's gone
+    hooks::use_focus_trap(dialog_ref.clone(), true);
+
+    {
+        let dialog_ref = dialog_ref.clone();
+        use_effect_with((), move |_| {
+            if let Some(dialog) = dialog_ref.cast::<web_sys::HtmlDialogElement>() {
+                let _ = dialog.show_modal();
+            }
+            || ()
+        });
+    }
+
+    let on_close_click = props.on_close.reform(|_: MouseEvent| ());
+    // A click on the dialog itself (not its contents) is a click on the backdrop
+    let on_backdrop_click = {
+        let on_close = props.on_close.clone();
+        let dialog_ref = dialog_ref.clone();
+        Callback::from(move |e: MouseEvent| {
+            let on_backdrop = e
+                .target()
+                .zip(dialog_ref.get())
+                .is_some_and(|(target, dialog)| dialog.is_same_node(target.dyn_ref::<web_sys::Node>()));
+            if on_backdrop {
+                on_close.emit(());
+            }
+        })
+    };
+
+    html! {
+        <dialog
+            ref={dialog_ref}
+            class="qr-dialog"
+            aria-label={t.qr_code}
+            onclose={props.on_close.reform(|_: Event| ())}
+            onkeydown={Callback::from(|e: KeyboardEvent| e.stop_propagation())}
+            onclick={on_backdrop_click}
+        >
+            <article>
+                <header>
+                    <button aria-label={t.close} rel="prev" onclick={on_close_click} />
+                    <strong>{ &props.title }</strong>
+                </header>
+                {
+                    match &*qr {
+                        Some(src) => html! {
+                            <img class="qr-code" src={src.clone()} alt={t.qr_code_alt} width={QR_SIZE.to_string()} height={QR_SIZE.to_string()} />
+                        },
+                        None => html! { <p>{ t.qr_code_failed }</p> },
+                    }
+                }
+                <p class="qr-link"><small>{ &props.url }</small></p>
+            </article>
+        </dialog>
+    }
+}
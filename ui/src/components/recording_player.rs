@@ -0,0 +1,55 @@
+use yew::prelude::*;
+use crate::i18n::use_messages;
+use crate::models::Attachments;
+
+/// Props for the RecordingPlayer component
+#[derive(Properties, PartialEq)]
+pub struct RecordingPlayerProps {
+    /// The song's attachments; nothing is rendered without a recording
+    pub attachments: Attachments,
+}
+
+/// RecordingPlayer component - a collapsible reference recording under the chart
+///
+/// Plays the song's attached YouTube video (embedded from youtube-nocookie.com)
+/// or, failing that, its audio file. The player is only created once the
+/// section is opened, so songs aren't slowed down by an embed nobody plays,
+/// and closing it stops playback.
+#[function_component(RecordingPlayer)]
+pub fn recording_player(props: &RecordingPlayerProps) -> Html {
+    let t = use_messages();
+    let open = use_state(|| false);
+
+    let on_toggle = {
+        let open = open.clone();
+        Callback::from(move |e: Event| {
+            let details: web_sys::HtmlDetailsElement = e.target_unchecked_into();
+            open.set(details.open());
+        })
+    };
+
+    let player = match (&props.attachments.youtube_id, &props.attachments.audio_url) {
+        (Some(id), _) => html! {
+            <iframe
+                class="recording-video"
+                src={format!("https://www.youtube-nocookie.com/embed/{}", id)}
+                title={t.reference_recording}
+                allow="encrypted-media; picture-in-picture"
+                allowfullscreen=true
+            />
+        },
+        (None, Some(url)) => html! {
+            <audio class="recording-audio" controls=true src={url.clone()} />
+        },
+        (None, None) => return html! {},
+    };
+
+    html! {
+        <details class="recording-player" ontoggle={on_toggle}>
+            <summary>{ t.reference_recording }</summary>
+            if *open {
+                { player }
+            }
+        </details>
+    }
+}
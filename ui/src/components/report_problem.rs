@@ -0,0 +1,126 @@
+use yew::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::api;
+use crate::components::use_toaster;
+use crate::i18n::use_messages;
+use crate::models::{CorrectionRequest, RealBookEntry};
+
+/// Props for the ReportProblem component
+#[derive(Properties, PartialEq)]
+pub struct ReportProblemProps {
+    /// The song being viewed (key the component by its id, so the form starts
+    /// over for each song)
+    pub entry: RealBookEntry,
+}
+
+/// ReportProblem component - a small "Report a problem" link under the chart
+///
+/// Opens a form prefilled with the song's title and pages; the reader fixes
+/// whichever is wrong, optionally says why, and sends only what changed to
+/// `POST /entries/<id>/corrections`, where it waits for a moderator.
+#[function_component(ReportProblem)]
+pub fn report_problem(props: &ReportProblemProps) -> Html {
+    let t = use_messages();
+    let toaster = use_toaster();
+    let open = use_state(|| false);
+    let title = use_state(|| props.entry.title.clone());
+    let page_s = use_state(|| props.entry.page_s.to_string());
+    let page_e = use_state(|| props.entry.page_e.to_string());
+    let comment = use_state(String::new);
+    let sending = use_state(|| false);
+
+    let on_toggle = {
+        let open = open.clone();
+        Callback::from(move |e: Event| {
+            let details: web_sys::HtmlDetailsElement = e.target_unchecked_into();
+            open.set(details.open());
+        })
+    };
+
+    let on_submit = {
+        let entry = props.entry.clone();
+        let (open, title, page_s, page_e, comment, sending) =
+            (open.clone(), title.clone(), page_s.clone(), page_e.clone(), comment.clone(), sending.clone());
+        let (unchanged, sent) = (t.correction_unchanged, t.correction_sent);
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            // Only what differs from the entry (the API checks the rest)
+            let page = |text: &str| text.trim().parse::<u32>().ok();
+            let correction = CorrectionRequest {
+                title: Some(title.trim().to_string()).filter(|title| !title.is_empty() && *title != entry.title),
+                page_s: page(&page_s).filter(|page| *page != entry.page_s),
+                page_e: page(&page_e).filter(|page| *page != entry.page_e),
+                comment: Some(comment.trim().to_string()).filter(|comment| !comment.is_empty()),
+            };
+            if correction.title.is_none() && correction.page_s.is_none() && correction.page_e.is_none() {
+                toaster.error(unchanged);
+                return;
+            }
+            sending.set(true);
+            let (toaster, open, comment, sending, id) =
+                (toaster.clone(), open.clone(), comment.clone(), sending.clone(), entry.id);
+            spawn_local(async move {
+                match api::submit_correction(id, &correction).await {
+                    Ok(()) => {
+                        toaster.success(sent);
+                        comment.set(String::new());
+                        open.set(false);
+                    }
+                    Err(e) => toaster.error(e.message),
+                }
+                sending.set(false);
+            });
+        })
+    };
+
+    let text_input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            state.set(input.value());
+        })
+    };
+
+    html! {
+        <details class="report-problem" open={*open} ontoggle={on_toggle}>
+            <summary>{ t.report_problem }</summary>
+            if *open {
+                <form onsubmit={on_submit}>
+                    <p><small>{ t.report_problem_hint }</small></p>
+                    <label>
+                        { t.correction_title }
+                        <input type="text" maxlength="200" value={(*title).clone()} oninput={text_input(&title)} />
+                    </label>
+                    <div class="grid">
+                        <label>
+                            { t.correction_first_page }
+                            <input type="number" min="1" value={(*page_s).clone()} oninput={text_input(&page_s)} />
+                        </label>
+                        <label>
+                            { t.correction_last_page }
+                            <input type="number" min="1" value={(*page_e).clone()} oninput={text_input(&page_e)} />
+                        </label>
+                    </div>
+                    <label>
+                        { t.correction_comment }
+                        <textarea
+                            rows="2"
+                            maxlength="1000"
+                            value={(*comment).clone()}
+                            oninput={{
+                                let comment = comment.clone();
+                                Callback::from(move |e: InputEvent| {
+                                    let input: web_sys::HtmlTextAreaElement = e.target_unchecked_into();
+                                    comment.set(input.value());
+                                })
+                            }}
+                        />
+                    </label>
+                    <button type="submit" class="secondary" disabled={*sending} aria-busy={sending.to_string()}>
+                        { t.send_correction }
+                    </button>
+                </form>
+            }
+        </details>
+    }
+}
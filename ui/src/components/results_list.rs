@@ -1,5 +1,60 @@
 use yew::prelude::*;
-use crate::models::{RealBookEntry, SearchResponse};
+use wasm_bindgen::{JsCast, closure::Closure};
+use web_sys::HtmlSelectElement;
+use crate::i18n::{self, use_messages};
+use crate::models::{RealBookEntry, SearchResponse, SortOrder};
+use crate::review::ReviewItem;
+
+/// Rows rendered beyond each edge of the visible ones, so fast scrolling
+/// doesn't show blank space before the next render
+const OVERSCAN: usize = 5;
+
+/// How far below the visible rows the next page starts loading, so it's
+/// usually there before the user scrolls to it
+const LOAD_AHEAD: &str = "0px 0px 400px 0px";
+
+/// Placeholder rows shown while the first results load
+const SKELETON_ROWS: usize = 6;
+
+/// Placeholder rows shown below the list while the next page loads
+const SKELETON_MORE_ROWS: usize = 2;
+
+/// Songs listed in the review queue on the placeholder
+const MAX_REVIEW_ITEMS: usize = 8;
+
+/// Recently viewed songs listed on the placeholder
+const MAX_RECENTLY_VIEWED: usize = 8;
+
+/// Grey stand-ins shaped like result rows (same fixed height, so nothing moves
+/// when the results replace them)
+fn skeleton_rows(count: usize) -> Html {
+    (0..count)
+        .map(|_| html! {
+            <div class="result-item skeleton-row" aria-hidden="true">
+                <div class="skeleton skeleton-line skeleton-title" />
+                <div class="skeleton skeleton-line skeleton-meta" />
+            </div>
+        })
+        .collect()
+}
+
+/// Element id of the result row at `index` (the list's active descendant)
+fn result_id(index: usize) -> String {
+    format!("result-{}", index)
+}
+
+/// Letters in the A-Z strip ('#' covers titles starting with a digit)
+const INDEX_LETTERS: &str = "#ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Range of rows to mount for a list of `row_height` rows scrolled to
+/// `scroll_top` showing `viewport_height` pixels
+fn visible_rows(scroll_top: f64, viewport_height: f64, row_height: f64, total: usize) -> (usize, usize) {
+    let first = (scroll_top / row_height).floor().max(0.0) as usize;
+    let shown = (viewport_height / row_height).ceil() as usize + 1;
+    let start = first.saturating_sub(OVERSCAN).min(total);
+    let end = (first + shown + OVERSCAN).min(total);
+    (start, end)
+}
 
 /// Props for the ResultsList component
 #[derive(Properties, PartialEq)]
@@ -11,13 +66,74 @@ pub struct ResultsListProps {
     /// Whether data is currently loading
     pub loading: bool,
 
+    /// Height of one result row plus the gap below it, in CSS pixels
+    /// (set by the density and text size; `--row-height` in index.html)
+    pub row_height: f64,
+
     /// Index of the currently selected result (via keyboard navigation)
     /// None means no result is selected via keyboard
     pub selected_index: Option<usize>,
 
+    /// Ids of the starred songs
+    pub favorite_ids: Vec<u32>,
+
+    /// Order the results are listed in
+    pub sort_order: SortOrder,
+
+    /// Volume the results are filtered to, if any
+    pub selected_volume: Option<u32>,
+
+    /// Index letter the results are filtered to, if any
+    pub initial: Option<char>,
+
     /// Callback fired when a user clicks on a result
     /// Passes the clicked entry to the parent component
     pub on_entry_click: Callback<RealBookEntry>,
+
+    /// Callback fired when a result's star is clicked
+    pub on_toggle_favorite: Callback<RealBookEntry>,
+
+    /// Callback fired when a user clicks a "did you mean" suggestion
+    /// Passes the suggested title to the parent component
+    pub on_suggestion_click: Callback<String>,
+
+    /// Callback fired when the user asks for the next page of results
+    pub on_load_more: Callback<()>,
+
+    /// Callback fired when a different sort order is picked
+    pub on_sort_change: Callback<SortOrder>,
+
+    /// Callback fired when a letter in the A-Z strip is clicked
+    /// Passes the letter, or None when the current one is clicked again
+    pub on_initial_select: Callback<Option<char>>,
+
+    /// Callback fired when a volume count is clicked
+    /// Passes the volume to filter to, or None when the current one is clicked again
+    pub on_volume_click: Callback<Option<u32>>,
+
+    /// Scroll offset to restore once the first results arrive (after a reload)
+    #[prop_or_default]
+    pub initial_scroll: Option<f64>,
+
+    /// Callback fired as the list scrolls, with its scroll offset in pixels
+    #[prop_or_default]
+    pub on_scroll_top: Callback<f64>,
+
+    /// Songs from the user's book due for practice, shown on the placeholder
+    #[prop_or_default]
+    pub review_queue: Vec<ReviewItem>,
+
+    /// Today's pick from the catalog, suggested on the placeholder
+    #[prop_or_default]
+    pub song_of_the_day: Option<RealBookEntry>,
+
+    /// Songs opened lately (newest first), shown on the placeholder
+    #[prop_or_default]
+    pub recently_viewed: Vec<RealBookEntry>,
+
+    /// Callback fired when the recently viewed list is cleared
+    #[prop_or_default]
+    pub on_clear_recently_viewed: Callback<()>,
 }
 
 /// ResultsList component - displays search results or a placeholder message
@@ -25,93 +141,446 @@ pub struct ResultsListProps {
 /// This component demonstrates conditional rendering in Yew:
 /// - If there are results, display them in a scrollable list
 /// - If there are no results, display a helpful placeholder message
+///
+/// Only the rows in view (plus a few either side) are mounted; spacers stand in
+/// for the rest, so long result lists stay cheap to re-render on every keystroke.
+/// Paged results load their next page as the list is scrolled near its end.
+/// Rows have a fixed height (`row_height`, from the appearance settings) so
+/// positions can be computed without measuring.
+///
+/// For assistive tech the list is a listbox of options (with their position
+/// in the full list, since only some are mounted), the keyboard-selected row is
+/// its active descendant, and a polite live region announces the result count.
 #[function_component(ResultsList)]
 pub fn results_list(props: &ResultsListProps) -> Html {
-    // Auto-scroll selected item into view when selection changes
+    let t = use_messages();
+    let list_ref = use_node_ref();
+    let total = props.results.as_ref().map_or(0, |response| response.results.len());
+
+    // Mounted row range; only re-renders when scrolling moves it
+    let row_height = props.row_height;
+    let rows = use_state_eq(|| visible_rows(0.0, 600.0, row_height, total));
+    let update_rows = {
+        let list_ref = list_ref.clone();
+        let rows = rows.clone();
+        move || {
+            if let Some(list) = list_ref.cast::<web_sys::Element>() {
+                rows.set(visible_rows(f64::from(list.scroll_top()), f64::from(list.client_height()), row_height, total));
+            }
+        }
+    };
+
+    // New results or row size: recompute the range for wherever the list is scrolled to
     {
-        let selected_index = props.selected_index;
-        use_effect_with(selected_index, move |sel_idx| {
-            if let Some(idx) = sel_idx
-                && let Some(document) = web_sys::window().and_then(|w| w.document())
-                && let Some(element) = document.query_selector(&format!(".result-item[data-index='{}']", idx)).ok().flatten() {
-                // Use "nearest" behavior - only scrolls if element is not visible
-                // This works smoothly for both up and down navigation
-                let options = web_sys::ScrollIntoViewOptions::new();
-                options.set_block(web_sys::ScrollLogicalPosition::Nearest);
-                options.set_behavior(web_sys::ScrollBehavior::Smooth);
-                let _ = element.scroll_into_view_with_scroll_into_view_options(&options);
+        let update_rows = update_rows.clone();
+        use_effect_with((total, row_height.to_bits()), move |_| {
+            update_rows();
+            || ()
+        });
+    }
+
+    // Put a restored session's list back where it was, once there's a list to scroll
+    let pending_scroll = use_mut_ref(|| props.initial_scroll);
+    {
+        let list_ref = list_ref.clone();
+        let pending_scroll = pending_scroll.clone();
+        use_effect_with(total, move |total| {
+            if *total > 0
+                && let Some(list) = list_ref.cast::<web_sys::Element>()
+                && let Some(top) = pending_scroll.borrow_mut().take() {
+                list.set_scroll_top(top as i32);
+            }
+            || ()
+        });
+    }
+
+    let on_scroll = {
+        let update_rows = update_rows.clone();
+        let list_ref = list_ref.clone();
+        let on_scroll_top = props.on_scroll_top.clone();
+        Callback::from(move |_: Event| {
+            update_rows();
+            if let Some(list) = list_ref.cast::<web_sys::Element>() {
+                on_scroll_top.emit(f64::from(list.scroll_top()));
+            }
+        })
+    };
+
+    // Scroll the selected row into view when the selection changes (the row may
+    // not be mounted, so this works from row positions rather than elements)
+    // Like "nearest": only scrolls when the row is out of view
+    {
+        let list_ref = list_ref.clone();
+        use_effect_with(props.selected_index, move |selected| {
+            if let Some(index) = *selected
+                && let Some(list) = list_ref.cast::<web_sys::Element>() {
+                let top = index as f64 * row_height;
+                let bottom = top + row_height;
+                let (view_top, view_height) = (f64::from(list.scroll_top()), f64::from(list.client_height()));
+                let target = if top < view_top {
+                    Some(top)
+                } else if bottom > view_top + view_height {
+                    Some(bottom - view_height)
+                } else {
+                    None
+                };
+                if let Some(target) = target {
+                    let options = web_sys::ScrollToOptions::new();
+                    options.set_top(target);
+                    options.set_behavior(web_sys::ScrollBehavior::Smooth);
+                    list.scroll_to_with_scroll_to_options(&options);
+                }
             }
             || ()
         });
     }
 
+    // Infinite scroll: load the next page when a sentinel after the last row
+    // comes near the visible part of the list (the "Load more" button stays as
+    // a fallback). Watched afresh after each page arrives, so a page too short
+    // to fill the list loads the next one straight away.
+    let sentinel_ref = use_node_ref();
+    {
+        let list_ref = list_ref.clone();
+        let sentinel_ref = sentinel_ref.clone();
+        let on_load_more = props.on_load_more.clone();
+        let has_more = props.results.as_ref().is_some_and(|response| response.has_more());
+        use_effect_with((total, has_more, props.loading), move |(_, has_more, loading)| {
+            let observer = (*has_more && !*loading)
+                .then(|| {
+                    let callback = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+                        let visible = entries
+                            .iter()
+                            .any(|entry| entry.unchecked_into::<web_sys::IntersectionObserverEntry>().is_intersecting());
+                        if visible {
+                            on_load_more.emit(());
+                        }
+                    });
+                    let options = web_sys::IntersectionObserverInit::new();
+                    options.set_root(list_ref.cast::<web_sys::Element>().as_ref());
+                    options.set_root_margin(LOAD_AHEAD);
+                    let observer =
+                        web_sys::IntersectionObserver::new_with_options(callback.as_ref().unchecked_ref(), &options).ok()?;
+                    observer.observe(&sentinel_ref.cast::<web_sys::Element>()?);
+                    Some((observer, callback))
+                })
+                .flatten();
+            move || {
+                if let Some((observer, _callback)) = observer {
+                    observer.disconnect();
+                }
+            }
+        });
+    }
+
+    let (start, end) = *rows;
+    let (start, end) = (start.min(total), end.min(total));
+
+    // Result count for screen readers, once the results are in
+    let announcement = match &props.results {
+        Some(_) if props.loading => String::new(),
+        Some(response) if response.total == 1 => t.result_count_one.to_string(),
+        Some(response) => i18n::fill(t.result_count, &[&response.total]),
+        None => String::new(),
+    };
+
     html! {
-        // Pico CSS styles <article> with aria-busy showing built-in loading spinner
-        <article aria-busy={props.loading.to_string()}>
-            {
-                if let Some(response) = &props.results {
-                    // We have results - display them
-                    html! {
-                        <>
-                            <header>
-                                <h2>{ format!("Results ({})", response.total) }</h2>
-                            </header>
-
-                            <div class="results-list">
-                                {
-                                    // Iterate over results and create a div for each
-                                    // enumerate() gives us the index along with each entry
-                                    for response.results.iter().enumerate().map(|(index, entry)| {
-                                        // Clone the entry so we can move it into the closure
-                                        let entry_clone = entry.clone();
-
-                                        // Check if this is the currently selected result (via keyboard)
-                                        let is_selected = props.selected_index == Some(index);
-
-                                        // Add 'selected' class if this result is highlighted
-                                        let class = if is_selected {
-                                            "result-item selected"
-                                        } else {
-                                            "result-item"
-                                        };
-
-                                        // Create a click handler for this specific result
-                                        let on_click = {
-                                            let callback = props.on_entry_click.clone();
-                                            let entry = entry_clone.clone();
-                                            // The move keyword captures entry by value
-                                            Callback::from(move |_| {
-                                                callback.emit(entry.clone());
+        <article>
+            <div class="results-layout">
+                <div class="results-main">
+                    // Kept in the DOM so screen readers notice its text change
+                    <p class="visually-hidden" role="status" aria-live="polite">{ announcement }</p>
+                    {
+                        if let Some(response) = &props.results {
+                            // We have results - display them
+                            html! {
+                                <>
+                                    <header class="results-header">
+                                        <div class="results-summary">
+                                            <h2>{ i18n::fill(t.results_heading, &[&response.total]) }</h2>
+                                            // Matches per volume; clicking one filters to it
+                                            if !response.volume_counts.is_empty() {
+                                                <span class="volume-counts">
+                                                    { "— " }
+                                                    {
+                                                        for response.volume_counts.iter().enumerate().map(|(index, info)| {
+                                                            let active = props.selected_volume == Some(info.volume);
+                                                            let volume = info.volume;
+                                                            let on_click = props
+                                                                .on_volume_click
+                                                                .reform(move |_: MouseEvent| (!active).then_some(volume));
+                                                            html! {
+                                                                <>
+                                                                    if index > 0 { { " · " } }
+                                                                    <button
+                                                                        class={classes!("volume-count", active.then_some("active"))}
+                                                                        aria-pressed={active.to_string()}
+                                                                        title={if active { t.show_all_volumes.to_string() } else { i18n::fill(t.only_volume, &[&volume]) }}
+                                                                        onclick={on_click}
+                                                                    >
+                                                                        { format!("V{}: {}", info.volume, info.count) }
+                                                                    </button>
+                                                                </>
+                                                            }
+                                                        })
+                                                    }
+                                                </span>
+                                            }
+                                        </div>
+                                        <select
+                                            class="sort-select"
+                                            aria-label={t.sort_results}
+                                            onchange={props.on_sort_change.reform(|e: Event| {
+                                                let select: HtmlSelectElement = e.target_unchecked_into();
+                                                SortOrder::from_key(&select.value()).unwrap_or_default()
+                                            })}
+                                        >
+                                            {
+                                                for SortOrder::ALL.iter().map(|&order| html! {
+                                                    <option value={order.key()} selected={props.sort_order == order}>
+                                                        { t.sort_label(order) }
+                                                    </option>
+                                                })
+                                            }
+                                        </select>
+                                    </header>
+
+                                    // Nothing matched - offer close titles instead
+                                    if !response.suggestions.is_empty() {
+                                        <p class="suggestions">
+                                            { t.did_you_mean }
+                                            {
+                                                for response.suggestions.iter().map(|title| {
+                                                    let on_click = {
+                                                        let callback = props.on_suggestion_click.clone();
+                                                        let title = title.clone();
+                                                        Callback::from(move |_| callback.emit(title.clone()))
+                                                    };
+                                                    html! {
+                                                        <button class="chip secondary outline" onclick={on_click}>
+                                                            { format!("{}?", title) }
+                                                        </button>
+                                                    }
+                                                })
+                                            }
+                                        </p>
+                                    }
+
+                                    <div
+                                        class="results-list"
+                                        ref={list_ref.clone()}
+                                        onscroll={on_scroll}
+                                        role="listbox"
+                                        tabindex="0"
+                                        aria-label={t.results_label}
+                                        aria-activedescendant={props.selected_index.filter(|index| (start..end).contains(index)).map(result_id)}
+                                    >
+                                        // Stands in for the unmounted rows above the window
+                                        <div role="presentation" style={format!("height: {}px", start as f64 * row_height)} />
+                                        {
+                                            // Iterate over the mounted results and create a div for each
+                                            // enumerate() gives us the index along with each entry
+                                            for response.results.iter().enumerate().skip(start).take(end - start).map(|(index, entry)| {
+                                                // Clone the entry so we can move it into the closure
+                                                let entry_clone = entry.clone();
+
+                                                // Check if this is the currently selected result (via keyboard)
+                                                let is_selected = props.selected_index == Some(index);
+
+                                                // Add 'selected' class if this result is highlighted
+                                                let class = if is_selected {
+                                                    "result-item selected"
+                                                } else {
+                                                    "result-item"
+                                                };
+
+                                                // Create a click handler for this specific result
+                                                let on_click = {
+                                                    let callback = props.on_entry_click.clone();
+                                                    let entry = entry_clone.clone();
+                                                    // The move keyword captures entry by value
+                                                    Callback::from(move |_| {
+                                                        callback.emit(entry.clone());
+                                                    })
+                                                };
+
+                                                // Star toggle (doesn't open the song)
+                                                let starred = props.favorite_ids.contains(&entry.id);
+                                                let on_star = {
+                                                    let callback = props.on_toggle_favorite.clone();
+                                                    let entry = entry_clone.clone();
+                                                    Callback::from(move |e: MouseEvent| {
+                                                        e.stop_propagation();
+                                                        callback.emit(entry.clone());
+                                                    })
+                                                };
+
+                                                html! {
+                                                    <div
+                                                        id={result_id(index)}
+                                                        {class}
+                                                        onclick={on_click}
+                                                        data-index={index.to_string()}
+                                                        role="option"
+                                                        aria-selected={is_selected.to_string()}
+                                                        aria-posinset={(index + 1).to_string()}
+                                                        aria-setsize={response.total.to_string()}
+                                                    >
+                                                        <button
+                                                            class={classes!("star-toggle", starred.then_some("starred"))}
+                                                            onclick={on_star}
+                                                            aria-pressed={starred.to_string()}
+                                                            aria-label={if starred { t.unstar } else { t.star }}
+                                                        >
+                                                            { if starred { "★" } else { "☆" } }
+                                                        </button>
+
+                                                        // Title in bold
+                                                        <div class="result-title">
+                                                            { &entry.title }
+                                                        </div>
+
+                                                        // Volume and page info in smaller, muted text
+                                                        <div class="result-meta">
+                                                            { i18n::fill(t.result_meta, &[&entry.volume, &entry.page_range()]) }
+                                                        </div>
+                                                    </div>
+                                                }
                                             })
-                                        };
-
-                                        html! {
-                                            <div {class} onclick={on_click} data-index={index.to_string()}>
-                                                // Title in bold
-                                                <div class="result-title">
-                                                    { &entry.title }
-                                                </div>
-
-                                                // Volume and page info in smaller, muted text
-                                                <div class="result-meta">
-                                                    { format!("Vol. {} | Pages {}", entry.volume, entry.page_range()) }
-                                                </div>
-                                            </div>
                                         }
-                                    })
-                                }
-                            </div>
-                        </>
+                                        // ... and below it
+                                        <div role="presentation" style={format!("height: {}px", (total - end) as f64 * row_height)} />
+                                        <div ref={sentinel_ref} aria-hidden="true" />
+
+                                        // Paginated results (browse mode) - the next page on its way, or a button to fetch it
+                                        if props.loading && response.has_more() {
+                                            { skeleton_rows(SKELETON_MORE_ROWS) }
+                                        } else if response.has_more() {
+                                            <button class="secondary outline load-more" onclick={props.on_load_more.reform(|_| ())}>
+                                                { i18n::fill(t.load_more, &[&response.results.len(), &response.total]) }
+                                            </button>
+                                        }
+                                    </div>
+                                </>
+                            }
+                        } else if props.loading {
+                            // First results on their way
+                            html! {
+                                <div class="results-list" role="status" aria-label={t.loading_results}>
+                                    { skeleton_rows(SKELETON_ROWS) }
+                                </div>
+                            }
+                        } else {
+                            // No results yet - show placeholder (and the songs due for review)
+                            html! {
+                                <div class="placeholder">
+                                    <p>{ t.results_placeholder }</p>
+                                    if let Some(entry) = &props.song_of_the_day {
+                                        <p class="song-of-the-day">
+                                            <small>{ t.song_of_the_day }</small>
+                                            <a href="#" onclick={props.on_entry_click.reform({
+                                                let entry = entry.clone();
+                                                move |e: MouseEvent| {
+                                                    e.prevent_default();
+                                                    entry.clone()
+                                                }
+                                            })}>
+                                                { &entry.title }
+                                            </a>
+                                            <small>{ i18n::fill(t.song_meta, &[&entry.volume, &entry.page_range()]) }</small>
+                                        </p>
+                                    }
+                                    if !props.review_queue.is_empty() {
+                                        <section class="review-queue">
+                                            <h3>{ t.review_queue }</h3>
+                                            <p><small>{ t.review_queue_hint }</small></p>
+                                            <ul>
+                                                {
+                                                    for props.review_queue.iter().take(MAX_REVIEW_ITEMS).map(|item| html! {
+                                                        <li>
+                                                            <a href="#" onclick={props.on_entry_click.reform({
+                                                                let entry = item.entry.clone();
+                                                                move |e: MouseEvent| {
+                                                                    e.prevent_default();
+                                                                    entry.clone()
+                                                                }
+                                                            })}>
+                                                                { &item.entry.title }
+                                                            </a>
+                                                            <small>
+                                                                {
+                                                                    match item.days_since {
+                                                                        None => t.review_never.to_string(),
+                                                                        Some(0 | 1) => t.review_yesterday.to_string(),
+                                                                        Some(days) => i18n::fill(t.review_days_ago, &[&days]),
+                                                                    }
+                                                                }
+                                                            </small>
+                                                        </li>
+                                                    })
+                                                }
+                                            </ul>
+                                            if props.review_queue.len() > MAX_REVIEW_ITEMS {
+                                                <p><small>{ i18n::fill(t.review_more, &[&(props.review_queue.len() - MAX_REVIEW_ITEMS)]) }</small></p>
+                                            }
+                                        </section>
+                                    }
+                                    if !props.recently_viewed.is_empty() {
+                                        <section class="recently-viewed">
+                                            <h3>
+                                                { t.recently_viewed }
+                                                <button
+                                                    class="secondary outline"
+                                                    onclick={props.on_clear_recently_viewed.reform(|_: MouseEvent| ())}
+                                                >
+                                                    { t.clear_recently_viewed }
+                                                </button>
+                                            </h3>
+                                            <ul>
+                                                {
+                                                    for props.recently_viewed.iter().take(MAX_RECENTLY_VIEWED).map(|entry| html! {
+                                                        <li>
+                                                            <a href="#" onclick={props.on_entry_click.reform({
+                                                                let entry = entry.clone();
+                                                                move |e: MouseEvent| {
+                                                                    e.prevent_default();
+                                                                    entry.clone()
+                                                                }
+                                                            })}>
+                                                                { &entry.title }
+                                                            </a>
+                                                            <small>{ i18n::fill(t.song_meta, &[&entry.volume, &entry.page_range()]) }</small>
+                                                        </li>
+                                                    })
+                                                }
+                                            </ul>
+                                        </section>
+                                    }
+                                </div>
+                            }
+                        }
                     }
-                } else {
-                    // No results yet - show placeholder
-                    html! {
-                        <div class="placeholder">
-                            <p>{ "Search for a song or click Random to get started" }</p>
-                        </div>
+                </div>
+
+                // A-Z strip: lists the titles filed under a letter, like a phone's contacts
+                <nav class="alphabet-bar" aria-label={t.titles_by_letter}>
+                    {
+                        for INDEX_LETTERS.chars().map(|letter| {
+                            let active = props.initial == Some(letter);
+                            html! {
+                                <button
+                                    class={classes!(active.then_some("active"))}
+                                    aria-pressed={active.to_string()}
+                                    title={if letter == '#' { t.titles_number.to_string() } else { i18n::fill(t.titles_letter, &[&letter]) }}
+                                    onclick={props.on_initial_select.reform(move |_: MouseEvent| (!active).then_some(letter))}
+                                >
+                                    { letter }
+                                </button>
+                            }
+                        })
                     }
-                }
-            }
+                </nav>
+            </div>
         </article>
     }
 }
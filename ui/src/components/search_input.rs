@@ -1,5 +1,14 @@
 use yew::prelude::*;
-use web_sys::{HtmlInputElement, HtmlSelectElement};
+use web_sys::{HtmlDetailsElement, HtmlInputElement, HtmlSelectElement};
+use crate::i18n::{self, use_messages};
+use crate::repertoire::RepertoireFilter;
+use crate::saved_searches::SavedSearch;
+
+/// Element id of the search box (focused by the global "/" shortcut)
+pub const SEARCH_INPUT_ID: &str = "search-input";
+
+/// Element id of the autocomplete list
+const COMPLETIONS_ID: &str = "search-completions";
 
 /// Props for the SearchInput component
 ///
@@ -13,14 +22,44 @@ pub struct SearchInputProps {
     /// Current search query text
     pub query: String,
 
+    /// Earlier queries the current query is refining
+    pub refine_terms: Vec<String>,
+
     /// Currently selected volume (None means "All Volumes")
     pub selected_volume: Option<u32>,
 
+    /// Whether browse mode is on (empty query lists the selected volume)
+    pub browse_mode: bool,
+
+    /// Page filter text: a page ("134") or a span ("100-120")
+    pub page_input: String,
+
     /// Whether the Random button is loading
     pub random_loading: bool,
 
-    /// Error message to display (None means no error)
-    pub error: Option<String>,
+    /// Whether results are limited to starred songs
+    pub favorites_only: bool,
+
+    /// Tags in use, offered by the tag filter
+    pub tags: Vec<String>,
+
+    /// Tag the results are limited to, if any
+    pub tag_filter: Option<String>,
+
+    /// Part of the user's repertoire the results are limited to, if any
+    pub repertoire_filter: Option<RepertoireFilter>,
+
+    /// Name of the setlist shown as the results, if one is open
+    pub setlist_name: Option<String>,
+
+    /// Titles completing the query, shown in a dropdown under the search box
+    pub completions: Vec<String>,
+
+    /// Recently submitted queries, shown in the dropdown while the box is empty
+    pub recent_searches: Vec<String>,
+
+    /// Named searches, listed in the "Saved searches" menu
+    pub saved_searches: Vec<SavedSearch>,
 
     /// Callback fired when the query input changes
     /// Takes the new query string as a parameter
@@ -30,14 +69,58 @@ pub struct SearchInputProps {
     /// Takes the new volume (or None for "All Volumes") as a parameter
     pub on_volume_change: Callback<Option<u32>>,
 
+    /// Callback fired when the browse mode switch is toggled
+    pub on_browse_toggle: Callback<bool>,
+
+    /// Callback fired when the page filter input changes
+    /// Takes the raw input text as a parameter
+    pub on_page_change: Callback<String>,
+
+    /// Callback fired when the Refine button is clicked
+    /// (pins the current query and searches within its results)
+    pub on_refine: Callback<()>,
+
+    /// Callback fired when a refinement chip is removed
+    /// Takes the index of the removed term as a parameter
+    pub on_remove_refine: Callback<usize>,
+
     /// Callback fired when the Random button is clicked
     pub on_random: Callback<()>,
 
+    /// Callback fired when the Favorites filter chip is toggled
+    pub on_favorites_toggle: Callback<bool>,
+
+    /// Callback fired when a tag is picked in the tag filter (None: all tags)
+    pub on_tag_filter: Callback<Option<String>>,
+
+    /// Callback fired when a repertoire filter is picked (None: whole catalog)
+    pub on_repertoire_filter: Callback<Option<RepertoireFilter>>,
+
+    /// Callback fired when the open setlist's chip is closed
+    pub on_close_setlist: Callback<()>,
+
     /// Callback for arrow key navigation (up/down)
     pub on_navigate: Callback<String>,
 
     /// Callback for Enter key (to view selected result)
     pub on_enter: Callback<()>,
+
+    /// Callback fired when an autocomplete title or a recent search is picked
+    /// Takes the text as a parameter
+    pub on_completion_select: Callback<String>,
+
+    /// Callback fired when "Clear history" is clicked in the recent searches
+    pub on_clear_recent: Callback<()>,
+
+    /// Callback fired when "Save current search…" is picked
+    pub on_save_search: Callback<()>,
+
+    /// Callback fired when a saved search is picked to run again
+    pub on_run_saved_search: Callback<SavedSearch>,
+
+    /// Callback fired when a saved search is deleted
+    /// Takes its index in `saved_searches` as a parameter
+    pub on_delete_saved_search: Callback<usize>,
 }
 
 /// SearchInput component - handles search query, volume filter, and action buttons
@@ -47,6 +130,7 @@ pub struct SearchInputProps {
 /// of changes via callbacks. This is the React pattern of "lifting state up".
 #[function_component(SearchInput)]
 pub fn search_input(props: &SearchInputProps) -> Html {
+    let t = use_messages();
     // Create a ref to the input element so we can focus it
     let input_ref = use_node_ref();
 
@@ -75,14 +159,79 @@ pub fn search_input(props: &SearchInputProps) -> Html {
         })
     };
 
+    // Dropdown state: whether the search box has focus, which option the arrow
+    // keys are on, and the query the list was dismissed at (Escape, or picking
+    // an option) so it stays closed until the query changes or the box is refocused
+    let focused = use_state(|| false);
+    let highlighted = use_state(|| Option::<usize>::None);
+    let dismissed_for = use_state(|| Option::<String>::None);
+
+    // The dropdown offers recent searches while the box is empty, and
+    // autocomplete titles once something is typed
+    let showing_recent = props.query.trim().is_empty();
+    let options = if showing_recent { props.recent_searches.clone() } else { props.completions.clone() };
+
+    // Start over at the top of the list whenever it changes
+    {
+        let highlighted = highlighted.clone();
+        use_effect_with(options.clone(), move |_| {
+            highlighted.set(None);
+            || ()
+        });
+    }
+
+    let dropdown_open = *focused
+        && !options.is_empty()
+        && dismissed_for.as_deref() != Some(props.query.as_str());
+
+    let on_completion_select = {
+        let highlighted = highlighted.clone();
+        let dismissed_for = dismissed_for.clone();
+        let callback = props.on_completion_select.clone();
+        Callback::from(move |title: String| {
+            highlighted.set(None);
+            dismissed_for.set(Some(title.clone()));
+            callback.emit(title);
+        })
+    };
+
     // Handle keyboard events in the input field
     // Arrow keys navigate results, Enter views selected result
+    // While the dropdown is open, ↓ moves into it (↑ past its top returns to
+    // the results), Enter picks the highlighted option, and Escape closes the
+    // list without reaching the global Escape (clear the query)
     let on_keydown = {
         let navigate = props.on_navigate.clone();
         let enter = props.on_enter.clone();
+        let highlighted = highlighted.clone();
+        let dismissed_for = dismissed_for.clone();
+        let on_completion_select = on_completion_select.clone();
+        let options = options.clone();
+        let query = props.query.clone();
         Callback::from(move |e: KeyboardEvent| {
             let key = e.key();
             match key.as_str() {
+                "ArrowUp" if dropdown_open && highlighted.is_some() => {
+                    e.prevent_default();
+                    highlighted.set(highlighted.and_then(|index| index.checked_sub(1)));
+                }
+                "ArrowDown" if dropdown_open => {
+                    e.prevent_default();
+                    let last = options.len() - 1;
+                    highlighted.set(Some(highlighted.map_or(0, |index| (index + 1).min(last))));
+                }
+                "Enter" if dropdown_open && highlighted.is_some() => {
+                    e.prevent_default();
+                    if let Some(title) = highlighted.and_then(|index| options.get(index)) {
+                        on_completion_select.emit(title.clone());
+                    }
+                }
+                "Escape" if dropdown_open => {
+                    e.prevent_default();
+                    e.stop_propagation();
+                    highlighted.set(None);
+                    dismissed_for.set(Some(query.clone()));
+                }
                 "ArrowUp" => {
                     e.prevent_default(); // Prevent cursor from moving in input
                     navigate.emit("up".to_string());
@@ -120,6 +269,33 @@ pub fn search_input(props: &SearchInputProps) -> Html {
         })
     };
 
+    // Handle page filter input changes
+    // The raw text is kept so partial input like "100-" isn't wiped
+    let on_page_input = {
+        let callback = props.on_page_change.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            callback.emit(input.value());
+        })
+    };
+
+    // Handle browse mode switch
+    let on_browse_change = {
+        let callback = props.on_browse_toggle.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            callback.emit(input.checked());
+        })
+    };
+
+    // Handle Refine button click
+    let on_refine_click = {
+        let callback = props.on_refine.clone();
+        Callback::from(move |_| {
+            callback.emit(());
+        })
+    };
+
     // Handle Random button click
     let on_random_click = {
         let callback = props.on_random.clone();
@@ -128,6 +304,21 @@ pub fn search_input(props: &SearchInputProps) -> Html {
         })
     };
 
+    // Saved searches menu (a Pico dropdown), closed again after picking an item
+    let saved_menu_ref = use_node_ref();
+    let close_saved_menu = {
+        let saved_menu_ref = saved_menu_ref.clone();
+        move || {
+            if let Some(menu) = saved_menu_ref.cast::<HtmlDetailsElement>() {
+                menu.set_open(false);
+            }
+        }
+    };
+    let can_save = !props.query.trim().is_empty()
+        || !props.refine_terms.is_empty()
+        || !props.page_input.trim().is_empty()
+        || props.selected_volume.is_some();
+
     html! {
         // Pico CSS automatically styles <section> elements nicely with padding/margins
         <section>
@@ -136,52 +327,282 @@ pub fn search_input(props: &SearchInputProps) -> Html {
                 // The "value" prop makes this a controlled input
                 // Search happens automatically as you type
                 // Arrow keys and Enter work even when focused in this input
+                // Autocomplete titles (recent searches while it's empty) drop
+                // down under it; mousedown is cancelled
+                // on them so a click doesn't blur the box (closing the list) first
+                <div class="search-box">
+                    <input
+                        ref={input_ref}
+                        id={SEARCH_INPUT_ID}
+                        type="text"
+                        placeholder={t.search_placeholder}
+                        role="combobox"
+                        aria-autocomplete="list"
+                        aria-expanded={dropdown_open.to_string()}
+                        aria-controls={COMPLETIONS_ID}
+                        aria-activedescendant={highlighted.map(|index| format!("{}-{}", COMPLETIONS_ID, index))}
+                        value={props.query.clone()}
+                        oninput={on_input}
+                        onkeydown={on_keydown}
+                        onfocus={{
+                            let focused = focused.clone();
+                            let dismissed_for = dismissed_for.clone();
+                            Callback::from(move |_: FocusEvent| {
+                                focused.set(true);
+                                dismissed_for.set(None);
+                            })
+                        }}
+                        onblur={{
+                            let focused = focused.clone();
+                            Callback::from(move |_: FocusEvent| focused.set(false))
+                        }}
+                    />
+                    if dropdown_open {
+                        <ul
+                            id={COMPLETIONS_ID}
+                            class="completions"
+                            role="listbox"
+                            aria-label={if showing_recent { t.recent_searches } else { t.suggestions }}
+                        >
+                            {
+                                for options.iter().enumerate().map(|(index, title)| {
+                                    let selected = *highlighted == Some(index);
+                                    html! {
+                                        <li
+                                            id={format!("{}-{}", COMPLETIONS_ID, index)}
+                                            role="option"
+                                            aria-selected={selected.to_string()}
+                                            class={classes!(selected.then_some("highlighted"))}
+                                            onmousedown={Callback::from(|e: MouseEvent| e.prevent_default())}
+                                            onclick={on_completion_select.reform({
+                                                let title = title.clone();
+                                                move |_: MouseEvent| title.clone()
+                                            })}
+                                        >
+                                            if showing_recent {
+                                                <span class="recent-icon" aria-hidden="true">{ "↺ " }</span>
+                                            }
+                                            { title }
+                                        </li>
+                                    }
+                                })
+                            }
+                            if showing_recent {
+                                <li
+                                    class="completions-action"
+                                    onmousedown={Callback::from(|e: MouseEvent| e.prevent_default())}
+                                    onclick={props.on_clear_recent.reform(|_: MouseEvent| ())}
+                                >
+                                    { t.clear_history }
+                                </li>
+                            }
+                        </ul>
+                    }
+                </div>
+
+                // Page filter - lists every chart covering a page or page span
                 <input
-                    ref={input_ref}
                     type="text"
-                    placeholder="Type to search... (↑↓ navigate, Enter to view)"
-                    value={props.query.clone()}
-                    oninput={on_input}
-                    onkeydown={on_keydown}
+                    inputmode="numeric"
+                    class="page-input"
+                    placeholder={t.page_placeholder}
+                    aria-label={t.page_label}
+                    value={props.page_input.clone()}
+                    oninput={on_page_input}
                 />
 
                 // Volume filter dropdown
                 <select onchange={on_change}>
                     <option value="" selected={props.selected_volume.is_none()}>
-                        { "All Volumes" }
+                        { t.all_volumes }
                     </option>
                     <option value="1" selected={props.selected_volume == Some(1)}>
-                        { "Volume 1" }
+                        { i18n::fill(t.volume_option, &[&1]) }
                     </option>
                     <option value="2" selected={props.selected_volume == Some(2)}>
-                        { "Volume 2" }
+                        { i18n::fill(t.volume_option, &[&2]) }
                     </option>
                     <option value="3" selected={props.selected_volume == Some(3)}>
-                        { "Volume 3" }
+                        { i18n::fill(t.volume_option, &[&3]) }
                     </option>
                 </select>
 
+                // Browse mode - list the whole selected volume when the query is empty
+                <label class="browse-toggle" title={t.browse_title}>
+                    <input
+                        type="checkbox"
+                        role="switch"
+                        checked={props.browse_mode}
+                        disabled={props.selected_volume.is_none()}
+                        onchange={on_browse_change}
+                    />
+                    { t.browse }
+                </label>
+
+                // Refine button - search within the current results
+                <button
+                    class="secondary"
+                    onclick={on_refine_click}
+                    disabled={props.query.trim().is_empty()}
+                    title={t.refine_title}
+                >
+                    { t.refine }
+                </button>
+
                 // Random button - disabled during loading
                 <button
                     onclick={on_random_click}
                     disabled={props.random_loading}
                     aria-busy={props.random_loading.to_string()}
+                    title={match props.selected_volume {
+                        Some(volume) => i18n::fill(t.random_volume_title, &[&volume]),
+                        None => t.random_title.to_string(),
+                    }}
                 >
-                    { "🎲 Random" }
+                    { t.random }
                 </button>
             </div>
 
-            // Display error message if present
-            // Pico CSS styles <mark> elements for emphasis/alerts
-            {if let Some(error_msg) = &props.error {
-                html! {
-                    <mark style="background-color: var(--pico-del-color); padding: var(--pico-spacing);">
-                        { error_msg }
-                    </mark>
+            // Filter chips
+            <div class="filter-chips">
+                <button
+                    class={classes!("chip", "secondary", (!props.favorites_only).then_some("outline"))}
+                    aria-pressed={props.favorites_only.to_string()}
+                    onclick={props.on_favorites_toggle.reform({
+                        let enabled = !props.favorites_only;
+                        move |_: MouseEvent| enabled
+                    })}
+                    title={t.favorites_title}
+                >
+                    { t.favorites }
+                </button>
+                if !props.tags.is_empty() || props.tag_filter.is_some() {
+                    <select
+                        class="chip filter-select"
+                        aria-label={t.tag_filter}
+                        onchange={props.on_tag_filter.reform(|e: Event| {
+                            let select: HtmlSelectElement = e.target_unchecked_into();
+                            Some(select.value()).filter(|tag| !tag.is_empty())
+                        })}
+                    >
+                        <option value="" selected={props.tag_filter.is_none()}>{ t.all_tags_option }</option>
+                        {
+                            for props.tags.iter().map(|tag| html! {
+                                <option value={tag.clone()} selected={props.tag_filter.as_ref() == Some(tag)}>
+                                    { format!("#{}", tag) }
+                                </option>
+                            })
+                        }
+                    </select>
                 }
-            } else {
-                html! {}
-            }}
+                <select
+                    class="chip filter-select"
+                    aria-label={t.repertoire_filter}
+                    onchange={props.on_repertoire_filter.reform(|e: Event| {
+                        let select: HtmlSelectElement = e.target_unchecked_into();
+                        RepertoireFilter::from_value(&select.value())
+                    })}
+                >
+                    <option value="" selected={props.repertoire_filter.is_none()}>{ t.whole_catalog_option }</option>
+                    {
+                        for RepertoireFilter::all().into_iter().map(|filter| html! {
+                            <option value={filter.value()} selected={props.repertoire_filter == Some(filter)}>
+                                {
+                                    match filter {
+                                        RepertoireFilter::Book => t.my_book_option.to_string(),
+                                        RepertoireFilter::Status(status) => t.status_label(status).to_string(),
+                                        RepertoireFilter::Rating(stars) => i18n::fill(t.rating_filter_option, &[&stars]),
+                                    }
+                                }
+                            </option>
+                        })
+                    }
+                </select>
+                <details class="dropdown saved-searches" ref={saved_menu_ref}>
+                    <summary role="button" class="chip secondary outline">{ t.saved_searches }</summary>
+                    <ul>
+                        if props.saved_searches.is_empty() {
+                            <li><small>{ t.no_saved_searches }</small></li>
+                        }
+                        {
+                            for props.saved_searches.iter().enumerate().map(|(index, saved)| {
+                                let on_run = {
+                                    let saved = saved.clone();
+                                    let close = close_saved_menu.clone();
+                                    let callback = props.on_run_saved_search.clone();
+                                    Callback::from(move |e: MouseEvent| {
+                                        e.prevent_default();
+                                        close();
+                                        callback.emit(saved.clone());
+                                    })
+                                };
+                                html! {
+                                    <li class="saved-search">
+                                        <a href="#" onclick={on_run} title={saved.summary()}>
+                                            { &saved.name }
+                                            <small>{ saved.summary() }</small>
+                                        </a>
+                                        <button
+                                            class="secondary outline"
+                                            onclick={props.on_delete_saved_search.reform(move |_: MouseEvent| index)}
+                                            aria-label={i18n::fill(t.delete_saved_search, &[&saved.name])}
+                                        >
+                                            { "×" }
+                                        </button>
+                                    </li>
+                                }
+                            })
+                        }
+                        if can_save {
+                            <li>
+                                <a
+                                    href="#"
+                                    onclick={{
+                                        let close = close_saved_menu.clone();
+                                        let callback = props.on_save_search.clone();
+                                        Callback::from(move |e: MouseEvent| {
+                                            e.prevent_default();
+                                            close();
+                                            callback.emit(());
+                                        })
+                                    }}
+                                >
+                                    { t.save_search }
+                                </a>
+                            </li>
+                        }
+                    </ul>
+                </details>
+                if let Some(name) = &props.setlist_name {
+                    <button
+                        class="chip secondary"
+                        onclick={props.on_close_setlist.reform(|_: MouseEvent| ())}
+                        title={t.close_setlist_title}
+                    >
+                        { i18n::fill(t.setlist_chip, &[name]) }
+                    </button>
+                }
+            </div>
+
+            // Active refinements, each removable
+            if !props.refine_terms.is_empty() {
+                <div class="refine-chips">
+                    <small>{ t.within }</small>
+                    {
+                        for props.refine_terms.iter().enumerate().map(|(index, term)| {
+                            let on_remove = {
+                                let callback = props.on_remove_refine.clone();
+                                Callback::from(move |_| callback.emit(index))
+                            };
+                            html! {
+                                <button class="chip secondary outline" onclick={on_remove} title={t.remove_refinement}>
+                                    { format!("{} ✕", term) }
+                                </button>
+                            }
+                        })
+                    }
+                </div>
+            }
         </section>
     }
 }
@@ -0,0 +1,259 @@
+use yew::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::models::RealBookEntry;
+use crate::setlists::{self, Setlist};
+use crate::{api, utils};
+
+/// Props for the SetlistPanel component
+#[derive(Properties, PartialEq)]
+pub struct SetlistPanelProps {
+    /// Saved setlists
+    pub setlists: Vec<Setlist>,
+
+    /// Callback with the updated setlists after any change (created, imported,
+    /// reordered, or deleted)
+    pub on_change: Callback<Vec<Setlist>>,
+
+    /// Callback to show a setlist's songs as the results (by setlist id)
+    pub on_open: Callback<String>,
+
+    /// Random practice set waiting to be kept or discarded, if one was dealt
+    pub dealt_set: Option<Vec<RealBookEntry>>,
+
+    /// Whether a set (or a replacement song) is being dealt
+    pub dealing: bool,
+
+    /// Callback to deal a fresh random set
+    pub on_deal: Callback<()>,
+
+    /// Callback to swap one dealt song for another (by position in the set)
+    pub on_reroll: Callback<usize>,
+
+    /// Callback to keep the dealt set as a setlist
+    pub on_keep_dealt: Callback<()>,
+
+    /// Callback to throw the dealt set away
+    pub on_discard_dealt: Callback<()>,
+
+    /// Callback to open one song's chart
+    pub on_song_click: Callback<RealBookEntry>,
+
+    /// Callback to report a failed import or export
+    pub on_error: Callback<String>,
+}
+
+/// SetlistPanel component - collapsible panel for managing setlists
+///
+/// Each setlist can be opened as the result list, reordered, exported as a
+/// JSON file for bandmates, or downloaded as a printable one-page PDF index.
+/// "Import" reads a file exported from another browser. "Deal a set" picks a
+/// few random songs for practice; any of them can be swapped for another
+/// before the set is kept as a setlist or thrown away.
+#[function_component(SetlistPanel)]
+pub fn setlist_panel(props: &SetlistPanelProps) -> Html {
+    let on_new = {
+        let setlists = props.setlists.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(name) = utils::prompt_text("Setlist name") {
+                let mut setlists = setlists.clone();
+                setlists.push(setlists::create(&name));
+                on_change.emit(setlists);
+            }
+        })
+    };
+
+    let on_import = {
+        let setlists = props.setlists.clone();
+        let on_change = props.on_change.clone();
+        let on_error = props.on_error.clone();
+        Callback::from(move |e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            // Allow picking the same file again later
+            input.set_value("");
+            let setlists = setlists.clone();
+            let on_change = on_change.clone();
+            let on_error = on_error.clone();
+            spawn_local(async move {
+                match setlists::read_file(file).await.and_then(|contents| setlists::import_json(&contents)) {
+                    Ok(setlist) => {
+                        let mut setlists = setlists;
+                        setlists.push(setlist);
+                        on_change.emit(setlists);
+                    }
+                    Err(message) => on_error.emit(message),
+                }
+            });
+        })
+    };
+
+    let on_delete = |index: usize| {
+        let setlists = props.setlists.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut setlists = setlists.clone();
+            setlists.remove(index);
+            on_change.emit(setlists);
+        })
+    };
+
+    html! {
+        <details class="setlist-panel">
+            <summary>{ format!("Setlists ({})", props.setlists.len()) }</summary>
+            <article>
+                <div class="setlist-actions">
+                    <button class="secondary" onclick={on_new}>{ "New setlist" }</button>
+                    <label role="button" class="secondary outline">
+                        { "Import…" }
+                        <input type="file" accept=".json,application/json" onchange={on_import} hidden=true />
+                    </label>
+                    <button
+                        class="secondary outline"
+                        onclick={props.on_deal.reform(|_: MouseEvent| ())}
+                        disabled={props.dealing}
+                        aria-busy={(props.dealing && props.dealt_set.is_none()).to_string()}
+                        title="Pick a few random songs from the current volume to practice"
+                    >
+                        { "🎲 Deal a set" }
+                    </button>
+                </div>
+
+                if let Some(songs) = &props.dealt_set {
+                    <section class="dealt-set" aria-busy={props.dealing.to_string()}>
+                        <strong>{ "Dealt set" }</strong>
+                        <ol class="setlist-songs">
+                            {
+                                for songs.iter().enumerate().map(|(position, song)| html! {
+                                    <li>
+                                        <a href="#" onclick={props.on_song_click.reform({
+                                            let song = song.clone();
+                                            move |e: MouseEvent| {
+                                                e.prevent_default();
+                                                song.clone()
+                                            }
+                                        })}>
+                                            { format!("{} (Vol. {}, p. {})", song.title, song.volume, song.page_range()) }
+                                        </a>
+                                        <button
+                                            class="secondary outline"
+                                            disabled={props.dealing}
+                                            onclick={props.on_reroll.reform(move |_: MouseEvent| position)}
+                                            aria-label="Deal a different song"
+                                        >
+                                            { "↻" }
+                                        </button>
+                                    </li>
+                                })
+                            }
+                        </ol>
+                        <div class="setlist-actions">
+                            <button onclick={props.on_keep_dealt.reform(|_: MouseEvent| ())}>{ "Keep as setlist…" }</button>
+                            <button class="secondary outline" disabled={props.dealing} onclick={props.on_deal.reform(|_: MouseEvent| ())}>
+                                { "Deal again" }
+                            </button>
+                            <button class="secondary outline" onclick={props.on_discard_dealt.reform(|_: MouseEvent| ())}>
+                                { "Discard" }
+                            </button>
+                        </div>
+                    </section>
+                }
+
+                if props.setlists.is_empty() {
+                    <p class="placeholder">{ "No setlists yet. Create one, then use \"Add to setlist\" on a song." }</p>
+                }
+                {
+                    for props.setlists.iter().enumerate().map(|(index, setlist)| {
+                        let on_export = {
+                            let setlist = setlist.clone();
+                            let on_error = props.on_error.clone();
+                            Callback::from(move |_: MouseEvent| {
+                                let filename = format!("{}.json", setlists::file_stem(&setlist.name));
+                                if let Err(message) = setlists::download(&filename, &setlists::export_json(&setlist), "application/json") {
+                                    on_error.emit(message);
+                                }
+                            })
+                        };
+                        let ids: Vec<u32> = setlist.songs.iter().map(|song| song.id).collect();
+                        html! {
+                            <details class="setlist">
+                                <summary>{ format!("{} ({} songs)", setlist.name, setlist.songs.len()) }</summary>
+                                <div class="setlist-actions">
+                                    <button class="secondary outline" onclick={props.on_open.reform({
+                                        let id = setlist.id.clone();
+                                        move |_: MouseEvent| id.clone()
+                                    })}>
+                                        { "Open" }
+                                    </button>
+                                    <button class="secondary outline" onclick={on_export}>{ "Export" }</button>
+                                    if !ids.is_empty() {
+                                        <a
+                                            role="button"
+                                            class="secondary outline"
+                                            href={api::setlist_index_pdf_url(&setlist.name, &ids)}
+                                            download=""
+                                        >
+                                            { "PDF index" }
+                                        </a>
+                                        <a
+                                            role="button"
+                                            class="secondary outline"
+                                            href={api::setlist_book_pdf_url(&setlist.name, &ids)}
+                                            download=""
+                                            title="The index, then every chart in order, as one PDF to print"
+                                        >
+                                            { "Print setlist" }
+                                        </a>
+                                    }
+                                    <button class="secondary outline" onclick={on_delete(index)}>{ "Delete" }</button>
+                                </div>
+                                <ol class="setlist-songs">
+                                    {
+                                        for setlist.songs.iter().enumerate().map(|(position, song)| {
+                                            // Apply `change` to this setlist's songs at this position
+                                            let edit = |change: fn(&mut Vec<RealBookEntry>, usize)| {
+                                                let setlists = props.setlists.clone();
+                                                let on_change = props.on_change.clone();
+                                                Callback::from(move |_: MouseEvent| {
+                                                    let mut setlists = setlists.clone();
+                                                    change(&mut setlists[index].songs, position);
+                                                    on_change.emit(setlists);
+                                                })
+                                            };
+                                            html! {
+                                                <li>
+                                                    <span>{ format!("{} (Vol. {}, p. {})", song.title, song.volume, song.page_range()) }</span>
+                                                    <span>
+                                                        <button
+                                                            class="secondary outline"
+                                                            disabled={position == 0}
+                                                            onclick={edit(|songs, position| songs.swap(position - 1, position))}
+                                                            aria-label="Move up"
+                                                        >
+                                                            { "↑" }
+                                                        </button>
+                                                        <button
+                                                            class="secondary outline"
+                                                            onclick={edit(|songs, position| {
+                                                                songs.remove(position);
+                                                            })}
+                                                            aria-label="Remove from setlist"
+                                                        >
+                                                            { "×" }
+                                                        </button>
+                                                    </span>
+                                                </li>
+                                            }
+                                        })
+                                    }
+                                </ol>
+                            </details>
+                        }
+                    })
+                }
+            </article>
+        </details>
+    }
+}
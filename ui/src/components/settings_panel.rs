@@ -0,0 +1,747 @@
+use yew::prelude::*;
+use gloo_events::{EventListener, EventListenerOptions};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use crate::archive::ImportStrategy;
+use crate::i18n::Locale;
+use crate::keymap::{self, Action, Keymap};
+use crate::midi::{MidiAction, MidiMap, MidiStatus};
+use crate::models::{LinkCode, Passkey, RealBookEntry};
+use crate::offline::{self, StorageUsage};
+use crate::passkeys;
+use crate::repertoire::Status;
+use crate::review::{self, ReviewIntervals};
+use crate::sync::SyncStatus;
+use crate::theme::{self, Accent, Appearance, Density, Theme};
+use crate::utils;
+
+/// Format a byte count as a short human-readable size
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Props for the SettingsPanel component
+#[derive(Properties, PartialEq)]
+pub struct SettingsPanelProps {
+    /// Current appearance preferences
+    pub appearance: Appearance,
+
+    /// Callback when appearance preferences change
+    pub on_appearance_change: Callback<Appearance>,
+
+    /// UI language
+    pub locale: Locale,
+
+    /// Callback when a different language is picked
+    pub on_locale_change: Callback<Locale>,
+
+    /// Keyboard shortcut bindings
+    pub keymap: Keymap,
+
+    /// Callback when a shortcut is rebound or the bindings are reset
+    pub on_keymap_change: Callback<Keymap>,
+
+    /// Whether MIDI is connected
+    pub midi_status: MidiStatus,
+
+    /// MIDI pedal messages learned for each action
+    pub midi_map: MidiMap,
+
+    /// Action waiting for a pedal press to learn
+    pub midi_learning: Option<MidiAction>,
+
+    /// Callback to request MIDI access
+    pub on_midi_connect: Callback<()>,
+
+    /// Callback to start (Some) or cancel (None) learning a pedal for an action
+    pub on_midi_learn: Callback<Option<MidiAction>>,
+
+    /// Callback when a learned pedal is forgotten
+    pub on_midi_map_change: Callback<MidiMap>,
+
+    /// Songs saved for offline use
+    pub offline_songs: Vec<RealBookEntry>,
+
+    /// Callback to remove one song from offline storage
+    pub on_remove_offline: Callback<RealBookEntry>,
+
+    /// Callback to remove every offline song
+    pub on_clear_offline: Callback<()>,
+
+    /// Tags in use, with how many songs have each
+    pub tag_counts: Vec<(String, usize)>,
+
+    /// Callback to rename a tag on every song: (old name, new name)
+    pub on_rename_tag: Callback<(String, String)>,
+
+    /// Callback to remove a tag from every song
+    pub on_delete_tag: Callback<String>,
+
+    /// Days between reviews for each repertoire status
+    pub review_intervals: ReviewIntervals,
+
+    /// Callback when a review interval changes
+    pub on_review_intervals_change: Callback<ReviewIntervals>,
+
+    /// Callback to download the user's data archive
+    pub on_export_data: Callback<()>,
+
+    /// Callback to restore a data archive file, combined by the chosen strategy
+    pub on_import_data: Callback<(web_sys::File, ImportStrategy)>,
+
+    /// Where syncing with other devices stands
+    pub sync_status: SyncStatus,
+
+    /// Code for another device to join with, once made
+    pub link_code: Option<LinkCode>,
+
+    /// Callback to make a link code (and start syncing)
+    pub on_link_device: Callback<()>,
+
+    /// Callback to join another device with its code
+    pub on_join_device: Callback<String>,
+
+    /// Callback to stop syncing this device
+    pub on_stop_sync: Callback<()>,
+
+    /// Passkeys that sign in to this browser's session
+    pub passkeys: Vec<Passkey>,
+
+    /// Callback to make a passkey with the given name
+    pub on_create_passkey: Callback<String>,
+
+    /// Callback to sign in with a passkey
+    pub on_passkey_sign_in: Callback<()>,
+
+    /// Callback to remove a passkey, by id
+    pub on_remove_passkey: Callback<String>,
+
+    /// Callback to sign this browser out of its session
+    pub on_sign_out: Callback<()>,
+}
+
+/// SettingsPanel component - collapsible panel with appearance and offline storage controls
+///
+/// Appearance covers the color scheme and whether sheets are inverted. Keyboard
+/// shortcuts are rebound by clicking "Change" and pressing the new key (Escape
+/// cancels). MIDI pedals are learned the same way: "Learn", then press the
+/// pedal. Tags can be renamed (merging into another tag of the new name) or
+/// removed from every song at once. Review intervals set how often songs from
+/// the user's book come up for review, by status. "Export my data" downloads
+/// everything the user has added as one JSON file, and "Import…" restores one,
+/// merged with what's here or replacing it. "Link a device" shows a code to
+/// enter on another device (within a few minutes) so the two keep their data
+/// in sync; the other device enters it under "Join". A passkey does the same
+/// with the device's screen lock: once made, "Sign in with a passkey" brings
+/// any browser in to the session, and "Sign out" leaves it (syncing first, then
+/// clearing this device's copy of the data). It also shows
+/// how much browser storage the app uses and lists songs saved for
+/// offline use, each with a Remove button. The usage estimate is refreshed
+/// when the panel opens and whenever the saved list changes.
+#[function_component(SettingsPanel)]
+pub fn settings_panel(props: &SettingsPanelProps) -> Html {
+    let usage = use_state(|| Option::<StorageUsage>::None);
+
+    let refresh_usage = {
+        let usage = usage.clone();
+        Callback::from(move |_: ()| {
+            let usage = usage.clone();
+            spawn_local(async move {
+                usage.set(offline::storage_usage().await);
+            });
+        })
+    };
+
+    {
+        let refresh_usage = refresh_usage.clone();
+        use_effect_with(props.offline_songs.len(), move |_| {
+            refresh_usage.emit(());
+            || ()
+        });
+    }
+
+    // Action waiting for its new key
+    let rebinding = use_state(|| Option::<Action>::None);
+
+    // How an imported archive combines with the data already here
+    let import_strategy = use_state(ImportStrategy::default);
+    let on_import = {
+        let import_strategy = import_strategy.clone();
+        let on_import_data = props.on_import_data.clone();
+        Callback::from(move |e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            // Allow picking the same file again later
+            input.set_value("");
+            on_import_data.emit((file, *import_strategy));
+        })
+    };
+
+    // Link code typed in to join another device
+    let join_code = use_state(String::new);
+    let on_join = {
+        let join_code = join_code.clone();
+        let on_join_device = props.on_join_device.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let code = join_code.trim().to_string();
+            if !code.is_empty() {
+                on_join_device.emit(code);
+                join_code.set(String::new());
+            }
+        })
+    };
+
+    // Name for a new passkey
+    let passkey_name = use_state(String::new);
+    let on_create_passkey = {
+        let passkey_name = passkey_name.clone();
+        let on_create_passkey = props.on_create_passkey.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let name = passkey_name.trim().to_string();
+            if !name.is_empty() {
+                on_create_passkey.emit(name);
+                passkey_name.set(String::new());
+            }
+        })
+    };
+
+    // While rebinding, the next key press (captured before the app's own
+    // shortcut handlers see it) becomes the action's key
+    {
+        let rebinding = rebinding.clone();
+        let keymap = props.keymap.clone();
+        let on_keymap_change = props.on_keymap_change.clone();
+        use_effect_with(*rebinding, move |action| {
+            let action = *action;
+            let listener = action.map(|action| {
+                let window = web_sys::window().unwrap();
+                let options = EventListenerOptions { passive: false, ..EventListenerOptions::run_in_capture_phase() };
+                EventListener::new_with_options(&window, "keydown", options, move |event| {
+                    let event = event.dyn_ref::<web_sys::KeyboardEvent>().unwrap();
+                    let key = event.key();
+                    // Lone modifiers can't be shortcuts; wait for the real key
+                    if matches!(key.as_str(), "Shift" | "Control" | "Alt" | "Meta") {
+                        return;
+                    }
+                    event.prevent_default();
+                    event.stop_propagation();
+                    if key != "Escape" {
+                        on_keymap_change.emit(keymap.bind(action, &key));
+                    }
+                    rebinding.set(None);
+                })
+            });
+            move || drop(listener)
+        });
+    }
+
+    let on_reset_keymap = props.on_keymap_change.reform(|_: MouseEvent| Keymap::default());
+
+    let on_toggle = refresh_usage.reform(|_: Event| ());
+    let on_clear = props.on_clear_offline.reform(|_: MouseEvent| ());
+
+    let on_crop_toggle = {
+        let appearance = props.appearance;
+        props.on_appearance_change.reform(move |_: Event| Appearance {
+            crop_margins: !appearance.crop_margins,
+            ..appearance
+        })
+    };
+
+    let on_half_page_toggle = {
+        let appearance = props.appearance;
+        props.on_appearance_change.reform(move |_: Event| Appearance {
+            half_page_turns: !appearance.half_page_turns,
+            ..appearance
+        })
+    };
+
+    let on_invert_toggle = {
+        let appearance = props.appearance;
+        props.on_appearance_change.reform(move |_: Event| Appearance {
+            invert_sheets: !appearance.invert_sheets,
+            ..appearance
+        })
+    };
+
+    let on_font_scale = {
+        let appearance = props.appearance;
+        props.on_appearance_change.reform(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            Appearance {
+                font_scale: input.value().parse().unwrap_or(appearance.font_scale),
+                ..appearance
+            }
+        })
+    };
+
+    html! {
+        <details class="settings-panel" ontoggle={on_toggle}>
+            <summary>{ "Settings" }</summary>
+            <article>
+                <h3>{ "Appearance" }</h3>
+                <fieldset>
+                    <legend>{ "Theme" }</legend>
+                    {
+                        for Theme::ALL.iter().map(|&theme| {
+                            let on_select = {
+                                let appearance = props.appearance;
+                                props.on_appearance_change.reform(move |_: Event| Appearance { theme, ..appearance })
+                            };
+                            html! {
+                                <label>
+                                    <input
+                                        type="radio"
+                                        name="theme"
+                                        checked={props.appearance.theme == theme}
+                                        onchange={on_select}
+                                    />
+                                    { theme.label() }
+                                </label>
+                            }
+                        })
+                    }
+                </fieldset>
+                <fieldset>
+                    <legend>{ "Accent color" }</legend>
+                    {
+                        for Accent::ALL.iter().map(|&accent| {
+                            let on_select = {
+                                let appearance = props.appearance;
+                                props.on_appearance_change.reform(move |_: Event| Appearance { accent, ..appearance })
+                            };
+                            html! {
+                                <label>
+                                    <input
+                                        type="radio"
+                                        name="accent"
+                                        checked={props.appearance.accent == accent}
+                                        onchange={on_select}
+                                    />
+                                    { accent.label() }
+                                </label>
+                            }
+                        })
+                    }
+                </fieldset>
+                <fieldset>
+                    <legend>{ "Density" }</legend>
+                    {
+                        for Density::ALL.iter().map(|&density| {
+                            let on_select = {
+                                let appearance = props.appearance;
+                                props.on_appearance_change.reform(move |_: Event| Appearance { density, ..appearance })
+                            };
+                            html! {
+                                <label>
+                                    <input
+                                        type="radio"
+                                        name="density"
+                                        checked={props.appearance.density == density}
+                                        onchange={on_select}
+                                    />
+                                    { density.label() }
+                                </label>
+                            }
+                        })
+                    }
+                </fieldset>
+                <label>
+                    { format!("Text size: {}%", props.appearance.font_scale) }
+                    <input
+                        type="range"
+                        min={theme::MIN_FONT_SCALE.to_string()}
+                        max={theme::MAX_FONT_SCALE.to_string()}
+                        step={theme::FONT_SCALE_STEP.to_string()}
+                        value={props.appearance.font_scale.to_string()}
+                        oninput={on_font_scale}
+                    />
+                </label>
+                <label>
+                    <input
+                        type="checkbox"
+                        role="switch"
+                        checked={props.appearance.invert_sheets}
+                        onchange={on_invert_toggle}
+                    />
+                    { "Invert sheet music (white notes on black)" }
+                </label>
+                <label>
+                    <input
+                        type="checkbox"
+                        role="switch"
+                        checked={props.appearance.crop_margins}
+                        onchange={on_crop_toggle}
+                    />
+                    { "Trim page margins" }
+                </label>
+                <label>
+                    <input
+                        type="checkbox"
+                        role="switch"
+                        checked={props.appearance.half_page_turns}
+                        onchange={on_half_page_toggle}
+                    />
+                    { "Turn pages by half a screen (keeps the bottom of the view at the top)" }
+                </label>
+
+                <label>
+                    { "Language" }
+                    <select
+                        onchange={props.on_locale_change.reform(|e: Event| {
+                            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                            Locale::from_code(&select.value()).unwrap_or_default()
+                        })}
+                    >
+                        {
+                            for Locale::ALL.iter().map(|&locale| html! {
+                                <option value={locale.code()} lang={locale.code()} selected={props.locale == locale}>
+                                    { locale.name() }
+                                </option>
+                            })
+                        }
+                    </select>
+                </label>
+
+                <h3>{ "Keyboard shortcuts" }</h3>
+                <table class="keymap">
+                    <tbody>
+                        {
+                            for Action::ALL.iter().map(|&action| {
+                                let waiting = *rebinding == Some(action);
+                                let on_change = {
+                                    let rebinding = rebinding.clone();
+                                    Callback::from(move |_: MouseEvent| rebinding.set(Some(action)))
+                                };
+                                html! {
+                                    <tr>
+                                        <td>{ action.label() }</td>
+                                        <td>
+                                            if waiting {
+                                                <small>{ "Press a key… (Esc cancels)" }</small>
+                                            } else {
+                                                {
+                                                    for props.keymap.keys(action).iter().map(|key| html! {
+                                                        <kbd>{ keymap::key_label(key) }</kbd>
+                                                    })
+                                                }
+                                            }
+                                        </td>
+                                        <td>
+                                            <button class="secondary outline" onclick={on_change} disabled={waiting}>
+                                                { "Change" }
+                                            </button>
+                                        </td>
+                                    </tr>
+                                }
+                            })
+                        }
+                    </tbody>
+                </table>
+                <button class="secondary outline" onclick={on_reset_keymap}>{ "Reset shortcuts" }</button>
+
+                <h3>{ "MIDI pedals" }</h3>
+                <p><small>
+                    {
+                        match &props.midi_status {
+                            MidiStatus::Off => "Not connected.".to_string(),
+                            MidiStatus::Connecting => "Connecting…".to_string(),
+                            MidiStatus::Connected(1) => "Connected to 1 MIDI input.".to_string(),
+                            MidiStatus::Connected(count) => format!("Connected to {} MIDI inputs.", count),
+                            MidiStatus::Unavailable(reason) => format!("{}.", reason),
+                        }
+                    }
+                </small></p>
+                if !matches!(props.midi_status, MidiStatus::Connected(_)) {
+                    <button
+                        class="secondary outline"
+                        onclick={props.on_midi_connect.reform(|_: MouseEvent| ())}
+                        disabled={props.midi_status == MidiStatus::Connecting}
+                    >
+                        { "Connect MIDI" }
+                    </button>
+                } else {
+                    <table class="keymap">
+                        <tbody>
+                            {
+                                for MidiAction::ALL.iter().map(|&action| {
+                                    let learning = props.midi_learning == Some(action);
+                                    let learned = props.midi_map.message(action);
+                                    let on_learn = props.on_midi_learn.reform(move |_: MouseEvent| (!learning).then_some(action));
+                                    let on_forget = {
+                                        let midi_map = props.midi_map.clone();
+                                        props.on_midi_map_change.reform(move |_: MouseEvent| midi_map.forget(action))
+                                    };
+                                    html! {
+                                        <tr>
+                                            <td>{ action.label() }</td>
+                                            <td>
+                                                if learning {
+                                                    <small>{ "Press the pedal…" }</small>
+                                                } else if let Some(message) = learned {
+                                                    <kbd>{ message.label() }</kbd>
+                                                }
+                                            </td>
+                                            <td>
+                                                <button class="secondary outline" onclick={on_learn}>
+                                                    { if learning { "Cancel" } else { "Learn" } }
+                                                </button>
+                                                if learned.is_some() {
+                                                    <button class="secondary outline" onclick={on_forget} aria-label="Forget">{ "×" }</button>
+                                                }
+                                            </td>
+                                        </tr>
+                                    }
+                                })
+                            }
+                        </tbody>
+                    </table>
+                }
+
+                <h3>{ "Tags" }</h3>
+                if props.tag_counts.is_empty() {
+                    <p class="placeholder">{ "No tags yet. Add some (ballad, jam session, wedding) under a song's title to filter searches by them." }</p>
+                } else {
+                    <ul class="tag-list">
+                        {
+                            for props.tag_counts.iter().map(|(tag, count)| {
+                                let on_rename = {
+                                    let tag = tag.clone();
+                                    let callback = props.on_rename_tag.clone();
+                                    Callback::from(move |_: MouseEvent| {
+                                        if let Some(name) = utils::prompt_text(&format!("Rename the tag \"{}\" to:", tag)) {
+                                            callback.emit((tag.clone(), name));
+                                        }
+                                    })
+                                };
+                                let on_delete = {
+                                    let tag = tag.clone();
+                                    props.on_delete_tag.reform(move |_: MouseEvent| tag.clone())
+                                };
+                                html! {
+                                    <li>
+                                        <span>{ format!("#{} ({} {})", tag, count, if *count == 1 { "song" } else { "songs" }) }</span>
+                                        <span>
+                                            <button class="secondary outline" onclick={on_rename}>{ "Rename" }</button>
+                                            { " " }
+                                            <button class="secondary outline" onclick={on_delete}>{ "Remove" }</button>
+                                        </span>
+                                    </li>
+                                }
+                            })
+                        }
+                    </ul>
+                }
+
+                <h3>{ "Review queue" }</h3>
+                <p><small>{ "Songs in your book come up for review when they haven't been practiced for this many days." }</small></p>
+                <div class="review-intervals">
+                    {
+                        for Status::ALL.iter().map(|&status| {
+                            let intervals = props.review_intervals;
+                            let on_input = props.on_review_intervals_change.reform(move |e: InputEvent| {
+                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                let days = input.value().parse().unwrap_or(intervals.days(status));
+                                intervals.with_days(status, days)
+                            });
+                            html! {
+                                <label>
+                                    { match status {
+                                        Status::Learning => "Learning",
+                                        Status::Learned => "Learned",
+                                        Status::Ready => "Performance-ready",
+                                    } }
+                                    <input
+                                        type="number"
+                                        min="1"
+                                        max={review::MAX_INTERVAL_DAYS.to_string()}
+                                        value={intervals.days(status).to_string()}
+                                        oninput={on_input}
+                                    />
+                                </label>
+                            }
+                        })
+                    }
+                </div>
+
+                <h3>{ "Your data" }</h3>
+                <p><small>{ "Favorites, setlists, annotations, notes, tags, your book, and the practice log, as one JSON file to back up or move to another device." }</small></p>
+                <div class="data-actions">
+                    <button class="secondary" onclick={props.on_export_data.reform(|_: MouseEvent| ())}>{ "Export my data" }</button>
+                    <select
+                        aria-label="When importing"
+                        onchange={{
+                            let import_strategy = import_strategy.clone();
+                            Callback::from(move |e: Event| {
+                                let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                                import_strategy.set(ImportStrategy::from_value(&select.value()).unwrap_or_default());
+                            })
+                        }}
+                    >
+                        {
+                            for ImportStrategy::ALL.iter().map(|&strategy| html! {
+                                <option value={strategy.value()} selected={*import_strategy == strategy}>
+                                    { match strategy {
+                                        ImportStrategy::Merge => "Merge, keeping what's here",
+                                        ImportStrategy::Overwrite => "Merge, taking the archive's",
+                                        ImportStrategy::Replace => "Replace what's here",
+                                    } }
+                                </option>
+                            })
+                        }
+                    </select>
+                    <label role="button" class="secondary outline">
+                        { "Import…" }
+                        <input type="file" accept=".json,application/json" onchange={on_import} hidden=true />
+                    </label>
+                </div>
+
+                <h3>{ "Sync across devices" }</h3>
+                <p><small>{ "Keep the same data on your phone, tablet, and laptop. Changes made offline are sent when the connection comes back; when an item was changed on two devices, the later change wins." }</small></p>
+                if let Some(link) = &props.link_code {
+                    <p class="link-code">
+                        { "On the other device, join with " }
+                        <strong>{ &link.code }</strong>
+                        { format!(" within {} minutes.", link.expires_in.div_ceil(60)) }
+                    </p>
+                }
+                <p><small>
+                    { match &props.sync_status {
+                        SyncStatus::Off => "This device doesn't sync.".to_string(),
+                        SyncStatus::Syncing => "Syncing…".to_string(),
+                        SyncStatus::Synced(Some(at)) => format!("Synced at {}.", utils::local_time(*at)),
+                        SyncStatus::Synced(None) => "Syncing is on.".to_string(),
+                        SyncStatus::Offline => "Offline; changes will sync when the connection is back.".to_string(),
+                        SyncStatus::Failed(reason) => format!("The last sync failed: {}", reason),
+                    } }
+                </small></p>
+                <div class="data-actions">
+                    <button class="secondary" onclick={props.on_link_device.reform(|_: MouseEvent| ())}>
+                        { if props.sync_status == SyncStatus::Off { "Link a device" } else { "Link another device" } }
+                    </button>
+                    if props.sync_status != SyncStatus::Off {
+                        <button class="secondary outline" onclick={props.on_stop_sync.reform(|_: MouseEvent| ())}>{ "Stop syncing" }</button>
+                    }
+                </div>
+                <form class="data-actions" onsubmit={on_join}>
+                    <input
+                        type="text"
+                        placeholder="Code from another device"
+                        aria-label="Link code"
+                        autocomplete="off"
+                        value={(*join_code).clone()}
+                        oninput={{
+                            let join_code = join_code.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                join_code.set(input.value());
+                            })
+                        }}
+                    />
+                    <button type="submit" class="secondary" disabled={join_code.trim().is_empty()}>{ "Join" }</button>
+                </form>
+
+                <h3>{ "Passkeys" }</h3>
+                <p><small>{ "Sign in to this session on any device, like a shared band tablet, with a fingerprint, face, or screen lock instead of a password. Signing in brings along the data synced to the session; signing out removes it from this device." }</small></p>
+                if !passkeys::is_supported() {
+                    <p><small>{ "Passkeys aren't available in this browser (they also need a secure https connection)." }</small></p>
+                } else {
+                    if !props.passkeys.is_empty() {
+                        <ul class="passkeys">
+                            {
+                                for props.passkeys.iter().map(|passkey| {
+                                    let on_remove = {
+                                        let id = passkey.id.clone();
+                                        props.on_remove_passkey.reform(move |_: MouseEvent| id.clone())
+                                    };
+                                    let used = passkey
+                                        .last_used
+                                        .as_ref()
+                                        .map_or_else(|| "not used yet".to_string(), |day| format!("last used {}", day));
+                                    html! {
+                                        <li>
+                                            <span>{ format!("{} (added {}, {})", passkey.name, passkey.created_at, used) }</span>
+                                            <button class="secondary outline" onclick={on_remove}>{ "Remove" }</button>
+                                        </li>
+                                    }
+                                })
+                            }
+                        </ul>
+                    }
+                    <form class="data-actions" onsubmit={on_create_passkey}>
+                        <input
+                            type="text"
+                            placeholder="Whose passkey (e.g. Dana)"
+                            aria-label="Passkey name"
+                            maxlength="64"
+                            value={(*passkey_name).clone()}
+                            oninput={{
+                                let passkey_name = passkey_name.clone();
+                                Callback::from(move |e: InputEvent| {
+                                    let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                    passkey_name.set(input.value());
+                                })
+                            }}
+                        />
+                        <button type="submit" class="secondary" disabled={passkey_name.trim().is_empty()}>{ "Create a passkey" }</button>
+                    </form>
+                    <div class="data-actions">
+                        <button class="secondary" onclick={props.on_passkey_sign_in.reform(|_: MouseEvent| ())}>{ "Sign in with a passkey" }</button>
+                        if !props.passkeys.is_empty() {
+                            <button class="secondary outline" onclick={props.on_sign_out.reform(|_: MouseEvent| ())}>{ "Sign out" }</button>
+                        }
+                    </div>
+                }
+
+                <h3>{ "Offline storage" }</h3>
+                {
+                    if let Some(usage) = &*usage {
+                        html! {
+                            <>
+                                <progress value={usage.used.to_string()} max={usage.quota.to_string()} />
+                                <p><small>
+                                    { format!("{} used of {} available", format_bytes(usage.used), format_bytes(usage.quota)) }
+                                </small></p>
+                            </>
+                        }
+                    } else {
+                        html! { <p><small>{ "Storage usage isn't available in this browser." }</small></p> }
+                    }
+                }
+
+                if props.offline_songs.is_empty() {
+                    <p class="placeholder">{ "No songs saved offline yet. Use \"Save offline\" on a song to keep it for gigs with no signal." }</p>
+                } else {
+                    <ul class="offline-songs">
+                        {
+                            for props.offline_songs.iter().map(|entry| {
+                                let on_remove = {
+                                    let entry = entry.clone();
+                                    props.on_remove_offline.reform(move |_: MouseEvent| entry.clone())
+                                };
+                                html! {
+                                    <li>
+                                        <span>{ format!("{} (Vol. {}, p. {})", entry.title, entry.volume, entry.page_range()) }</span>
+                                        <button class="secondary outline" onclick={on_remove}>{ "Remove" }</button>
+                                    </li>
+                                }
+                            })
+                        }
+                    </ul>
+                    <button class="secondary" onclick={on_clear}>{ "Remove all offline songs" }</button>
+                }
+            </article>
+        </details>
+    }
+}
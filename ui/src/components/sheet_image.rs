@@ -1,4 +1,10 @@
+use gloo_timers::callback::Timeout;
 use yew::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::annotations::{self, Bookmark, PageAnnotations, Placement, Sticker, Stroke, Tool};
+use crate::api;
+use crate::components::use_toaster;
+use crate::models::PageProblem;
 
 /// Props for the SheetImage component
 #[derive(Properties, PartialEq)]
@@ -6,45 +12,483 @@ pub struct SheetImageProps {
     /// URL of the sheet music image
     pub url: String,
 
+    /// URL of a tiny version of the page, shown blurred until the image loads
+    pub thumbnail_url: String,
+
     /// Alt text for the image
     pub alt: String,
+
+    /// Volume the page is in (for reporting a broken image)
+    pub volume: u32,
+
+    /// Page number in the volume (used for bookmark ids)
+    pub page: u32,
+
+    /// File name used when the page is downloaded
+    pub filename: String,
+
+    /// Drawings on this page
+    pub annotations: PageAnnotations,
+
+    /// Drawing tool in use, or None when the page isn't being annotated
+    pub tool: Option<Tool>,
+
+    /// Sticker or bookmark waiting to be placed by the next tap on a page
+    pub placing: Option<Placement>,
+
+    /// Callback with the page's drawings after a stroke is added or undone
+    pub on_annotations_change: Callback<PageAnnotations>,
+}
+
+/// Automatic retries after a page fails to load, before the error card shows
+const AUTO_RETRIES: u32 = 3;
+
+/// Delay before the first automatic retry, in milliseconds; it doubles each time
+const RETRY_BASE_MS: u32 = 1_000;
+
+/// `url` for the given attempt: retries add a throwaway query parameter so
+/// neither the browser nor the service worker hands back the failed response
+fn attempt_url(url: &str, attempt: u32) -> String {
+    if attempt == 0 {
+        url.to_string()
+    } else {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{}{}retry={}", url, separator, attempt)
+    }
+}
+
+/// Inline styles that rotate the image by `rotation` degrees and keep it
+/// filling the container's width
+///
+/// Quarter turns swap the image's visual width and height, so the wrapper takes
+/// the rotated aspect ratio and the image is sized (before rotating) to the
+/// wrapper's height, then centered and turned. The image's aspect ratio is also
+/// exposed as `--sheet-aspect` for the margin-cropping styles. The second style
+/// applies to the page (image plus drawing canvas), so drawings turn with it.
+fn rotation_styles(rotation: u32, natural_size: Option<(u32, u32)>) -> (String, String) {
+    let aspect = match natural_size {
+        Some((width, height)) if width > 0 => format!("--sheet-aspect: {:.4};", f64::from(height) / f64::from(width)),
+        _ => String::new(),
+    };
+
+    match (rotation, natural_size) {
+        (90 | 270, Some((width, height))) if width > 0 && height > 0 => (
+            format!("aspect-ratio: {} / {};", height, width),
+            format!(
+                "position: absolute; top: 50%; left: 50%; width: {:.4}%; transform: translate(-50%, -50%) rotate({}deg);",
+                f64::from(width) / f64::from(height) * 100.0,
+                rotation,
+            ),
+        ),
+        (0, _) => (String::new(), aspect),
+        _ => (String::new(), format!("{} transform: rotate({}deg);", aspect, rotation)),
+    }
 }
 
 /// SheetImage component - displays a single sheet music image with its own loading state
 ///
-/// Each image manages its own loading state independently, showing a spinner
-/// until the image's onload event fires.
+/// Each image manages its own loading state independently, showing a
+/// page-shaped placeholder until the image's onload event fires. Pages can be
+/// rotated in quarter turns (for skewed scans or landscape tablets); rotation
+/// resets for a new image.
+/// The download button saves the JPEG under a readable name rather than the
+/// CDN's page number.
+///
+/// A page that fails to load is retried a few times with growing delays (CDN
+/// hiccups are usually brief); if it still fails, a card says so and offers a
+/// Retry button instead of leaving a blank space. The card (and the page tools,
+/// for a wrong or unreadable scan) has a button to report the page, so bad
+/// scans show up in the admin validation view; it's reported once per song.
+///
+/// A canvas over the image shows the page's drawings. While a tool is selected
+/// it takes pointer input (mouse, pen, or finger) and reports each finished
+/// stroke through `on_annotations_change`. Stickers are HTML labels above the
+/// canvas; while annotating they can be dragged around or removed, and a tap
+/// places the pending sticker or bookmark (`placing`). Bookmarks show as flags
+/// on the page's left edge.
 #[function_component(SheetImage)]
 pub fn sheet_image(props: &SheetImageProps) -> Html {
     // Track loading state for this specific image
     let loading = use_state(|| true);
+    // Retries so far, and whether they ran out (shows the error card)
+    let attempt = use_state(|| 0u32);
+    let failed = use_state(|| false);
+    // Whether the page was reported broken (the button then stays disabled)
+    let reported = use_state(|| false);
+    let retry_timer = use_mut_ref(|| Option::<Timeout>::None);
+    // Clockwise rotation in degrees (0, 90, 180, or 270)
+    let rotation = use_state(|| 0u32);
+    // Intrinsic size, read once loaded (needed to fit quarter-turned pages)
+    let natural_size = use_state(|| Option::<(u32, u32)>::None);
+    let img_ref = use_node_ref();
+    let canvas_ref = use_node_ref();
+    // Stroke being drawn (not yet part of the page's annotations)
+    let current_stroke = use_mut_ref(|| Option::<Stroke>::None);
+    // Sticker being dragged: its index and current position
+    let dragging = use_mut_ref(|| Option::<(usize, (f32, f32))>::None);
+    let force_update = use_force_update();
+    let editing = props.tool.is_some() || props.placing.is_some();
 
     // Reset loading state when URL changes (e.g., when switching between songs)
     {
         let loading = loading.clone();
+        let rotation = rotation.clone();
+        let natural_size = natural_size.clone();
+        let attempt = attempt.clone();
+        let failed = failed.clone();
+        let reported = reported.clone();
+        let retry_timer = retry_timer.clone();
         let url = props.url.clone();
         use_effect_with(url, move |_| {
             loading.set(true);
-            || ()
+            rotation.set(0);
+            natural_size.set(None);
+            attempt.set(0);
+            failed.set(false);
+            reported.set(false);
+            // Dropping a pending retry cancels it
+            move || drop(retry_timer.borrow_mut().take())
         });
     }
 
     // Callback fired when image finishes loading
     let on_load = {
         let loading = loading.clone();
+        let natural_size = natural_size.clone();
+        let failed = failed.clone();
+        let img_ref = img_ref.clone();
         Callback::from(move |_: Event| {
+            if let Some(img) = img_ref.cast::<web_sys::HtmlImageElement>() {
+                natural_size.set(Some((img.natural_width(), img.natural_height())));
+            }
             loading.set(false);
+            failed.set(false);
+        })
+    };
+
+    // Callback fired when the image fails: retry after a delay, or give up
+    // and show the error card once the automatic retries are spent
+    let on_error = {
+        let attempt = attempt.clone();
+        let failed = failed.clone();
+        let retry_timer = retry_timer.clone();
+        Callback::from(move |_: Event| {
+            if *attempt < AUTO_RETRIES {
+                let next = *attempt + 1;
+                let delay = RETRY_BASE_MS << *attempt;
+                let attempt = attempt.clone();
+                *retry_timer.borrow_mut() = Some(Timeout::new(delay, move || attempt.set(next)));
+            } else {
+                failed.set(true);
+            }
+        })
+    };
+
+    let on_retry = {
+        let attempt = attempt.clone();
+        let failed = failed.clone();
+        Callback::from(move |_: MouseEvent| {
+            failed.set(false);
+            attempt.set(*attempt + 1);
+        })
+    };
+
+    // Report the page: "missing" if it never loaded, else a bad scan
+    let on_report = {
+        let toaster = use_toaster();
+        let reported = reported.clone();
+        let (volume, page) = (props.volume, props.page);
+        let problem = if *failed { PageProblem::Missing } else { PageProblem::BadScan };
+        Callback::from(move |_: MouseEvent| {
+            reported.set(true);
+            let (toaster, reported) = (toaster.clone(), reported.clone());
+            spawn_local(async move {
+                match api::report_page(volume, page, problem).await {
+                    Ok(()) => toaster.success(format!("Thanks, page {} was reported", page)),
+                    Err(e) => {
+                        reported.set(false);
+                        toaster.error(e.message);
+                    }
+                }
+            });
+        })
+    };
+
+    // Size the canvas to the scan's pixels (CSS stretches it over the image)
+    // and redraw whenever the page's drawings change
+    {
+        let canvas_ref = canvas_ref.clone();
+        use_effect_with((props.annotations.clone(), *natural_size), move |(annotations, natural_size)| {
+            if let (Some(canvas), Some((width, height))) = (canvas_ref.cast::<web_sys::HtmlCanvasElement>(), natural_size) {
+                if canvas.width() != *width || canvas.height() != *height {
+                    canvas.set_width(*width);
+                    canvas.set_height(*height);
+                }
+                annotations::draw(&canvas, &annotations.strokes);
+            }
+            || ()
+        });
+    }
+
+    // Pointer position as fractions of the page (offsets are in the canvas's
+    // own, unrotated coordinates)
+    let page_point = {
+        let canvas_ref = canvas_ref.clone();
+        move |e: &PointerEvent| {
+            let canvas = canvas_ref.cast::<web_sys::HtmlCanvasElement>()?;
+            let (width, height) = (canvas.client_width(), canvas.client_height());
+            (width > 0 && height > 0).then(|| {
+                (e.offset_x() as f32 / width as f32, e.offset_y() as f32 / height as f32)
+            })
+        }
+    };
+
+    let redraw = {
+        let canvas_ref = canvas_ref.clone();
+        let current_stroke = current_stroke.clone();
+        let strokes = props.annotations.strokes.clone();
+        move || {
+            if let Some(canvas) = canvas_ref.cast::<web_sys::HtmlCanvasElement>() {
+                annotations::draw(&canvas, strokes.iter().chain(current_stroke.borrow().as_ref()));
+            }
+        }
+    };
+
+    let on_pointer_down = {
+        let current_stroke = current_stroke.clone();
+        let page_point = page_point.clone();
+        let redraw = redraw.clone();
+        let tool = props.tool;
+        let placing = props.placing.clone();
+        let annotations = props.annotations.clone();
+        let on_annotations_change = props.on_annotations_change.clone();
+        Callback::from(move |e: PointerEvent| {
+            let Some(point) = page_point(&e) else {
+                return;
+            };
+            if let Some(placement) = &placing {
+                let mut annotations = annotations.clone();
+                match placement {
+                    Placement::Sticker(text) => {
+                        annotations.stickers.push(Sticker { text: text.clone(), x: point.0, y: point.1 })
+                    }
+                    Placement::Bookmark(name) => {
+                        annotations.bookmarks.push(Bookmark { name: name.clone(), y: point.1 })
+                    }
+                }
+                on_annotations_change.emit(annotations);
+                return;
+            }
+            let Some(tool) = tool else {
+                return;
+            };
+            e.prevent_default();
+            // Keep receiving moves if the pointer leaves the page mid-stroke
+            if let Some(canvas) = e.target_dyn_into::<web_sys::Element>() {
+                let _ = canvas.set_pointer_capture(e.pointer_id());
+            }
+            *current_stroke.borrow_mut() = Some(Stroke { tool, points: vec![point] });
+            redraw();
+        })
+    };
+
+    let on_pointer_move = {
+        let current_stroke = current_stroke.clone();
+        let dragging = dragging.clone();
+        let redraw = redraw.clone();
+        Callback::from(move |e: PointerEvent| {
+            let Some(point) = page_point(&e) else {
+                return;
+            };
+            if let Some((_, position)) = dragging.borrow_mut().as_mut() {
+                *position = (point.0.clamp(0.0, 1.0), point.1.clamp(0.0, 1.0));
+                force_update.force_update();
+                return;
+            }
+            if let Some(stroke) = current_stroke.borrow_mut().as_mut() {
+                stroke.points.push(point);
+            } else {
+                return;
+            }
+            redraw();
         })
     };
 
+    let on_pointer_up = {
+        let current_stroke = current_stroke.clone();
+        let annotations = props.annotations.clone();
+        let on_annotations_change = props.on_annotations_change.clone();
+        let dragging = dragging.clone();
+        Callback::from(move |_: PointerEvent| {
+            if let Some(stroke) = current_stroke.borrow_mut().take() {
+                let mut annotations = annotations.clone();
+                annotations.strokes.push(stroke);
+                on_annotations_change.emit(annotations);
+            }
+            if let Some((index, (x, y))) = dragging.borrow_mut().take() {
+                let mut annotations = annotations.clone();
+                if let Some(sticker) = annotations.stickers.get_mut(index) {
+                    sticker.x = x;
+                    sticker.y = y;
+                }
+                on_annotations_change.emit(annotations);
+            }
+        })
+    };
+
+    // Drags are tracked through the canvas (pointer capture), whose offsets
+    // are in page coordinates even when the page is rotated
+    let on_sticker_pointer_down = |index: usize, sticker: &Sticker| {
+        let dragging = dragging.clone();
+        let canvas_ref = canvas_ref.clone();
+        let position = (sticker.x, sticker.y);
+        Callback::from(move |e: PointerEvent| {
+            if !editing {
+                return;
+            }
+            e.prevent_default();
+            e.stop_propagation();
+            if let Some(canvas) = canvas_ref.cast::<web_sys::Element>() {
+                let _ = canvas.set_pointer_capture(e.pointer_id());
+            }
+            *dragging.borrow_mut() = Some((index, position));
+        })
+    };
+
+    let on_sticker_remove = |index: usize| {
+        let annotations = props.annotations.clone();
+        let on_annotations_change = props.on_annotations_change.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.stop_propagation();
+            let mut annotations = annotations.clone();
+            annotations.stickers.remove(index);
+            on_annotations_change.emit(annotations);
+        })
+    };
+
+    let on_undo = {
+        let annotations = props.annotations.clone();
+        let on_annotations_change = props.on_annotations_change.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut annotations = annotations.clone();
+            annotations.strokes.pop();
+            on_annotations_change.emit(annotations);
+        })
+    };
+
+    let rotate_by = |degrees: u32| {
+        let rotation = rotation.clone();
+        Callback::from(move |_: MouseEvent| rotation.set((*rotation + degrees) % 360))
+    };
+
+    let (wrapper_style, image_style) = rotation_styles(*rotation, *natural_size);
+
     html! {
-        <article class="sheet-image-container" aria-busy={loading.to_string()}>
-            <img
-                src={props.url.clone()}
-                alt={props.alt.clone()}
-                onload={on_load}
-                style={if *loading { "display: none;" } else { "" }}
-            />
+        <article class="sheet-image-container">
+            // Page-shaped placeholder while the image loads, so the layout
+            // doesn't jump when it arrives; the blurred thumbnail fills it as
+            // soon as it's in, giving a sense of the page on slow connections
+            if *failed {
+                <div class="page-error" role="alert">
+                    <p>{ format!("Couldn't load page {}", props.page) }</p>
+                    <button class="secondary" onclick={on_retry}>{ "Retry" }</button>
+                    <button class="secondary outline" onclick={on_report.clone()} disabled={*reported}>
+                        { if *reported { "Reported" } else { "Report broken image" } }
+                    </button>
+                </div>
+            } else if *loading {
+                <div class="skeleton page-skeleton" role="status" aria-label="Loading page">
+                    <img class="page-thumbnail" src={props.thumbnail_url.clone()} alt="" />
+                </div>
+            }
+            if !*loading {
+                <div class="page-controls" role="group" aria-label="Page tools">
+                    if props.tool.is_some() && !props.annotations.strokes.is_empty() {
+                        <button class="secondary outline" onclick={on_undo} aria-label="Undo last stroke">{ "↶" }</button>
+                    }
+                    <button class="secondary outline" onclick={rotate_by(270)} aria-label="Rotate left">{ "↺" }</button>
+                    <button class="secondary outline" onclick={rotate_by(90)} aria-label="Rotate right">{ "↻" }</button>
+                    <a
+                        role="button"
+                        class="secondary outline"
+                        href={props.url.clone()}
+                        download={props.filename.clone()}
+                        aria-label="Download page"
+                        title={format!("Download {}", props.filename)}
+                    >
+                        { "⤓" }
+                    </a>
+                    <button
+                        class="secondary outline"
+                        onclick={on_report}
+                        disabled={*reported}
+                        aria-label="Report a bad scan"
+                        title={if *reported { "Reported" } else { "Report a wrong or unreadable scan" }}
+                    >
+                        { "⚑" }
+                    </button>
+                </div>
+            }
+            <div
+                class={classes!("sheet-image-frame", matches!(*rotation, 90 | 270).then_some("quarter-turn"))}
+                style={wrapper_style}
+            >
+                <div class="sheet-page" style={if *loading { "display: none;".to_string() } else { image_style }}>
+                    <img
+                        ref={img_ref}
+                        src={attempt_url(&props.url, *attempt)}
+                        alt={props.alt.clone()}
+                        onload={on_load}
+                        onerror={on_error}
+                    />
+                    <canvas
+                        ref={canvas_ref.clone()}
+                        class={classes!(editing.then_some("annotating"))}
+                        onpointerdown={on_pointer_down}
+                        onpointermove={on_pointer_move}
+                        onpointerup={on_pointer_up.clone()}
+                        onpointercancel={on_pointer_up}
+                    />
+                    {
+                        for props.annotations.bookmarks.iter().enumerate().map(|(index, bookmark)| html! {
+                            <span
+                                id={annotations::bookmark_id(props.page, index)}
+                                class="bookmark-flag"
+                                style={format!("top: {:.2}%;", bookmark.y * 100.0)}
+                            >
+                                { &bookmark.name }
+                            </span>
+                        })
+                    }
+                    {
+                        for props.annotations.stickers.iter().enumerate().map(|(index, sticker)| {
+                            let (x, y) = match *dragging.borrow() {
+                                Some((dragged, position)) if dragged == index => position,
+                                _ => (sticker.x, sticker.y),
+                            };
+                            html! {
+                                <span
+                                    class={classes!("sticker", editing.then_some("editable"))}
+                                    style={format!("left: {:.2}%; top: {:.2}%;", x * 100.0, y * 100.0)}
+                                    onpointerdown={on_sticker_pointer_down(index, sticker)}
+                                >
+                                    { &sticker.text }
+                                    if editing {
+                                        <button
+                                            class="sticker-remove"
+                                            onpointerdown={Callback::from(|e: PointerEvent| e.stop_propagation())}
+                                            onclick={on_sticker_remove(index)}
+                                            aria-label={format!("Remove {}", sticker.text)}
+                                        >
+                                            { "×" }
+                                        </button>
+                                    }
+                                </span>
+                            }
+                        })
+                    }
+                </div>
+            </div>
         </article>
     }
 }
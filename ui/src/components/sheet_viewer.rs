@@ -1,6 +1,48 @@
 use yew::prelude::*;
-use crate::models::RealBookEntry;
-use crate::components::SheetImage;
+use gloo_events::{EventListener, EventListenerOptions};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use crate::models::{Attachments, RealBookEntry};
+use crate::keymap::{self, Action, Keymap};
+use crate::setlists::Setlist;
+use crate::components::{DroneDock, MetronomeDock, PracticeTimer, QrDialog, RecordingPlayer, ReportProblem, SheetImage, use_toaster};
+use crate::annotations::{self, MARKERS, PageAnnotations, Placement, SongAnnotations, Tool};
+use crate::i18n::{self, use_messages};
+use crate::listen::{self, Service};
+use crate::share::{self, Shared};
+use crate::stage::{self, StageChannel, StageMessage};
+use crate::auto_scroll::{self, AutoScroller};
+use crate::repertoire::{self, Status};
+use crate::{api, hooks, notes, router, storage, utils};
+
+/// Element id of the song title (labels the viewer when it's a dialog)
+const SHEET_TITLE_ID: &str = "sheet-title";
+
+/// Element id of the list of tags suggested while adding one
+const TAG_SUGGESTIONS_ID: &str = "tag-suggestions";
+
+/// sessionStorage key for the zoom level
+const ZOOM_STORAGE_KEY: &str = "realbook.zoom";
+
+/// Zoom limits and step, in percent of the viewer width
+const MIN_ZOOM: u32 = 50;
+const MAX_ZOOM: u32 = 300;
+const ZOOM_STEP: u32 = 10;
+const DEFAULT_ZOOM: u32 = 100;
+
+/// Zoom one step in (`step` = 1) or out (-1), within the limits
+fn step_zoom(zoom: u32, step: i32) -> u32 {
+    zoom.saturating_add_signed(step * ZOOM_STEP as i32).clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// A page turn requested from outside the viewer (a MIDI pedal)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PageTurn {
+    /// Bumped for every request, so repeated turns the same way still register
+    pub serial: u32,
+    /// 1 = next page, -1 = previous
+    pub step: i32,
+}
 
 /// Props for the SheetViewer component
 #[derive(Properties, PartialEq)]
@@ -8,41 +50,1141 @@ pub struct SheetViewerProps {
     /// The currently selected entry to display sheet music for
     pub entry: Option<RealBookEntry>,
 
-    /// Whether data is currently loading (shows Pico CSS spinner via aria-busy)
+    /// Whether a song is being picked (shows a skeleton of the chart)
     pub loading: bool,
+
+    /// Whether the entry's pages are saved for offline use
+    pub saved_offline: bool,
+
+    /// Whether the entry's pages are being downloaded for offline use
+    pub saving_offline: bool,
+
+    /// Whether the entry is starred
+    pub favorite: bool,
+
+    /// Callback to save the entry's pages for offline use
+    pub on_save_offline: Callback<RealBookEntry>,
+
+    /// Callback to star or unstar the entry
+    pub on_toggle_favorite: Callback<RealBookEntry>,
+
+    /// The entry's tags
+    #[prop_or_default]
+    pub tags: Vec<String>,
+
+    /// Every tag in use (suggested while adding one)
+    #[prop_or_default]
+    pub all_tags: Vec<String>,
+
+    /// Callback to replace the entry's tags
+    #[prop_or_default]
+    pub on_tags_change: Callback<(RealBookEntry, Vec<String>)>,
+
+    /// The entry's repertoire status and star rating
+    #[prop_or_default]
+    pub progress: (Option<Status>, Option<u8>),
+
+    /// Callback to set the entry's repertoire status and star rating
+    #[prop_or_default]
+    pub on_progress_change: Callback<(RealBookEntry, Option<Status>, Option<u8>)>,
+
+    /// Callback fired after the practice timer logs a session
+    #[prop_or_default]
+    pub on_practice_logged: Callback<()>,
+
+    /// Saved setlists (for "Add to setlist")
+    pub setlists: Vec<Setlist>,
+
+    /// Keyboard shortcut bindings (for the page-turn keys)
+    pub keymap: Keymap,
+
+    /// Latest page turn from a MIDI pedal
+    #[prop_or_default]
+    pub page_turn: PageTurn,
+
+    /// Callback to add the entry to a setlist by id (None: a new setlist)
+    pub on_add_to_setlist: Callback<(Option<String>, RealBookEntry)>,
+
+    /// Callback to close the viewer and return to the results
+    pub on_close: Callback<()>,
+
+    /// Callback to open the adjacent song in the results (1 = next, -1 = previous),
+    /// used when swiping or pedaling past the first or last page
+    pub on_adjacent_song: Callback<i32>,
+
+    /// Song after this one in the results (or the open setlist), whose pages
+    /// are fetched in the background
+    #[prop_or_default]
+    pub next_entry: Option<RealBookEntry>,
+
+    /// Whether the viewer covers the whole screen (narrow screens); it then
+    /// acts as a dialog and keeps keyboard focus until it closes
+    #[prop_or_default]
+    pub modal: bool,
+
+    /// Whether the results column is collapsed, or None where it can't be
+    /// (narrow screens, which show one pane at a time anyway)
+    #[prop_or_default]
+    pub results_collapsed: Option<bool>,
+
+    /// Callback to collapse or restore the results column
+    #[prop_or_default]
+    pub on_toggle_results: Callback<()>,
+
+    /// Whether page turns move half a screen instead of a whole page
+    #[prop_or_default]
+    pub half_page_turns: bool,
+}
+
+/// Scroll the page at `index` within the sheet images container into view
+fn scroll_to_page(images_ref: &NodeRef, index: usize) {
+    let page = images_ref
+        .cast::<web_sys::Element>()
+        .and_then(|images| images.children().item(index as u32));
+    if let Some(page) = page {
+        let options = web_sys::ScrollIntoViewOptions::new();
+        options.set_behavior(web_sys::ScrollBehavior::Smooth);
+        options.set_block(web_sys::ScrollLogicalPosition::Start);
+        page.scroll_into_view_with_scroll_into_view_options(&options);
+    }
+}
+
+/// Scroll the window half its height down (`step` = 1) or up (-1), so the
+/// bottom half of the old view is at the top of the new one; returns the new
+/// scroll position, or None if the page is already at that end
+fn scroll_half_page(step: i32) -> Option<f64> {
+    let window = web_sys::window()?;
+    let height = window.inner_height().ok()?.as_f64()?;
+    let bottom = f64::from(window.document()?.document_element()?.scroll_height()) - height;
+    let top = window.scroll_y().ok()?;
+    if (step > 0 && top >= bottom - 1.0) || (step < 0 && top <= 0.0) {
+        return None;
+    }
+    // Instant, so quick repeated turns each start from where the last one ended
+    let target = (top + f64::from(step) * height / 2.0).clamp(0.0, bottom.max(0.0));
+    window.scroll_to_with_x_and_y(window.scroll_x().unwrap_or_default(), target);
+    Some(target)
+}
+
+/// Index of the page filling the middle of the view when the window is
+/// scrolled to `top`
+fn page_in_view(images_ref: &NodeRef, top: f64) -> usize {
+    let (Some(window), Some(images)) = (web_sys::window(), images_ref.cast::<web_sys::Element>()) else {
+        return 0;
+    };
+    let scrolled = window.scroll_y().unwrap_or_default();
+    let middle = top + window.inner_height().ok().and_then(|height| height.as_f64()).unwrap_or_default() / 2.0;
+    let pages = images.children();
+    (0..pages.length())
+        .take_while(|&index| {
+            pages.item(index).is_some_and(|page| page.get_bounding_client_rect().top() + scrolled <= middle)
+        })
+        .count()
+        .saturating_sub(1)
+}
+
+/// Turn from `page` (of `page_count`) by `step`: a whole page, or half a
+/// screen when `half`. Returns the page now in view, or None past the first or
+/// last page (the top or bottom, for half turns).
+fn turn_page(images_ref: &NodeRef, page: i32, page_count: i32, step: i32, half: bool) -> Option<usize> {
+    if half {
+        let top = scroll_half_page(step)?;
+        return Some(page_in_view(images_ref, top));
+    }
+    let target = page + step;
+    (0..page_count).contains(&target).then(|| {
+        scroll_to_page(images_ref, target as usize);
+        target as usize
+    })
+}
+
+/// Scroll a bookmark's flag (see `annotations::bookmark_id`) into the middle of the view
+fn scroll_to_bookmark(id: &str) {
+    let flag = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(id));
+    if let Some(flag) = flag {
+        let options = web_sys::ScrollIntoViewOptions::new();
+        options.set_behavior(web_sys::ScrollBehavior::Smooth);
+        options.set_block(web_sys::ScrollLogicalPosition::Center);
+        flag.scroll_into_view_with_scroll_into_view_options(&options);
+    }
 }
 
 /// SheetViewer component - displays sheet music images for the selected song
+///
+/// On touch screens, swiping left/right moves to the next/previous page of the
+/// chart, and past its last/first page to the adjacent song in the results.
+///
+/// Pages can be zoomed with the +/- buttons, Ctrl+scroll over the sheet, or the
+/// `+`, `-`, and `0` keys; the zoom level lasts for the browser session.
+///
+/// Picking a pen, highlighter, or eraser turns the pages into drawing surfaces
+/// (and turns off swiping); picking a marker or custom text places it with the
+/// next tap on a page. Drawings and markers are saved per song in localStorage.
+///
+/// Named bookmarks ("bridge", "shout chorus") are placed the same way and listed
+/// in a sidebar that jumps to them.
+///
+/// Songs with an attached reference recording get a collapsible player under
+/// the chart (see `RecordingPlayer`), and an iReal Pro link adds an "Open in
+/// iReal Pro" button for its backing tracks. Songs with a MusicXML version get a
+/// "Download MusicXML" button next to the PDF one.
+///
+/// A "Report a problem" link under the chart sends a corrected title or page
+/// range for moderation (see `ReportProblem`).
+///
+/// A metronome and a drone can be docked under the chart (see `MetronomeDock`
+/// and `DroneDock`).
+///
+/// The next song's pages are loaded by hidden, low-priority images, so the
+/// browser already has them cached when the set moves on.
+///
+/// Full screen (`modal`), the viewer is a dialog labelled by the song title:
+/// focus moves into it when it opens, Tab cycles within it, and focus returns
+/// to where it was when it closes.
 #[function_component(SheetViewer)]
 pub fn sheet_viewer(props: &SheetViewerProps) -> Html {
+    let t = use_messages();
+    let viewer_ref = use_node_ref();
+    hooks::use_focus_trap(viewer_ref.clone(), props.modal && props.entry.is_some());
+    let images_ref = use_node_ref();
+    let zoom_ref = use_node_ref();
+    let zoom = use_state(|| storage::load_session(ZOOM_STORAGE_KEY).unwrap_or(DEFAULT_ZOOM));
+
+    // Remember the zoom level for the rest of the session
+    use_effect_with(*zoom, |zoom| {
+        storage::save_session(ZOOM_STORAGE_KEY, zoom);
+        || ()
+    });
+
+    // Keyboard zoom (+/-/0, ignored while typing) and Ctrl+scroll zoom over the sheet
+    // Re-registered when the zoom changes so the listeners see the current level
+    {
+        let zoom = zoom.clone();
+        let zoom_ref = zoom_ref.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        use_effect_with((*zoom, entry_id), move |(current, _)| {
+            let current = *current;
+            let document = web_sys::window().unwrap().document().unwrap();
+
+            let keydown = {
+                let zoom = zoom.clone();
+                EventListener::new(&document, "keydown", move |event| {
+                    let event = event.dyn_ref::<web_sys::KeyboardEvent>().unwrap();
+                    if event.ctrl_key() || event.meta_key() || event.alt_key() || utils::is_typing(event) {
+                        return;
+                    }
+                    match event.key().as_str() {
+                        "+" | "=" => zoom.set(step_zoom(current, 1)),
+                        "-" => zoom.set(step_zoom(current, -1)),
+                        "0" => zoom.set(DEFAULT_ZOOM),
+                        _ => return,
+                    }
+                    event.prevent_default();
+                })
+            };
+
+            // Not passive, so the browser's own Ctrl+scroll page zoom can be prevented
+            let wheel = zoom_ref.cast::<web_sys::Element>().map(|element| {
+                EventListener::new_with_options(
+                    &element,
+                    "wheel",
+                    EventListenerOptions::enable_prevent_default(),
+                    move |event| {
+                        let event = event.dyn_ref::<web_sys::WheelEvent>().unwrap();
+                        if event.ctrl_key() {
+                            event.prevent_default();
+                            zoom.set(step_zoom(current, if event.delta_y() < 0.0 { 1 } else { -1 }));
+                        }
+                    },
+                )
+            });
+
+            move || drop((keydown, wheel))
+        });
+    }
+
+    // Send the chart's link to a bandmate (share sheet, else the clipboard)
+    let on_share = {
+        let toaster = use_toaster();
+        let entry = props.entry.clone();
+        let (copied, prompt) = (t.link_copied, t.copy_link_prompt);
+        Callback::from(move |_: MouseEvent| {
+            let Some(entry) = entry.clone() else {
+                return;
+            };
+            let toaster = toaster.clone();
+            spawn_local(async move {
+                let url = router::song_url(entry.id);
+                if share::share_link(&entry.title, &url, prompt).await == Shared::Copied {
+                    toaster.success(copied);
+                }
+            });
+        })
+    };
+
+    // QR code of the chart's link, for bandmates to scan
+    let qr_open = use_state(|| false);
+    let on_toggle_qr = {
+        let qr_open = qr_open.clone();
+        Callback::from(move |open: bool| qr_open.set(open))
+    };
+
+    // Stage view: a chart-only window following this viewer's song and page
+    let on_open_stage = {
+        let toaster = use_toaster();
+        let blocked = t.stage_blocked;
+        Callback::from(move |_: MouseEvent| {
+            if !stage::open_window() {
+                toaster.error(blocked);
+            }
+        })
+    };
+
+    // Print just the sheet pages (see the print stylesheet in index.html)
+    let on_print = Callback::from(|_: MouseEvent| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.print();
+        }
+    });
+
+    let on_zoom_in = {
+        let zoom = zoom.clone();
+        Callback::from(move |_: MouseEvent| zoom.set(step_zoom(*zoom, 1)))
+    };
+    let on_zoom_out = {
+        let zoom = zoom.clone();
+        Callback::from(move |_: MouseEvent| zoom.set(step_zoom(*zoom, -1)))
+    };
+    let on_zoom_reset = {
+        let zoom = zoom.clone();
+        Callback::from(move |_: MouseEvent| zoom.set(DEFAULT_ZOOM))
+    };
+    // Extras attached to the song (recording, iReal Pro link, MusicXML), tagged with the song
+    // they belong to so a slow response for a previous song is ignored
+    let attachments = use_state(|| Option::<(u32, Attachments)>::None);
+    {
+        let attachments = attachments.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        use_effect_with(entry_id, move |entry_id| {
+            attachments.set(None);
+            if let Some(id) = *entry_id {
+                spawn_local(async move {
+                    // Most songs have none, and they're optional: failures are ignored
+                    if let Ok(fetched) = api::get_attachments(id).await {
+                        attachments.set(Some((id, fetched)));
+                    }
+                });
+            }
+            || ()
+        });
+    }
+    let song_attachments = attachments
+        .as_ref()
+        .filter(|(id, _)| props.entry.as_ref().is_some_and(|entry| entry.id == *id))
+        .map(|(_, attachments)| attachments.clone())
+        .unwrap_or_default();
+
+    // Whether the metronome and drone are docked under the chart (closing one
+    // stops its sound)
+    // Auto-scroll: whether it's running, and how fast (persisted). Pauses when
+    // another song opens or the end of the page is reached.
+    let auto_scrolling = use_state(|| false);
+    let scroll_speed = use_state(auto_scroll::load_speed);
+    let scroller = use_mut_ref(|| Option::<AutoScroller>::None);
+    {
+        let auto_scrolling = auto_scrolling.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        use_effect_with(entry_id, move |_| {
+            auto_scrolling.set(false);
+            || ()
+        });
+    }
+    {
+        let scroller = scroller.clone();
+        let auto_scrolling = auto_scrolling.clone();
+        let speed = *scroll_speed;
+        use_effect_with(*auto_scrolling, move |running| {
+            *scroller.borrow_mut() = running.then(|| AutoScroller::start(speed, move || auto_scrolling.set(false)));
+            move || drop(scroller.borrow_mut().take())
+        });
+    }
+    {
+        let scroller = scroller.clone();
+        use_effect_with(*scroll_speed, move |speed| {
+            auto_scroll::save_speed(*speed);
+            if let Some(scroller) = &*scroller.borrow() {
+                scroller.set_speed(*speed);
+            }
+            || ()
+        });
+    }
+    let on_toggle_auto_scroll = {
+        let auto_scrolling = auto_scrolling.clone();
+        Callback::from(move |_: MouseEvent| auto_scrolling.set(!*auto_scrolling))
+    };
+    let on_scroll_speed = {
+        let scroll_speed = scroll_speed.clone();
+        Callback::from(move |step: i32| scroll_speed.set(auto_scroll::step_speed(*scroll_speed, step)))
+    };
+
+    // Auto-scroll keys from the keymap (ignored while typing), while a song is open
+    {
+        let auto_scrolling = auto_scrolling.clone();
+        let scroll_speed = scroll_speed.clone();
+        let has_entry = props.entry.is_some();
+        use_effect_with((props.keymap.clone(), *auto_scrolling, *scroll_speed, has_entry), move |(keymap, running, speed, has_entry)| {
+            let (keymap, running, speed) = (keymap.clone(), *running, *speed);
+            let document = web_sys::window().unwrap().document().unwrap();
+            let keydown = has_entry.then(|| EventListener::new(&document, "keydown", move |event| {
+                let event = event.dyn_ref::<web_sys::KeyboardEvent>().unwrap();
+                if event.ctrl_key() || event.meta_key() || event.alt_key() || utils::is_typing(event) {
+                    return;
+                }
+                match keymap.action_for(&event.key()) {
+                    Some(Action::AutoScroll) => auto_scrolling.set(!running),
+                    Some(Action::ScrollFaster) => scroll_speed.set(auto_scroll::step_speed(speed, 1)),
+                    Some(Action::ScrollSlower) => scroll_speed.set(auto_scroll::step_speed(speed, -1)),
+                    _ => return,
+                }
+                event.prevent_default();
+            }));
+            move || drop(keydown)
+        });
+    }
+
+    let metronome_open = use_state(|| false);
+    let on_toggle_metronome = {
+        let metronome_open = metronome_open.clone();
+        Callback::from(move |_: MouseEvent| metronome_open.set(!*metronome_open))
+    };
+    let drone_open = use_state(|| false);
+    let on_toggle_drone = {
+        let drone_open = drone_open.clone();
+        Callback::from(move |_: MouseEvent| drone_open.set(!*drone_open))
+    };
+
+    // Drawing tool in use (None when just reading)
+    let tool = use_state(|| Option::<Tool>::None);
+    let song_annotations = use_state(SongAnnotations::new);
+
+    // Load the song's drawings whenever a different song is shown
+    {
+        let song_annotations = song_annotations.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        use_effect_with(entry_id, move |entry_id| {
+            song_annotations.set(entry_id.map(annotations::load).unwrap_or_default());
+            || ()
+        });
+    }
+
+    // Personal note on the song, saved as it's typed (and followed as other
+    // tabs edit it)
+    let note = use_state(String::new);
+    {
+        let note = note.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        use_effect_with(entry_id, move |entry_id| {
+            let entry_id = *entry_id;
+            note.set(entry_id.map(notes::get).unwrap_or_default());
+            let listener = entry_id.map(|id| notes::watch(move || note.set(notes::get(id))));
+            move || drop(listener)
+        });
+    }
+    let on_note_input = {
+        let note = note.clone();
+        let entry = props.entry.clone();
+        Callback::from(move |e: InputEvent| {
+            let Some(entry) = &entry else {
+                return;
+            };
+            let text = e.target_unchecked_into::<web_sys::HtmlTextAreaElement>().value();
+            notes::save(entry, &text);
+            note.set(text);
+        })
+    };
+
+    // Add the tag typed in the tag box (on Enter, or when it loses focus)
+    let on_add_tag = {
+        let entry = props.entry.clone();
+        let tags = props.tags.clone();
+        let on_tags_change = props.on_tags_change.clone();
+        Callback::from(move |e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let tag = input.value();
+            input.set_value("");
+            if let Some(entry) = &entry
+                && !tag.trim().is_empty() {
+                let mut tags = tags.clone();
+                tags.push(tag);
+                on_tags_change.emit((entry.clone(), tags));
+            }
+        })
+    };
+
+    // Sticker or bookmark waiting to be placed (set instead of a drawing tool)
+    let placing = use_state(|| Option::<Placement>::None);
+
+    // Save one page's annotations; anything being placed is now placed
+    let on_page_annotations_change = {
+        let song_annotations = song_annotations.clone();
+        let placing = placing.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        Callback::from(move |(page, page_annotations): (u32, PageAnnotations)| {
+            let Some(entry_id) = entry_id else {
+                return;
+            };
+            placing.set(None);
+            let mut updated = (*song_annotations).clone();
+            updated.insert(page, page_annotations);
+            annotations::save(entry_id, &updated);
+            song_annotations.set(updated);
+        })
+    };
+
+    // Ask for a name, then place the bookmark with the next tap on a page
+    let on_add_bookmark = {
+        let tool = tool.clone();
+        let placing = placing.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(name) = utils::prompt_text(t.bookmark_name_prompt) {
+                tool.set(None);
+                placing.set(Some(Placement::Bookmark(name)));
+            }
+        })
+    };
+
+    let on_remove_bookmark = {
+        let song_annotations = song_annotations.clone();
+        let on_page_annotations_change = on_page_annotations_change.clone();
+        Callback::from(move |(page, index): (u32, usize)| {
+            if let Some(page_annotations) = song_annotations.get(&page) {
+                let mut page_annotations = page_annotations.clone();
+                page_annotations.bookmarks.remove(index);
+                on_page_annotations_change.emit((page, page_annotations));
+            }
+        })
+    };
+
+    // Bookmarks in reading order: (page, index on the page, name)
+    let bookmarks: Vec<(u32, usize, String)> = {
+        let mut bookmarks: Vec<(u32, usize, f32, String)> = song_annotations
+            .iter()
+            .flat_map(|(page, notes)| {
+                notes.bookmarks.iter().enumerate().map(|(index, bookmark)| (*page, index, bookmark.y, bookmark.name.clone()))
+            })
+            .collect();
+        bookmarks.sort_by(|a, b| (a.0, a.2).partial_cmp(&(b.0, b.2)).unwrap_or(std::cmp::Ordering::Equal));
+        bookmarks.into_iter().map(|(page, index, _, name)| (page, index, name)).collect()
+    };
+
+    // Selecting the active tool again puts it down
+    let on_tool_select = {
+        let tool = tool.clone();
+        let placing = placing.clone();
+        Callback::from(move |selected: Tool| {
+            placing.set(None);
+            tool.set((*tool != Some(selected)).then_some(selected));
+        })
+    };
+
+    // The marker menu's value is the sticker text; "text" asks for custom text
+    let on_marker_select = {
+        let tool = tool.clone();
+        let placing = placing.clone();
+        Callback::from(move |e: Event| {
+            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+            let text = match select.value().as_str() {
+                "" => None,
+                "text" => utils::prompt_text(t.sticker_text_prompt),
+                marker => Some(marker.to_string()),
+            };
+            if text.is_some() {
+                tool.set(None);
+            } else {
+                select.set_value("");
+            }
+            placing.set(text.map(Placement::Sticker));
+        })
+    };
+
+    // Page (0-based) the last swipe moved to
+    let current_page = use_state(|| 0usize);
+    // Where the current single-finger touch started
+    let touch_start = use_mut_ref(|| Option::<(f64, f64)>::None);
+
+    // Start from the first page whenever a different song is shown
+    {
+        let current_page = current_page.clone();
+        let entry_id = props.entry.as_ref().map(|entry| entry.id);
+        use_effect_with(entry_id, move |_| {
+            current_page.set(0);
+            || ()
+        });
+    }
+
+    let page_count = props.entry.as_ref().map_or(0, |entry| entry.page_e - entry.page_s + 1) as i32;
+
+    // Keep any stage window on this song and page; one that opens later asks
+    // for them with `Ready`
+    let stage_shown = use_mut_ref(|| Option::<StageMessage>::None);
+    let stage_channel = use_mut_ref(|| Option::<StageChannel>::None);
+    {
+        let stage_shown = stage_shown.clone();
+        let stage_channel = stage_channel.clone();
+        use_effect_with((), move |_| {
+            *stage_channel.borrow_mut() = StageChannel::open({
+                let stage_shown = stage_shown.clone();
+                let stage_channel = stage_channel.clone();
+                move |message| {
+                    if message == StageMessage::Ready
+                        && let (Some(shown), Some(channel)) = (&*stage_shown.borrow(), &*stage_channel.borrow()) {
+                        channel.send(shown);
+                    }
+                }
+            });
+            move || drop(stage_channel.borrow_mut().take())
+        });
+    }
+    {
+        let entry = props.entry.clone();
+        use_effect_with((entry, *current_page), move |(entry, page)| {
+            let shown = entry.clone().map(|entry| StageMessage::Show { entry, page: *page });
+            if let (Some(shown), Some(channel)) = (&shown, &*stage_channel.borrow()) {
+                channel.send(shown);
+            }
+            *stage_shown.borrow_mut() = shown;
+            || ()
+        });
+    }
+
+    // Page-turn keys from the keymap (←/→, PageUp/PageDown; ignored while typing)
+    // Only listened for while a multi-page chart is open (or pages turn by half
+    // a screen), so single-page charts keep the browser's own arrow-key and
+    // PageUp/PageDown scrolling
+    {
+        let current_page = current_page.clone();
+        let images_ref = images_ref.clone();
+        let half = props.half_page_turns;
+        use_effect_with((props.keymap.clone(), *current_page, page_count, half), move |(keymap, page, page_count, half)| {
+            let (keymap, page, page_count, half) = (keymap.clone(), *page as i32, *page_count, *half);
+            let document = web_sys::window().unwrap().document().unwrap();
+            let keydown = (page_count > 1 || half).then(|| EventListener::new(&document, "keydown", move |event| {
+                let event = event.dyn_ref::<web_sys::KeyboardEvent>().unwrap();
+                if event.ctrl_key() || event.meta_key() || event.alt_key() || utils::is_typing(event) {
+                    return;
+                }
+                let step = match keymap.action_for(&event.key()) {
+                    Some(Action::NextPage) => 1,
+                    Some(Action::PrevPage) => -1,
+                    _ => return,
+                };
+                // Past the first/last page the key keeps its usual scrolling
+                if let Some(target) = turn_page(&images_ref, page, page_count, step, half) {
+                    event.prevent_default();
+                    current_page.set(target);
+                }
+            }));
+            move || drop(keydown)
+        });
+    }
+
+    // Pedal page turns go on to the adjacent song past the first/last page,
+    // like swipes, so a pedal alone can walk through a setlist
+    // (turns from before the viewer was mounted are ignored)
+    let handled_turn = use_mut_ref(|| props.page_turn.serial);
+    {
+        let current_page = current_page.clone();
+        let images_ref = images_ref.clone();
+        let on_adjacent_song = props.on_adjacent_song.clone();
+        let half = props.half_page_turns;
+        use_effect_with(props.page_turn, move |turn| {
+            if *handled_turn.borrow() != turn.serial {
+                *handled_turn.borrow_mut() = turn.serial;
+                match turn_page(&images_ref, *current_page as i32, page_count, turn.step, half) {
+                    Some(target) => current_page.set(target),
+                    None => on_adjacent_song.emit(turn.step),
+                }
+            }
+            || ()
+        });
+    }
+
+    // Page turns in time with the metronome
+    let on_metronome_page = {
+        let current_page = current_page.clone();
+        let images_ref = images_ref.clone();
+        Callback::from(move |page: u32| {
+            current_page.set(page as usize);
+            scroll_to_page(&images_ref, page as usize);
+        })
+    };
+
+    let on_touch_start = {
+        let touch_start = touch_start.clone();
+        let drawing = tool.is_some() || placing.is_some();
+        Callback::from(move |e: TouchEvent| {
+            let touches = e.touches();
+            *touch_start.borrow_mut() = match touches.length() {
+                1 if !drawing => touches.get(0).map(|touch| (f64::from(touch.client_x()), f64::from(touch.client_y()))),
+                _ => None,
+            };
+        })
+    };
+
+    // A second finger means pinch-zoom, not a swipe
+    let on_touch_move = {
+        let touch_start = touch_start.clone();
+        Callback::from(move |e: TouchEvent| {
+            if e.touches().length() > 1 {
+                *touch_start.borrow_mut() = None;
+            }
+        })
+    };
+
+    let on_touch_end = {
+        let touch_start = touch_start.clone();
+        let current_page = current_page.clone();
+        let images_ref = images_ref.clone();
+        let on_adjacent_song = props.on_adjacent_song.clone();
+        let half = props.half_page_turns;
+        Callback::from(move |e: TouchEvent| {
+            let Some((start_x, start_y)) = touch_start.borrow_mut().take() else {
+                return;
+            };
+            let Some(end) = e.changed_touches().get(0) else {
+                return;
+            };
+            let dx = f64::from(end.client_x()) - start_x;
+            let dy = f64::from(end.client_y()) - start_y;
+            let Some(step) = utils::swipe_step(dx, dy) else {
+                return;
+            };
+
+            match turn_page(&images_ref, *current_page as i32, page_count, step, half) {
+                Some(target) => current_page.set(target),
+                None => on_adjacent_song.emit(step),
+            }
+        })
+    };
+
+    let page_keys_hint = {
+        let keys = |action| props.keymap.keys(action).iter().map(|key| keymap::key_label(key)).collect::<Vec<_>>().join("/");
+        i18n::fill(t.page_keys_hint, &[&keys(Action::PrevPage), &keys(Action::NextPage)])
+    };
+
+    let sticker_text = match &*placing {
+        Some(Placement::Sticker(text)) => Some(text.as_str()),
+        _ => None,
+    };
+
     html! {
-        // aria-busy shows Pico CSS's built-in loading spinner
-        <article aria-busy={props.loading.to_string()}>
+        <article
+            class="sheet-viewer"
+            ref={viewer_ref}
+            tabindex="-1"
+            role={props.modal.then_some("dialog")}
+            aria-modal={props.modal.then_some("true")}
+            aria-labelledby={props.entry.is_some().then_some(SHEET_TITLE_ID)}
+        >
             {
                 if let Some(entry) = &props.entry {
                     html! {
                         <>
                             <header>
-                                <h2>{ &entry.title }</h2>
+                                <button class="secondary outline back-to-results" onclick={props.on_close.reform(|_: MouseEvent| ())}>
+                                    { t.back_to_results }
+                                </button>
+                                if let Some(collapsed) = props.results_collapsed {
+                                    <button
+                                        class="secondary outline toggle-results"
+                                        aria-pressed={collapsed.to_string()}
+                                        title={(!collapsed).then_some(t.hide_results_title)}
+                                        onclick={props.on_toggle_results.reform(|_: MouseEvent| ())}
+                                    >
+                                        { if collapsed { t.show_results } else { t.hide_results } }
+                                    </button>
+                                }
+                                <h2 id={SHEET_TITLE_ID}>
+                                    <button
+                                        class={classes!("star-toggle", props.favorite.then_some("starred"))}
+                                        onclick={{
+                                            let entry = entry.clone();
+                                            props.on_toggle_favorite.reform(move |_: MouseEvent| entry.clone())
+                                        }}
+                                        aria-pressed={props.favorite.to_string()}
+                                        aria-label={if props.favorite { t.unstar } else { t.star }}
+                                    >
+                                        { if props.favorite { "★" } else { "☆" } }
+                                    </button>
+                                    { &entry.title }
+                                </h2>
                                 <p>
-                                    { format!("Volume {} | Pages {}", entry.volume, entry.page_range()) }
+                                    { i18n::fill(t.song_meta, &[&entry.volume, &entry.page_range()]) }
                                 </p>
-                            </header>
-
-                            <div class="sheet-images">
                                 {
-                                    for entry.all_image_urls().iter().map(|url| {
+                                    if props.saved_offline {
+                                        html! { <small class="offline-status">{ t.available_offline }</small> }
+                                    } else {
+                                        let on_save = {
+                                            let entry = entry.clone();
+                                            props.on_save_offline.reform(move |_: MouseEvent| entry.clone())
+                                        };
                                         html! {
-                                            <SheetImage
-                                                url={url.clone()}
-                                                alt={format!("Sheet music for {}", entry.title)}
-                                            />
+                                            <button
+                                                class="secondary outline"
+                                                onclick={on_save}
+                                                disabled={props.saving_offline}
+                                                aria-busy={props.saving_offline.to_string()}
+                                            >
+                                                { t.save_offline }
+                                            </button>
+                                        }
+                                    }
+                                }
+                                <select
+                                    class="marker-select"
+                                    aria-label={t.add_to_setlist}
+                                    onchange={{
+                                        let entry = entry.clone();
+                                        props.on_add_to_setlist.reform(move |e: Event| {
+                                            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                                            let value = select.value();
+                                            select.set_value("");
+                                            ((value != "new").then_some(value), entry.clone())
+                                        })
+                                    }}
+                                >
+                                    <option value="" selected=true disabled=true>{ t.add_to_setlist_option }</option>
+                                    {
+                                        for props.setlists.iter().map(|setlist| html! {
+                                            <option value={setlist.id.clone()}>{ &setlist.name }</option>
+                                        })
+                                    }
+                                    <option value="new">{ t.new_setlist_option }</option>
+                                </select>
+                                <button class="secondary outline print-button" onclick={on_share.clone()}>
+                                    { t.share }
+                                </button>
+                                <button class="secondary outline print-button" onclick={on_toggle_qr.reform(|_: MouseEvent| true)}>
+                                    { t.qr_code }
+                                </button>
+                                <button
+                                    class="secondary outline print-button"
+                                    title={t.stage_view_title}
+                                    onclick={on_open_stage}
+                                >
+                                    { t.stage_view }
+                                </button>
+                                if *qr_open {
+                                    <QrDialog
+                                        url={router::song_url(entry.id)}
+                                        title={entry.title.clone()}
+                                        on_close={on_toggle_qr.reform(|()| false)}
+                                    />
+                                }
+                                <button class="secondary outline print-button" onclick={on_print.clone()}>
+                                    { t.print }
+                                </button>
+                                <a
+                                    role="button"
+                                    class="secondary outline print-button"
+                                    href={api::entry_pdf_url(entry.id)}
+                                    download=""
+                                >
+                                    { t.download_pdf }
+                                </a>
+                                if let Some(url) = &song_attachments.musicxml_url {
+                                    <a role="button" class="secondary outline print-button" href={url.clone()} download="">
+                                        { t.download_musicxml }
+                                    </a>
+                                }
+                                if let Some(uri) = &song_attachments.irealpro {
+                                    // Opens the iReal Pro app (not a web page), so no new tab
+                                    <a role="button" class="secondary outline print-button" href={uri.clone()}>
+                                        { t.open_in_irealpro }
+                                    </a>
+                                }
+                                <div class="listen-links" role="group" aria-label={t.listen}>
+                                    {
+                                        // The catalog has no composers yet, so search by title alone
+                                        for Service::ALL.iter().map(|service| html! {
+                                            <a
+                                                role="button"
+                                                class="secondary outline"
+                                                href={service.search_url(&listen::search_terms(&entry.title, None))}
+                                                target="_blank"
+                                                rel="noopener noreferrer"
+                                                title={i18n::fill(t.listen_on, &[&service.name()])}
+                                            >
+                                                { service.name() }
+                                            </a>
+                                        })
+                                    }
+                                </div>
+                                <div class="annotate-tools" role="group" aria-label={t.draw_on_pages}>
+                                    {
+                                        for Tool::ALL.iter().map(|&option| html! {
+                                            <button
+                                                class={classes!("secondary", (*tool != Some(option)).then_some("outline"))}
+                                                aria-pressed={(*tool == Some(option)).to_string()}
+                                                onclick={on_tool_select.reform(move |_: MouseEvent| option)}
+                                            >
+                                                { t.tool_label(option) }
+                                            </button>
+                                        })
+                                    }
+                                </div>
+                                <select class="marker-select" aria-label={t.add_marker} onchange={on_marker_select.clone()}>
+                                    <option value="" selected={sticker_text.is_none()}>{ t.add_marker_option }</option>
+                                    {
+                                        for MARKERS.iter().map(|(name, text)| html! {
+                                            <option value={*text} selected={sticker_text == Some(*text)}>{ *name }</option>
+                                        })
+                                    }
+                                    <option
+                                        value="text"
+                                        selected={sticker_text.is_some_and(|text| !MARKERS.iter().any(|(_, marker)| *marker == text))}
+                                    >
+                                        { t.marker_text_option }
+                                    </option>
+                                </select>
+                                <button
+                                    class={classes!("secondary", "print-button", (!matches!(*placing, Some(Placement::Bookmark(_)))).then_some("outline"))}
+                                    onclick={on_add_bookmark.clone()}
+                                >
+                                    { t.add_bookmark }
+                                </button>
+                                <button
+                                    class={classes!("secondary", "print-button", (!*metronome_open).then_some("outline"))}
+                                    aria-pressed={metronome_open.to_string()}
+                                    onclick={on_toggle_metronome.clone()}
+                                >
+                                    { t.metronome }
+                                </button>
+                                <button
+                                    class={classes!("secondary", "print-button", (!*drone_open).then_some("outline"))}
+                                    aria-pressed={drone_open.to_string()}
+                                    onclick={on_toggle_drone.clone()}
+                                >
+                                    { t.drone }
+                                </button>
+                                <PracticeTimer entry={entry.clone()} on_logged={props.on_practice_logged.clone()} />
+                                <div class="zoom-controls" role="group" aria-label={t.zoom}>
+                                    <button class="secondary outline" onclick={on_zoom_out.clone()} disabled={*zoom <= MIN_ZOOM} aria-label={t.zoom_out}>
+                                        { "−" }
+                                    </button>
+                                    <button class="secondary outline" onclick={on_zoom_reset.clone()} title={t.zoom_reset}>
+                                        { format!("{}%", *zoom) }
+                                    </button>
+                                    <button class="secondary outline" onclick={on_zoom_in.clone()} disabled={*zoom >= MAX_ZOOM} aria-label={t.zoom_in}>
+                                        { "+" }
+                                    </button>
+                                </div>
+                                <div class="zoom-controls" role="group" aria-label={t.auto_scroll}>
+                                    <button class="secondary outline" onclick={on_scroll_speed.reform(|_: MouseEvent| -1)} disabled={*scroll_speed <= auto_scroll::MIN_SPEED} aria-label={t.auto_scroll_slower}>
+                                        { "−" }
+                                    </button>
+                                    <button
+                                        class={classes!("secondary", (!*auto_scrolling).then_some("outline"))}
+                                        aria-pressed={auto_scrolling.to_string()}
+                                        onclick={on_toggle_auto_scroll}
+                                        title={i18n::fill(t.auto_scroll_speed, &[&*scroll_speed])}
+                                    >
+                                        { if *auto_scrolling { t.auto_scroll_pause } else { t.auto_scroll } }
+                                    </button>
+                                    <button class="secondary outline" onclick={on_scroll_speed.reform(|_: MouseEvent| 1)} disabled={*scroll_speed >= auto_scroll::MAX_SPEED} aria-label={t.auto_scroll_faster}>
+                                        { "+" }
+                                    </button>
+                                </div>
+                                if page_count > 1 {
+                                    <span class="page-position" title={page_keys_hint.clone()}>
+                                        { i18n::fill(t.page_position, &[&(*current_page + 1), &page_count]) }
+                                    </span>
+                                }
+                                <div class="song-progress">
+                                    <select
+                                        aria-label={t.song_status}
+                                        onchange={{
+                                            let entry = entry.clone();
+                                            let (_, rating) = props.progress;
+                                            props.on_progress_change.reform(move |e: Event| {
+                                                let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                                                (entry.clone(), Status::from_value(&select.value()), rating)
+                                            })
+                                        }}
+                                    >
+                                        <option value="" selected={props.progress.0.is_none()}>{ t.status_none_option }</option>
+                                        {
+                                            for Status::ALL.iter().map(|&status| html! {
+                                                <option value={status.value()} selected={props.progress.0 == Some(status)}>
+                                                    { t.status_label(status) }
+                                                </option>
+                                            })
+                                        }
+                                    </select>
+                                    <span class="star-rating" role="group" aria-label={t.song_rating}>
+                                        {
+                                            for (1..=repertoire::MAX_RATING).map(|stars| {
+                                                let (status, rating) = props.progress;
+                                                let lit = rating.is_some_and(|rating| stars <= rating);
+                                                // Picking the current rating again clears it
+                                                let picked = (rating != Some(stars)).then_some(stars);
+                                                let entry = entry.clone();
+                                                html! {
+                                                    <button
+                                                        class={classes!(lit.then_some("lit"))}
+                                                        aria-pressed={(rating == Some(stars)).to_string()}
+                                                        aria-label={i18n::fill(t.rate_stars, &[&stars])}
+                                                        onclick={props.on_progress_change.reform(move |_: MouseEvent| (entry.clone(), status, picked))}
+                                                    >
+                                                        { if lit { "★" } else { "☆" } }
+                                                    </button>
+                                                }
+                                            })
                                         }
-                                    })
+                                    </span>
+                                </div>
+                                <div class="song-tags" role="group" aria-label={t.song_tags}>
+                                    {
+                                        for props.tags.iter().map(|tag| {
+                                            let remaining: Vec<String> = props.tags.iter().filter(|other| *other != tag).cloned().collect();
+                                            let entry = entry.clone();
+                                            html! {
+                                                <button
+                                                    class="chip secondary outline"
+                                                    onclick={props.on_tags_change.reform(move |_: MouseEvent| (entry.clone(), remaining.clone()))}
+                                                    aria-label={i18n::fill(t.remove_tag, &[tag])}
+                                                >
+                                                    { format!("#{} ✕", tag) }
+                                                </button>
+                                            }
+                                        })
+                                    }
+                                    <input
+                                        type="text"
+                                        class="tag-input"
+                                        list={TAG_SUGGESTIONS_ID}
+                                        placeholder={t.add_tag_placeholder}
+                                        aria-label={t.add_tag_placeholder}
+                                        onchange={on_add_tag}
+                                    />
+                                    <datalist id={TAG_SUGGESTIONS_ID}>
+                                        {
+                                            for props.all_tags.iter().filter(|tag| !props.tags.contains(tag)).map(|tag| html! {
+                                                <option value={tag.clone()} />
+                                            })
+                                        }
+                                    </datalist>
+                                </div>
+                                <textarea
+                                    class="song-notes"
+                                    rows="2"
+                                    aria-label={t.song_notes}
+                                    placeholder={t.song_notes_placeholder}
+                                    value={(*note).clone()}
+                                    oninput={on_note_input}
+                                />
+                            </header>
+
+                            <div class="sheet-body">
+                                if !bookmarks.is_empty() {
+                                    <aside class="bookmark-list" aria-label={t.bookmarks}>
+                                        <ul>
+                                            {
+                                                for bookmarks.iter().map(|(page, index, name)| {
+                                                    let id = annotations::bookmark_id(*page, *index);
+                                                    let (page, index) = (*page, *index);
+                                                    html! {
+                                                        <li>
+                                                            <a href={format!("#{}", id)} onclick={Callback::from(move |e: MouseEvent| {
+                                                                e.prevent_default();
+                                                                scroll_to_bookmark(&id);
+                                                            })}>
+                                                                { name }
+                                                            </a>
+                                                            <button
+                                                                class="secondary outline"
+                                                                onclick={on_remove_bookmark.reform(move |_: MouseEvent| (page, index))}
+                                                                aria-label={i18n::fill(t.remove_bookmark, &[name])}
+                                                            >
+                                                                { "×" }
+                                                            </button>
+                                                        </li>
+                                                    }
+                                                })
+                                            }
+                                        </ul>
+                                    </aside>
                                 }
+                                // Scrolls in both directions once zoomed past the viewer width
+                                <div class="sheet-zoom" ref={zoom_ref.clone()}>
+                                    <div
+                                        class="sheet-images"
+                                        style={format!("width: {}%;", *zoom)}
+                                        ref={images_ref.clone()}
+                                        ontouchstart={on_touch_start.clone()}
+                                        ontouchmove={on_touch_move.clone()}
+                                        ontouchend={on_touch_end.clone()}
+                                    >
+                                        {
+                                            for (entry.page_s..=entry.page_e).map(|page| {
+                                                html! {
+                                                    <SheetImage
+                                                        url={entry.image_url(page)}
+                                                        thumbnail_url={entry.thumbnail_url(page)}
+                                                        alt={i18n::fill(t.sheet_alt, &[&entry.title])}
+                                                        volume={entry.volume}
+                                                        {page}
+                                                        filename={entry.page_filename(page)}
+                                                        annotations={song_annotations.get(&page).cloned().unwrap_or_default()}
+                                                        tool={*tool}
+                                                        placing={(*placing).clone()}
+                                                        on_annotations_change={on_page_annotations_change.reform(move |notes| (page, notes))}
+                                                    />
+                                                }
+                                            })
+                                        }
+                                    </div>
+                                </div>
                             </div>
+                            <RecordingPlayer attachments={song_attachments.clone()} />
+                            <ReportProblem key={entry.id} entry={entry.clone()} />
+                            if *metronome_open || *drone_open {
+                                <div class="practice-docks">
+                                    if *metronome_open {
+                                        <MetronomeDock
+                                            entry_id={entry.id}
+                                            page_count={page_count as u32}
+                                            on_page_turn={on_metronome_page.clone()}
+                                        />
+                                    }
+                                    if *drone_open {
+                                        <DroneDock />
+                                    }
+                                </div>
+                            }
+                            if let Some(next) = &props.next_entry {
+                                <div class="preload-pages" hidden=true aria-hidden="true">
+                                    {
+                                        for next.all_image_urls().into_iter().map(|url| html! {
+                                            <img key={url.clone()} src={url} alt="" fetchpriority="low" />
+                                        })
+                                    }
+                                </div>
+                            }
                         </>
                     }
+                } else if props.loading {
+                    // Skeleton of the title, details, and first page
+                    html! {
+                        <div role="status" aria-label={t.loading_song}>
+                            <div class="skeleton skeleton-line skeleton-title" />
+                            <div class="skeleton skeleton-line skeleton-meta" />
+                            <div class="skeleton page-skeleton" />
+                        </div>
+                    }
                 } else {
                     html! { <></> }
                 }
@@ -0,0 +1,83 @@
+use yew::prelude::*;
+use crate::i18n::{self, Locale};
+use crate::models::RealBookEntry;
+use crate::stage::{StageChannel, StageMessage};
+use crate::theme::Appearance;
+
+/// StageView component - the chart-only window for a monitor facing the band
+///
+/// Rendered instead of the App at `/stage` (see `stage.rs`). Shows one page of
+/// the song open in the main window, as large as the screen allows, and follows
+/// its song changes and page turns. Every page of the song is loaded up front
+/// (only the current one is shown), so turns are instant. Takes the saved
+/// appearance and language, as the App would.
+#[function_component(StageView)]
+pub fn stage_view() -> Html {
+    let t = use_memo((), |_| Locale::load()).messages();
+    let shown = use_state(|| Option::<(RealBookEntry, usize)>::None);
+
+    {
+        let shown = shown.clone();
+        use_effect_with((), move |_| {
+            Appearance::load().apply();
+            Locale::load().apply();
+
+            let channel = StageChannel::open(move |message| {
+                if let StageMessage::Show { entry, page } = message {
+                    shown.set(Some((entry, page)));
+                }
+            });
+            // Ask the main window for the chart it's showing
+            if let Some(channel) = &channel {
+                channel.send(&StageMessage::Ready);
+            }
+            move || drop(channel)
+        });
+    }
+
+    // Name the window after the song, to tell it apart in the taskbar
+    {
+        let title = shown.as_ref().map(|(entry, _)| entry.title.clone());
+        let fallback = t.stage_view;
+        use_effect_with(title, move |title| {
+            if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+                document.set_title(title.as_deref().unwrap_or(fallback));
+            }
+            || ()
+        });
+    }
+
+    html! {
+        <main class="stage-view">
+            {
+                match &*shown {
+                    None => html! { <p class="stage-waiting">{ t.stage_waiting }</p> },
+                    Some((entry, page)) => {
+                        let page_count = entry.page_e - entry.page_s + 1;
+                        html! {
+                            <>
+                                {
+                                    for (entry.page_s..=entry.page_e).enumerate().map(|(index, number)| html! {
+                                        <img
+                                            key={number}
+                                            class="stage-page"
+                                            src={entry.image_url(number)}
+                                            alt={i18n::fill(t.sheet_alt, &[&entry.title])}
+                                            hidden={index != *page}
+                                        />
+                                    })
+                                }
+                                <p class="stage-caption">
+                                    <strong>{ &entry.title }</strong>
+                                    if page_count > 1 {
+                                        { " · " }{ i18n::fill(t.page_position, &[&(page + 1), &page_count]) }
+                                    }
+                                </p>
+                            </>
+                        }
+                    }
+                }
+            }
+        </main>
+    }
+}
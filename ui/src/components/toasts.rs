@@ -0,0 +1,151 @@
+use std::rc::Rc;
+use gloo_timers::callback::Timeout;
+use yew::prelude::*;
+
+/// How long a toast stays up, in milliseconds (errors get longer to be read)
+const TOAST_MS: u32 = 4_000;
+const ERROR_TOAST_MS: u32 = 8_000;
+
+/// Kind of message, which sets the toast's color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Toast {
+    id: u32,
+    kind: ToastKind,
+    message: String,
+}
+
+/// Toasts on screen, oldest first
+#[derive(Default, PartialEq)]
+struct ToastList {
+    toasts: Vec<Toast>,
+}
+
+enum ToastAction {
+    Show(Toast),
+    Dismiss(u32),
+}
+
+impl Reducible for ToastList {
+    type Action = ToastAction;
+
+    fn reduce(self: Rc<Self>, action: ToastAction) -> Rc<Self> {
+        let mut toasts = self.toasts.clone();
+        match action {
+            // A repeated message (e.g. the same search error while typing)
+            // replaces the one on screen instead of stacking up
+            ToastAction::Show(new) => {
+                toasts.retain(|toast| !(toast.kind == new.kind && toast.message == new.message));
+                toasts.push(new);
+            }
+            ToastAction::Dismiss(id) => toasts.retain(|toast| toast.id != id),
+        }
+        Rc::new(ToastList { toasts })
+    }
+}
+
+/// Shows transient messages; get one with `use_toaster()` anywhere under
+/// `ToastProvider`
+#[derive(Clone, PartialEq)]
+pub struct Toaster {
+    show: Callback<(ToastKind, String)>,
+}
+
+impl Toaster {
+    pub fn info(&self, message: impl Into<String>) {
+        self.show.emit((ToastKind::Info, message.into()));
+    }
+
+    pub fn success(&self, message: impl Into<String>) {
+        self.show.emit((ToastKind::Success, message.into()));
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.show.emit((ToastKind::Error, message.into()));
+    }
+}
+
+/// The `Toaster` provided by the enclosing `ToastProvider`
+#[hook]
+pub fn use_toaster() -> Toaster {
+    use_context::<Toaster>().expect("use_toaster called outside ToastProvider")
+}
+
+/// Props for the ToastProvider component
+#[derive(Properties, PartialEq)]
+pub struct ToastProviderProps {
+    /// The app, which can show toasts through `use_toaster()`
+    pub children: Children,
+}
+
+/// ToastProvider component - provides a `Toaster` context and renders its toasts
+///
+/// Toasts stack in a corner of the page (rendered into `<body>` through a
+/// portal, so they sit above every pane) and dismiss themselves after a few
+/// seconds, or when clicked.
+#[function_component(ToastProvider)]
+pub fn toast_provider(props: &ToastProviderProps) -> Html {
+    let list = use_reducer(ToastList::default);
+    let next_id = use_mut_ref(|| 0u32);
+
+    let toaster = {
+        let dispatcher = list.dispatcher();
+        use_memo((), move |_| Toaster {
+            show: Callback::from(move |(kind, message): (ToastKind, String)| {
+                let id = {
+                    let mut next_id = next_id.borrow_mut();
+                    *next_id += 1;
+                    *next_id
+                };
+                dispatcher.dispatch(ToastAction::Show(Toast { id, kind, message }));
+                let duration = if kind == ToastKind::Error { ERROR_TOAST_MS } else { TOAST_MS };
+                let dispatcher = dispatcher.clone();
+                Timeout::new(duration, move || dispatcher.dispatch(ToastAction::Dismiss(id))).forget();
+            }),
+        })
+    };
+
+    let stack = html! {
+        <div class="toast-stack" aria-live="polite">
+            {
+                for list.toasts.iter().map(|toast| {
+                    let id = toast.id;
+                    let dispatcher = list.dispatcher();
+                    let class = match toast.kind {
+                        ToastKind::Info => "toast",
+                        ToastKind::Success => "toast toast-success",
+                        ToastKind::Error => "toast toast-error",
+                    };
+                    html! {
+                        <div
+                            key={id}
+                            {class}
+                            role={if toast.kind == ToastKind::Error { "alert" } else { "status" }}
+                            title="Dismiss"
+                            onclick={Callback::from(move |_: MouseEvent| dispatcher.dispatch(ToastAction::Dismiss(id)))}
+                        >
+                            { &toast.message }
+                        </div>
+                    }
+                })
+            }
+        </div>
+    };
+
+    let host = web_sys::window().and_then(|window| window.document()).and_then(|document| document.body());
+
+    html! {
+        <ContextProvider<Toaster> context={(*toaster).clone()}>
+            { props.children.clone() }
+            if let Some(host) = host {
+                { create_portal(stack, host.into()) }
+            }
+        </ContextProvider<Toaster>>
+    }
+}
@@ -0,0 +1,48 @@
+use yew::prelude::*;
+use crate::i18n::{self, use_messages};
+use crate::models::Release;
+
+/// Most change lines listed before the rest are counted instead
+const MAX_LINES: usize = 5;
+
+/// Props for the WhatsNew component
+#[derive(Properties, PartialEq)]
+pub struct WhatsNewProps {
+    /// Catalog releases not seen yet, newest first (nothing shows if empty)
+    pub releases: Vec<Release>,
+
+    /// Callback when the banner is dismissed
+    pub on_dismiss: Callback<()>,
+}
+
+/// WhatsNew component - dismissible "catalog updated" banner
+///
+/// Lists the changes of the releases since the user's last visit (see
+/// `changelog.rs`), newest first, until dismissed.
+#[function_component(WhatsNew)]
+pub fn whats_new(props: &WhatsNewProps) -> Html {
+    let t = use_messages();
+    if props.releases.is_empty() {
+        return html! {};
+    }
+
+    let changes: Vec<&String> = props.releases.iter().flat_map(|release| &release.changes).collect();
+    let hidden = changes.len().saturating_sub(MAX_LINES);
+
+    html! {
+        <aside class="whats-new" role="status">
+            <div>
+                <strong>{ t.catalog_updated }</strong>
+                <ul>
+                    { for changes.iter().take(MAX_LINES).map(|change| html! { <li>{ *change }</li> }) }
+                    if hidden > 0 {
+                        <li>{ i18n::fill(t.more_changes, &[&hidden]) }</li>
+                    }
+                </ul>
+            </div>
+            <button class="secondary outline" onclick={props.on_dismiss.reform(|_| ())} aria-label={t.close}>
+                { "×" }
+            </button>
+        </aside>
+    }
+}
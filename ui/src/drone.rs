@@ -0,0 +1,85 @@
+//! Sustained reference pitch for intonation practice (Web Audio)
+//!
+//! A soft triangle wave on the chosen note with a sine an octave below for
+//! body, in equal temperament from A4 = 440 Hz. Pitch and volume changes glide
+//! rather than jump, so adjusting them while playing doesn't click.
+
+use web_sys::{AudioContext, GainNode, OscillatorNode, OscillatorType};
+
+/// Octaves offered (scientific pitch notation: C4 is middle C)
+pub const OCTAVES: [i32; 4] = [2, 3, 4, 5];
+pub const DEFAULT_OCTAVE: i32 = 3;
+/// Pitch class 9 is A
+pub const DEFAULT_PITCH: u8 = 9;
+pub const DEFAULT_VOLUME: u32 = 50;
+
+/// Loudness at full volume (the drone sits under the playing, not over it)
+const MAX_GAIN: f32 = 0.3;
+/// Fade in/out and glide time constant, in seconds
+const GLIDE_SECS: f64 = 0.05;
+
+/// Frequency in Hz of pitch class `pitch` (0 = C ... 11 = B) in `octave`
+pub fn frequency(pitch: u8, octave: i32) -> f32 {
+    let semitones_from_a4 = (octave - 4) * 12 + pitch as i32 - 9;
+    440.0 * 2f32.powf(semitones_from_a4 as f32 / 12.0)
+}
+
+fn gain_for(volume: u32) -> f32 {
+    MAX_GAIN * volume.min(100) as f32 / 100.0
+}
+
+/// A sounding drone; stops when dropped
+pub struct Drone {
+    context: AudioContext,
+    root: OscillatorNode,
+    sub: OscillatorNode,
+    gain: GainNode,
+}
+
+impl Drone {
+    /// Start the drone (call from a click handler: browsers only allow audio
+    /// to start from a user gesture). None if Web Audio is unavailable.
+    pub fn start(pitch: u8, octave: i32, volume: u32) -> Option<Self> {
+        let context = AudioContext::new().ok()?;
+        let root = context.create_oscillator().ok()?;
+        let sub = context.create_oscillator().ok()?;
+        let sub_gain = context.create_gain().ok()?;
+        let gain = context.create_gain().ok()?;
+
+        root.set_type(OscillatorType::Triangle);
+        sub_gain.gain().set_value(0.5);
+        root.connect_with_audio_node(&gain).ok()?;
+        sub.connect_with_audio_node(&sub_gain).ok()?;
+        sub_gain.connect_with_audio_node(&gain).ok()?;
+        gain.connect_with_audio_node(&context.destination()).ok()?;
+
+        // Fade in from silence
+        gain.gain().set_value(0.0);
+        let drone = Drone { context, root, sub, gain };
+        drone.set_pitch(pitch, octave);
+        drone.set_volume(volume);
+        drone.root.start().ok()?;
+        drone.sub.start().ok()?;
+        Some(drone)
+    }
+
+    /// Glide to another note
+    pub fn set_pitch(&self, pitch: u8, octave: i32) {
+        let now = self.context.current_time();
+        let hz = frequency(pitch, octave);
+        let _ = self.root.frequency().set_target_at_time(hz, now, GLIDE_SECS);
+        let _ = self.sub.frequency().set_target_at_time(hz / 2.0, now, GLIDE_SECS);
+    }
+
+    /// Fade to `volume` (0-100)
+    pub fn set_volume(&self, volume: u32) {
+        let now = self.context.current_time();
+        let _ = self.gain.gain().set_target_at_time(gain_for(volume), now, GLIDE_SECS);
+    }
+}
+
+impl Drop for Drone {
+    fn drop(&mut self) {
+        let _ = self.context.close();
+    }
+}
@@ -0,0 +1,38 @@
+//! Starred songs
+//!
+//! Favorites are kept in localStorage (in the order they were starred), so the
+//! "Favorites" filter can search them locally with the same rules as the catalog.
+
+use gloo_events::EventListener;
+use crate::models::RealBookEntry;
+use crate::storage;
+
+/// localStorage key for the starred songs
+const STORAGE_KEY: &str = "realbook.favorites";
+
+/// Songs the user has starred
+pub fn load() -> Vec<RealBookEntry> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the favorites as other tabs star and unstar songs
+pub fn watch(on_change: impl Fn(Vec<RealBookEntry>) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// Store the whole list (used when restoring an archive)
+pub fn save_all(favorites: &[RealBookEntry]) {
+    storage::save(STORAGE_KEY, &favorites);
+}
+
+/// Star or unstar a song, returning (and storing) the updated list
+pub fn toggle(favorites: &[RealBookEntry], entry: &RealBookEntry) -> Vec<RealBookEntry> {
+    let mut favorites = favorites.to_vec();
+    if let Some(position) = favorites.iter().position(|song| song.id == entry.id) {
+        favorites.remove(position);
+    } else {
+        favorites.push(entry.clone());
+    }
+    storage::save(STORAGE_KEY, &favorites);
+    favorites
+}
@@ -0,0 +1,119 @@
+//! Custom hooks shared by components
+
+use yew::prelude::*;
+use gloo_events::{EventListener, EventListenerOptions};
+use gloo_timers::callback::Timeout;
+use wasm_bindgen::JsCast;
+
+/// Elements Tab stops at
+const FOCUSABLE: &str =
+    "a[href], button:not([disabled]), input:not([disabled]), select:not([disabled]), textarea:not([disabled]), [tabindex]:not([tabindex='-1'])";
+
+/// Whether a CSS media query currently matches, updating when it changes
+/// (e.g. `use_media_query("(max-width: 767px)")` for narrow screens)
+#[hook]
+pub fn use_media_query(query: &'static str) -> bool {
+    let media_query_list = || web_sys::window().and_then(|window| window.match_media(query).ok().flatten());
+    let matches = use_state(|| media_query_list().is_some_and(|list| list.matches()));
+
+    {
+        let matches = matches.clone();
+        use_effect_with(query, move |_| {
+            let listener = media_query_list().map(|list| {
+                let target = list.clone();
+                EventListener::new(&target, "change", move |_| matches.set(list.matches()))
+            });
+            move || drop(listener)
+        });
+    }
+
+    *matches
+}
+
+/// Keep keyboard focus inside `container` while `active`, for a view that
+/// covers the rest of the page. Focus moves to the container (which needs
+/// `tabindex="-1"`) when the trap comes up, Tab and Shift+Tab wrap around its
+/// focusable elements, and focus goes back where it was when it's released.
+#[hook]
+pub fn use_focus_trap(container: NodeRef, active: bool) {
+    use_effect_with(active, move |active| {
+        let trap = web_sys::window()
+            .and_then(|window| window.document())
+            .filter(|_| *active)
+            .map(|document| {
+                let previous = document.active_element();
+                if let Some(element) = container.cast::<web_sys::HtmlElement>() {
+                    let _ = element.focus();
+                }
+
+                let listener = EventListener::new_with_options(
+                    &document.clone(),
+                    "keydown",
+                    EventListenerOptions::enable_prevent_default(),
+                    move |event| {
+                        let Some(event) = event.dyn_ref::<web_sys::KeyboardEvent>() else {
+                            return;
+                        };
+                        let Some(element) = container.cast::<web_sys::Element>() else {
+                            return;
+                        };
+                        if event.key() != "Tab" {
+                            return;
+                        }
+                        let focusable: Vec<web_sys::HtmlElement> = element
+                            .query_selector_all(FOCUSABLE)
+                            .map(|list| (0..list.length()).filter_map(|index| list.item(index)?.dyn_into().ok()).collect())
+                            .unwrap_or_default();
+                        let (Some(first), Some(last)) = (focusable.first(), focusable.last()) else {
+                            event.prevent_default();
+                            return;
+                        };
+                        let current = document.active_element();
+                        let inside = current.as_ref().is_some_and(|current| element.contains(Some(current)));
+                        let at = |target: &web_sys::Node| current.as_ref().is_some_and(|current| current.is_same_node(Some(target)));
+                        let target = if !inside {
+                            Some(first)
+                        } else if event.shift_key() && (at(first) || at(&element)) {
+                            Some(last)
+                        } else if !event.shift_key() && at(last) {
+                            Some(first)
+                        } else {
+                            None
+                        };
+                        if let Some(target) = target {
+                            event.prevent_default();
+                            let _ = target.focus();
+                        }
+                    },
+                );
+                (listener, previous)
+            });
+
+        move || {
+            let Some((listener, previous)) = trap else {
+                return;
+            };
+            drop(listener);
+            // Deferred until the view behind has rendered again; if the element
+            // that had focus was re-created meanwhile, find it again by id
+            Timeout::new(0, move || {
+                let Some(previous) = previous else {
+                    return;
+                };
+                let element = if previous.is_connected() {
+                    Some(previous)
+                } else {
+                    let id = previous.id();
+                    web_sys::window()
+                        .and_then(|window| window.document())
+                        .filter(|_| !id.is_empty())
+                        .and_then(|document| document.get_element_by_id(&id))
+                };
+                if let Some(element) = element.and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok()) {
+                    let _ = element.focus();
+                }
+            })
+            .forget();
+        }
+    });
+}
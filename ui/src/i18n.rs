@@ -0,0 +1,924 @@
+//! UI text in each supported language
+//!
+//! A plain message catalog: `Messages` has a field per string, so a language
+//! missing a translation doesn't compile. Strings with values in them mark each
+//! spot with `{}`, filled in order by `fill`. Components read the catalog for
+//! the active language with `use_messages()`; the language is provided by `App`
+//! as a context, picked in Settings, and kept in localStorage (`realbook.locale`).
+//! Until one is picked, the browser's preferred language is used if supported.
+
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+use crate::annotations::Tool;
+use crate::metronome::Subdivision;
+use crate::models::SortOrder;
+use crate::repertoire::Status;
+use crate::storage;
+
+/// localStorage key for the chosen language
+const STORAGE_KEY: &str = "realbook.locale";
+
+/// Supported UI language
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::En, Locale::Es, Locale::Fr];
+
+    /// Language tag, as used by `<html lang>` and `navigator.language`
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+        }
+    }
+
+    /// Name of the language in that language (for the switcher)
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::Es => "Español",
+            Locale::Fr => "Français",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|locale| locale.code() == code)
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Locale::En => &EN,
+            Locale::Es => &ES,
+            Locale::Fr => &FR,
+        }
+    }
+
+    /// The saved language, else the browser's if supported, else English
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_else(|| {
+            web_sys::window()
+                .and_then(|window| window.navigator().language())
+                .and_then(|language| Locale::from_code(language.split('-').next().unwrap_or_default()))
+                .unwrap_or_default()
+        })
+    }
+
+    /// Save the language and tag the document with it (for screen readers)
+    pub fn apply(self) {
+        storage::save(STORAGE_KEY, &self);
+        if let Some(root) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.document_element())
+        {
+            let _ = root.set_attribute("lang", self.code());
+        }
+    }
+}
+
+/// Catalog for the language provided by the enclosing `App` (English outside it)
+#[hook]
+pub fn use_messages() -> &'static Messages {
+    use_context::<Locale>().unwrap_or_default().messages()
+}
+
+/// Fill a message's `{}` placeholders with `values`, in order
+pub fn fill(template: &str, values: &[&dyn Display]) -> String {
+    let mut values = values.iter();
+    let mut text = String::with_capacity(template.len());
+    for (index, part) in template.split("{}").enumerate() {
+        if index > 0
+            && let Some(value) = values.next() {
+            text.push_str(&value.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+/// Every string shown by the header, search bar, results list, and viewer
+pub struct Messages {
+    // Header
+    pub tagline: &'static str,
+    pub shortcuts_intro: &'static str,
+    pub shortcut_or: &'static str,
+    pub shortcut_search: &'static str,
+    pub shortcut_clear: &'static str,
+    pub shortcut_navigate: &'static str,
+    pub shortcut_first_last: &'static str,
+    pub shortcut_view: &'static str,
+    pub shortcut_random: &'static str,
+    pub shortcut_turn_pages: &'static str,
+    pub shortcut_zoom: &'static str,
+    pub shortcut_rebind: &'static str,
+    pub sync_synced: &'static str,
+    pub sync_syncing: &'static str,
+    pub sync_offline: &'static str,
+    pub sync_failed: &'static str,
+    /// Tooltip on the sync indicator
+    pub sync_now: &'static str,
+
+    // Search bar
+    pub search_placeholder: &'static str,
+    pub recent_searches: &'static str,
+    pub suggestions: &'static str,
+    pub clear_history: &'static str,
+    pub page_placeholder: &'static str,
+    pub page_label: &'static str,
+    pub all_volumes: &'static str,
+    /// `{}`: volume number
+    pub volume_option: &'static str,
+    pub browse: &'static str,
+    pub browse_title: &'static str,
+    pub refine: &'static str,
+    pub refine_title: &'static str,
+    pub random: &'static str,
+    pub random_title: &'static str,
+    /// `{}`: volume number
+    pub random_volume_title: &'static str,
+    pub favorites: &'static str,
+    pub favorites_title: &'static str,
+    pub tag_filter: &'static str,
+    pub all_tags_option: &'static str,
+    pub repertoire_filter: &'static str,
+    pub whole_catalog_option: &'static str,
+    pub my_book_option: &'static str,
+    /// `{}`: minimum stars
+    pub rating_filter_option: &'static str,
+    pub saved_searches: &'static str,
+    pub no_saved_searches: &'static str,
+    /// `{}`: saved search name
+    pub delete_saved_search: &'static str,
+    pub save_search: &'static str,
+    /// `{}`: setlist name
+    pub setlist_chip: &'static str,
+    pub close_setlist_title: &'static str,
+    pub within: &'static str,
+    pub remove_refinement: &'static str,
+
+    // Results list
+    /// `{}`: number of results
+    pub results_heading: &'static str,
+    pub show_all_volumes: &'static str,
+    /// `{}`: volume number
+    pub only_volume: &'static str,
+    pub sort_results: &'static str,
+    pub sort_relevance: &'static str,
+    pub sort_title: &'static str,
+    pub sort_page: &'static str,
+    pub did_you_mean: &'static str,
+    pub star: &'static str,
+    pub unstar: &'static str,
+    /// `{}`: volume, page range
+    pub result_meta: &'static str,
+    /// `{}`: results shown, total results
+    pub load_more: &'static str,
+    pub loading_results: &'static str,
+    pub results_label: &'static str,
+    /// Announced to screen readers when one result is found
+    pub result_count_one: &'static str,
+    /// Announced to screen readers when results arrive; `{}`: number of results
+    pub result_count: &'static str,
+    pub results_placeholder: &'static str,
+    pub song_of_the_day: &'static str,
+    pub recently_viewed: &'static str,
+    pub clear_recently_viewed: &'static str,
+    pub review_queue: &'static str,
+    pub review_queue_hint: &'static str,
+    pub review_never: &'static str,
+    pub review_yesterday: &'static str,
+    /// `{}`: days
+    pub review_days_ago: &'static str,
+    /// `{}`: number of songs not listed
+    pub review_more: &'static str,
+    pub titles_by_letter: &'static str,
+    pub titles_number: &'static str,
+    /// `{}`: letter
+    pub titles_letter: &'static str,
+
+    // Viewer
+    pub back_to_results: &'static str,
+    pub hide_results: &'static str,
+    pub hide_results_title: &'static str,
+    pub show_results: &'static str,
+    /// `{}`: volume, page range
+    pub song_meta: &'static str,
+    pub available_offline: &'static str,
+    pub save_offline: &'static str,
+    pub add_to_setlist: &'static str,
+    pub add_to_setlist_option: &'static str,
+    pub new_setlist_option: &'static str,
+    pub share: &'static str,
+    pub link_copied: &'static str,
+    pub copy_link_prompt: &'static str,
+    pub qr_code: &'static str,
+    pub qr_code_alt: &'static str,
+    pub qr_code_failed: &'static str,
+    pub close: &'static str,
+    pub catalog_updated: &'static str,
+    /// `{}`: number of changes not listed
+    pub more_changes: &'static str,
+    pub auto_scroll: &'static str,
+    pub auto_scroll_pause: &'static str,
+    pub auto_scroll_slower: &'static str,
+    pub auto_scroll_faster: &'static str,
+    /// `{}`: speed level
+    pub auto_scroll_speed: &'static str,
+    pub stage_view: &'static str,
+    pub stage_view_title: &'static str,
+    pub stage_waiting: &'static str,
+    pub stage_blocked: &'static str,
+    pub practice: &'static str,
+    pub practice_title: &'static str,
+    /// `{}`: time practiced so far
+    pub practice_stop: &'static str,
+    pub song_notes: &'static str,
+    pub song_notes_placeholder: &'static str,
+    pub song_tags: &'static str,
+    pub add_tag_placeholder: &'static str,
+    /// `{}`: tag
+    pub remove_tag: &'static str,
+    pub song_status: &'static str,
+    pub status_none_option: &'static str,
+    pub status_learning: &'static str,
+    pub status_learned: &'static str,
+    pub status_ready: &'static str,
+    pub song_rating: &'static str,
+    /// `{}`: stars
+    pub rate_stars: &'static str,
+    pub print: &'static str,
+    pub download_pdf: &'static str,
+    pub download_musicxml: &'static str,
+    pub listen: &'static str,
+    /// `{}`: streaming service
+    pub listen_on: &'static str,
+    pub reference_recording: &'static str,
+    pub report_problem: &'static str,
+    pub report_problem_hint: &'static str,
+    pub correction_title: &'static str,
+    pub correction_first_page: &'static str,
+    pub correction_last_page: &'static str,
+    pub correction_comment: &'static str,
+    pub send_correction: &'static str,
+    pub correction_unchanged: &'static str,
+    pub correction_sent: &'static str,
+    pub open_in_irealpro: &'static str,
+    pub draw_on_pages: &'static str,
+    pub tool_pen: &'static str,
+    pub tool_highlighter: &'static str,
+    pub tool_eraser: &'static str,
+    pub add_marker: &'static str,
+    pub add_marker_option: &'static str,
+    pub marker_text_option: &'static str,
+    pub sticker_text_prompt: &'static str,
+    pub add_bookmark: &'static str,
+    pub bookmark_name_prompt: &'static str,
+    pub bookmarks: &'static str,
+    /// `{}`: bookmark name
+    pub remove_bookmark: &'static str,
+    pub zoom: &'static str,
+    pub zoom_out: &'static str,
+    pub zoom_in: &'static str,
+    pub zoom_reset: &'static str,
+    /// `{}`: page, page count
+    pub page_position: &'static str,
+    /// `{}`: previous page keys, next page keys
+    pub page_keys_hint: &'static str,
+    /// `{}`: song title
+    pub sheet_alt: &'static str,
+    pub loading_song: &'static str,
+
+    // Metronome and drone
+    pub sound_start: &'static str,
+    pub sound_stop: &'static str,
+    pub audio_unavailable: &'static str,
+    pub metronome: &'static str,
+    pub tempo: &'static str,
+    pub tempo_saved: &'static str,
+    pub tempo_slower: &'static str,
+    pub tempo_faster: &'static str,
+    pub bpm: &'static str,
+    pub tap_tempo: &'static str,
+    pub tap_tempo_title: &'static str,
+    pub subdivision: &'static str,
+    pub turn_pages: &'static str,
+    pub turn_pages_title: &'static str,
+    pub form_bars: &'static str,
+    pub beats_per_bar: &'static str,
+    pub subdivision_quarter: &'static str,
+    pub subdivision_eighth: &'static str,
+    pub subdivision_triplet: &'static str,
+    pub subdivision_sixteenth: &'static str,
+    pub drone: &'static str,
+    pub drone_pitch: &'static str,
+    pub drone_octave: &'static str,
+    pub drone_volume: &'static str,
+    /// C through B
+    pub note_names: [&'static str; 12],
+}
+
+impl Messages {
+    pub fn sort_label(&self, order: SortOrder) -> &'static str {
+        match order {
+            SortOrder::Relevance => self.sort_relevance,
+            SortOrder::Title => self.sort_title,
+            SortOrder::Page => self.sort_page,
+        }
+    }
+
+    pub fn status_label(&self, status: Status) -> &'static str {
+        match status {
+            Status::Learning => self.status_learning,
+            Status::Learned => self.status_learned,
+            Status::Ready => self.status_ready,
+        }
+    }
+
+    pub fn tool_label(&self, tool: Tool) -> &'static str {
+        match tool {
+            Tool::Pen => self.tool_pen,
+            Tool::Highlighter => self.tool_highlighter,
+            Tool::Eraser => self.tool_eraser,
+        }
+    }
+
+    pub fn subdivision_label(&self, subdivision: Subdivision) -> &'static str {
+        match subdivision {
+            Subdivision::Quarter => self.subdivision_quarter,
+            Subdivision::Eighth => self.subdivision_eighth,
+            Subdivision::Triplet => self.subdivision_triplet,
+            Subdivision::Sixteenth => self.subdivision_sixteenth,
+        }
+    }
+}
+
+static EN: Messages = Messages {
+    tagline: "Find jazz standards by title, volume, or page number",
+    shortcuts_intro: "Keyboard shortcuts: ",
+    shortcut_or: " or ",
+    shortcut_search: " search, ",
+    shortcut_clear: " clear/close, ",
+    shortcut_navigate: " navigate, ",
+    shortcut_first_last: " first/last, ",
+    shortcut_view: " view selected, ",
+    shortcut_random: " random, ",
+    shortcut_turn_pages: " turn pages, ",
+    shortcut_zoom: " zoom ",
+    shortcut_rebind: "(rebind in Settings)",
+    sync_synced: "Synced",
+    sync_syncing: "Syncing…",
+    sync_offline: "Offline, will sync later",
+    sync_failed: "Sync failed",
+    sync_now: "Sync now",
+
+    search_placeholder: "Type to search... (↑↓ navigate, Enter to view, Esc to clear)",
+    recent_searches: "Recent searches",
+    suggestions: "Suggestions",
+    clear_history: "Clear history",
+    page_placeholder: "Page or 100-120",
+    page_label: "Page number or page range",
+    all_volumes: "All Volumes",
+    volume_option: "Volume {}",
+    browse: "Browse",
+    browse_title: "List the whole volume in page order when the search box is empty",
+    refine: "Refine",
+    refine_title: "Keep these results and search within them",
+    random: "🎲 Random",
+    random_title: "Open a random song",
+    random_volume_title: "Open a random song from Volume {}",
+    favorites: "★ Favorites",
+    favorites_title: "Only show starred songs",
+    tag_filter: "Filter by tag",
+    all_tags_option: "All tags",
+    repertoire_filter: "Filter by repertoire",
+    whole_catalog_option: "Whole catalog",
+    my_book_option: "My book",
+    rating_filter_option: "{}★ and up",
+    saved_searches: "Saved searches",
+    no_saved_searches: "No saved searches yet",
+    delete_saved_search: "Delete saved search {}",
+    save_search: "Save current search…",
+    setlist_chip: "Setlist: {} ✕",
+    close_setlist_title: "Back to the whole catalog",
+    within: "Within: ",
+    remove_refinement: "Remove refinement",
+
+    results_heading: "Results ({})",
+    show_all_volumes: "Show all volumes",
+    only_volume: "Only volume {}",
+    sort_results: "Sort results",
+    sort_relevance: "Relevance",
+    sort_title: "A–Z",
+    sort_page: "Page order",
+    did_you_mean: "Did you mean: ",
+    star: "Star",
+    unstar: "Unstar",
+    result_meta: "Vol. {} | Pages {}",
+    load_more: "Load more ({} of {})",
+    loading_results: "Loading results",
+    results_label: "Search results",
+    result_count_one: "1 result",
+    result_count: "{} results",
+    results_placeholder: "Search for a song, enter a page number, or click Random to get started",
+    song_of_the_day: "Song of the day",
+    recently_viewed: "Recently viewed",
+    clear_recently_viewed: "Clear",
+    review_queue: "Due for review",
+    review_queue_hint: "Songs from your book you haven't practiced lately",
+    review_never: "not practiced yet",
+    review_yesterday: "last practiced yesterday",
+    review_days_ago: "last practiced {} days ago",
+    review_more: "and {} more",
+    titles_by_letter: "Titles by first letter",
+    titles_number: "Titles starting with a number",
+    titles_letter: "Titles starting with {}",
+
+    back_to_results: "← Back to results",
+    hide_results: "⇤ Hide results",
+    hide_results_title: "Give the chart the full width (Esc brings the results back)",
+    show_results: "⇥ Show results",
+    song_meta: "Volume {} | Pages {}",
+    available_offline: "✓ Available offline",
+    save_offline: "Save offline",
+    add_to_setlist: "Add to setlist",
+    add_to_setlist_option: "Add to setlist…",
+    new_setlist_option: "New setlist…",
+    share: "Share",
+    link_copied: "Link copied",
+    copy_link_prompt: "Copy this link:",
+    qr_code: "QR code",
+    qr_code_alt: "QR code that opens this chart",
+    qr_code_failed: "This link is too long for a QR code",
+    close: "Close",
+    catalog_updated: "The catalog was updated",
+    more_changes: "…and {} more changes",
+    auto_scroll: "Auto-scroll",
+    auto_scroll_pause: "Pause scroll",
+    auto_scroll_slower: "Scroll slower",
+    auto_scroll_faster: "Scroll faster",
+    auto_scroll_speed: "Speed {}",
+    stage_view: "Stage view",
+    stage_view_title: "Open a chart-only window for a second screen that follows this one",
+    stage_waiting: "Waiting for a song from the main window…",
+    stage_blocked: "The stage window was blocked; allow pop-ups for this site",
+    practice: "Practice",
+    practice_title: "Time your practice on this song (sessions are logged on this device)",
+    practice_stop: "Stop {}",
+    song_notes: "Notes",
+    song_notes_placeholder: "Your notes on this song (kept on this device, and searchable)",
+    song_tags: "Tags",
+    add_tag_placeholder: "Add a tag…",
+    remove_tag: "Remove tag {}",
+    song_status: "Repertoire status",
+    status_none_option: "Not in my book",
+    status_learning: "Learning",
+    status_learned: "Learned",
+    status_ready: "Performance-ready",
+    song_rating: "Rating",
+    rate_stars: "Rate {} stars",
+    print: "Print",
+    download_pdf: "Download PDF",
+    download_musicxml: "Download MusicXML",
+    listen: "Listen",
+    listen_on: "Find recordings on {}",
+    reference_recording: "Reference recording",
+    report_problem: "Report a problem",
+    report_problem_hint: "Wrong title or pages? Propose the fix and a moderator will check it.",
+    correction_title: "Title",
+    correction_first_page: "First page",
+    correction_last_page: "Last page",
+    correction_comment: "Comment (optional)",
+    send_correction: "Send report",
+    correction_unchanged: "Change the title or pages first",
+    correction_sent: "Thanks! Your correction will be reviewed.",
+    open_in_irealpro: "Open in iReal Pro",
+    draw_on_pages: "Draw on pages",
+    tool_pen: "Pen",
+    tool_highlighter: "Highlighter",
+    tool_eraser: "Eraser",
+    add_marker: "Add a marker",
+    add_marker_option: "Add marker…",
+    marker_text_option: "Text…",
+    sticker_text_prompt: "Sticker text",
+    add_bookmark: "Add bookmark",
+    bookmark_name_prompt: "Bookmark name",
+    bookmarks: "Bookmarks",
+    remove_bookmark: "Remove bookmark {}",
+    zoom: "Zoom",
+    zoom_out: "Zoom out",
+    zoom_in: "Zoom in",
+    zoom_reset: "Reset zoom (0)",
+    page_position: "Page {} of {}",
+    page_keys_hint: "{} previous page, {} next page",
+    sheet_alt: "Sheet music for {}",
+    loading_song: "Loading song",
+
+    sound_start: "▶ Start",
+    sound_stop: "■ Stop",
+    audio_unavailable: "This browser can't play audio",
+    metronome: "♩ Metronome",
+    tempo: "Tempo",
+    tempo_saved: "Saved for this song",
+    tempo_slower: "Slower",
+    tempo_faster: "Faster",
+    bpm: "BPM",
+    tap_tempo: "Tap",
+    tap_tempo_title: "Tap along to set the tempo",
+    subdivision: "Clicks per beat",
+    turn_pages: "Turn pages",
+    turn_pages_title: "Count in one bar, then turn pages in time, spreading the song's bars evenly over its pages (saved for the song)",
+    form_bars: "bars",
+    beats_per_bar: "beats/bar",
+    subdivision_quarter: "Quarter notes",
+    subdivision_eighth: "Eighth notes",
+    subdivision_triplet: "Triplets",
+    subdivision_sixteenth: "Sixteenth notes",
+    drone: "♫ Drone",
+    drone_pitch: "Note",
+    drone_octave: "Octave",
+    drone_volume: "Volume",
+    note_names: ["C", "C♯/D♭", "D", "D♯/E♭", "E", "F", "F♯/G♭", "G", "G♯/A♭", "A", "A♯/B♭", "B"],
+};
+
+static ES: Messages = Messages {
+    tagline: "Encuentra standards de jazz por título, volumen o número de página",
+    shortcuts_intro: "Atajos de teclado: ",
+    shortcut_or: " o ",
+    shortcut_search: " buscar, ",
+    shortcut_clear: " borrar/cerrar, ",
+    shortcut_navigate: " moverse, ",
+    shortcut_first_last: " primero/último, ",
+    shortcut_view: " ver la selección, ",
+    shortcut_random: " al azar, ",
+    shortcut_turn_pages: " pasar páginas, ",
+    shortcut_zoom: " zoom ",
+    shortcut_rebind: "(se cambian en Settings)",
+    sync_synced: "Sincronizado",
+    sync_syncing: "Sincronizando…",
+    sync_offline: "Sin conexión, se sincronizará después",
+    sync_failed: "Error al sincronizar",
+    sync_now: "Sincronizar ahora",
+
+    search_placeholder: "Escribe para buscar... (↑↓ moverse, Intro para ver, Esc para borrar)",
+    recent_searches: "Búsquedas recientes",
+    suggestions: "Sugerencias",
+    clear_history: "Borrar historial",
+    page_placeholder: "Página o 100-120",
+    page_label: "Número o rango de páginas",
+    all_volumes: "Todos los volúmenes",
+    volume_option: "Volumen {}",
+    browse: "Explorar",
+    browse_title: "Muestra todo el volumen por orden de página cuando la búsqueda está vacía",
+    refine: "Refinar",
+    refine_title: "Conserva estos resultados y busca dentro de ellos",
+    random: "🎲 Al azar",
+    random_title: "Abre una canción al azar",
+    random_volume_title: "Abre una canción al azar del volumen {}",
+    favorites: "★ Favoritas",
+    favorites_title: "Muestra solo las canciones con estrella",
+    tag_filter: "Filtrar por etiqueta",
+    all_tags_option: "Todas las etiquetas",
+    repertoire_filter: "Filtrar por repertorio",
+    whole_catalog_option: "Todo el catálogo",
+    my_book_option: "Mi repertorio",
+    rating_filter_option: "{}★ o más",
+    saved_searches: "Búsquedas guardadas",
+    no_saved_searches: "Aún no hay búsquedas guardadas",
+    delete_saved_search: "Eliminar la búsqueda guardada {}",
+    save_search: "Guardar esta búsqueda…",
+    setlist_chip: "Repertorio: {} ✕",
+    close_setlist_title: "Volver a todo el catálogo",
+    within: "Dentro de: ",
+    remove_refinement: "Quitar este filtro",
+
+    results_heading: "Resultados ({})",
+    show_all_volumes: "Mostrar todos los volúmenes",
+    only_volume: "Solo el volumen {}",
+    sort_results: "Ordenar resultados",
+    sort_relevance: "Relevancia",
+    sort_title: "A–Z",
+    sort_page: "Orden de página",
+    did_you_mean: "Quizá buscabas: ",
+    star: "Marcar con estrella",
+    unstar: "Quitar la estrella",
+    result_meta: "Vol. {} | Págs. {}",
+    load_more: "Cargar más ({} de {})",
+    loading_results: "Cargando resultados",
+    results_label: "Resultados de la búsqueda",
+    result_count_one: "1 resultado",
+    result_count: "{} resultados",
+    results_placeholder: "Busca una canción, escribe un número de página o pulsa Al azar para empezar",
+    song_of_the_day: "Canción del día",
+    recently_viewed: "Vistas recientemente",
+    clear_recently_viewed: "Borrar",
+    review_queue: "Para repasar",
+    review_queue_hint: "Canciones de tu repertorio que no has practicado últimamente",
+    review_never: "aún sin practicar",
+    review_yesterday: "practicada ayer",
+    review_days_ago: "practicada hace {} días",
+    review_more: "y {} más",
+    titles_by_letter: "Títulos por inicial",
+    titles_number: "Títulos que empiezan por un número",
+    titles_letter: "Títulos que empiezan por {}",
+
+    back_to_results: "← Volver a los resultados",
+    hide_results: "⇤ Ocultar resultados",
+    hide_results_title: "Da todo el ancho a la partitura (Esc vuelve a mostrar los resultados)",
+    show_results: "⇥ Mostrar resultados",
+    song_meta: "Volumen {} | Páginas {}",
+    available_offline: "✓ Disponible sin conexión",
+    save_offline: "Guardar sin conexión",
+    add_to_setlist: "Añadir al repertorio",
+    add_to_setlist_option: "Añadir al repertorio…",
+    new_setlist_option: "Nuevo repertorio…",
+    share: "Compartir",
+    link_copied: "Enlace copiado",
+    copy_link_prompt: "Copia este enlace:",
+    qr_code: "Código QR",
+    qr_code_alt: "Código QR que abre esta partitura",
+    qr_code_failed: "Este enlace es demasiado largo para un código QR",
+    close: "Cerrar",
+    catalog_updated: "Se actualizó el catálogo",
+    more_changes: "…y {} cambios más",
+    auto_scroll: "Desplazamiento automático",
+    auto_scroll_pause: "Pausar desplazamiento",
+    auto_scroll_slower: "Desplazar más despacio",
+    auto_scroll_faster: "Desplazar más rápido",
+    auto_scroll_speed: "Velocidad {}",
+    stage_view: "Vista de escenario",
+    stage_view_title: "Abrir una ventana solo con la partitura para una segunda pantalla, que sigue a esta",
+    stage_waiting: "Esperando una canción desde la ventana principal…",
+    stage_blocked: "Se bloqueó la ventana de escenario; permite las ventanas emergentes para este sitio",
+    practice: "Practicar",
+    practice_title: "Cronometra tu práctica de esta canción (las sesiones se registran en este dispositivo)",
+    practice_stop: "Parar {}",
+    song_notes: "Notas",
+    song_notes_placeholder: "Tus notas sobre esta canción (se guardan en este dispositivo y se pueden buscar)",
+    song_tags: "Etiquetas",
+    add_tag_placeholder: "Añadir etiqueta…",
+    remove_tag: "Quitar la etiqueta {}",
+    song_status: "Estado en el repertorio",
+    status_none_option: "No está en mi repertorio",
+    status_learning: "Aprendiendo",
+    status_learned: "Aprendida",
+    status_ready: "Lista para tocar",
+    song_rating: "Valoración",
+    rate_stars: "Valorar con {} estrellas",
+    print: "Imprimir",
+    download_pdf: "Descargar PDF",
+    download_musicxml: "Descargar MusicXML",
+    listen: "Escuchar",
+    listen_on: "Buscar grabaciones en {}",
+    reference_recording: "Grabación de referencia",
+    report_problem: "Informar de un problema",
+    report_problem_hint: "¿Título o páginas incorrectos? Propón la corrección y un moderador la revisará.",
+    correction_title: "Título",
+    correction_first_page: "Primera página",
+    correction_last_page: "Última página",
+    correction_comment: "Comentario (opcional)",
+    send_correction: "Enviar",
+    correction_unchanged: "Cambia primero el título o las páginas",
+    correction_sent: "¡Gracias! Revisaremos tu corrección.",
+    open_in_irealpro: "Abrir en iReal Pro",
+    draw_on_pages: "Dibujar en las páginas",
+    tool_pen: "Bolígrafo",
+    tool_highlighter: "Resaltador",
+    tool_eraser: "Borrador",
+    add_marker: "Añadir una marca",
+    add_marker_option: "Añadir marca…",
+    marker_text_option: "Texto…",
+    sticker_text_prompt: "Texto de la marca",
+    add_bookmark: "Añadir marcador",
+    bookmark_name_prompt: "Nombre del marcador",
+    bookmarks: "Marcadores",
+    remove_bookmark: "Quitar el marcador {}",
+    zoom: "Zoom",
+    zoom_out: "Alejar",
+    zoom_in: "Acercar",
+    zoom_reset: "Restablecer el zoom (0)",
+    page_position: "Página {} de {}",
+    page_keys_hint: "{} página anterior, {} página siguiente",
+    sheet_alt: "Partitura de {}",
+    loading_song: "Cargando la canción",
+
+    sound_start: "▶ Iniciar",
+    sound_stop: "■ Detener",
+    audio_unavailable: "Este navegador no puede reproducir audio",
+    metronome: "♩ Metrónomo",
+    tempo: "Tempo",
+    tempo_saved: "Guardado para esta canción",
+    tempo_slower: "Más lento",
+    tempo_faster: "Más rápido",
+    bpm: "BPM",
+    tap_tempo: "Marcar",
+    tap_tempo_title: "Marca el pulso para fijar el tempo",
+    subdivision: "Golpes por pulso",
+    turn_pages: "Pasar páginas",
+    turn_pages_title: "Cuenta un compás y luego pasa las páginas a tempo, repartiendo los compases de la canción entre sus páginas (se guarda para la canción)",
+    form_bars: "compases",
+    beats_per_bar: "tiempos/compás",
+    subdivision_quarter: "Negras",
+    subdivision_eighth: "Corcheas",
+    subdivision_triplet: "Tresillos",
+    subdivision_sixteenth: "Semicorcheas",
+    drone: "♫ Bordón",
+    drone_pitch: "Nota",
+    drone_octave: "Octava",
+    drone_volume: "Volumen",
+    note_names: ["Do", "Do♯/Re♭", "Re", "Re♯/Mi♭", "Mi", "Fa", "Fa♯/Sol♭", "Sol", "Sol♯/La♭", "La", "La♯/Si♭", "Si"],
+};
+
+static FR: Messages = Messages {
+    tagline: "Trouvez des standards de jazz par titre, volume ou numéro de page",
+    shortcuts_intro: "Raccourcis clavier : ",
+    shortcut_or: " ou ",
+    shortcut_search: " rechercher, ",
+    shortcut_clear: " effacer/fermer, ",
+    shortcut_navigate: " naviguer, ",
+    shortcut_first_last: " premier/dernier, ",
+    shortcut_view: " afficher la sélection, ",
+    shortcut_random: " au hasard, ",
+    shortcut_turn_pages: " tourner les pages, ",
+    shortcut_zoom: " zoom ",
+    shortcut_rebind: "(modifiables dans Settings)",
+    sync_synced: "Synchronisé",
+    sync_syncing: "Synchronisation…",
+    sync_offline: "Hors ligne, synchronisation plus tard",
+    sync_failed: "Échec de la synchronisation",
+    sync_now: "Synchroniser maintenant",
+
+    search_placeholder: "Tapez pour rechercher... (↑↓ naviguer, Entrée pour afficher, Échap pour effacer)",
+    recent_searches: "Recherches récentes",
+    suggestions: "Suggestions",
+    clear_history: "Effacer l'historique",
+    page_placeholder: "Page ou 100-120",
+    page_label: "Numéro ou plage de pages",
+    all_volumes: "Tous les volumes",
+    volume_option: "Volume {}",
+    browse: "Parcourir",
+    browse_title: "Liste tout le volume dans l'ordre des pages quand la recherche est vide",
+    refine: "Affiner",
+    refine_title: "Garde ces résultats et cherche parmi eux",
+    random: "🎲 Au hasard",
+    random_title: "Ouvre un morceau au hasard",
+    random_volume_title: "Ouvre un morceau au hasard du volume {}",
+    favorites: "★ Favoris",
+    favorites_title: "N'afficher que les morceaux favoris",
+    tag_filter: "Filtrer par étiquette",
+    all_tags_option: "Toutes les étiquettes",
+    repertoire_filter: "Filtrer par répertoire",
+    whole_catalog_option: "Tout le catalogue",
+    my_book_option: "Mon répertoire",
+    rating_filter_option: "{}★ et plus",
+    saved_searches: "Recherches enregistrées",
+    no_saved_searches: "Aucune recherche enregistrée",
+    delete_saved_search: "Supprimer la recherche enregistrée {}",
+    save_search: "Enregistrer cette recherche…",
+    setlist_chip: "Setlist : {} ✕",
+    close_setlist_title: "Revenir à tout le catalogue",
+    within: "Parmi : ",
+    remove_refinement: "Retirer ce filtre",
+
+    results_heading: "Résultats ({})",
+    show_all_volumes: "Afficher tous les volumes",
+    only_volume: "Uniquement le volume {}",
+    sort_results: "Trier les résultats",
+    sort_relevance: "Pertinence",
+    sort_title: "A–Z",
+    sort_page: "Ordre des pages",
+    did_you_mean: "Vouliez-vous dire : ",
+    star: "Ajouter aux favoris",
+    unstar: "Retirer des favoris",
+    result_meta: "Vol. {} | Pages {}",
+    load_more: "Charger plus ({} sur {})",
+    loading_results: "Chargement des résultats",
+    results_label: "Résultats de la recherche",
+    result_count_one: "1 résultat",
+    result_count: "{} résultats",
+    results_placeholder: "Cherchez un morceau, saisissez un numéro de page ou cliquez sur Au hasard pour commencer",
+    song_of_the_day: "Morceau du jour",
+    recently_viewed: "Consultés récemment",
+    clear_recently_viewed: "Effacer",
+    review_queue: "À réviser",
+    review_queue_hint: "Les morceaux de votre répertoire que vous n'avez pas travaillés récemment",
+    review_never: "pas encore travaillé",
+    review_yesterday: "travaillé hier",
+    review_days_ago: "travaillé il y a {} jours",
+    review_more: "et {} de plus",
+    titles_by_letter: "Titres par initiale",
+    titles_number: "Titres commençant par un chiffre",
+    titles_letter: "Titres commençant par {}",
+
+    back_to_results: "← Retour aux résultats",
+    hide_results: "⇤ Masquer les résultats",
+    hide_results_title: "Donne toute la largeur à la partition (Échap réaffiche les résultats)",
+    show_results: "⇥ Afficher les résultats",
+    song_meta: "Volume {} | Pages {}",
+    available_offline: "✓ Disponible hors ligne",
+    save_offline: "Enregistrer hors ligne",
+    add_to_setlist: "Ajouter à une setlist",
+    add_to_setlist_option: "Ajouter à une setlist…",
+    new_setlist_option: "Nouvelle setlist…",
+    share: "Partager",
+    link_copied: "Lien copié",
+    copy_link_prompt: "Copiez ce lien :",
+    qr_code: "Code QR",
+    qr_code_alt: "Code QR qui ouvre cette partition",
+    qr_code_failed: "Ce lien est trop long pour un code QR",
+    close: "Fermer",
+    catalog_updated: "Le catalogue a été mis à jour",
+    more_changes: "…et {} autres modifications",
+    auto_scroll: "Défilement auto",
+    auto_scroll_pause: "Suspendre le défilement",
+    auto_scroll_slower: "Défiler plus lentement",
+    auto_scroll_faster: "Défiler plus vite",
+    auto_scroll_speed: "Vitesse {}",
+    stage_view: "Vue scène",
+    stage_view_title: "Ouvrir une fenêtre avec la partition seule pour un second écran, qui suit celle-ci",
+    stage_waiting: "En attente d'un morceau depuis la fenêtre principale…",
+    stage_blocked: "La fenêtre de scène a été bloquée ; autorisez les fenêtres surgissantes pour ce site",
+    practice: "S'exercer",
+    practice_title: "Chronométrez votre travail sur ce morceau (les séances sont enregistrées sur cet appareil)",
+    practice_stop: "Arrêter {}",
+    song_notes: "Notes",
+    song_notes_placeholder: "Vos notes sur ce morceau (gardées sur cet appareil, et consultables par la recherche)",
+    song_tags: "Étiquettes",
+    add_tag_placeholder: "Ajouter une étiquette…",
+    remove_tag: "Retirer l'étiquette {}",
+    song_status: "Statut dans le répertoire",
+    status_none_option: "Pas dans mon répertoire",
+    status_learning: "En apprentissage",
+    status_learned: "Appris",
+    status_ready: "Prêt pour la scène",
+    song_rating: "Note",
+    rate_stars: "Noter {} étoiles",
+    print: "Imprimer",
+    download_pdf: "Télécharger le PDF",
+    download_musicxml: "Télécharger le MusicXML",
+    listen: "Écouter",
+    listen_on: "Chercher des enregistrements sur {}",
+    reference_recording: "Enregistrement de référence",
+    report_problem: "Signaler un problème",
+    report_problem_hint: "Titre ou pages erronés ? Proposez la correction, un modérateur la vérifiera.",
+    correction_title: "Titre",
+    correction_first_page: "Première page",
+    correction_last_page: "Dernière page",
+    correction_comment: "Commentaire (facultatif)",
+    send_correction: "Envoyer",
+    correction_unchanged: "Modifiez d'abord le titre ou les pages",
+    correction_sent: "Merci ! Votre correction sera examinée.",
+    open_in_irealpro: "Ouvrir dans iReal Pro",
+    draw_on_pages: "Dessiner sur les pages",
+    tool_pen: "Stylo",
+    tool_highlighter: "Surligneur",
+    tool_eraser: "Gomme",
+    add_marker: "Ajouter un repère",
+    add_marker_option: "Ajouter un repère…",
+    marker_text_option: "Texte…",
+    sticker_text_prompt: "Texte du repère",
+    add_bookmark: "Ajouter un signet",
+    bookmark_name_prompt: "Nom du signet",
+    bookmarks: "Signets",
+    remove_bookmark: "Supprimer le signet {}",
+    zoom: "Zoom",
+    zoom_out: "Zoom arrière",
+    zoom_in: "Zoom avant",
+    zoom_reset: "Réinitialiser le zoom (0)",
+    page_position: "Page {} sur {}",
+    page_keys_hint: "{} page précédente, {} page suivante",
+    sheet_alt: "Partition de {}",
+    loading_song: "Chargement du morceau",
+
+    sound_start: "▶ Démarrer",
+    sound_stop: "■ Arrêter",
+    audio_unavailable: "Ce navigateur ne peut pas jouer de son",
+    metronome: "♩ Métronome",
+    tempo: "Tempo",
+    tempo_saved: "Enregistré pour ce morceau",
+    tempo_slower: "Plus lent",
+    tempo_faster: "Plus rapide",
+    bpm: "BPM",
+    tap_tempo: "Taper",
+    tap_tempo_title: "Tapez la pulsation pour régler le tempo",
+    subdivision: "Clics par temps",
+    turn_pages: "Tourner les pages",
+    turn_pages_title: "Compte une mesure, puis tourne les pages en rythme en répartissant les mesures du morceau sur ses pages (enregistré pour le morceau)",
+    form_bars: "mesures",
+    beats_per_bar: "temps/mesure",
+    subdivision_quarter: "Noires",
+    subdivision_eighth: "Croches",
+    subdivision_triplet: "Triolets",
+    subdivision_sixteenth: "Doubles croches",
+    drone: "♫ Bourdon",
+    drone_pitch: "Note",
+    drone_octave: "Octave",
+    drone_volume: "Volume",
+    note_names: ["Do", "Do♯/Ré♭", "Ré", "Ré♯/Mi♭", "Mi", "Fa", "Fa♯/Sol♭", "Sol", "Sol♯/La♭", "La", "La♯/Si♭", "Si"],
+};
@@ -0,0 +1,168 @@
+//! Rebindable keyboard shortcuts
+//!
+//! The global keydown handlers look keys up here instead of matching on key
+//! names, so users can rebind them in Settings. Bindings are `KeyboardEvent.key`
+//! values, or space-separated sequences typed in quick succession (vim's "g g"),
+//! stored in localStorage; actions missing from a stored keymap (e.g. added by a
+//! newer build) fall back to their default keys.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::storage;
+
+/// localStorage key for the keymap
+const STORAGE_KEY: &str = "realbook.keymap";
+
+/// Something a shortcut can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    NextResult,
+    PrevResult,
+    FirstResult,
+    LastResult,
+    OpenResult,
+    Random,
+    NextPage,
+    PrevPage,
+    AutoScroll,
+    ScrollFaster,
+    ScrollSlower,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::NextResult,
+        Action::PrevResult,
+        Action::FirstResult,
+        Action::LastResult,
+        Action::OpenResult,
+        Action::Random,
+        Action::NextPage,
+        Action::PrevPage,
+        Action::AutoScroll,
+        Action::ScrollFaster,
+        Action::ScrollSlower,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::NextResult => "Next result",
+            Action::PrevResult => "Previous result",
+            Action::FirstResult => "First result",
+            Action::LastResult => "Last result",
+            Action::OpenResult => "Open result",
+            Action::Random => "Random song",
+            Action::NextPage => "Next sheet page",
+            Action::PrevPage => "Previous sheet page",
+            Action::AutoScroll => "Start/pause auto-scroll",
+            Action::ScrollFaster => "Auto-scroll faster",
+            Action::ScrollSlower => "Auto-scroll slower",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            // Vim-style j/k, g g/G, and o alongside the arrows and Enter
+            Action::NextResult => &["ArrowDown", "j"],
+            Action::PrevResult => &["ArrowUp", "k"],
+            Action::FirstResult => &["g g"],
+            Action::LastResult => &["G"],
+            Action::OpenResult => &["Enter", "o"],
+            Action::Random => &["r"],
+            Action::NextPage => &["PageDown", "ArrowRight"],
+            Action::PrevPage => &["PageUp", "ArrowLeft"],
+            Action::AutoScroll => &["s"],
+            Action::ScrollFaster => &["]"],
+            Action::ScrollSlower => &["["],
+        }
+    }
+}
+
+/// What a key press means, given the keys pressed just before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyPress {
+    Action(Action),
+    /// The start of a sequence binding; wait for the next key
+    Prefix,
+    Unbound,
+}
+
+/// Keys bound to each action
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<String>>,
+}
+
+impl Keymap {
+    /// Load the saved keymap (the defaults if nothing is saved)
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    /// Persist the keymap
+    pub fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    /// Keys that trigger `action`
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        match self.bindings.get(&action) {
+            Some(keys) => keys.clone(),
+            None => action.default_keys().iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Action bound to `key`, if any
+    pub fn action_for(&self, key: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|&action| self.keys(action).iter().any(|bound| bound == key))
+    }
+
+    /// Interpret `key`, given the unfinished sequence typed before it
+    /// (space-separated keys, empty if none)
+    pub fn press(&self, pending: &str, key: &str) -> KeyPress {
+        if !pending.is_empty() {
+            let sequence = format!("{} {}", pending, key);
+            if let Some(action) = self.action_for(&sequence) {
+                return KeyPress::Action(action);
+            }
+            if self.starts_sequence(&sequence) {
+                return KeyPress::Prefix;
+            }
+        }
+        match self.action_for(key) {
+            Some(action) => KeyPress::Action(action),
+            None if self.starts_sequence(key) => KeyPress::Prefix,
+            None => KeyPress::Unbound,
+        }
+    }
+
+    /// Whether some sequence binding begins with `keys`
+    fn starts_sequence(&self, keys: &str) -> bool {
+        let prefix = format!("{} ", keys);
+        Action::ALL.into_iter().any(|action| self.keys(action).iter().any(|bound| bound.starts_with(&prefix)))
+    }
+
+    /// Bind `key` to `action` alone, taking it away from any other action
+    pub fn bind(&self, action: Action, key: &str) -> Self {
+        let mut bindings: BTreeMap<Action, Vec<String>> =
+            Action::ALL.into_iter().map(|action| (action, self.keys(action))).collect();
+        for keys in bindings.values_mut() {
+            keys.retain(|bound| bound != key);
+        }
+        bindings.insert(action, vec![key.to_string()]);
+        Keymap { bindings }
+    }
+}
+
+/// How a binding is shown to users (letters keep their case: "G" is Shift+g)
+pub fn key_label(key: &str) -> String {
+    match key {
+        " " => "Space".to_string(),
+        "ArrowDown" => "↓".to_string(),
+        "ArrowUp" => "↑".to_string(),
+        "ArrowLeft" => "←".to_string(),
+        "ArrowRight" => "→".to_string(),
+        sequence if sequence.contains(' ') => sequence.split(' ').map(key_label).collect::<Vec<_>>().join(" "),
+        key => key.to_string(),
+    }
+}
@@ -0,0 +1,39 @@
+//! Links to recordings of a song on streaming services
+//!
+//! The catalog has titles only (no composers or recording ids), so these are
+//! search links: they open the service's search for the title in a new tab.
+
+/// Where recordings can be looked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Spotify,
+    YouTube,
+}
+
+impl Service {
+    pub const ALL: [Service; 2] = [Service::Spotify, Service::YouTube];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Spotify => "Spotify",
+            Service::YouTube => "YouTube",
+        }
+    }
+
+    /// Search page for `terms` on the service
+    pub fn search_url(self, terms: &str) -> String {
+        let terms = urlencoding::encode(terms);
+        match self {
+            Service::Spotify => format!("https://open.spotify.com/search/{}", terms),
+            Service::YouTube => format!("https://www.youtube.com/results?search_query={}", terms),
+        }
+    }
+}
+
+/// Search terms for a song: the title, plus the composer when one is known
+pub fn search_terms(title: &str, composer: Option<&str>) -> String {
+    match composer.map(str::trim).filter(|composer| !composer.is_empty()) {
+        Some(composer) => format!("{} {}", title.trim(), composer),
+        None => title.trim().to_string(),
+    }
+}
@@ -0,0 +1,281 @@
+//! Click track for practicing from the chart (Web Audio)
+//!
+//! Clicks are scheduled on the audio clock a little ahead of time by a short
+//! timer, so they stay steady even when the main thread is busy rendering
+//! pages. Each song remembers the tempo it was last practiced at, stored in
+//! localStorage under `realbook.tempo.<entry id>`, and its form (bars and
+//! beats per bar) under `realbook.form.<entry id>`, which lets page turns follow
+//! the click.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use gloo_timers::callback::Interval;
+use serde::{Deserialize, Serialize};
+use web_sys::AudioContext;
+use crate::storage;
+
+/// Tempo limits, in beats per minute
+pub const MIN_BPM: u32 = 30;
+pub const MAX_BPM: u32 = 300;
+pub const DEFAULT_BPM: u32 = 120;
+
+/// How often the scheduler wakes up, and how far ahead it schedules clicks
+const SCHEDULER_MS: u32 = 25;
+const LOOKAHEAD_SECS: f64 = 0.1;
+
+/// Taps further apart than this start a new tap tempo
+const TAP_RESET_MS: f64 = 2_000.0;
+/// Most recent taps averaged for the tap tempo
+const MAX_TAPS: usize = 5;
+
+/// Click pitches (Hz) and length (s): beats are higher and louder than the
+/// clicks between them
+const BEAT_PITCH: f32 = 1_500.0;
+const SUBDIVISION_PITCH: f32 = 1_000.0;
+const CLICK_SECS: f64 = 0.05;
+
+/// Clicks per beat
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Subdivision {
+    #[default]
+    Quarter,
+    Eighth,
+    Triplet,
+    Sixteenth,
+}
+
+impl Subdivision {
+    pub const ALL: [Subdivision; 4] = [
+        Subdivision::Quarter,
+        Subdivision::Eighth,
+        Subdivision::Triplet,
+        Subdivision::Sixteenth,
+    ];
+
+    pub fn clicks_per_beat(self) -> u32 {
+        match self {
+            Subdivision::Quarter => 1,
+            Subdivision::Eighth => 2,
+            Subdivision::Triplet => 3,
+            Subdivision::Sixteenth => 4,
+        }
+    }
+
+    /// Value for a `<select>` option
+    pub fn code(self) -> &'static str {
+        match self {
+            Subdivision::Quarter => "1",
+            Subdivision::Eighth => "2",
+            Subdivision::Triplet => "3",
+            Subdivision::Sixteenth => "4",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Subdivision::ALL.into_iter().find(|subdivision| subdivision.code() == code)
+    }
+}
+
+fn tempo_key(entry_id: u32) -> String {
+    format!("realbook.tempo.{}", entry_id)
+}
+
+/// Tempo the song was last practiced at, if any
+pub fn load_tempo(entry_id: u32) -> Option<u32> {
+    storage::load(&tempo_key(entry_id))
+}
+
+/// Remember the song's tempo
+pub fn save_tempo(entry_id: u32, bpm: u32) {
+    storage::save(&tempo_key(entry_id), &bpm);
+}
+
+pub fn clamp_bpm(bpm: u32) -> u32 {
+    bpm.clamp(MIN_BPM, MAX_BPM)
+}
+
+/// Length of a song's form, for turning pages in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Form {
+    /// Bars in one time through the chart (e.g. 32 for AABA)
+    pub bars: u32,
+    /// Beats in a bar (4 for 4/4, 3 for a waltz)
+    pub beats_per_bar: u32,
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Form { bars: 32, beats_per_bar: 4 }
+    }
+}
+
+/// Limits for the form inputs
+pub const MAX_BARS: u32 = 512;
+pub const MAX_BEATS_PER_BAR: u32 = 16;
+
+impl Form {
+    /// The same form with both values in range
+    pub fn clamped(self) -> Self {
+        Form { bars: self.bars.clamp(1, MAX_BARS), beats_per_bar: self.beats_per_bar.clamp(1, MAX_BEATS_PER_BAR) }
+    }
+
+    /// Page (0-based) of a `page_count`-page chart being played on `beat`
+    /// (0 = the downbeat after the count-in), the bars spread evenly over the
+    /// pages and starting over for each chorus. The page turns a beat early, so
+    /// the next page is up before its first bar.
+    pub fn page_at(self, beat: u64, page_count: u32) -> u32 {
+        let form = self.clamped();
+        let bar = (beat + 1) / u64::from(form.beats_per_bar) % u64::from(form.bars);
+        ((bar * u64::from(page_count)) / u64::from(form.bars)) as u32
+    }
+}
+
+fn form_key(entry_id: u32) -> String {
+    format!("realbook.form.{}", entry_id)
+}
+
+/// The song's form, if one was entered
+pub fn load_form(entry_id: u32) -> Option<Form> {
+    storage::load(&form_key(entry_id))
+}
+
+/// Remember the song's form
+pub fn save_form(entry_id: u32, form: Form) {
+    storage::save(&form_key(entry_id), &form);
+}
+
+/// Record a tap at `now` (ms) and return the tempo of the recent taps, once
+/// there are at least two
+pub fn tap_tempo(taps: &mut Vec<f64>, now: f64) -> Option<u32> {
+    if taps.last().is_some_and(|last| now - last > TAP_RESET_MS) {
+        taps.clear();
+    }
+    taps.push(now);
+    if taps.len() > MAX_TAPS {
+        taps.remove(0);
+    }
+    if taps.len() < 2 {
+        return None;
+    }
+    let interval = (taps[taps.len() - 1] - taps[0]) / (taps.len() - 1) as f64;
+    Some(clamp_bpm((60_000.0 / interval).round() as u32))
+}
+
+/// Where the click track is up to
+struct Schedule {
+    bpm: u32,
+    subdivision: Subdivision,
+    /// Audio clock time of the next click, in seconds
+    next_click: f64,
+    /// Clicks since the last beat
+    tick: u32,
+    /// Number of the next beat to schedule (negative during the count-in)
+    beat: i64,
+    /// Scheduled beats not yet heard: audio clock time and number
+    upcoming: VecDeque<(f64, i64)>,
+}
+
+/// Schedule one short click at `time` on the audio clock
+fn click(context: &AudioContext, time: f64, beat: bool) -> Result<(), wasm_bindgen::JsValue> {
+    let oscillator = context.create_oscillator()?;
+    let gain = context.create_gain()?;
+    oscillator.frequency().set_value(if beat { BEAT_PITCH } else { SUBDIVISION_PITCH });
+    // Quick attack and exponential decay, so the click doesn't pop
+    gain.gain().set_value_at_time(if beat { 0.8 } else { 0.4 }, time)?;
+    gain.gain().exponential_ramp_to_value_at_time(0.001, time + CLICK_SECS)?;
+    oscillator.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&context.destination())?;
+    oscillator.start_with_when(time)?;
+    oscillator.stop_with_when(time + CLICK_SECS)?;
+    Ok(())
+}
+
+/// A running click track; stops when dropped
+pub struct Metronome {
+    context: AudioContext,
+    schedule: Rc<RefCell<Schedule>>,
+    _scheduler: Interval,
+}
+
+impl Metronome {
+    /// Start clicking (call from a click or key handler: browsers only allow
+    /// audio to start from a user gesture). None if Web Audio is unavailable.
+    ///
+    /// `on_beat` is called as each beat is heard (to within the scheduler's
+    /// 25ms) with its number: negative during the first `count_in` beats, then
+    /// 0, 1, 2...
+    pub fn start(bpm: u32, subdivision: Subdivision, count_in: u32, on_beat: impl Fn(i64) + 'static) -> Option<Self> {
+        let context = AudioContext::new().ok()?;
+        let schedule = Rc::new(RefCell::new(Schedule {
+            bpm: clamp_bpm(bpm),
+            subdivision,
+            next_click: context.current_time() + 0.05,
+            tick: 0,
+            beat: -i64::from(count_in),
+            upcoming: VecDeque::new(),
+        }));
+
+        let scheduler = {
+            let context = context.clone();
+            let schedule = schedule.clone();
+            Interval::new(SCHEDULER_MS, move || {
+                let now = context.current_time();
+                // Report the beats heard since the last wake-up (outside the
+                // borrow, in case the callback reaches back into the metronome)
+                let heard: Vec<i64> = {
+                    let mut schedule = schedule.borrow_mut();
+                    let mut heard = Vec::new();
+                    while schedule.upcoming.front().is_some_and(|(time, _)| *time <= now) {
+                        heard.extend(schedule.upcoming.pop_front().map(|(_, beat)| beat));
+                    }
+                    heard
+                };
+                for beat in heard {
+                    on_beat(beat);
+                }
+
+                let mut schedule = schedule.borrow_mut();
+                // Timers are throttled in background tabs; skip the missed
+                // clicks rather than playing them all at once
+                if schedule.next_click < now {
+                    schedule.next_click = now;
+                }
+                let horizon = now + LOOKAHEAD_SECS;
+                while schedule.next_click < horizon {
+                    let _ = click(&context, schedule.next_click, schedule.tick == 0);
+                    if schedule.tick == 0 {
+                        let (time, beat) = (schedule.next_click, schedule.beat);
+                        schedule.upcoming.push_back((time, beat));
+                        schedule.beat += 1;
+                    }
+                    let clicks_per_beat = schedule.subdivision.clicks_per_beat();
+                    schedule.next_click += 60.0 / (schedule.bpm * clicks_per_beat) as f64;
+                    schedule.tick = (schedule.tick + 1) % clicks_per_beat;
+                }
+            })
+        };
+
+        Some(Metronome { context, schedule, _scheduler: scheduler })
+    }
+
+    /// Change the tempo from the next click on
+    pub fn set_bpm(&self, bpm: u32) {
+        self.schedule.borrow_mut().bpm = clamp_bpm(bpm);
+    }
+
+    /// Change the subdivision; the next click counts as a beat
+    pub fn set_subdivision(&self, subdivision: Subdivision) {
+        let mut schedule = self.schedule.borrow_mut();
+        if schedule.subdivision != subdivision {
+            schedule.subdivision = subdivision;
+            schedule.tick = 0;
+        }
+    }
+}
+
+impl Drop for Metronome {
+    fn drop(&mut self) {
+        let _ = self.context.close();
+    }
+}
@@ -0,0 +1,198 @@
+//! MIDI foot controllers and pedals (Web MIDI)
+//!
+//! Page-turn pedals and floor controllers send program-change or control-change
+//! messages. A message learned for an action (Settings > MIDI pedals) turns the
+//! chart's pages or steps through the songs in the results, which makes a pedal
+//! walk through an open setlist. Learned messages are stored in localStorage
+//! (`realbook.midi`). Browsers ask before granting MIDI access, so it's only
+//! requested once the user connects or has learned a pedal before.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use yew::Callback;
+use crate::storage;
+
+/// localStorage key for the learned messages
+const STORAGE_KEY: &str = "realbook.midi";
+
+/// Something a pedal can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MidiAction {
+    NextPage,
+    PrevPage,
+    NextSong,
+    PrevSong,
+}
+
+impl MidiAction {
+    pub const ALL: [MidiAction; 4] = [
+        MidiAction::NextPage,
+        MidiAction::PrevPage,
+        MidiAction::NextSong,
+        MidiAction::PrevSong,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MidiAction::NextPage => "Next page",
+            MidiAction::PrevPage => "Previous page",
+            MidiAction::NextSong => "Next song",
+            MidiAction::PrevSong => "Previous song",
+        }
+    }
+
+    /// +1 for the next page or song, -1 for the previous one
+    pub fn step(self) -> i32 {
+        match self {
+            MidiAction::NextPage | MidiAction::NextSong => 1,
+            MidiAction::PrevPage | MidiAction::PrevSong => -1,
+        }
+    }
+}
+
+/// A message a pedal sends (channels are 1-16, as printed on devices)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MidiMessage {
+    ProgramChange { channel: u8, program: u8 },
+    ControlChange { channel: u8, controller: u8 },
+}
+
+impl MidiMessage {
+    /// Read a raw message, ignoring everything but program changes and
+    /// control changes that press (value 64 and up, so a sustain-style pedal
+    /// fires once per press rather than again on release)
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (&status, rest) = data.split_first()?;
+        let channel = (status & 0x0f) + 1;
+        match (status & 0xf0, rest) {
+            (0xc0, [program, ..]) => Some(MidiMessage::ProgramChange { channel, program: *program }),
+            (0xb0, [controller, value, ..]) if *value >= 64 => {
+                Some(MidiMessage::ControlChange { channel, controller: *controller })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            MidiMessage::ProgramChange { channel, program } => format!("Program {} (ch. {})", program, channel),
+            MidiMessage::ControlChange { channel, controller } => format!("CC {} (ch. {})", controller, channel),
+        }
+    }
+}
+
+/// Learned message for each action
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MidiMap {
+    bindings: Vec<(MidiAction, MidiMessage)>,
+}
+
+impl MidiMap {
+    /// Load the learned messages (none if nothing is saved)
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    /// Persist the learned messages
+    pub fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Message learned for `action`, if any
+    pub fn message(&self, action: MidiAction) -> Option<MidiMessage> {
+        self.bindings.iter().find(|(bound, _)| *bound == action).map(|(_, message)| *message)
+    }
+
+    /// Action triggered by `message`, if any
+    pub fn action_for(&self, message: &MidiMessage) -> Option<MidiAction> {
+        self.bindings.iter().find(|(_, bound)| bound == message).map(|(action, _)| *action)
+    }
+
+    /// Map `message` to `action`, taking it away from any other action
+    pub fn learn(&self, action: MidiAction, message: MidiMessage) -> Self {
+        let mut bindings: Vec<_> = self
+            .bindings
+            .iter()
+            .filter(|(bound_action, bound)| *bound_action != action && *bound != message)
+            .copied()
+            .collect();
+        bindings.push((action, message));
+        MidiMap { bindings }
+    }
+
+    /// Drop the message learned for `action`
+    pub fn forget(&self, action: MidiAction) -> Self {
+        let bindings = self.bindings.iter().filter(|(bound, _)| *bound != action).copied().collect();
+        MidiMap { bindings }
+    }
+}
+
+/// State of the MIDI connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum MidiStatus {
+    Off,
+    Connecting,
+    /// Number of connected inputs
+    Connected(u32),
+    /// Why MIDI can't be used (unsupported browser, access denied)
+    Unavailable(String),
+}
+
+/// Open MIDI connection; messages stop arriving once it's dropped
+pub struct MidiConnection {
+    _inputs: Rc<RefCell<Vec<EventListener>>>,
+    _state_change: EventListener,
+}
+
+/// Request MIDI access and pass on messages from every input, including ones
+/// plugged in later (`on_inputs` gets the input count whenever it changes)
+pub async fn connect(on_message: Callback<MidiMessage>, on_inputs: Callback<u32>) -> Result<MidiConnection, String> {
+    let navigator = web_sys::window().ok_or("No window")?.navigator();
+    let request = navigator
+        .request_midi_access()
+        .map_err(|_| "MIDI isn't supported in this browser".to_string())?;
+    let access: web_sys::MidiAccess = JsFuture::from(request)
+        .await
+        .map_err(|_| "MIDI access was denied".to_string())?
+        .unchecked_into();
+
+    let inputs = Rc::new(RefCell::new(Vec::new()));
+    let listen = {
+        let access = access.clone();
+        let inputs = inputs.clone();
+        move || {
+            // Listening for messages also opens the port
+            let listeners: Vec<EventListener> = access
+                .inputs()
+                .values()
+                .into_iter()
+                .flatten()
+                .map(|input| {
+                    let input: web_sys::MidiInput = input.unchecked_into();
+                    let on_message = on_message.clone();
+                    EventListener::new(&input, "midimessage", move |event| {
+                        let event = event.unchecked_ref::<web_sys::MidiMessageEvent>();
+                        if let Some(message) = event.data().ok().and_then(|data| MidiMessage::parse(&data)) {
+                            on_message.emit(message);
+                        }
+                    })
+                })
+                .collect();
+            on_inputs.emit(listeners.len() as u32);
+            *inputs.borrow_mut() = listeners;
+        }
+    };
+    listen();
+    let state_change = EventListener::new(&access, "statechange", move |_| listen());
+
+    Ok(MidiConnection { _inputs: inputs, _state_change: state_change })
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 /// Must match backend model exactly for deserialization
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RealBookEntry {
+    pub id: u32,
     pub title: String,
     pub volume: u32,
     pub page_s: u32,
@@ -13,10 +14,12 @@ pub struct RealBookEntry {
 impl RealBookEntry {
     /// Generate the image URL for a specific page in this entry
     pub fn image_url(&self, page: u32) -> String {
-        format!(
-            "https://wypn9z41ir5bzmgjjalyna.on.drv.tw/realbook/rendered/{}.jpeg",
-            self.volume * 1000 + page
-        )
+        crate::api::image_url(self.volume, page)
+    }
+
+    /// Thumbnail URL for a specific page in this entry
+    pub fn thumbnail_url(&self, page: u32) -> String {
+        crate::api::thumbnail_url(self.volume, page)
     }
 
     /// Get all image URLs for this entry (from page_s to page_e)
@@ -26,6 +29,21 @@ impl RealBookEntry {
             .collect()
     }
 
+    /// Download file name for one page, e.g. "Stella_By_Starlight_v1_p432.jpeg"
+    pub fn page_filename(&self, page: u32) -> String {
+        let words: Vec<String> = self
+            .title
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let lower = word.to_ascii_lowercase();
+                lower[..1].to_ascii_uppercase() + &lower[1..]
+            })
+            .collect();
+        let stem = if words.is_empty() { "Chart".to_string() } else { words.join("_") };
+        format!("{}_v{}_p{}.jpeg", stem, self.volume, page)
+    }
+
     /// Get page range as a display string
     pub fn page_range(&self) -> String {
         if self.page_s == self.page_e {
@@ -36,10 +54,351 @@ impl RealBookEntry {
     }
 }
 
+impl realbook_core::Entry for RealBookEntry {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn volume(&self) -> u32 {
+        self.volume
+    }
+
+    fn pages(&self) -> (u32, u32) {
+        (self.page_s, self.page_e)
+    }
+}
+
+/// Search parameters sent to the API
+/// None fields are omitted from the query string
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchParams {
+    pub query: Option<String>,
+    /// Earlier queries the current one refines (all must match)
+    pub within: Vec<String>,
+    /// Only titles filed under this index letter (A-Z strip)
+    pub initial: Option<char>,
+    pub volume: Option<u32>,
+    pub page: Option<u32>,
+    pub page_from: Option<u32>,
+    pub page_to: Option<u32>,
+    /// Approximate page: entries within `tolerance` pages of it, closest first
+    pub page_near: Option<u32>,
+    /// Pages either side of `page_near` (the API defaults to 5)
+    pub tolerance: Option<u32>,
+    /// Number of matches to skip (pagination)
+    pub offset: Option<usize>,
+    /// Maximum number of matches to return (pagination)
+    pub limit: Option<usize>,
+}
+
+impl SearchParams {
+    /// Apply the page field as typed by the user: a single page ("134"), an
+    /// inclusive span ("100-120"), or an approximate page ("~300", or
+    /// "~300±3" for a tolerance other than the default). Unparseable input
+    /// is ignored.
+    pub fn with_page_input(mut self, input: &str) -> Self {
+        let input = input.trim();
+        if let Some(near) = input.strip_prefix('~') {
+            let (page, tolerance) = near.split_once('±').or_else(|| near.split_once("+-")).unwrap_or((near, ""));
+            self.page_near = page.trim().parse().ok();
+            self.tolerance = tolerance.trim().parse().ok();
+        } else if let Some((from, to)) = input.split_once('-') {
+            self.page_from = from.trim().parse().ok();
+            self.page_to = to.trim().parse().ok();
+        } else {
+            self.page = input.parse().ok();
+        }
+        self
+    }
+
+    /// The page filter as the user would type it (inverse of `with_page_input`)
+    pub fn page_input(&self) -> Option<String> {
+        match (self.page, self.page_from, self.page_to, self.page_near) {
+            (Some(page), _, _, _) => Some(page.to_string()),
+            (None, None, None, Some(near)) => Some(match self.tolerance {
+                Some(tolerance) => format!("~{}±{}", near, tolerance),
+                None => format!("~{}", near),
+            }),
+            (None, None, None, None) => None,
+            (None, from, to, _) => Some(format!(
+                "{}-{}",
+                from.map(|p| p.to_string()).unwrap_or_default(),
+                to.map(|p| p.to_string()).unwrap_or_default(),
+            )),
+        }
+    }
+
+    /// Whether any filter that narrows the catalog is set
+    /// (a volume alone doesn't count - it would list a whole book)
+    pub fn has_criteria(&self) -> bool {
+        self.query.as_ref().is_some_and(|q| !q.is_empty())
+            || !self.within.is_empty()
+            || self.initial.is_some()
+            || self.page.is_some()
+            || self.page_from.is_some()
+            || self.page_to.is_some()
+            || self.page_near.is_some()
+    }
+}
+
 /// Search results response from API
+/// When paginated, `results` holds the loaded window and `total` counts all matches
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SearchResponse {
     pub results: Vec<RealBookEntry>,
     pub total: usize,
+    /// Matches in each volume, ignoring the volume filter
+    #[serde(default)]
+    pub volume_counts: Vec<VolumeInfo>,
+    /// Closest titles when nothing matched (omitted by the API when empty)
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+}
+
+impl SearchResponse {
+    /// Whether more matches exist beyond the loaded results
+    pub fn has_more(&self) -> bool {
+        self.results.len() < self.total
+    }
+}
+
+/// Extras attached to an entry, from `/api/v1/entries/<id>/attachments`
+/// (most songs have none)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Attachments {
+    /// YouTube video id of a reference recording
+    pub youtube_id: Option<String>,
+    /// Direct link to an audio file of a reference recording
+    pub audio_url: Option<String>,
+    /// iReal Pro link to the song's chart
+    pub irealpro: Option<String>,
+    /// Where to download an editable MusicXML version of the song
+    pub musicxml_url: Option<String>,
+}
+
+/// The full catalog from `/api/v1/catalog`, cached for local search
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CatalogSnapshot {
+    pub version: String,
+    pub entries: Vec<RealBookEntry>,
+}
+
+/// One catalog release from `/api/v1/changelog`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Release {
+    pub version: u32,
+    /// YYYY-MM-DD
+    pub date: String,
+    /// What changed, one line each
+    pub changes: Vec<String>,
+}
+
+/// The catalog's releases, newest first
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Changelog {
+    /// Content hash of the catalog (as in `CatalogSnapshot`)
+    pub catalog_version: String,
+    /// The newest release's version (None before the first)
+    pub latest: Option<u32>,
+    pub releases: Vec<Release>,
+}
+
+/// Volume information from API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeInfo {
+    pub volume: u32,
+    pub count: usize,
+}
+
+/// Order of the results list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// As the search returned them (catalog order, page order for page spans,
+    /// playing order for a setlist)
+    #[default]
+    Relevance,
+    /// By title, ignoring case
+    Title,
+    /// By volume, then first page
+    Page,
 }
 
+impl SortOrder {
+    pub const ALL: [SortOrder; 3] = [SortOrder::Relevance, SortOrder::Title, SortOrder::Page];
+
+    /// Value used in the sort `<select>`
+    pub fn key(self) -> &'static str {
+        match self {
+            SortOrder::Relevance => "relevance",
+            SortOrder::Title => "title",
+            SortOrder::Page => "page",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|order| order.key() == key)
+    }
+
+    /// Reorder `entries` (stable, so ties keep the search's order; Relevance
+    /// leaves them as they are)
+    pub fn sort(self, entries: &mut [RealBookEntry]) {
+        match self {
+            SortOrder::Relevance => {}
+            SortOrder::Title => entries.sort_by_cached_key(|entry| entry.title.to_lowercase()),
+            SortOrder::Page => entries.sort_by_key(|entry| (entry.volume, entry.page_s)),
+        }
+    }
+}
+
+/// Autocomplete titles from API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SuggestResponse {
+    pub suggestions: Vec<String>,
+}
+
+/// The day's pick from the API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SongOfTheDay {
+    /// Day the song is picked for (YYYY-MM-DD)
+    pub date: String,
+    pub entry: RealBookEntry,
+}
+
+/// What this browser's anonymous session on the API remembers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionSummary {
+    /// Songs viewed, newest first
+    pub recently_viewed: Vec<RealBookEntry>,
+    /// Songs picked by Random, newest first
+    pub recent_randoms: Vec<RealBookEntry>,
+}
+
+/// What the API found in an imported archive (`/me/import`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImportSummary {
+    pub strategy: String,
+    pub version: u32,
+    /// The browser sections present in the archive
+    pub sections: Vec<String>,
+    /// Recently viewed songs in the session after the import
+    pub recently_viewed: usize,
+    /// Recently viewed ids not in the catalog (skipped)
+    pub unknown_entries: Vec<u32>,
+}
+
+/// One item of a local store, as exchanged by `/sync`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncRecord {
+    /// Store it belongs to (an archive section name, e.g. `notes`)
+    pub store: String,
+    /// The item's key within the store
+    pub key: String,
+    /// The item, or None once it's been deleted
+    pub value: Option<serde_json::Value>,
+    /// When it was last changed (ms since the Unix epoch)
+    pub updated_at: f64,
+}
+
+/// This device's half of a sync exchange
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncRequest {
+    /// Highest record number seen so far
+    pub cursor: u64,
+    /// Epoch of the log the cursor is from
+    pub epoch: Option<String>,
+    /// This device's random id
+    pub device: String,
+    /// Records changed here since the last exchange
+    pub changes: Vec<SyncRecord>,
+}
+
+/// The API's half of a sync exchange
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncResponse {
+    pub cursor: u64,
+    pub epoch: String,
+    /// The API lost its records (or dropped deletions this device missed);
+    /// send everything again
+    pub reset: bool,
+    pub accepted: usize,
+    /// Records changed elsewhere
+    pub changes: Vec<SyncRecord>,
+}
+
+/// A code for joining this browser's session from another device
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LinkCode {
+    pub code: String,
+    /// Seconds until it stops working
+    pub expires_in: u64,
+}
+
+/// What's wrong with a reported sheet image
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PageProblem {
+    /// The image doesn't load at all
+    Missing,
+    /// It loads, but it's the wrong page or can't be read
+    BadScan,
+}
+
+/// A report of a broken sheet image
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PageReport {
+    pub problem: PageProblem,
+}
+
+/// A reported mistake in an entry, with the fix proposed; fields left out
+/// stay as they are
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CorrectionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_s: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_e: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// A passkey that signs in to this browser's session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Passkey {
+    /// Credential id (base64url)
+    pub id: String,
+    pub name: String,
+    /// YYYY-MM-DD
+    pub created_at: String,
+    pub last_used: Option<String>,
+}
+
+/// Catalog-wide statistics from API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CatalogStats {
+    pub total_songs: usize,
+    pub songs_per_volume: Vec<VolumeInfo>,
+    pub total_pages: usize,
+    pub average_pages_per_song: f64,
+    pub longest_chart: Option<RealBookEntry>,
+    pub shortest_chart: Option<RealBookEntry>,
+}
+
+/// One detail of an API error (e.g. a rejected query parameter)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ErrorDetail {
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// Error body returned by the API for every failed request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiErrorResponse {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Vec<ErrorDetail>,
+}
@@ -0,0 +1,95 @@
+//! Personal notes on songs ("Latin feel, take it in Ab with the singer")
+//!
+//! Notes are kept in localStorage, one per song, along with the song itself so
+//! searches can find songs by their notes without the catalog. There's no
+//! account to sync them to yet, so they stay on this device.
+
+use std::collections::BTreeMap;
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use crate::models::{RealBookEntry, SearchParams, SearchResponse, VolumeInfo};
+use crate::search_index;
+use crate::storage;
+
+/// localStorage key for the notes
+const STORAGE_KEY: &str = "realbook.notes";
+
+/// A note and the song it's about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub entry: RealBookEntry,
+    pub text: String,
+}
+
+/// Every note, by entry id
+pub fn load() -> BTreeMap<u32, Note> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// The note on one song (empty if none)
+pub fn get(id: u32) -> String {
+    load().remove(&id).map(|note| note.text).unwrap_or_default()
+}
+
+/// Follow the notes as other tabs edit them
+pub fn watch(on_change: impl Fn() + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, on_change)
+}
+
+/// Store every note (used when restoring an archive)
+pub fn save_all(notes: &BTreeMap<u32, Note>) {
+    storage::save(STORAGE_KEY, notes);
+}
+
+/// Store the note on a song (a blank note deletes it)
+pub fn save(entry: &RealBookEntry, text: &str) {
+    let mut notes = load();
+    if text.trim().is_empty() {
+        notes.remove(&entry.id);
+    } else {
+        notes.insert(entry.id, Note { entry: entry.clone(), text: text.to_string() });
+    }
+    storage::save(STORAGE_KEY, &notes);
+}
+
+/// Add the songs whose notes match the query (but whose titles don't, so they
+/// aren't already there) to the first page of a search's results. The other
+/// filters apply as usual; refine terms may match the title or the note.
+/// `songs` limits the matches to the setlist or favorites being searched.
+pub fn add_matches(response: &mut SearchResponse, params: &SearchParams, songs: Option<&[RealBookEntry]>) {
+    let Some(query) = params.query.as_deref().map(str::to_lowercase).filter(|query| !query.is_empty()) else {
+        return;
+    };
+    if params.offset.unwrap_or(0) > 0 {
+        return;
+    }
+
+    let contains = |text: &str, term: &str| text.to_lowercase().contains(&term.to_lowercase());
+    let noted: Vec<RealBookEntry> = load()
+        .into_values()
+        .filter(|note| contains(&note.text, &query) && !contains(&note.entry.title, &query))
+        .filter(|note| {
+            params.within.iter().all(|term| contains(&note.entry.title, term) || contains(&note.text, term))
+        })
+        .filter(|note| songs.is_none_or(|songs| songs.iter().any(|song| song.id == note.entry.id)))
+        .map(|note| note.entry)
+        .collect();
+    if noted.is_empty() {
+        return;
+    }
+
+    // Only the query and refine terms were checked against the notes
+    let filters = SearchParams { query: None, within: Vec::new(), offset: None, limit: None, ..params.clone() };
+    let found = search_index::search_entries(&noted, &filters);
+
+    for count in found.volume_counts {
+        match response.volume_counts.iter_mut().find(|info| info.volume == count.volume) {
+            Some(info) => info.count += count.count,
+            None => response.volume_counts.push(VolumeInfo { volume: count.volume, count: count.count }),
+        }
+    }
+    response.volume_counts.sort_by_key(|info| info.volume);
+    response.total += found.total;
+    response.suggestions.clear();
+    response.results.splice(0..0, found.results);
+}
@@ -0,0 +1,107 @@
+//! Songs saved for offline use
+//!
+//! Page images are stored in a dedicated Cache API cache, which the service
+//! worker (`sw.js`) checks before the network, so saved songs open with no
+//! connectivity. The list of saved songs is kept in localStorage for display.
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use crate::models::RealBookEntry;
+use crate::storage;
+
+/// Cache holding saved page images (must match IMAGE_CACHE in sw.js)
+const IMAGE_CACHE: &str = "realbook-images-v1";
+
+/// localStorage key for the list of saved songs
+const STORAGE_KEY: &str = "realbook.offline";
+
+/// Browser storage used by this origin, in bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageUsage {
+    pub used: f64,
+    pub quota: f64,
+}
+
+fn js_error(context: &str, error: JsValue) -> String {
+    let detail = error
+        .dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .unwrap_or_else(|| format!("{:?}", error));
+    format!("{}: {}", context, detail)
+}
+
+async fn open_image_cache() -> Result<web_sys::Cache, String> {
+    let caches = web_sys::window()
+        .ok_or("No window")?
+        .caches()
+        .map_err(|e| js_error("Offline storage unavailable", e))?;
+    JsFuture::from(caches.open(IMAGE_CACHE))
+        .await
+        .map(|cache| cache.unchecked_into())
+        .map_err(|e| js_error("Couldn't open offline storage", e))
+}
+
+/// Songs currently saved for offline use
+pub fn saved_songs() -> Vec<RealBookEntry> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Download and store every page of the given songs; returns the updated list
+pub async fn save_songs(entries: &[RealBookEntry]) -> Result<Vec<RealBookEntry>, String> {
+    let cache = open_image_cache().await?;
+    let urls: js_sys::Array = entries
+        .iter()
+        .flat_map(|entry| entry.all_image_urls())
+        .map(JsValue::from)
+        .collect();
+    JsFuture::from(cache.add_all_with_str_sequence(&urls))
+        .await
+        .map_err(|e| js_error("Couldn't download pages", e))?;
+
+    let mut saved = saved_songs();
+    for entry in entries {
+        if !saved.iter().any(|song| song.id == entry.id) {
+            saved.push(entry.clone());
+        }
+    }
+    storage::save(STORAGE_KEY, &saved);
+    Ok(saved)
+}
+
+/// Drop a song's pages from offline storage; returns the updated list
+pub async fn remove_song(entry: &RealBookEntry) -> Result<Vec<RealBookEntry>, String> {
+    let cache = open_image_cache().await?;
+    for url in entry.all_image_urls() {
+        JsFuture::from(cache.delete_with_str(&url))
+            .await
+            .map_err(|e| js_error("Couldn't remove pages", e))?;
+    }
+
+    let mut saved = saved_songs();
+    saved.retain(|song| song.id != entry.id);
+    storage::save(STORAGE_KEY, &saved);
+    Ok(saved)
+}
+
+/// Drop every saved song
+pub async fn clear() -> Result<(), String> {
+    let caches = web_sys::window()
+        .ok_or("No window")?
+        .caches()
+        .map_err(|e| js_error("Offline storage unavailable", e))?;
+    JsFuture::from(caches.delete(IMAGE_CACHE))
+        .await
+        .map_err(|e| js_error("Couldn't clear offline storage", e))?;
+    storage::save(STORAGE_KEY, &Vec::<RealBookEntry>::new());
+    Ok(())
+}
+
+/// How much storage this origin uses (None if the browser won't say)
+pub async fn storage_usage() -> Option<StorageUsage> {
+    let promise = web_sys::window()?.navigator().storage().estimate().ok()?;
+    let estimate: web_sys::StorageEstimate = JsFuture::from(promise).await.ok()?.unchecked_into();
+    Some(StorageUsage {
+        used: estimate.get_usage()?,
+        quota: estimate.get_quota()?,
+    })
+}
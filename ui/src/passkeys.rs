@@ -0,0 +1,72 @@
+//! Passkeys: signing in to the session with the device's screen lock
+//!
+//! On a shared band tablet nobody wants to type credentials. A passkey made in
+//! the settings panel signs any browser in to this one's session (and so its
+//! synced data) with a fingerprint, face, or PIN, or by scanning a QR code
+//! with a phone that holds the passkey. The API (`api/src/passkeys.rs`)
+//! issues the challenges and checks the answers; this module only runs the
+//! browser's WebAuthn ceremonies in between.
+//!
+//! The options and responses travel as webauthn-rs's JSON types (binary
+//! fields base64url), which `webauthn-rs-proto` converts to and from the
+//! browser's WebAuthn objects.
+
+use js_sys::Reflect;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CredentialsContainer, PublicKeyCredential};
+use webauthn_rs_proto::{RegisterPublicKeyCredential, ResidentKeyRequirement};
+use crate::api;
+use crate::models::Passkey;
+
+fn get(target: &JsValue, property: &str) -> Result<JsValue, String> {
+    Reflect::get(target, &JsValue::from_str(property)).map_err(|_| format!("The passkey response has no {}", property))
+}
+
+/// Why a ceremony failed, in words for a toast
+fn ceremony_error(error: JsValue) -> String {
+    let name = get(&error, "name").ok().and_then(|name| name.as_string()).unwrap_or_default();
+    match name.as_str() {
+        // Cancelled, timed out, or no passkey picked
+        "NotAllowedError" => "No passkey was used".to_string(),
+        "InvalidStateError" => "This device already has a passkey for this session".to_string(),
+        _ => error
+            .dyn_ref::<js_sys::Error>()
+            .map(|e| format!("The passkey didn't work: {}", String::from(e.message())))
+            .unwrap_or_else(|| "The passkey didn't work".to_string()),
+    }
+}
+
+/// `navigator.credentials`
+fn credentials() -> Result<CredentialsContainer, String> {
+    Ok(web_sys::window().ok_or("No window")?.navigator().credentials())
+}
+
+/// Whether this browser can use passkeys (WebAuthn, on a secure origin)
+pub fn is_supported() -> bool {
+    web_sys::window().is_some_and(|window| {
+        window.is_secure_context() && get(&window, "PublicKeyCredential").is_ok_and(|value| !value.is_undefined())
+    })
+}
+
+/// Make a passkey named `name` (whose it is, e.g. "Dana") for this browser's session
+pub async fn register(name: &str) -> Result<Passkey, String> {
+    let mut options = api::passkey_challenge(name).await.map_err(|e| e.message)?;
+    // Stored on the authenticator, so signing in needs no user name
+    if let Some(selection) = options.public_key.authenticator_selection.as_mut() {
+        selection.resident_key = Some(ResidentKeyRequirement::Required);
+        selection.require_resident_key = true;
+    }
+
+    let promise = credentials()?.create_with_options(&options.into()).map_err(ceremony_error)?;
+    let credential: PublicKeyCredential = JsFuture::from(promise).await.map_err(ceremony_error)?.unchecked_into();
+    api::register_passkey(&RegisterPublicKeyCredential::from(credential)).await.map_err(|e| e.message)
+}
+
+/// Sign in to the session of whichever passkey the user picks; answers with it
+pub async fn sign_in() -> Result<Passkey, String> {
+    let options = api::sign_in_challenge().await.map_err(|e| e.message)?;
+    let promise = credentials()?.get_with_options(&options.into()).map_err(ceremony_error)?;
+    let credential: PublicKeyCredential = JsFuture::from(promise).await.map_err(ceremony_error)?.unchecked_into();
+    api::passkey_sign_in(&credential.into()).await.map_err(|e| e.message)
+}
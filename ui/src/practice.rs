@@ -0,0 +1,126 @@
+//! Practice log: timed sessions on songs, and the week's totals
+//!
+//! The viewer's practice timer logs a session whenever it's stopped, another
+//! song opens, or the page is hidden. There's no practice-log API on the server,
+//! so sessions are kept in localStorage (`realbook.practice`), newest last.
+
+use std::collections::BTreeMap;
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use crate::models::RealBookEntry;
+use crate::storage;
+
+/// localStorage key for the log
+const STORAGE_KEY: &str = "realbook.practice";
+
+/// Sessions shorter than this (seconds) are a slip of the finger, not practice
+const MIN_SECONDS: u32 = 10;
+
+/// Sessions kept; the oldest are dropped past this
+const MAX_SESSIONS: usize = 2000;
+
+/// One timed session on a song
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PracticeSession {
+    pub entry_id: u32,
+    pub title: String,
+    /// When the timer started (ms since the Unix epoch)
+    pub started_at: f64,
+    pub seconds: u32,
+}
+
+/// Practice in the current week (Monday to Sunday, local time)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeekSummary {
+    /// Seconds practiced each day, Monday first
+    pub days: [u32; 7],
+    /// Seconds per song title, most practiced first
+    pub songs: Vec<(String, u32)>,
+}
+
+impl WeekSummary {
+    pub fn total(&self) -> u32 {
+        self.days.iter().sum()
+    }
+}
+
+/// Every logged session, oldest first
+pub fn load() -> Vec<PracticeSession> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the log as other tabs add to it
+pub fn watch(on_change: impl Fn(Vec<PracticeSession>) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// Log a session on `entry` that started at `started_at` and ran until now;
+/// false if it was too short to count
+pub fn log(entry: &RealBookEntry, started_at: f64) -> bool {
+    let seconds = ((js_sys::Date::now() - started_at) / 1000.0) as u32;
+    if seconds < MIN_SECONDS {
+        return false;
+    }
+    let mut sessions = load();
+    sessions.push(PracticeSession { entry_id: entry.id, title: entry.title.clone(), started_at, seconds });
+    save_all(sessions);
+    true
+}
+
+/// Store the whole log, oldest first, dropping the oldest past the limit
+pub fn save_all(mut sessions: Vec<PracticeSession>) {
+    let excess = sessions.len().saturating_sub(MAX_SESSIONS);
+    sessions.drain(..excess);
+    storage::save(STORAGE_KEY, &sessions);
+}
+
+/// Day of the week of a time, local time (Monday = 0)
+fn weekday(time: f64) -> usize {
+    // getDay() counts from Sunday = 0
+    (js_sys::Date::new(&JsValue::from_f64(time)).get_day() as usize + 6) % 7
+}
+
+/// Midnight at the start of this week's Monday, local time (ms since the epoch)
+fn week_start() -> f64 {
+    let today = js_sys::Date::new_0();
+    // A day before the 1st rolls back into the previous month
+    let monday = today.get_date() as i32 - weekday(today.get_time()) as i32;
+    js_sys::Date::new_with_year_month_day(today.get_full_year(), today.get_month() as i32, monday).get_time()
+}
+
+/// Sum up this week's sessions
+pub fn this_week(sessions: &[PracticeSession]) -> WeekSummary {
+    let start = week_start();
+    let mut summary = WeekSummary::default();
+    let mut songs: BTreeMap<&str, u32> = BTreeMap::new();
+    for session in sessions.iter().filter(|session| session.started_at >= start) {
+        summary.days[weekday(session.started_at)] += session.seconds;
+        *songs.entry(&session.title).or_insert(0) += session.seconds;
+    }
+    summary.songs = songs.into_iter().map(|(title, seconds)| (title.to_string(), seconds)).collect();
+    summary.songs.sort_by_key(|(_, seconds)| std::cmp::Reverse(*seconds));
+    summary
+}
+
+/// Format seconds as "1h 05m", "12m 30s", or "45s"
+pub fn format_duration(seconds: u32) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Format seconds as a running clock: "4:05" or "1:04:05"
+pub fn format_clock(seconds: u32) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
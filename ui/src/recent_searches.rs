@@ -0,0 +1,40 @@
+//! Recently submitted search queries
+//!
+//! A query counts as submitted when Enter is pressed in the search box or one
+//! of its results is clicked. The most recent are kept in localStorage, newest
+//! first, and offered in a dropdown when the search box is focused and empty.
+
+use gloo_events::EventListener;
+use crate::storage;
+
+/// localStorage key for the recent queries
+const STORAGE_KEY: &str = "realbook.recent_searches";
+
+/// Number of queries remembered
+const MAX_RECENT: usize = 8;
+
+/// Recent queries, newest first
+pub fn load() -> Vec<String> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the recent queries as other tabs add to them
+pub fn watch(on_change: impl Fn(Vec<String>) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// Put `query` at the front (moving it if it's already there, ignoring case),
+/// returning (and storing) the updated list
+pub fn add(recent: &[String], query: &str) -> Vec<String> {
+    let query = query.trim();
+    let mut recent: Vec<String> = recent.iter().filter(|earlier| !earlier.eq_ignore_ascii_case(query)).cloned().collect();
+    recent.insert(0, query.to_string());
+    recent.truncate(MAX_RECENT);
+    storage::save(STORAGE_KEY, &recent);
+    recent
+}
+
+/// Forget every recent query
+pub fn clear() {
+    storage::remove(STORAGE_KEY);
+}
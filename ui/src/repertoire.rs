@@ -0,0 +1,173 @@
+//! Repertoire tracker: how well the user knows each song, and a star rating
+//!
+//! Songs are marked learning, learned, or performance-ready, and may be rated
+//! one to five stars; both are kept in localStorage (`realbook.repertoire`), by
+//! entry id along with the song itself, so the repertoire filter can search the
+//! user's own book locally like the Favorites filter.
+
+use std::collections::BTreeMap;
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use crate::models::RealBookEntry;
+use crate::storage;
+
+/// localStorage key for the repertoire
+const STORAGE_KEY: &str = "realbook.repertoire";
+
+/// Highest star rating
+pub const MAX_RATING: u8 = 5;
+
+/// How well a song is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Learning,
+    Learned,
+    /// Learned well enough to call on a gig
+    Ready,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Learning, Status::Learned, Status::Ready];
+
+    /// Value used in selects
+    pub fn value(self) -> &'static str {
+        match self {
+            Status::Learning => "learning",
+            Status::Learned => "learned",
+            Status::Ready => "ready",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Self> {
+        Status::ALL.into_iter().find(|status| status.value() == value)
+    }
+}
+
+/// Where a song stands: its status and rating (either may be unset)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub entry: RealBookEntry,
+    pub status: Option<Status>,
+    /// 1 to `MAX_RATING` stars
+    pub rating: Option<u8>,
+}
+
+/// Every song with a status or rating, by entry id
+pub type Repertoire = BTreeMap<u32, Progress>;
+
+/// Which songs the repertoire filter keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepertoireFilter {
+    /// Every song with a status: the user's own book
+    Book,
+    Status(Status),
+    /// Songs rated at least this many stars
+    Rating(u8),
+}
+
+impl RepertoireFilter {
+    /// Every filter, in the order offered
+    pub fn all() -> Vec<RepertoireFilter> {
+        let mut filters = vec![RepertoireFilter::Book];
+        filters.extend(Status::ALL.map(RepertoireFilter::Status));
+        filters.extend((1..=MAX_RATING).rev().map(RepertoireFilter::Rating));
+        filters
+    }
+
+    /// Value used in selects
+    pub fn value(self) -> String {
+        match self {
+            RepertoireFilter::Book => "book".to_string(),
+            RepertoireFilter::Status(status) => status.value().to_string(),
+            RepertoireFilter::Rating(stars) => format!("rating-{}", stars),
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Self> {
+        RepertoireFilter::all().into_iter().find(|filter| filter.value() == value)
+    }
+
+    fn keeps(self, progress: &Progress) -> bool {
+        match self {
+            RepertoireFilter::Book => progress.status.is_some(),
+            RepertoireFilter::Status(status) => progress.status == Some(status),
+            RepertoireFilter::Rating(stars) => progress.rating.is_some_and(|rating| rating >= stars),
+        }
+    }
+}
+
+/// How many songs are at each status
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Summary {
+    pub learning: usize,
+    pub learned: usize,
+    pub ready: usize,
+}
+
+impl Summary {
+    /// Songs with any status
+    pub fn total(&self) -> usize {
+        self.learning + self.learned + self.ready
+    }
+}
+
+/// The whole repertoire
+pub fn load() -> Repertoire {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the repertoire as other tabs edit it
+pub fn watch(on_change: impl Fn(Repertoire) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// One song's status and rating
+pub fn of(repertoire: &Repertoire, id: u32) -> (Option<Status>, Option<u8>) {
+    repertoire.get(&id).map_or((None, None), |progress| (progress.status, progress.rating))
+}
+
+/// Songs the filter keeps, by title
+pub fn songs(repertoire: &Repertoire, filter: RepertoireFilter) -> Vec<RealBookEntry> {
+    let mut songs: Vec<RealBookEntry> = repertoire
+        .values()
+        .filter(|progress| filter.keeps(progress))
+        .map(|progress| progress.entry.clone())
+        .collect();
+    songs.sort_by_key(|entry| entry.title.to_lowercase());
+    songs
+}
+
+/// Count the songs at each status
+pub fn summarize(repertoire: &Repertoire) -> Summary {
+    let mut summary = Summary::default();
+    for progress in repertoire.values() {
+        match progress.status {
+            Some(Status::Learning) => summary.learning += 1,
+            Some(Status::Learned) => summary.learned += 1,
+            Some(Status::Ready) => summary.ready += 1,
+            None => {}
+        }
+    }
+    summary
+}
+
+/// Store the whole repertoire (used when restoring an archive)
+pub fn save_all(repertoire: &Repertoire) {
+    storage::save(STORAGE_KEY, repertoire);
+}
+
+/// Set a song's status and rating, returning (and storing) the updated
+/// repertoire; a song with neither is dropped
+pub fn set(repertoire: &Repertoire, entry: &RealBookEntry, status: Option<Status>, rating: Option<u8>) -> Repertoire {
+    let mut repertoire = repertoire.clone();
+    let rating = rating.filter(|stars| (1..=MAX_RATING).contains(stars));
+    if status.is_none() && rating.is_none() {
+        repertoire.remove(&entry.id);
+    } else {
+        repertoire.insert(entry.id, Progress { entry: entry.clone(), status, rating });
+    }
+    storage::save(STORAGE_KEY, &repertoire);
+    repertoire
+}
@@ -0,0 +1,105 @@
+//! Review queue: songs from the user's book that are due for practice
+//!
+//! Each repertoire status has a review interval in days (songs still being
+//! learned come round sooner than performance-ready ones). A song is due once
+//! that long has passed since it was last practiced, going by the practice log,
+//! or straight away if it's never been practiced. The queue is shown on the
+//! empty results placeholder, most overdue first. Intervals are adjustable in
+//! the settings panel and kept in localStorage.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::models::RealBookEntry;
+use crate::practice::PracticeSession;
+use crate::repertoire::{Repertoire, Status};
+use crate::storage;
+
+/// localStorage key for the intervals
+const STORAGE_KEY: &str = "realbook.review_intervals";
+
+/// Longest interval offered, in days
+pub const MAX_INTERVAL_DAYS: u32 = 365;
+
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Days between reviews for each repertoire status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReviewIntervals {
+    pub learning: u32,
+    pub learned: u32,
+    pub ready: u32,
+}
+
+impl Default for ReviewIntervals {
+    fn default() -> Self {
+        ReviewIntervals { learning: 2, learned: 7, ready: 21 }
+    }
+}
+
+impl ReviewIntervals {
+    /// Load saved intervals, falling back to the defaults
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    pub fn days(&self, status: Status) -> u32 {
+        match status {
+            Status::Learning => self.learning,
+            Status::Learned => self.learned,
+            Status::Ready => self.ready,
+        }
+    }
+
+    /// These intervals with `status`'s set to `days` (within 1 and the maximum)
+    pub fn with_days(self, status: Status, days: u32) -> Self {
+        let days = days.clamp(1, MAX_INTERVAL_DAYS);
+        match status {
+            Status::Learning => ReviewIntervals { learning: days, ..self },
+            Status::Learned => ReviewIntervals { learned: days, ..self },
+            Status::Ready => ReviewIntervals { ready: days, ..self },
+        }
+    }
+}
+
+/// A song due for review
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewItem {
+    pub entry: RealBookEntry,
+    /// Whole days since it was last practiced (None if never)
+    pub days_since: Option<u32>,
+}
+
+/// Songs in the book that are due, most overdue (relative to their interval)
+/// first, never-practiced songs ahead of all of them
+pub fn queue(repertoire: &Repertoire, log: &[PracticeSession], intervals: ReviewIntervals) -> Vec<ReviewItem> {
+    let now = js_sys::Date::now();
+    // When each song was last played (the end of its latest session)
+    let mut last_played: BTreeMap<u32, f64> = BTreeMap::new();
+    for session in log {
+        let ended = session.started_at + f64::from(session.seconds) * 1000.0;
+        let last = last_played.entry(session.entry_id).or_insert(ended);
+        *last = last.max(ended);
+    }
+
+    let mut due: Vec<(f64, ReviewItem)> = repertoire
+        .values()
+        .filter_map(|progress| {
+            let status = progress.status?;
+            let days = last_played.get(&progress.entry.id).map(|last| (now - last) / DAY_MS);
+            let interval = f64::from(intervals.days(status));
+            let overdue = days.map_or(f64::INFINITY, |days| days / interval);
+            (overdue >= 1.0).then(|| {
+                (overdue, ReviewItem { entry: progress.entry.clone(), days_since: days.map(|days| days as u32) })
+            })
+        })
+        .collect();
+    due.sort_by(|(a, a_item), (b, b_item)| {
+        b.total_cmp(a).then_with(|| a_item.entry.title.to_lowercase().cmp(&b_item.entry.title.to_lowercase()))
+    });
+    due.into_iter().map(|(_, item)| item).collect()
+}
@@ -0,0 +1,146 @@
+//! Client-side routes backed by the browser History API
+//!
+//! Paths:
+//! - `/` - start page
+//! - `/song/:id` - one chart in the viewer
+//! - `/search?q=...&vol=2&page=100-120&song=42` - a search, optionally with
+//!   the chart open in the viewer
+//! - `/setlists/:id` - a setlist
+//! - `/stage` - the stage view window (see `stage.rs`)
+//!
+//! Built on gloo-history (the layer yew-router uses), so Back/Forward work and
+//! reloading or sharing a link restores what the user was looking at.
+
+use gloo_history::{BrowserHistory, History};
+use serde::{Deserialize, Serialize};
+
+/// Search and selection state carried in the `/search` query string
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchRoute {
+    /// Search text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Volume filter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vol: Option<u32>,
+    /// Page filter as typed ("134" or "100-120")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    /// Id of the entry open in the viewer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub song: Option<u32>,
+}
+
+impl SearchRoute {
+    /// Whether no search filter is set (the selected song doesn't count)
+    pub fn is_empty(&self) -> bool {
+        self.q.is_none() && self.vol.is_none() && self.page.is_none()
+    }
+
+    /// The same search with nothing open in the viewer
+    pub fn without_song(&self) -> SearchRoute {
+        SearchRoute { song: None, ..self.clone() }
+    }
+}
+
+/// A location in the app
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    Home,
+    Song(u32),
+    Search(SearchRoute),
+    Setlist(String),
+    Stage,
+    NotFound,
+}
+
+impl Route {
+    /// The route for the browser's current location
+    pub fn current() -> Route {
+        let location = BrowserHistory::new().location();
+        let segments: Vec<&str> = location.path().split('/').filter(|s| !s.is_empty()).collect();
+
+        match segments.as_slice() {
+            [] => Route::Home,
+            ["song", id] => id.parse().map(Route::Song).unwrap_or(Route::NotFound),
+            ["search"] => Route::Search(location.query::<SearchRoute>().unwrap_or_default()),
+            ["setlists", id] => Route::Setlist(id.to_string()),
+            ["stage"] => Route::Stage,
+            _ => Route::NotFound,
+        }
+    }
+
+    /// The shortest route showing this search and selection:
+    /// `/`, `/song/:id`, or `/search?...`
+    pub fn for_search(search: SearchRoute) -> Route {
+        match search.song {
+            _ if !search.is_empty() => Route::Search(search),
+            Some(id) => Route::Song(id),
+            None => Route::Home,
+        }
+    }
+
+    /// The search and selection this route shows
+    pub fn search(&self) -> SearchRoute {
+        match self {
+            Route::Song(id) => SearchRoute { song: Some(*id), ..Default::default() },
+            Route::Search(search) => search.clone(),
+            Route::Home | Route::Setlist(_) | Route::Stage | Route::NotFound => SearchRoute::default(),
+        }
+    }
+
+    /// Navigate to this route, adding a history entry (no-op if already there)
+    pub fn push(&self) {
+        self.navigate(false);
+    }
+
+    /// Navigate to this route in place of the current history entry,
+    /// so Back skips it (no-op if already there)
+    pub fn replace(&self) {
+        self.navigate(true);
+    }
+
+    /// Path of this route (without the query string of a search)
+    pub fn path(&self) -> String {
+        match self {
+            Route::Home | Route::NotFound => "/".to_string(),
+            Route::Song(id) => format!("/song/{}", id),
+            Route::Search(_) => "/search".to_string(),
+            Route::Setlist(id) => format!("/setlists/{}", id),
+            Route::Stage => "/stage".to_string(),
+        }
+    }
+
+    fn navigate(&self, replace: bool) {
+        if *self == Route::current() {
+            return;
+        }
+
+        let history = BrowserHistory::new();
+        let path = self.path();
+
+        match (self, replace) {
+            (Route::Search(search), false) => {
+                let _ = history.push_with_query(path, search);
+            }
+            (Route::Search(search), true) => {
+                let _ = history.replace_with_query(path, search);
+            }
+            (_, false) => history.push(path),
+            (_, true) => history.replace(path),
+        }
+    }
+}
+
+/// Full link to a chart (`https://host/song/:id`), for sharing
+pub fn song_url(id: u32) -> String {
+    let origin = web_sys::window()
+        .and_then(|window| window.location().origin().ok())
+        .unwrap_or_default();
+    format!("{}{}", origin, Route::Song(id).path())
+}
+
+/// Go back one history entry, like the browser's Back button
+pub fn back() {
+    BrowserHistory::new().back();
+}
@@ -0,0 +1,55 @@
+//! Named searches ("bossa tunes vol 2") the user can re-run from a menu
+//!
+//! A saved search keeps the query, refinements, and volume and page filters.
+//! They're stored in localStorage (`realbook.saved_searches`) in the order
+//! they were saved; there's no account to sync them to yet.
+
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use crate::storage;
+
+/// localStorage key for the saved searches
+const STORAGE_KEY: &str = "realbook.saved_searches";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    /// Earlier queries the search was refined within
+    #[serde(default)]
+    pub within: Vec<String>,
+    pub volume: Option<u32>,
+    /// Page filter as typed ("134" or "100-120")
+    #[serde(default)]
+    pub page_input: String,
+}
+
+impl SavedSearch {
+    /// One-line summary of what the search looks for, e.g. "bossa · Vol. 2"
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self.within.iter().chain([&self.query]).filter(|q| !q.is_empty()).cloned().collect();
+        if let Some(volume) = self.volume {
+            parts.push(format!("Vol. {}", volume));
+        }
+        if !self.page_input.is_empty() {
+            parts.push(format!("p. {}", self.page_input));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// All saved searches
+pub fn load() -> Vec<SavedSearch> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the saved searches as other tabs add and delete them
+pub fn watch(on_change: impl Fn(Vec<SavedSearch>) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// Store the saved searches, returning them for convenience
+pub fn save(searches: Vec<SavedSearch>) -> Vec<SavedSearch> {
+    storage::save(STORAGE_KEY, &searches);
+    searches
+}
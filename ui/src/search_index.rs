@@ -0,0 +1,103 @@
+//! Client-side search over a cached copy of the catalog
+//!
+//! The full catalog (`/api/v1/catalog`) is small enough to keep in
+//! localStorage, so live search runs locally on every keystroke and keeps
+//! working while the API is down. The matching itself is `realbook-core`'s,
+//! which the API's `/search` and `/suggest` run too, so both find the same
+//! songs.
+
+use realbook_core::{Matches, SearchFilter};
+pub use realbook_core::suggestions::completions;
+use serde::{Deserialize, Serialize};
+use crate::api::{self, ApiError};
+use crate::models::{CatalogSnapshot, RealBookEntry, SearchParams, SearchResponse, VolumeInfo};
+use crate::storage;
+
+/// localStorage key for the cached catalog
+const STORAGE_KEY: &str = "realbook.catalog";
+
+/// Age after which the cache is refreshed and searches prefer the API
+const MAX_AGE_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// A cached catalog snapshot plus when it was fetched
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchIndex {
+    /// Catalog content hash reported by the API
+    pub version: String,
+    /// When the snapshot was fetched (ms since the Unix epoch)
+    pub fetched_at: f64,
+    pub entries: Vec<RealBookEntry>,
+}
+
+impl SearchIndex {
+    /// Load the cached index from localStorage, if any
+    pub fn load() -> Option<Self> {
+        storage::load(STORAGE_KEY)
+    }
+
+    /// Build an index from a freshly fetched snapshot and cache it
+    pub fn store(snapshot: CatalogSnapshot) -> Self {
+        let index = SearchIndex {
+            version: snapshot.version,
+            fetched_at: js_sys::Date::now(),
+            entries: snapshot.entries,
+        };
+        storage::save(STORAGE_KEY, &index);
+        index
+    }
+
+    /// Whether the snapshot is old enough to refetch
+    pub fn is_stale(&self) -> bool {
+        js_sys::Date::now() - self.fetched_at > MAX_AGE_MS
+    }
+
+    /// Run a search locally, with the same semantics as `/api/v1/search`
+    pub fn search(&self, params: &SearchParams) -> SearchResponse {
+        search_entries(&self.entries, params)
+    }
+}
+
+/// Search any list of entries (the cached catalog, or e.g. just the favorites)
+/// with the same semantics as `/api/v1/search`
+pub fn search_entries(entries: &[RealBookEntry], params: &SearchParams) -> SearchResponse {
+    let filter = SearchFilter {
+        query: params.query.as_deref(),
+        within: &params.within,
+        initial: params.initial,
+        volume: params.volume,
+        page: params.page,
+        page_from: params.page_from,
+        page_to: params.page_to,
+        page_near: params.page_near,
+        tolerance: params.tolerance,
+        offset: params.offset,
+        limit: params.limit,
+    };
+    let Matches { results, total, volume_counts, suggestions } = realbook_core::search_entries(entries, &filter);
+    let volume_counts = volume_counts.into_iter().map(|(volume, count)| VolumeInfo { volume, count }).collect();
+
+    SearchResponse { results, total, volume_counts, suggestions }
+}
+
+/// Search the fresh local index when there is one; otherwise ask the API,
+/// falling back to a stale index if the API can't be reached
+pub async fn search(index: Option<&SearchIndex>, params: &SearchParams) -> Result<SearchResponse, ApiError> {
+    match index {
+        Some(index) if !index.is_stale() => Ok(index.search(params)),
+        Some(index) => Ok(api::search(params).await.unwrap_or_else(|_| index.search(params))),
+        None => api::search(params).await,
+    }
+}
+
+/// Autocomplete titles for a partly typed query, from the fresh local index
+/// when there is one (like `search`), within `volume` if given
+pub async fn suggest(index: Option<&SearchIndex>, query: &str, volume: Option<u32>) -> Result<Vec<String>, ApiError> {
+    let local = |index: &SearchIndex| {
+        completions(index.entries.iter().filter(|entry| volume.is_none_or(|vol| entry.volume == vol)), query)
+    };
+    match index {
+        Some(index) if !index.is_stale() => Ok(local(index)),
+        Some(index) => Ok(api::suggest(query, volume).await.unwrap_or_else(|_| local(index))),
+        None => api::suggest(query, volume).await,
+    }
+}
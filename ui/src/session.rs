@@ -0,0 +1,74 @@
+//! The last session: search, filters, open chart, and scroll positions
+//!
+//! Saved to localStorage whenever the page is hidden (a reload, closing the
+//! tab, or switching away on a phone), so an accidental refresh mid-rehearsal
+//! comes back to the same chart, scrolled to the same place. The URL already
+//! carries the search and open chart; the session adds what it doesn't (refine
+//! terms, the A-Z letter, browse, favorites, tag, and repertoire filters,
+//! scroll) and fills in the URL when the app is reopened at `/`.
+
+use serde::{Deserialize, Serialize};
+use crate::repertoire::RepertoireFilter;
+use crate::router::{Route, SearchRoute};
+use crate::storage;
+
+/// localStorage key for the last session
+const STORAGE_KEY: &str = "realbook.session";
+
+/// What the user was looking at when the page was last hidden
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Search and open chart, as in the URL
+    pub search: SearchRoute,
+    /// Id of the setlist shown as the results, if one was
+    pub setlist: Option<String>,
+    /// Earlier queries the search refined
+    pub refine_terms: Vec<String>,
+    /// Letter picked in the A-Z strip
+    pub title_initial: Option<char>,
+    /// Whether an empty query listed the selected volume
+    pub browse_mode: bool,
+    /// Whether searches were limited to starred songs
+    pub favorites_only: bool,
+    /// Tag searches were limited to, if any
+    pub tag: Option<String>,
+    /// Part of the repertoire searches were limited to, if any
+    pub repertoire: Option<RepertoireFilter>,
+    /// How far the results list was scrolled, in pixels
+    pub results_scroll: f64,
+    /// How far the page (the open chart) was scrolled, in pixels
+    pub page_scroll: f64,
+}
+
+impl Session {
+    /// The route the session was at
+    pub fn route(&self) -> Route {
+        match &self.setlist {
+            Some(id) => Route::Setlist(id.clone()),
+            None => Route::for_search(self.search.clone()),
+        }
+    }
+
+    /// Where the URL comes from: a reload of the same route, or reopening the app
+    /// at `/` (a link to somewhere else starts afresh)
+    fn resumes_at(&self, route: &Route) -> bool {
+        *route == Route::Home || *route == self.route()
+    }
+}
+
+/// The saved session, if it picks up at `route` (the URL the app opened at)
+pub fn restore(route: &Route) -> Option<Session> {
+    storage::load::<Session>(STORAGE_KEY).filter(|session| session.resumes_at(route))
+}
+
+/// Store the session, taking the search, open chart, and setlist from the URL
+pub fn save(mut session: Session) {
+    let route = Route::current();
+    session.setlist = match &route {
+        Route::Setlist(id) => Some(id.clone()),
+        _ => None,
+    };
+    session.search = route.search();
+    storage::save(STORAGE_KEY, &session);
+}
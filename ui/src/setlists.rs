@@ -0,0 +1,121 @@
+//! Setlists: named, ordered lists of songs for a gig
+//!
+//! Setlists are kept in localStorage (`realbook.setlists`). One can be exported
+//! as a JSON file and imported by a bandmate; the file carries each song's
+//! title, volume, and pages, so it stays readable without the app.
+
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use crate::models::RealBookEntry;
+use crate::storage;
+
+/// localStorage key for the setlists
+const STORAGE_KEY: &str = "realbook.setlists";
+
+/// Marker and version written into exported files
+const FILE_FORMAT: &str = "realbook-setlist";
+const FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Setlist {
+    /// Random id used in `/setlists/:id` URLs
+    pub id: String,
+    pub name: String,
+    /// Songs in playing order
+    pub songs: Vec<RealBookEntry>,
+}
+
+/// Exported setlist file
+#[derive(Serialize, Deserialize)]
+struct SetlistFile {
+    format: String,
+    version: u32,
+    name: String,
+    songs: Vec<RealBookEntry>,
+}
+
+/// All saved setlists
+pub fn load() -> Vec<Setlist> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the setlists as other tabs edit them
+pub fn watch(on_change: impl Fn(Vec<Setlist>) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// Store the setlists, returning them for convenience
+pub fn save(setlists: Vec<Setlist>) -> Vec<Setlist> {
+    storage::save(STORAGE_KEY, &setlists);
+    setlists
+}
+
+/// A fresh random setlist id
+fn new_id() -> String {
+    format!("{:08x}", (js_sys::Math::random() * f64::from(u32::MAX)) as u32)
+}
+
+/// An empty setlist with a new id
+pub fn create(name: &str) -> Setlist {
+    Setlist { id: new_id(), name: name.to_string(), songs: Vec::new() }
+}
+
+/// JSON file contents for sharing a setlist
+pub fn export_json(setlist: &Setlist) -> String {
+    let file = SetlistFile {
+        format: FILE_FORMAT.to_string(),
+        version: FILE_VERSION,
+        name: setlist.name.clone(),
+        songs: setlist.songs.clone(),
+    };
+    serde_json::to_string_pretty(&file).unwrap_or_default()
+}
+
+/// Read a shared setlist file (it gets a new id, so importing twice keeps both)
+pub fn import_json(contents: &str) -> Result<Setlist, String> {
+    let file: SetlistFile =
+        serde_json::from_str(contents).map_err(|e| format!("Not a setlist file: {}", e))?;
+    if file.format != FILE_FORMAT {
+        return Err("Not a setlist file".to_string());
+    }
+    if file.version > FILE_VERSION {
+        return Err("This setlist was exported by a newer version of the app".to_string());
+    }
+    Ok(Setlist { id: new_id(), name: file.name, songs: file.songs })
+}
+
+/// File-name-safe version of a setlist name (e.g. "Friday gig" -> "Friday_gig")
+pub fn file_stem(name: &str) -> String {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() { "setlist".to_string() } else { words.join("_") }
+}
+
+/// Offer `contents` as a file download
+pub fn download(filename: &str, contents: &str, mime_type: &str) -> Result<(), String> {
+    let fail = |_| "Couldn't create the download".to_string();
+    let document = web_sys::window().and_then(|window| window.document()).ok_or("No document")?;
+
+    let parts = js_sys::Array::of1(&contents.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(fail)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(fail)?;
+
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a").map_err(fail)?.unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    let _ = web_sys::Url::revoke_object_url(&url);
+    Ok(())
+}
+
+/// Text of a file picked in an `<input type="file">`
+pub async fn read_file(file: web_sys::File) -> Result<String, String> {
+    let text = JsFuture::from(file.text()).await.map_err(|_| "Couldn't read the file".to_string())?;
+    text.as_string().ok_or_else(|| "Couldn't read the file".to_string())
+}
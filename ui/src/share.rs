@@ -0,0 +1,54 @@
+//! Sending a link to someone: the system share sheet where there is one
+//! (phones and tablets, via the Web Share API), else the clipboard
+//!
+//! Both APIs are missing in some browsers and on plain-http origins, so each is
+//! feature-detected before use; as a last resort the link is shown in a prompt
+//! to copy by hand.
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+
+/// How a link was passed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shared {
+    /// Through the share sheet (or the user dismissed it)
+    ShareSheet,
+    /// Copied to the clipboard
+    Copied,
+    /// Shown for the user to copy
+    Shown,
+}
+
+/// Whether `navigator` has a property, e.g. "share" (missing on desktop
+/// browsers without the Web Share API)
+fn supports(navigator: &web_sys::Navigator, property: &str) -> bool {
+    js_sys::Reflect::get(navigator, &JsValue::from_str(property)).is_ok_and(|value| !value.is_undefined())
+}
+
+/// Share `url` titled `title`; `prompt` labels the last-resort copy prompt
+pub async fn share_link(title: &str, url: &str, prompt: &str) -> Shared {
+    let Some(window) = web_sys::window() else {
+        return Shared::Shown;
+    };
+    let navigator = window.navigator();
+
+    if supports(&navigator, "share") {
+        let data = web_sys::ShareData::new();
+        data.set_title(title);
+        data.set_url(url);
+        // Some browsers that can share predate canShare
+        if !supports(&navigator, "canShare") || navigator.can_share_with_data(&data) {
+            // Rejects when the user dismisses the sheet, which needs no fallback
+            let _ = JsFuture::from(navigator.share_with_data(&data)).await;
+            return Shared::ShareSheet;
+        }
+    }
+
+    if supports(&navigator, "clipboard")
+        && JsFuture::from(navigator.clipboard().write_text(url)).await.is_ok() {
+        return Shared::Copied;
+    }
+
+    let _ = window.prompt_with_message_and_default(prompt, url);
+    Shared::Shown
+}
@@ -0,0 +1,81 @@
+//! Stage view: a chart-only window (`/stage`) for a monitor facing the band,
+//! following the song and page shown in the main window
+//!
+//! The windows talk over a BroadcastChannel. The viewer posts `Show` whenever
+//! its song or page changes; a stage window posts `Ready` when it opens, and
+//! any viewer showing a chart answers with it, so the stage never waits for the
+//! next page turn to catch up.
+
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use crate::models::RealBookEntry;
+use crate::router::Route;
+
+/// Name of the BroadcastChannel between the main window and the stage
+const CHANNEL_NAME: &str = "realbook.stage";
+
+/// Name of the stage window, so opening it again reuses the one already open
+const WINDOW_NAME: &str = "realbook-stage";
+
+/// A message between the main window and the stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StageMessage {
+    /// A stage window opened and wants the current chart
+    Ready,
+    /// Show `entry`, at `page` (0-based, within the song)
+    Show { entry: RealBookEntry, page: usize },
+}
+
+/// One end of the stage channel, listening until dropped
+pub struct StageChannel {
+    channel: web_sys::BroadcastChannel,
+    _listener: EventListener,
+}
+
+impl StageChannel {
+    /// Join the channel, calling `on_message` for each message from another
+    /// window (None where BroadcastChannel is unsupported)
+    pub fn open(on_message: impl Fn(StageMessage) + 'static) -> Option<Self> {
+        let channel = web_sys::BroadcastChannel::new(CHANNEL_NAME).ok()?;
+        let listener = EventListener::new(&channel, "message", move |event| {
+            let Some(event) = event.dyn_ref::<web_sys::MessageEvent>() else {
+                return;
+            };
+            // Messages are JSON strings; anything else isn't ours
+            if let Some(message) = event.data().as_string().and_then(|data| serde_json::from_str(&data).ok()) {
+                on_message(message);
+            }
+        });
+        Some(StageChannel { channel, _listener: listener })
+    }
+
+    /// Post a message to the other windows (a message never reaches its sender)
+    pub fn send(&self, message: &StageMessage) {
+        if let Ok(data) = serde_json::to_string(message) {
+            let _ = self.channel.post_message(&JsValue::from_str(&data));
+        }
+    }
+}
+
+impl Drop for StageChannel {
+    fn drop(&mut self) {
+        self.channel.close();
+    }
+}
+
+/// Open the stage window (or bring the open one forward); false if the
+/// browser blocked it
+pub fn open_window() -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    match window.open_with_url_and_target_and_features(&Route::Stage.path(), WINDOW_NAME, "popup") {
+        Ok(Some(stage)) => {
+            let _ = stage.focus();
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,88 @@
+//! Typed access to the browser's localStorage and sessionStorage
+//!
+//! Values are stored as JSON under a key. Reads that fail (storage disabled,
+//! missing key, or a value written by an incompatible older build) return None
+//! so callers can fall back to defaults.
+//!
+//! localStorage is shared by every tab of the app; `watch` follows the changes
+//! the others make.
+
+use gloo_events::EventListener;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use wasm_bindgen::JsCast;
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+fn session_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.session_storage().ok()?
+}
+
+fn read<T: DeserializeOwned>(storage: Option<web_sys::Storage>, key: &str) -> Option<T> {
+    let raw = storage?.get_item(key).ok()??;
+    serde_json::from_str(&raw).ok()
+}
+
+fn write<T: Serialize>(storage: Option<web_sys::Storage>, key: &str, value: &T) {
+    if let (Some(storage), Ok(raw)) = (storage, serde_json::to_string(value)) {
+        let _ = storage.set_item(key, &raw);
+    }
+}
+
+/// Read and deserialize a stored value
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    read(local_storage(), key)
+}
+
+/// Serialize and store a value (silently skipped if storage is unavailable or full)
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    write(local_storage(), key, value)
+}
+
+/// Delete a stored value
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
+
+/// Keys of the stored values whose key starts with `prefix`
+pub fn keys(prefix: &str) -> Vec<String> {
+    let Some(storage) = local_storage() else {
+        return Vec::new();
+    };
+    let count = storage.length().unwrap_or(0);
+    (0..count)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .filter(|key| key.starts_with(prefix))
+        .collect()
+}
+
+/// Call `on_change` when another tab or window stores a value under `key`,
+/// deletes it, or clears storage, until the returned listener is dropped.
+/// Browsers fire the `storage` event only in the other tabs, never the one
+/// that wrote, so reacting to it can't echo back and forth.
+pub fn watch(key: &'static str, on_change: impl Fn() + 'static) -> EventListener {
+    let window = web_sys::window().expect("no window");
+    EventListener::new(&window, "storage", move |event| {
+        // A missing key means the whole storage was cleared
+        let changed = event
+            .dyn_ref::<web_sys::StorageEvent>()
+            .is_some_and(|event| event.key().is_none_or(|changed| changed == key));
+        if changed {
+            on_change();
+        }
+    })
+}
+
+/// Read a value stored for this browser tab's session
+pub fn load_session<T: DeserializeOwned>(key: &str) -> Option<T> {
+    read(session_storage(), key)
+}
+
+/// Store a value for this browser tab's session (cleared when the tab closes)
+pub fn save_session<T: Serialize>(key: &str, value: &T) {
+    write(session_storage(), key, value)
+}
@@ -0,0 +1,358 @@
+//! Sync of the local stores with the other devices on the anonymous session
+//!
+//! Everything the user builds up stays local-first in localStorage; sync only
+//! carries it across devices. Each store (the archive sections of
+//! `archive.rs`) is split into records: one per song for the per-song maps,
+//! one per favorite, setlist, or practice session for the lists. A journal
+//! (`realbook.sync`) remembers a fingerprint of every record as last synced
+//! and when it last changed, so an exchange sends only the records that differ
+//! (deletions included) stamped with the time they were noticed, and takes
+//! the records other devices changed since the last exchange. The API keeps
+//! the later change of each record (see `api/src/sync.rs`).
+//!
+//! A device's data from before its first sync counts as older than anything
+//! already synced, so joining a session never clobbers the other devices'
+//! edits; it only adds what they don't have. Sync is off until the user links
+//! a device or joins one in the settings panel.
+
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::models::{SyncRecord, SyncRequest};
+use crate::{api, archive, storage};
+
+/// localStorage key for the journal
+const STORAGE_KEY: &str = "realbook.sync";
+
+/// How often to sync while the app is open (ms)
+pub const SYNC_INTERVAL_MS: u32 = 30_000;
+
+/// Where sync stands, for the header's indicator
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStatus {
+    /// This device doesn't sync
+    Off,
+    Syncing,
+    /// Last synced at this time (ms since the epoch; None if not yet this visit)
+    Synced(Option<f64>),
+    /// No connection; changes wait for it to come back
+    Offline,
+    Failed(String),
+}
+
+/// A record as last synced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Synced {
+    /// Fingerprint of its value (None once deleted)
+    fingerprint: Option<String>,
+    updated_at: f64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Journal {
+    enabled: bool,
+    cursor: u64,
+    /// Epoch of the server's log the cursor is from
+    epoch: Option<String>,
+    /// Random id of this device, so the server knows when every device has
+    /// seen a deletion
+    device: String,
+    /// By `store/key`
+    records: BTreeMap<String, Synced>,
+}
+
+impl Journal {
+    fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    /// The records in `sections` that changed since they were last synced
+    /// (deletions included), noted as synced at `now`; with `resend`, every
+    /// record and deletion with the time it last changed
+    fn outgoing(&mut self, sections: &Map<String, Value>, now: f64, resend: bool) -> Vec<SyncRecord> {
+        let first_sync = self.records.is_empty();
+        let mut changes = Vec::new();
+        let mut present = BTreeSet::new();
+        for (store, section) in sections {
+            for (key, value) in split(store, section) {
+                let id = format!("{}/{}", store, key);
+                let print = fingerprint(&value);
+                let updated_at = match self.records.get(&id) {
+                    Some(synced) if synced.fingerprint.as_ref() == Some(&print) && !resend => {
+                        present.insert(id);
+                        continue;
+                    }
+                    Some(synced) if synced.fingerprint.as_ref() == Some(&print) => synced.updated_at,
+                    None if first_sync => 0.0,
+                    _ => now,
+                };
+                self.records.insert(id.clone(), Synced { fingerprint: Some(print), updated_at });
+                changes.push(SyncRecord { store: store.clone(), key, value: Some(value), updated_at });
+                present.insert(id);
+            }
+        }
+        for (id, synced) in self.records.iter_mut() {
+            let deleted_here = synced.fingerprint.is_some() && !present.contains(id);
+            if deleted_here || (resend && synced.fingerprint.is_none()) {
+                if deleted_here {
+                    *synced = Synced { fingerprint: None, updated_at: now };
+                }
+                let (store, key) = id.split_once('/').unwrap_or((id.as_str(), ""));
+                changes.push(SyncRecord { store: store.to_string(), key: key.to_string(), value: None, updated_at: synced.updated_at });
+            }
+        }
+        changes
+    }
+
+    /// Apply other devices' `changes` to `sections`, skipping what this device
+    /// already has; answers with the stores that changed
+    fn incoming(&mut self, sections: &mut Map<String, Value>, changes: Vec<SyncRecord>) -> Vec<String> {
+        let mut touched = Vec::new();
+        for record in changes {
+            let Some(section) = sections.get_mut(&record.store) else {
+                continue;
+            };
+            let id = format!("{}/{}", record.store, record.key);
+            let print = record.value.as_ref().map(fingerprint);
+            if self.records.get(&id).is_some_and(|synced| synced.fingerprint == print) {
+                continue;
+            }
+            apply(&record.store, section, &record.key, record.value);
+            self.records.insert(id, Synced { fingerprint: print, updated_at: record.updated_at });
+            touched.push(record.store);
+        }
+        touched.sort();
+        touched.dedup();
+        touched
+    }
+}
+
+/// Whether this device syncs
+pub fn is_enabled() -> bool {
+    Journal::load().enabled
+}
+
+/// Start syncing; `fresh` forgets what was synced before (after joining
+/// another session), so everything here is offered to it as new
+pub fn enable(fresh: bool) {
+    let journal = Journal::load();
+    let journal = if fresh { Journal::default() } else { journal };
+    Journal { enabled: true, ..journal }.save();
+}
+
+/// Stop syncing (the data stays on this device)
+pub fn disable() {
+    storage::remove(STORAGE_KEY);
+}
+
+/// Whether the browser thinks it has a connection
+pub fn is_online() -> bool {
+    web_sys::window().is_none_or(|window| window.navigator().on_line())
+}
+
+/// 64-bit FNV-1a of a value's JSON (maps serialize with sorted keys, so equal
+/// values always match)
+fn fingerprint(value: &Value) -> String {
+    let hash = value
+        .to_string()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// Key of an item in a list section
+fn list_key(store: &str, item: &Value) -> Option<String> {
+    match store {
+        "practice" => Some(format!("{}@{}", item.get("entry_id")?, item.get("started_at")?)),
+        _ => item.get("id").map(|id| id.as_str().map_or_else(|| id.to_string(), str::to_string)),
+    }
+}
+
+/// A section's records, by key
+fn split(store: &str, section: &Value) -> BTreeMap<String, Value> {
+    match section {
+        Value::Object(items) => items.clone().into_iter().collect(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| Some((list_key(store, item)?, item.clone())))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Put a record into a section (or take it out, for a deletion), keeping
+/// lists in their order
+fn apply(store: &str, section: &mut Value, key: &str, value: Option<Value>) {
+    match section {
+        Value::Object(items) => match value {
+            Some(value) => drop(items.insert(key.to_string(), value)),
+            None => drop(items.remove(key)),
+        },
+        Value::Array(items) => {
+            let position = items.iter().position(|item| list_key(store, item).as_deref() == Some(key));
+            match (position, value) {
+                (Some(index), Some(value)) => items[index] = value,
+                (Some(index), None) => drop(items.remove(index)),
+                (None, Some(value)) => items.push(value),
+                (None, None) => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Exchange changes with the API; true if anything here changed
+pub async fn sync() -> Result<bool, String> {
+    let mut journal = Journal::load();
+    if !journal.enabled {
+        return Ok(false);
+    }
+    if journal.device.is_empty() {
+        journal.device = format!("{:016x}", (js_sys::Math::random() * 2f64.powi(53)) as u64);
+    }
+    let mut changed = false;
+    // After a reset, a second round sends every record again
+    let mut resend = false;
+    loop {
+        let mut sections = archive::local_sections();
+        let changes = journal.outgoing(&sections, js_sys::Date::now(), resend);
+
+        let cursor = if resend { 0 } else { journal.cursor };
+        let request = SyncRequest { cursor, epoch: journal.epoch.clone(), device: journal.device.clone(), changes };
+        let response = api::sync(&request).await.map_err(|e| e.message)?;
+
+        for store in journal.incoming(&mut sections, response.changes) {
+            if let Some(section) = sections.remove(&store)
+                && archive::save_section(&store, section)
+            {
+                changed = true;
+            }
+        }
+        journal.cursor = response.cursor;
+        journal.epoch = Some(response.epoch);
+        journal.save();
+
+        if response.reset && !resend {
+            resend = true;
+        } else {
+            return Ok(changed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sections(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn record(store: &str, key: &str, value: Option<Value>, updated_at: f64) -> SyncRecord {
+        SyncRecord { store: store.to_string(), key: key.to_string(), value, updated_at }
+    }
+
+    fn keys(changes: &[SyncRecord]) -> Vec<String> {
+        changes.iter().map(|change| format!("{}/{}", change.store, change.key)).collect()
+    }
+
+    #[test]
+    fn fingerprints_ignore_key_order() {
+        let a: Value = serde_json::from_str(r#"{"a": 1, "b": [2, 3]}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"b": [2, 3], "a": 1}"#).unwrap();
+        assert_eq!(fingerprint(&a), fingerprint(&b));
+        assert_ne!(fingerprint(&a), fingerprint(&json!({ "a": 1, "b": [3, 2] })));
+    }
+
+    #[test]
+    fn list_items_are_keyed_by_id_or_practice_start() {
+        assert_eq!(list_key("setlists", &json!({ "id": "gig" })).as_deref(), Some("gig"));
+        assert_eq!(list_key("favorites", &json!({ "id": 42 })).as_deref(), Some("42"));
+        assert_eq!(list_key("practice", &json!({ "entry_id": 42, "started_at": 1000.0 })).as_deref(), Some("42@1000.0"));
+        assert_eq!(list_key("practice", &json!({ "id": 42 })), None);
+        assert_eq!(list_key("favorites", &json!({ "title": "Solar" })), None);
+    }
+
+    #[test]
+    fn sections_split_into_records() {
+        let maps = split("notes", &json!({ "1": "a", "2": "b" }));
+        assert_eq!(maps.keys().collect::<Vec<_>>(), ["1", "2"]);
+        let lists = split("favorites", &json!([{ "id": 5 }, { "title": "no id" }, { "id": 3 }]));
+        assert_eq!(lists.keys().collect::<Vec<_>>(), ["3", "5"]);
+        assert!(split("notes", &json!("neither")).is_empty());
+    }
+
+    #[test]
+    fn applying_records_keeps_list_order() {
+        let mut section = json!([{ "id": 1, "v": "a" }, { "id": 2, "v": "b" }]);
+        apply("favorites", &mut section, "1", Some(json!({ "id": 1, "v": "changed" })));
+        apply("favorites", &mut section, "3", Some(json!({ "id": 3, "v": "new" })));
+        apply("favorites", &mut section, "2", None);
+        apply("favorites", &mut section, "9", None);
+        assert_eq!(section, json!([{ "id": 1, "v": "changed" }, { "id": 3, "v": "new" }]));
+
+        let mut section = json!({ "1": "a" });
+        apply("notes", &mut section, "2", Some(json!("b")));
+        apply("notes", &mut section, "1", None);
+        assert_eq!(section, json!({ "2": "b" }));
+    }
+
+    #[test]
+    fn data_from_before_the_first_sync_counts_as_oldest() {
+        let mut journal = Journal::default();
+        let changes = journal.outgoing(&sections(json!({ "notes": { "1": "a" } })), 500.0, false);
+        assert_eq!(keys(&changes), ["notes/1"]);
+        assert_eq!(changes[0].updated_at, 0.0);
+    }
+
+    #[test]
+    fn only_changes_and_deletions_are_sent_again() {
+        let mut journal = Journal::default();
+        journal.outgoing(&sections(json!({ "notes": { "1": "a", "2": "b" }, "favorites": [{ "id": 7 }] })), 100.0, false);
+
+        let local = sections(json!({ "notes": { "1": "a", "2": "edited", "3": "c" }, "favorites": [] }));
+        let changes = journal.outgoing(&local, 200.0, false);
+        assert_eq!(keys(&changes), ["notes/2", "notes/3", "favorites/7"]);
+        assert!(changes.iter().all(|change| change.updated_at == 200.0));
+        assert_eq!(changes[2].value, None);
+
+        assert!(journal.outgoing(&local, 300.0, false).is_empty());
+    }
+
+    #[test]
+    fn a_resend_offers_everything_with_when_it_changed() {
+        let mut journal = Journal::default();
+        journal.outgoing(&sections(json!({ "notes": { "1": "a", "2": "b" } })), 100.0, false);
+        journal.outgoing(&sections(json!({ "notes": { "1": "a" } })), 200.0, false);
+
+        let changes = journal.outgoing(&sections(json!({ "notes": { "1": "a" } })), 300.0, true);
+        let sent: Vec<_> = changes.iter().map(|change| (change.key.as_str(), change.value.is_some(), change.updated_at)).collect();
+        assert_eq!(sent, [("1", true, 0.0), ("2", false, 200.0)]);
+    }
+
+    #[test]
+    fn incoming_changes_apply_once() {
+        let mut journal = Journal::default();
+        let mut local = sections(json!({ "notes": { "1": "a" }, "favorites": [{ "id": 7 }] }));
+        journal.outgoing(&local, 100.0, false);
+
+        let changes = vec![
+            record("notes", "1", Some(json!("a")), 150.0),
+            record("notes", "2", Some(json!("b")), 150.0),
+            record("favorites", "7", None, 160.0),
+            record("unknown", "1", Some(json!("x")), 170.0),
+        ];
+        assert_eq!(journal.incoming(&mut local, changes), ["favorites", "notes"]);
+        assert_eq!(Value::Object(local.clone()), json!({ "notes": { "1": "a", "2": "b" }, "favorites": [] }));
+
+        // Applied records aren't echoed back
+        assert!(journal.outgoing(&local, 200.0, false).is_empty());
+        assert!(journal.incoming(&mut local, vec![record("notes", "2", Some(json!("b")), 150.0)]).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Free-form tags on songs (ballad, jam-session, wedding)
+//!
+//! Tags are kept in localStorage (`realbook.tags`), by entry id along with the
+//! song itself, so the tag filter can list and search a tag's songs locally
+//! like the Favorites filter. There's no account to sync them to yet, so they
+//! stay on this device. Tags are stored lowercase with single spaces, so
+//! "Jam  Session" and "jam session" are the same tag.
+
+use std::collections::BTreeMap;
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use crate::models::RealBookEntry;
+use crate::storage;
+
+/// localStorage key for the tags
+const STORAGE_KEY: &str = "realbook.tags";
+
+/// A song and its tags
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tagged {
+    pub entry: RealBookEntry,
+    /// Sorted, without duplicates
+    pub tags: Vec<String>,
+}
+
+/// Every tagged song, by entry id
+pub type Tags = BTreeMap<u32, Tagged>;
+
+/// Every tagged song
+pub fn load() -> Tags {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Follow the tags as other tabs edit them
+pub fn watch(on_change: impl Fn(Tags) + 'static) -> EventListener {
+    storage::watch(STORAGE_KEY, move || on_change(load()))
+}
+
+/// A tag as stored: trimmed, lowercase, single-spaced (None if blank)
+pub fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// One song's tags
+pub fn of(tags: &Tags, id: u32) -> Vec<String> {
+    tags.get(&id).map(|tagged| tagged.tags.clone()).unwrap_or_default()
+}
+
+/// Every tag in use, with how many songs have it, alphabetically
+pub fn counts(tags: &Tags) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in tags.values().flat_map(|tagged| &tagged.tags) {
+        *counts.entry(tag).or_insert(0) += 1;
+    }
+    counts.into_iter().map(|(tag, count)| (tag.to_string(), count)).collect()
+}
+
+/// Songs with a tag, by title
+pub fn songs_with(tags: &Tags, tag: &str) -> Vec<RealBookEntry> {
+    let mut songs: Vec<RealBookEntry> = tags
+        .values()
+        .filter(|tagged| tagged.tags.iter().any(|t| t == tag))
+        .map(|tagged| tagged.entry.clone())
+        .collect();
+    songs.sort_by_key(|entry| entry.title.to_lowercase());
+    songs
+}
+
+/// Replace a song's tags, returning (and storing) the updated tags
+pub fn set(tags: &Tags, entry: &RealBookEntry, song_tags: &[String]) -> Tags {
+    let mut tags = tags.clone();
+    let mut song_tags: Vec<String> = song_tags.iter().filter_map(|tag| normalize(tag)).collect();
+    song_tags.sort();
+    song_tags.dedup();
+    if song_tags.is_empty() {
+        tags.remove(&entry.id);
+    } else {
+        tags.insert(entry.id, Tagged { entry: entry.clone(), tags: song_tags });
+    }
+    save(tags)
+}
+
+/// Rename a tag on every song (merging it into `to` if that's already in use)
+pub fn rename(tags: &Tags, from: &str, to: &str) -> Tags {
+    let Some(to) = normalize(to) else {
+        return tags.clone();
+    };
+    retag(tags, |tag| if tag == from { Some(to.clone()) } else { Some(tag.to_string()) })
+}
+
+/// Remove a tag from every song
+pub fn delete(tags: &Tags, tag: &str) -> Tags {
+    retag(tags, |t| (t != tag).then(|| t.to_string()))
+}
+
+/// Map every song's tags through `f` (None drops the tag), keeping them sorted
+/// and dropping songs left with none
+fn retag(tags: &Tags, f: impl Fn(&str) -> Option<String>) -> Tags {
+    let tags = tags
+        .iter()
+        .filter_map(|(id, tagged)| {
+            let mut song_tags: Vec<String> = tagged.tags.iter().filter_map(|tag| f(tag)).collect();
+            song_tags.sort();
+            song_tags.dedup();
+            (!song_tags.is_empty()).then(|| (*id, Tagged { entry: tagged.entry.clone(), tags: song_tags }))
+        })
+        .collect();
+    save(tags)
+}
+
+/// Store every song's tags, returning them
+pub fn save(tags: Tags) -> Tags {
+    storage::save(STORAGE_KEY, &tags);
+    tags
+}
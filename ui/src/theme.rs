@@ -0,0 +1,189 @@
+//! Appearance preferences: color scheme and sheet image display
+//!
+//! Pico CSS follows the system color scheme unless `data-theme` is set on the
+//! root element. The sepia "paper" theme sets `data-theme="paper"`, which Pico
+//! treats as light and index.html recolors. Accent colors override Pico's
+//! primary color through `data-accent`. Inverted sheets, trimmed margins, and
+//! compact density are styled through the `invert-sheets`, `crop-margins`, and
+//! `density-compact` classes on the same element (see index.html), and the text
+//! size through its `--ui-scale` and `--row-height` properties.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use crate::storage;
+
+/// localStorage key for appearance preferences
+const STORAGE_KEY: &str = "realbook.appearance";
+
+/// Color scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    /// Follow the operating system setting
+    #[default]
+    Auto,
+    Light,
+    Dark,
+    /// Warm off-white page with brown ink, easier on the eyes for long reading
+    Paper,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [Theme::Auto, Theme::Light, Theme::Dark, Theme::Paper];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Auto => "Auto",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+            Theme::Paper => "Paper (sepia)",
+        }
+    }
+}
+
+/// Primary color for buttons, links, and highlights (Pico's color names)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Accent {
+    /// Pico's own blue
+    #[default]
+    Azure,
+    Jade,
+    Pumpkin,
+    Violet,
+    Slate,
+}
+
+impl Accent {
+    pub const ALL: [Accent; 5] = [Accent::Azure, Accent::Jade, Accent::Pumpkin, Accent::Violet, Accent::Slate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Accent::Azure => "Azure",
+            Accent::Jade => "Jade",
+            Accent::Pumpkin => "Pumpkin",
+            Accent::Violet => "Violet",
+            Accent::Slate => "Slate",
+        }
+    }
+
+    /// Value of `data-accent` (None leaves Pico's default)
+    fn attribute(self) -> Option<&'static str> {
+        match self {
+            Accent::Azure => None,
+            Accent::Jade => Some("jade"),
+            Accent::Pumpkin => Some("pumpkin"),
+            Accent::Violet => Some("violet"),
+            Accent::Slate => Some("slate"),
+        }
+    }
+}
+
+/// Spacing of the results list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Density {
+    #[default]
+    Comfortable,
+    /// Tighter rows, so more results fit on a laptop screen
+    Compact,
+}
+
+impl Density {
+    pub const ALL: [Density; 2] = [Density::Comfortable, Density::Compact];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Density::Comfortable => "Comfortable",
+            Density::Compact => "Compact",
+        }
+    }
+
+    /// Height of a result row plus the gap below it at 100% text size, in CSS pixels
+    fn row_height(self) -> f64 {
+        match self {
+            Density::Comfortable => 80.0,
+            Density::Compact => 60.0,
+        }
+    }
+}
+
+/// Text size range for the results list and headings, in percent
+pub const MIN_FONT_SCALE: u32 = 80;
+pub const MAX_FONT_SCALE: u32 = 200;
+pub const FONT_SCALE_STEP: u32 = 10;
+
+/// Appearance preferences, persisted in localStorage
+/// (missing fields default, so preferences saved by older builds still load)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    pub theme: Theme,
+    pub accent: Accent,
+    /// Show sheets as white notes on black (for dark venues)
+    pub invert_sheets: bool,
+    /// Trim the scans' white margins so the staves fill the screen
+    pub crop_margins: bool,
+    /// Turn pages by half a screen, keeping the bottom of the old view at the top
+    pub half_page_turns: bool,
+    pub density: Density,
+    /// Text size of the results list and headings, in percent (larger for a
+    /// tablet on a music stand, read from further away)
+    pub font_scale: u32,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance {
+            theme: Theme::default(),
+            accent: Accent::default(),
+            invert_sheets: false,
+            crop_margins: false,
+            half_page_turns: false,
+            density: Density::default(),
+            font_scale: 100,
+        }
+    }
+}
+
+impl Appearance {
+    /// Height of a result row plus the gap below it, in CSS pixels; rows are
+    /// a fixed height so the results list can be windowed, and grow with the text
+    pub fn row_height(&self) -> f64 {
+        (self.density.row_height() * f64::from(self.font_scale) / 100.0).round()
+    }
+
+    /// Load saved preferences (defaults if none)
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    /// Save preferences and apply them to the document
+    pub fn apply(&self) {
+        storage::save(STORAGE_KEY, self);
+
+        let Some(root) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.document_element())
+        else {
+            return;
+        };
+
+        let _ = match self.theme {
+            Theme::Auto => root.remove_attribute("data-theme"),
+            Theme::Light => root.set_attribute("data-theme", "light"),
+            Theme::Dark => root.set_attribute("data-theme", "dark"),
+            Theme::Paper => root.set_attribute("data-theme", "paper"),
+        };
+        let _ = match self.accent.attribute() {
+            Some(accent) => root.set_attribute("data-accent", accent),
+            None => root.remove_attribute("data-accent"),
+        };
+        let classes = root.class_list();
+        let _ = classes.toggle_with_force("invert-sheets", self.invert_sheets);
+        let _ = classes.toggle_with_force("crop-margins", self.crop_margins);
+        let _ = classes.toggle_with_force("density-compact", self.density == Density::Compact);
+        if let Some(root) = root.dyn_ref::<web_sys::HtmlElement>() {
+            let style = root.style();
+            let _ = style.set_property("--ui-scale", &format!("{}", f64::from(self.font_scale) / 100.0));
+            let _ = style.set_property("--row-height", &format!("{}px", self.row_height()));
+        }
+    }
+}
@@ -1,4 +1,6 @@
-/// Navigation utilities for keyboard result navigation
+//! UI utilities: keyboard result navigation, touch gestures, and text prompts
+
+use wasm_bindgen::JsCast;
 
 /// Calculate the next index when navigating down through results
 /// Wraps around to 0 if at the end
@@ -37,3 +39,63 @@ pub fn prev_result_index(current: Option<usize>, total_results: usize) -> usize
         }
     }
 }
+
+/// Minimum horizontal travel, in CSS pixels, for a touch to count as a swipe
+const SWIPE_MIN_DISTANCE: f64 = 50.0;
+
+/// Classify a finished touch as a horizontal swipe
+/// Returns 1 for a leftward swipe (next), -1 for rightward (previous), or None
+/// when the touch was too short or mostly vertical (i.e. scrolling)
+pub fn swipe_step(dx: f64, dy: f64) -> Option<i32> {
+    if dx.abs() < SWIPE_MIN_DISTANCE || dx.abs() < dy.abs() * 1.5 {
+        return None;
+    }
+
+    Some(if dx < 0.0 { 1 } else { -1 })
+}
+
+/// Index of the last result (vim's `G`)
+pub fn last_result_index(total_results: usize) -> usize {
+    total_results.saturating_sub(1)
+}
+
+/// Today's local date as YYYY-MM-DD
+pub fn local_date() -> String {
+    let today = js_sys::Date::new_0();
+    format!("{:04}-{:02}-{:02}", today.get_full_year(), today.get_month() + 1, today.get_date())
+}
+
+/// A time (ms since the epoch) as local HH:MM
+pub fn local_time(ms: f64) -> String {
+    let time = js_sys::Date::new(&ms.into());
+    format!("{:02}:{:02}", time.get_hours(), time.get_minutes())
+}
+
+/// Ask for a line of text with the browser's prompt dialog
+/// (None if cancelled or left blank)
+pub fn prompt_text(message: &str) -> Option<String> {
+    web_sys::window()
+        .and_then(|window| window.prompt_with_message(message).ok().flatten())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Whether a keyboard event comes from a text field (shortcuts should leave it alone)
+pub fn is_typing(event: &web_sys::KeyboardEvent) -> bool {
+    event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .is_some_and(|element| matches!(element.tag_name().to_lowercase().as_str(), "input" | "textarea"))
+}
+
+/// Focus a text input by element id, selecting its text so typing replaces it
+pub fn focus_input(id: &str) {
+    let input = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(id))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok());
+    if let Some(input) = input {
+        let _ = input.focus();
+        input.select();
+    }
+}
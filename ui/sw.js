@@ -0,0 +1,118 @@
+// Service worker: makes the app usable with no connectivity.
+//
+// - The app shell (index.html plus the hashed JS/WASM bundle it references)
+//   and the catalog are precached on install.
+// - Page navigations and API calls go to the network first and fall back to
+//   the cache, so a gig with no signal still gets the last known data.
+// - Page images the user saved for offline use (IMAGE_CACHE, filled by the
+//   app's "Save offline" action) are served from that cache first.
+// - Other static assets (Pico CSS, icons) are served cache-first.
+//
+// Bump CACHE_VERSION to drop old caches when the caching rules change.
+
+const CACHE_VERSION = "v1";
+const SHELL_CACHE = `realbook-shell-${CACHE_VERSION}`;
+const API_CACHE = `realbook-api-${CACHE_VERSION}`;
+// Managed by the app (ui/src/offline.rs); not versioned with the rules above
+const IMAGE_CACHE = "realbook-images-v1";
+
+const API_PREFIX = "/api/";
+const IMAGE_PATH = /^\/api\/(v1\/)?images\//;
+const CATALOG_URL = "/api/v1/catalog";
+const PICO_CSS = "https://cdn.jsdelivr.net/npm/@picocss/pico@2/css/pico.min.css";
+
+// Trunk fingerprints bundle filenames, so read them out of index.html
+async function shellAssets() {
+  const response = await fetch("/index.html", { cache: "no-cache" });
+  const html = await response.text();
+  const assets = [...html.matchAll(/(?:href|src)="([^"]+\.(?:js|wasm|css|svg|webmanifest))"/g)]
+    .map((match) => match[1])
+    .filter((url) => url.startsWith("/"));
+  return ["/", "/index.html", ...new Set(assets)];
+}
+
+self.addEventListener("install", (event) => {
+  event.waitUntil((async () => {
+    const shell = await caches.open(SHELL_CACHE);
+    await shell.addAll(await shellAssets());
+    await shell.add(new Request(PICO_CSS, { mode: "no-cors" }));
+
+    const api = await caches.open(API_CACHE);
+    await api.add(CATALOG_URL);
+
+    await self.skipWaiting();
+  })());
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil((async () => {
+    const current = [SHELL_CACHE, API_CACHE, IMAGE_CACHE];
+    const names = await caches.keys();
+    await Promise.all(
+      names
+        .filter((name) => name.startsWith("realbook-") && !current.includes(name))
+        .map((name) => caches.delete(name))
+    );
+    await self.clients.claim();
+  })());
+});
+
+// Network first; on success refresh the cached copy, on failure serve it
+async function networkFirst(request, cacheName, fallbackUrl) {
+  const cache = await caches.open(cacheName);
+  try {
+    const response = await fetch(request);
+    // no-store marks a stale page image served while the mirrors are down
+    const noStore = /no-store/i.test(response.headers.get("Cache-Control") || "");
+    if (response.ok && !noStore) {
+      cache.put(request, response.clone());
+    }
+    return response;
+  } catch (error) {
+    // Negotiated API responses carry "Vary: Accept"; any cached format will do
+    const cached = await cache.match(request, { ignoreVary: true })
+      || (fallbackUrl && await cache.match(fallbackUrl));
+    if (cached) {
+      return cached;
+    }
+    throw error;
+  }
+}
+
+async function cacheFirst(request) {
+  const cached = await caches.match(request);
+  if (cached) {
+    return cached;
+  }
+  const response = await fetch(request);
+  if (response.ok || response.type === "opaque") {
+    const cache = await caches.open(SHELL_CACHE);
+    cache.put(request, response.clone());
+  }
+  return response;
+}
+
+// Saved-offline pages first, then the usual network-first API handling
+async function savedImageFirst(request) {
+  const saved = await caches.open(IMAGE_CACHE).then((cache) => cache.match(request));
+  return saved || networkFirst(request, API_CACHE);
+}
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  if (request.method !== "GET") {
+    return;
+  }
+
+  const url = new URL(request.url);
+
+  if (request.mode === "navigate") {
+    event.respondWith(networkFirst(request, SHELL_CACHE, "/index.html"));
+  } else if (url.origin === self.location.origin && IMAGE_PATH.test(url.pathname)) {
+    event.respondWith(savedImageFirst(request));
+  } else if (url.origin === self.location.origin && url.pathname.startsWith(API_PREFIX)) {
+    event.respondWith(networkFirst(request, API_CACHE));
+  } else if (url.origin === self.location.origin || url.href === PICO_CSS) {
+    event.respondWith(cacheFirst(request));
+  }
+});